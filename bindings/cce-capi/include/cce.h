@@ -0,0 +1,186 @@
+#ifndef CCE_H
+#define CCE_H
+
+/* Generated by cbindgen from bindings/cce-capi. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum CceStatus {
+  CCE_STATUS_OK,
+  CCE_STATUS_DONE,
+  CCE_STATUS_ERROR,
+} CceStatus;
+
+typedef enum CceNodeKind {
+  CCE_NODE_KIND_COMMAND,
+  CCE_NODE_KIND_HOW_TO,
+  CCE_NODE_KIND_WHAT_IS,
+  CCE_NODE_KIND_MODULE,
+} CceNodeKind;
+
+typedef enum CceComponentKind {
+  CCE_COMPONENT_KIND_LITERAL,
+  CCE_COMPONENT_KIND_KEYWORD,
+  CCE_COMPONENT_KIND_SLOT,
+  CCE_COMPONENT_KIND_BACK_REF,
+  CCE_COMPONENT_KIND_NUMBER,
+  CCE_COMPONENT_KIND_BOOLEAN,
+} CceComponentKind;
+
+typedef enum CceStepKind {
+  CCE_STEP_KIND_COMMAND,
+  CCE_STEP_KIND_BLOCK,
+  CCE_STEP_KIND_WHEN,
+} CceStepKind;
+
+typedef enum CceAlternativeKind {
+  CCE_ALTERNATIVE_KIND_COMMAND,
+  CCE_ALTERNATIVE_KIND_FINAL,
+} CceAlternativeKind;
+
+typedef struct CceParser CceParser;
+
+/**
+ * `text` is the literal, keyword or slot name. `slot_type` is null unless
+ * the component is a typed slot.
+ */
+typedef struct CceComponent {
+  enum CceComponentKind kind;
+  const char *text;
+  const char *slot_type;
+} CceComponent;
+
+/**
+ * `name` is null for free-form modifiers
+ */
+typedef struct CceModifier {
+  const char *name;
+  const struct CceComponent *components;
+  size_t components_len;
+} CceModifier;
+
+typedef struct CceCommand {
+  const struct CceComponent *components;
+  size_t components_len;
+  const struct CceModifier *modifiers;
+  size_t modifiers_len;
+} CceCommand;
+
+/**
+ * `command` is the step itself, the block head or the `when` condition
+ */
+typedef struct CceStep {
+  enum CceStepKind kind;
+  struct CceCommand command;
+  const struct CceStep *steps;
+  size_t steps_len;
+} CceStep;
+
+/**
+ * `final_sequence` is null unless `kind` is `CCE_ALTERNATIVE_KIND_FINAL`;
+ * `language` is null unless the final sequence is tagged
+ */
+typedef struct CceAlternative {
+  bool canonical;
+  enum CceAlternativeKind kind;
+  struct CceCommand command;
+  const char *final_sequence;
+  const char *language;
+} CceAlternative;
+
+/**
+ * Commands fill `command`; howto and whatis statements fill `signature`
+ * and their steps or alternatives; modules fill `name` and `members`.
+ * Unused lists are empty and unused strings null.
+ */
+typedef struct CceNode {
+  enum CceNodeKind kind;
+  struct CceCommand command;
+  const struct CceComponent *signature;
+  size_t signature_len;
+  const struct CceStep *steps;
+  size_t steps_len;
+  const struct CceAlternative *alternatives;
+  size_t alternatives_len;
+  const char *name;
+  const struct CceNode *members;
+  size_t members_len;
+} CceNode;
+
+typedef struct CceSpan {
+  size_t start;
+  size_t end;
+  size_t line;
+  size_t column;
+} CceSpan;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parses `len` bytes of UTF-8 source. Returns null if `source` is null.
+ * Invalid UTF-8 is reported as an error by the first `cce_parser_next`.
+ *
+ * # Safety
+ *
+ * `source` must point to at least `len` readable bytes.
+ */
+struct CceParser *cce_parser_new(const uint8_t *source, size_t len);
+
+/**
+ * # Safety
+ *
+ * `parser` must be null or come from `cce_parser_new`, and must not be
+ * used afterwards.
+ */
+void cce_parser_free(struct CceParser *parser);
+
+/**
+ * Stores the next node in `out` and returns `CCE_STATUS_OK`. Returns
+ * `CCE_STATUS_DONE` at the end of the source and `CCE_STATUS_ERROR` once
+ * every node before a syntax error has been returned.
+ *
+ * # Safety
+ *
+ * `parser` must come from `cce_parser_new`, and `out` must be writable.
+ */
+enum CceStatus cce_parser_next(struct CceParser *parser, struct CceNode **out);
+
+/**
+ * The syntax error message, or null if there is none. The string is owned
+ * by the parser.
+ *
+ * # Safety
+ *
+ * `parser` must come from `cce_parser_new`.
+ */
+const char *cce_parser_error(const struct CceParser *parser);
+
+/**
+ * Writes the location of the syntax error to `out`. Returns false if there
+ * is no error or it has no location.
+ *
+ * # Safety
+ *
+ * `parser` must come from `cce_parser_new`, and `out` must be writable.
+ */
+bool cce_parser_error_span(const struct CceParser *parser, struct CceSpan *out);
+
+/**
+ * # Safety
+ *
+ * `node` must be null or come from `cce_parser_next`, and must not be used
+ * afterwards.
+ */
+void cce_node_free(struct CceNode *node);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CCE_H */
@@ -0,0 +1,507 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// C API for embedding the parser. A parser reads a whole UTF-8 buffer up
+// front and hands out one node per `cce_parser_next` call. Every node is
+// owned by the caller until it is passed to `cce_node_free`; all pointers
+// inside it stay valid until then.
+
+use std::collections::VecDeque;
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+use cce_ast::{
+  Command, CommandComponent, HowToCommand, Modifier, ParseNode, Parser, ParserError, Span,
+  WhatIsCommand,
+};
+
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CceStatus {
+  Ok,
+  Done,
+  Error,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CceNodeKind {
+  Command,
+  HowTo,
+  WhatIs,
+  Module,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CceComponentKind {
+  Literal,
+  Keyword,
+  Slot,
+  BackRef,
+  Number,
+  Boolean,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CceStepKind {
+  Command,
+  Block,
+  When,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CceAlternativeKind {
+  Command,
+  Final,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CceSpan {
+  pub start: usize,
+  pub end: usize,
+  pub line: usize,
+  pub column: usize,
+}
+
+/// `text` is the literal, keyword or slot name. `slot_type` is null unless
+/// the component is a typed slot.
+#[repr(C)]
+pub struct CceComponent {
+  pub kind: CceComponentKind,
+  pub text: *const c_char,
+  pub slot_type: *const c_char,
+}
+
+/// `name` is null for free-form modifiers
+#[repr(C)]
+pub struct CceModifier {
+  pub name: *const c_char,
+  pub components: *const CceComponent,
+  pub components_len: usize,
+}
+
+#[repr(C)]
+pub struct CceCommand {
+  pub components: *const CceComponent,
+  pub components_len: usize,
+  pub modifiers: *const CceModifier,
+  pub modifiers_len: usize,
+}
+
+/// `command` is the step itself, the block head or the `when` condition
+#[repr(C)]
+pub struct CceStep {
+  pub kind: CceStepKind,
+  pub command: CceCommand,
+  pub steps: *const CceStep,
+  pub steps_len: usize,
+}
+
+/// `final_sequence` is null unless `kind` is `CCE_ALTERNATIVE_KIND_FINAL`;
+/// `language` is null unless the final sequence is tagged
+#[repr(C)]
+pub struct CceAlternative {
+  pub canonical: bool,
+  pub kind: CceAlternativeKind,
+  pub command: CceCommand,
+  pub final_sequence: *const c_char,
+  pub language: *const c_char,
+}
+
+/// Commands fill `command`; howto and whatis statements fill `signature`
+/// and their steps or alternatives; modules fill `name` and `members`.
+/// Unused lists are empty and unused strings null.
+#[repr(C)]
+pub struct CceNode {
+  pub kind: CceNodeKind,
+  pub command: CceCommand,
+  pub signature: *const CceComponent,
+  pub signature_len: usize,
+  pub steps: *const CceStep,
+  pub steps_len: usize,
+  pub alternatives: *const CceAlternative,
+  pub alternatives_len: usize,
+  pub name: *const c_char,
+  pub members: *const CceNode,
+  pub members_len: usize,
+}
+
+pub struct CceParser {
+  nodes: VecDeque<ParseNode>,
+  error: Option<(CString, Option<Span>)>,
+}
+
+// A node together with the allocations its pointers refer to. `node` comes
+// first so a `*mut CceNode` can be turned back into the whole handle.
+#[repr(C)]
+struct NodeHandle {
+  node: CceNode,
+  storage: Storage,
+}
+
+#[derive(Default)]
+struct Storage {
+  strings: Vec<CString>,
+  components: Vec<Vec<CceComponent>>,
+  modifiers: Vec<Vec<CceModifier>>,
+  steps: Vec<Vec<CceStep>>,
+  alternatives: Vec<Vec<CceAlternative>>,
+  nodes: Vec<Vec<CceNode>>,
+}
+
+// Moving a Vec or CString does not move its heap buffer, so the pointers
+// handed out here stay valid for as long as the storage lives
+impl Storage {
+  fn string(&mut self, s: &str) -> *const c_char {
+    let s: CString = CString::new(s.replace('\0', "")).unwrap_or_default();
+    let p: *const c_char = s.as_ptr();
+    self.strings.push(s);
+    p
+  }
+
+  fn components(&mut self, components: &[CommandComponent]) -> (*const CceComponent, usize) {
+    let list: Vec<CceComponent> = components
+      .iter()
+      .map(|component| match component {
+        CommandComponent::Literal(text) => CceComponent {
+          kind: CceComponentKind::Literal,
+          text: self.string(text),
+          slot_type: ptr::null(),
+        },
+        CommandComponent::Keyword(text) => CceComponent {
+          kind: CceComponentKind::Keyword,
+          text: self.string(text),
+          slot_type: ptr::null(),
+        },
+        CommandComponent::Slot { name, ty } => CceComponent {
+          kind: CceComponentKind::Slot,
+          text: self.string(name),
+          slot_type: match ty {
+            Some(ty) => self.string(&ty.to_string()),
+            None => ptr::null(),
+          },
+        },
+        CommandComponent::BackRef(text) => CceComponent {
+          kind: CceComponentKind::BackRef,
+          text: self.string(text),
+          slot_type: ptr::null(),
+        },
+        // The unit, if any, follows the digits as it was written
+        CommandComponent::Number { value, unit } => CceComponent {
+          kind: CceComponentKind::Number,
+          text: self.string(&match unit {
+            Some(unit) => format!("{} {}", value, unit),
+            None => value.clone(),
+          }),
+          slot_type: ptr::null(),
+        },
+        CommandComponent::Boolean(value) => CceComponent {
+          kind: CceComponentKind::Boolean,
+          text: self.string(&value.to_string()),
+          slot_type: ptr::null(),
+        },
+      })
+      .collect();
+
+    let result = (list.as_ptr(), list.len());
+    self.components.push(list);
+    result
+  }
+
+  fn command(&mut self, command: &Command) -> CceCommand {
+    let (components, components_len) = self.components(&command.components);
+
+    let modifiers: Vec<CceModifier> = command
+      .modifiers
+      .iter()
+      .map(|modifier| {
+        let (name, args) = match modifier {
+          Modifier::Named { name, args } => (self.string(name), args),
+          Modifier::FreeForm(components) => (ptr::null(), components),
+        };
+        let (components, components_len) = self.components(args);
+
+        CceModifier { name, components, components_len }
+      })
+      .collect();
+
+    let result = CceCommand {
+      components,
+      components_len,
+      modifiers: modifiers.as_ptr(),
+      modifiers_len: modifiers.len(),
+    };
+    self.modifiers.push(modifiers);
+    result
+  }
+
+  fn steps(&mut self, body: &[HowToCommand]) -> (*const CceStep, usize) {
+    let steps: Vec<CceStep> = body
+      .iter()
+      .map(|step| {
+        let (kind, command, children) = match step {
+          HowToCommand::Command(command) => (CceStepKind::Command, command, &[][..]),
+          HowToCommand::Block { head, body } => (CceStepKind::Block, head, body.as_slice()),
+          HowToCommand::When { condition, body } => (CceStepKind::When, condition, body.as_slice()),
+        };
+
+        let command: CceCommand = self.command(command);
+        let (steps, steps_len) = self.steps(children);
+
+        CceStep { kind, command, steps, steps_len }
+      })
+      .collect();
+
+    let result = (steps.as_ptr(), steps.len());
+    self.steps.push(steps);
+    result
+  }
+}
+
+fn empty_command() -> CceCommand {
+  CceCommand {
+    components: ptr::null(),
+    components_len: 0,
+    modifiers: ptr::null(),
+    modifiers_len: 0,
+  }
+}
+
+fn build_node(node: &ParseNode) -> Box<NodeHandle> {
+  let mut storage: Storage = Storage::default();
+  let node: CceNode = storage.node(node);
+
+  Box::new(NodeHandle { node, storage })
+}
+
+impl Storage {
+  fn node(&mut self, node: &ParseNode) -> CceNode {
+    let mut built: CceNode = CceNode {
+      kind: CceNodeKind::Command,
+      command: empty_command(),
+      signature: ptr::null(),
+      signature_len: 0,
+      steps: ptr::null(),
+      steps_len: 0,
+      alternatives: ptr::null(),
+      alternatives_len: 0,
+      name: ptr::null(),
+      members: ptr::null(),
+      members_len: 0,
+    };
+
+    match node {
+      ParseNode::Command(command) => {
+        built.command = self.command(command);
+      }
+      ParseNode::HowToStatement(howto) => {
+        built.kind = CceNodeKind::HowTo;
+        (built.signature, built.signature_len) = self.components(&howto.signature);
+        (built.steps, built.steps_len) = self.steps(&howto.body);
+      }
+      ParseNode::WhatIsStatement(whatis) => {
+        built.kind = CceNodeKind::WhatIs;
+        (built.signature, built.signature_len) = self.components(&whatis.signature);
+
+        let alternatives: Vec<CceAlternative> = whatis
+          .alternatives
+          .iter()
+          .map(|alternative| match &alternative.command {
+            WhatIsCommand::Command(command) => CceAlternative {
+              canonical: alternative.canonical,
+              kind: CceAlternativeKind::Command,
+              command: self.command(command),
+              final_sequence: ptr::null(),
+              language: ptr::null(),
+            },
+            WhatIsCommand::Final(sequence) => CceAlternative {
+              canonical: alternative.canonical,
+              kind: CceAlternativeKind::Final,
+              command: empty_command(),
+              final_sequence: self.string(&sequence.code),
+              language: match &sequence.language {
+                Some(language) => self.string(language),
+                None => ptr::null(),
+              },
+            },
+          })
+          .collect();
+
+        built.alternatives = alternatives.as_ptr();
+        built.alternatives_len = alternatives.len();
+        self.alternatives.push(alternatives);
+      }
+      ParseNode::Module(module) => {
+        built.kind = CceNodeKind::Module;
+        built.name = self.string(&module.name);
+
+        let members: Vec<CceNode> = module.body.iter().map(|member| self.node(member)).collect();
+        built.members = members.as_ptr();
+        built.members_len = members.len();
+        self.nodes.push(members);
+      }
+    }
+
+    built
+  }
+}
+
+fn parse_all(source: &str) -> CceParser {
+  let mut parser = Parser::from(source);
+  let mut result = CceParser { nodes: VecDeque::new(), error: None };
+
+  loop {
+    match parser.next() {
+      Ok(Some(node)) => result.nodes.push_back(node),
+      Ok(None) => break,
+      Err(err) => {
+        result.error = Some(error_message(&err));
+        break;
+      }
+    }
+  }
+
+  result
+}
+
+fn error_message(err: &ParserError) -> (CString, Option<Span>) {
+  let message: String = match err.span() {
+    Some(span) => format!("{}:{}: {}", span.line, span.column, err),
+    None => err.to_string(),
+  };
+
+  (CString::new(message.replace('\0', "")).unwrap_or_default(), err.span())
+}
+
+/// Parses `len` bytes of UTF-8 source. Returns null if `source` is null.
+/// Invalid UTF-8 is reported as an error by the first `cce_parser_next`.
+///
+/// # Safety
+///
+/// `source` must point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cce_parser_new(source: *const u8, len: usize) -> *mut CceParser {
+  if source.is_null() {
+    return ptr::null_mut();
+  }
+
+  let bytes: &[u8] = std::slice::from_raw_parts(source, len);
+
+  let parser: CceParser = match std::str::from_utf8(bytes) {
+    Ok(source) => parse_all(source),
+    Err(err) => CceParser {
+      nodes: VecDeque::new(),
+      error: Some((CString::new(format!("Invalid UTF-8: {}", err)).unwrap_or_default(), None)),
+    },
+  };
+
+  Box::into_raw(Box::new(parser))
+}
+
+/// # Safety
+///
+/// `parser` must be null or come from `cce_parser_new`, and must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn cce_parser_free(parser: *mut CceParser) {
+  if !parser.is_null() {
+    drop(Box::from_raw(parser));
+  }
+}
+
+/// Stores the next node in `out` and returns `CCE_STATUS_OK`. Returns
+/// `CCE_STATUS_DONE` at the end of the source and `CCE_STATUS_ERROR` once
+/// every node before a syntax error has been returned.
+///
+/// # Safety
+///
+/// `parser` must come from `cce_parser_new`, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn cce_parser_next(parser: *mut CceParser, out: *mut *mut CceNode) -> CceStatus {
+  if parser.is_null() || out.is_null() {
+    return CceStatus::Error;
+  }
+
+  let parser: &mut CceParser = &mut *parser;
+
+  match parser.nodes.pop_front() {
+    Some(node) => {
+      *out = Box::into_raw(build_node(&node)) as *mut CceNode;
+      CceStatus::Ok
+    }
+    None if parser.error.is_some() => CceStatus::Error,
+    None => CceStatus::Done,
+  }
+}
+
+/// The syntax error message, or null if there is none. The string is owned
+/// by the parser.
+///
+/// # Safety
+///
+/// `parser` must come from `cce_parser_new`.
+#[no_mangle]
+pub unsafe extern "C" fn cce_parser_error(parser: *const CceParser) -> *const c_char {
+  match parser.as_ref().and_then(|parser| parser.error.as_ref()) {
+    Some((message, _)) => message.as_ptr(),
+    None => ptr::null(),
+  }
+}
+
+/// Writes the location of the syntax error to `out`. Returns false if there
+/// is no error or it has no location.
+///
+/// # Safety
+///
+/// `parser` must come from `cce_parser_new`, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn cce_parser_error_span(parser: *const CceParser, out: *mut CceSpan) -> bool {
+  let span: Option<Span> = parser.as_ref().and_then(|parser| parser.error.as_ref()).and_then(|(_, span)| *span);
+
+  match span {
+    Some(span) if !out.is_null() => {
+      *out = CceSpan {
+        start: span.start,
+        end: span.end,
+        line: span.line,
+        column: span.column,
+      };
+      true
+    }
+    _ => false,
+  }
+}
+
+/// # Safety
+///
+/// `node` must be null or come from `cce_parser_next`, and must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn cce_node_free(node: *mut CceNode) {
+  if !node.is_null() {
+    drop(Box::from_raw(node as *mut NodeHandle));
+  }
+}
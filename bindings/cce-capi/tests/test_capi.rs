@@ -0,0 +1,106 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::ffi::CStr;
+use std::ptr;
+
+use cce_capi::*;
+
+
+unsafe fn text(p: *const std::ffi::c_char) -> &'static str {
+  CStr::from_ptr(p).to_str().unwrap()
+}
+
+#[test]
+fn test_capi_nodes() {
+  let source: &str = "howto greet %who:string?\n- say hello to %who\n\ngreet 'Bob' | with tone = 'warm'.";
+
+  unsafe {
+    let parser: *mut CceParser = cce_parser_new(source.as_ptr(), source.len());
+    assert!(!parser.is_null());
+
+    let mut node: *mut CceNode = ptr::null_mut();
+
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Ok);
+    assert_eq!((*node).kind, CceNodeKind::HowTo);
+    assert_eq!((*node).signature_len, 2);
+
+    let slot: &CceComponent = &*(*node).signature.add(1);
+    assert_eq!(slot.kind, CceComponentKind::Slot);
+    assert_eq!(text(slot.text), "who");
+    assert_eq!(text(slot.slot_type), "string");
+
+    assert_eq!((*node).steps_len, 1);
+    assert_eq!((*(*node).steps).kind, CceStepKind::Command);
+    cce_node_free(node);
+
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Ok);
+    assert_eq!((*node).kind, CceNodeKind::Command);
+
+    let command: &CceCommand = &(*node).command;
+    assert_eq!(command.components_len, 2);
+    assert_eq!(command.modifiers_len, 1);
+    assert_eq!(text((*command.modifiers).name), "tone");
+    assert_eq!(text((*(*command.modifiers).components).text), "warm");
+    cce_node_free(node);
+
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Done);
+    assert!(cce_parser_error(parser).is_null());
+    cce_parser_free(parser);
+  }
+}
+
+#[test]
+fn test_capi_error() {
+  let source: &str = "greet 'Bob'.\n'oops'.";
+
+  unsafe {
+    let parser: *mut CceParser = cce_parser_new(source.as_ptr(), source.len());
+    let mut node: *mut CceNode = ptr::null_mut();
+
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Ok);
+    cce_node_free(node);
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Error);
+    assert!(text(cce_parser_error(parser)).starts_with("2:1: "));
+
+    let mut span: CceSpan = CceSpan { start: 0, end: 0, line: 0, column: 0 };
+    assert!(cce_parser_error_span(parser, &mut span));
+    assert_eq!((span.line, span.column), (2, 1));
+    cce_parser_free(parser);
+  }
+}
+
+#[test]
+fn test_capi_invalid_utf8() {
+  let source: &[u8] = &[b'h', 0xff, b'.'];
+
+  unsafe {
+    assert!(cce_parser_new(ptr::null(), 0).is_null());
+
+    let parser: *mut CceParser = cce_parser_new(source.as_ptr(), source.len());
+    let mut node: *mut CceNode = ptr::null_mut();
+
+    assert_eq!(cce_parser_next(parser, &mut node), CceStatus::Error);
+    assert!(text(cce_parser_error(parser)).starts_with("Invalid UTF-8"));
+    assert!(!cce_parser_error_span(parser, ptr::null_mut()));
+    cce_parser_free(parser);
+  }
+}
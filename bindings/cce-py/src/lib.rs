@@ -0,0 +1,540 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Python bindings for the parser and the inference AST. Nodes are exposed
+// as frozen, comparable value objects so they can be inspected, compared
+// and pattern-matched from notebooks.
+
+use std::collections::VecDeque;
+
+use cce_ast as ast;
+use cce_infer_ast as infer;
+use circelang_hash::CirceHash;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::IntoPyObjectExt;
+
+
+create_exception!(cce, ParseError, PyException);
+
+// Builds a dataclass-style repr such as `Component(kind='slot', ...)`
+fn repr(name: &str, fields: &[(&str, Bound<'_, PyAny>)]) -> PyResult<String> {
+  let fields: Vec<String> = fields
+    .iter()
+    .map(|(field, value)| Ok(format!("{}={}", field, value.repr()?)))
+    .collect::<PyResult<_>>()?;
+
+  Ok(format!("{}({})", name, fields.join(", ")))
+}
+
+// `kind` is one of "literal", "keyword", "slot" or "backref". `slot_type`
+// is only set for typed slots.
+#[pyclass(frozen, eq, get_all, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+  pub kind: String,
+  pub text: String,
+  pub slot_type: Option<String>,
+}
+
+#[pymethods]
+impl Component {
+  #[new]
+  #[pyo3(signature = (kind, text, slot_type = None))]
+  fn new(kind: String, text: String, slot_type: Option<String>) -> Self {
+    Component { kind, text, slot_type }
+  }
+
+  fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+    repr("Component", &[
+      ("kind", self.kind.clone().into_bound_py_any(py)?),
+      ("text", self.text.clone().into_bound_py_any(py)?),
+      ("slot_type", self.slot_type.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+// `name` is None for free-form modifiers
+#[pyclass(frozen, eq, get_all, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modifier {
+  pub name: Option<String>,
+  pub args: Vec<Component>,
+}
+
+#[pymethods]
+impl Modifier {
+  #[new]
+  #[pyo3(signature = (name, args))]
+  fn new(name: Option<String>, args: Vec<Component>) -> Self {
+    Modifier { name, args }
+  }
+
+  fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+    repr("Modifier", &[
+      ("name", self.name.clone().into_bound_py_any(py)?),
+      ("args", self.args.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+#[pyclass(frozen, eq, get_all, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+  pub components: Vec<Component>,
+  pub modifiers: Vec<Modifier>,
+}
+
+#[pymethods]
+impl Command {
+  #[new]
+  #[pyo3(signature = (components, modifiers = Vec::new()))]
+  fn new(components: Vec<Component>, modifiers: Vec<Modifier>) -> Self {
+    Command { components, modifiers }
+  }
+
+  fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+    repr("Command", &[
+      ("components", self.components.clone().into_bound_py_any(py)?),
+      ("modifiers", self.modifiers.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+// One step of a howto body. `kind` is "command", "block" or "when", and
+// `command` is the step itself, the block head or the condition.
+#[pyclass(frozen, eq, get_all, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+  pub kind: String,
+  pub command: Command,
+  pub body: Vec<Step>,
+}
+
+#[pymethods]
+impl Step {
+  #[new]
+  #[pyo3(signature = (kind, command, body = Vec::new()))]
+  fn new(kind: String, command: Command, body: Vec<Step>) -> Self {
+    Step { kind, command, body }
+  }
+
+  fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+    repr("Step", &[
+      ("kind", self.kind.clone().into_bound_py_any(py)?),
+      ("command", self.command.clone().into_bound_py_any(py)?),
+      ("body", self.body.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+// Exactly one of `command` and `final_sequence` is set; `language` is the
+// final sequence's tag, if it has one
+#[pyclass(frozen, eq, get_all, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternative {
+  pub canonical: bool,
+  pub command: Option<Command>,
+  pub final_sequence: Option<String>,
+  pub language: Option<String>,
+}
+
+#[pymethods]
+impl Alternative {
+  #[new]
+  #[pyo3(signature = (canonical, command = None, final_sequence = None, language = None))]
+  fn new(
+    canonical: bool,
+    command: Option<Command>,
+    final_sequence: Option<String>,
+    language: Option<String>,
+  ) -> Self {
+    Alternative { canonical, command, final_sequence, language }
+  }
+
+  fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+    repr("Alternative", &[
+      ("canonical", self.canonical.into_bound_py_any(py)?),
+      ("command", self.command.clone().into_bound_py_any(py)?),
+      ("final_sequence", self.final_sequence.clone().into_bound_py_any(py)?),
+      ("language", self.language.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+// The fields shared by `ParseNode` and `ProgramNode`. `kind` is "command",
+// "howto", "whatis" or "module"; fields that do not apply are None or empty.
+#[derive(Debug, Clone, PartialEq)]
+struct View {
+  kind: &'static str,
+  // The module's
+  name: Option<String>,
+  command: Option<Command>,
+  signature: Vec<Component>,
+  body: Vec<Step>,
+  alternatives: Vec<Alternative>,
+}
+
+impl View {
+  fn repr(&self, py: Python<'_>, name: &str) -> PyResult<String> {
+    repr(name, &[
+      ("kind", self.kind.into_bound_py_any(py)?),
+      ("name", self.name.clone().into_bound_py_any(py)?),
+      ("command", self.command.clone().into_bound_py_any(py)?),
+      ("signature", self.signature.clone().into_bound_py_any(py)?),
+      ("body", self.body.clone().into_bound_py_any(py)?),
+      ("alternatives", self.alternatives.clone().into_bound_py_any(py)?),
+    ])
+  }
+}
+
+// Both ASTs share the shape of everything below a statement, so the
+// conversions into the Python objects are generated for each of them
+macro_rules! views {
+  ($ast:ident, $command:ident, $components:ident) => {
+    impl From<&$ast::CommandComponent> for Component {
+      fn from(component: &$ast::CommandComponent) -> Self {
+        let (kind, text, slot_type) = match component {
+          $ast::CommandComponent::Literal(text) => ("literal", text.to_string(), None),
+          $ast::CommandComponent::Keyword(text) => ("keyword", text.to_string(), None),
+          $ast::CommandComponent::Slot { name, ty } => ("slot", name.to_string(), ty.as_ref().map(|ty| ty.to_string())),
+          $ast::CommandComponent::BackRef(text) => ("backref", text.to_string(), None),
+          // The unit, if any, follows the digits as it was written
+          $ast::CommandComponent::Number { value, unit: None } => ("number", value.clone(), None),
+          $ast::CommandComponent::Number { value, unit: Some(unit) } => ("number", format!("{} {}", value, unit), None),
+          $ast::CommandComponent::Boolean(value) => ("boolean", value.to_string(), None),
+        };
+
+        Component { kind: kind.to_string(), text, slot_type }
+      }
+    }
+
+    impl From<&$ast::Modifier> for Modifier {
+      fn from(modifier: &$ast::Modifier) -> Self {
+        let (name, args) = match modifier {
+          $ast::Modifier::Named { name, args } => (Some(name.clone()), args),
+          $ast::Modifier::FreeForm(args) => (None, args),
+        };
+
+        Modifier { name, args: args.iter().map(Component::from).collect() }
+      }
+    }
+
+    impl From<&$ast::$command> for Command {
+      fn from(command: &$ast::$command) -> Self {
+        Command {
+          components: command.$components.iter().map(Component::from).collect(),
+          modifiers: command.modifiers.iter().map(Modifier::from).collect(),
+        }
+      }
+    }
+
+    impl From<&$ast::HowToCommand> for Step {
+      fn from(step: &$ast::HowToCommand) -> Self {
+        let (kind, command, body) = match step {
+          $ast::HowToCommand::Command(command) => ("command", command, &[][..]),
+          $ast::HowToCommand::Block { head, body } => ("block", head, body.as_slice()),
+          $ast::HowToCommand::When { condition, body } => ("when", condition, body.as_slice()),
+        };
+
+        Step {
+          kind: kind.to_string(),
+          command: command.into(),
+          body: body.iter().map(Step::from).collect(),
+        }
+      }
+    }
+
+    impl From<&$ast::WhatIsAlternative> for Alternative {
+      fn from(alternative: &$ast::WhatIsAlternative) -> Self {
+        let (command, final_sequence, language) = match &alternative.command {
+          $ast::WhatIsCommand::Command(command) => (Some(command.into()), None, None),
+          $ast::WhatIsCommand::Final(sequence) => {
+            (None, Some(sequence.code.clone()), sequence.language.clone())
+          }
+        };
+
+        Alternative { canonical: alternative.canonical, command, final_sequence, language }
+      }
+    }
+  };
+}
+
+views!(ast, Command, components);
+views!(infer, CommandNode, command);
+
+impl From<&ast::ParseNode> for View {
+  fn from(node: &ast::ParseNode) -> Self {
+    match node {
+      ast::ParseNode::Command(command) => View::command(command.into()),
+      ast::ParseNode::HowToStatement(howto) => View::howto(&howto.signature, howto.body.iter().map(Step::from).collect()),
+      ast::ParseNode::WhatIsStatement(whatis) => View::whatis(&whatis.signature, whatis.alternatives.iter().map(Alternative::from).collect()),
+      ast::ParseNode::Module(module) => View::module(&module.name),
+    }
+  }
+}
+
+impl From<&infer::ProgramNode> for View {
+  fn from(node: &infer::ProgramNode) -> Self {
+    match node {
+      infer::ProgramNode::Command(command) => View::command(command.into()),
+      infer::ProgramNode::HowTo(howto) => View::howto(&howto.signature, howto.body.iter().map(Step::from).collect()),
+      infer::ProgramNode::WhatIs(whatis) => View::whatis(&whatis.signature, whatis.alternatives.iter().map(Alternative::from).collect()),
+      infer::ProgramNode::Module(module) => View::module(&module.name),
+    }
+  }
+}
+
+impl View {
+  fn command(command: Command) -> Self {
+    View {
+      kind: "command",
+      name: None,
+      command: Some(command),
+      signature: Vec::new(),
+      body: Vec::new(),
+      alternatives: Vec::new(),
+    }
+  }
+
+  fn howto<'a, C: 'a>(signature: &'a [C], body: Vec<Step>) -> Self where Component: From<&'a C> {
+    View {
+      kind: "howto",
+      name: None,
+      command: None,
+      signature: signature.iter().map(Component::from).collect(),
+      body,
+      alternatives: Vec::new(),
+    }
+  }
+
+  fn whatis<'a, C: 'a>(signature: &'a [C], alternatives: Vec<Alternative>) -> Self where Component: From<&'a C> {
+    View {
+      kind: "whatis",
+      name: None,
+      command: None,
+      signature: signature.iter().map(Component::from).collect(),
+      body: Vec::new(),
+      alternatives,
+    }
+  }
+
+  fn module(name: &str) -> Self {
+    View {
+      kind: "module",
+      name: Some(name.to_string()),
+      command: None,
+      signature: Vec::new(),
+      body: Vec::new(),
+      alternatives: Vec::new(),
+    }
+  }
+}
+
+// A statement as parsed by `cce_ast`. It keeps the original node so it can
+// be handed back to `convert`.
+#[pyclass(frozen, eq, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseNode {
+  pub node: ast::ParseNode,
+  view: View,
+}
+
+impl From<ast::ParseNode> for ParseNode {
+  fn from(node: ast::ParseNode) -> Self {
+    ParseNode { view: View::from(&node), node }
+  }
+}
+
+// A statement of the inference AST, as returned by `convert`
+#[pyclass(frozen, eq, module = "cce")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramNode {
+  pub node: infer::ProgramNode,
+  view: View,
+}
+
+impl From<infer::ProgramNode> for ProgramNode {
+  fn from(node: infer::ProgramNode) -> Self {
+    ProgramNode { view: View::from(&node), node }
+  }
+}
+
+// The same getters for both node classes
+macro_rules! node_methods {
+  ($name:ident $(, $($extra:tt)*)?) => {
+    #[pymethods]
+    impl $name {
+      #[getter]
+      fn kind(&self) -> &'static str {
+        self.view.kind
+      }
+
+      #[getter]
+      fn name(&self) -> Option<String> {
+        self.view.name.clone()
+      }
+
+      #[getter]
+      fn command(&self) -> Option<Command> {
+        self.view.command.clone()
+      }
+
+      #[getter]
+      fn signature(&self) -> Vec<Component> {
+        self.view.signature.clone()
+      }
+
+      #[getter]
+      fn body(&self) -> Vec<Step> {
+        self.view.body.clone()
+      }
+
+      #[getter]
+      fn alternatives(&self) -> Vec<Alternative> {
+        self.view.alternatives.clone()
+      }
+
+      fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        self.view.repr(py, stringify!($name))
+      }
+
+      $($($extra)*)?
+    }
+  };
+}
+
+node_methods!(ParseNode,
+  // The definitions in a module
+  #[getter]
+  fn members(&self) -> Vec<ParseNode> {
+    match &self.node {
+      ast::ParseNode::Module(module) => module.body.iter().cloned().map(ParseNode::from).collect(),
+      _ => Vec::new(),
+    }
+  }
+);
+node_methods!(ProgramNode,
+  #[getter]
+  fn members(&self) -> Vec<ProgramNode> {
+    match &self.node {
+      infer::ProgramNode::Module(module) => module.body.iter().cloned().map(ProgramNode::from).collect(),
+      _ => Vec::new(),
+    }
+  }
+
+  // The statement printed back as Circe source
+  fn __str__(&self) -> String {
+    self.node.to_string()
+  }
+
+  // The hex-encoded, versioned CirceHash digest of the statement
+  #[getter]
+  fn digest(&self) -> String {
+    self.node.digest().to_hex()
+  }
+);
+
+// Parses the whole source up front. Iterating yields every statement and
+// raises `ParseError` once it reaches a syntax error.
+#[pyclass(module = "cce")]
+pub struct Parser {
+  nodes: VecDeque<ast::ParseNode>,
+  error: Option<ast::ParserError>,
+}
+
+#[pymethods]
+impl Parser {
+  #[new]
+  pub fn new(source: &str) -> Self {
+    let mut parser = ast::Parser::from(source);
+    let mut nodes: VecDeque<ast::ParseNode> = VecDeque::new();
+
+    loop {
+      match parser.next() {
+        Ok(Some(node)) => nodes.push_back(node),
+        Ok(None) => return Parser { nodes, error: None },
+        Err(err) => return Parser { nodes, error: Some(err) },
+      }
+    }
+  }
+
+  fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+    slf
+  }
+
+  fn __next__(&mut self) -> PyResult<Option<ParseNode>> {
+    match self.nodes.pop_front() {
+      Some(node) => Ok(Some(node.into())),
+      None => match self.error.take() {
+        Some(err) => Err(parse_error(&err)),
+        None => Ok(None),
+      },
+    }
+  }
+}
+
+// `ParseError.args` is `(message, line, column)`; the position is None for
+// errors without a location
+fn parse_error(err: &ast::ParserError) -> PyErr {
+  let span: Option<ast::Span> = err.span();
+  ParseError::new_err((err.to_string(), span.map(|span| span.line), span.map(|span| span.column)))
+}
+
+#[pyfunction]
+pub fn parse(source: &str) -> PyResult<Vec<ParseNode>> {
+  let mut parser: Parser = Parser::new(source);
+  let mut nodes: Vec<ParseNode> = Vec::new();
+
+  while let Some(node) = parser.__next__()? {
+    nodes.push(node);
+  }
+
+  Ok(nodes)
+}
+
+// Accepts any iterable of `ParseNode`s, such as a `Parser`
+#[pyfunction]
+pub fn convert(program: &Bound<'_, PyAny>) -> PyResult<Vec<ProgramNode>> {
+  let nodes: Vec<ast::ParseNode> = program
+    .try_iter()?
+    .map(|node| Ok(node?.downcast::<ParseNode>()?.get().node.clone()))
+    .collect::<PyResult<_>>()?;
+
+  Ok(infer::convert(nodes).into_iter().map(ProgramNode::from).collect())
+}
+
+#[pymodule]
+pub fn cce(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add("ParseError", m.py().get_type::<ParseError>())?;
+  m.add_class::<Component>()?;
+  m.add_class::<Modifier>()?;
+  m.add_class::<Command>()?;
+  m.add_class::<Step>()?;
+  m.add_class::<Alternative>()?;
+  m.add_class::<ParseNode>()?;
+  m.add_class::<ProgramNode>()?;
+  m.add_class::<Parser>()?;
+  m.add_function(wrap_pyfunction!(parse, m)?)?;
+  m.add_function(wrap_pyfunction!(convert, m)?)?;
+  Ok(())
+}
@@ -0,0 +1,91 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// extension-module leaves libpython unlinked, so these only run without it
+#![cfg(not(feature = "extension-module"))]
+
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+
+// Runs a Python snippet with the module imported as `cce`
+fn run(code: &std::ffi::CStr) {
+  Python::with_gil(|py| {
+    let module = pyo3::wrap_pymodule!(cce::cce)(py);
+    let globals = PyDict::new(py);
+    globals.set_item("cce", module).unwrap();
+
+    if let Err(err) = py.run(code, Some(&globals), None) {
+      err.print(py);
+      panic!("Python snippet failed");
+    }
+  });
+}
+
+#[test]
+fn test_py_parse() {
+  run(c_str!(r#"
+nodes = cce.parse("howto greet %who:string?\n- say hello to %who\n\ngreet 'Bob' | with tone = 'warm'.")
+
+assert [node.kind for node in nodes] == ["howto", "command"]
+
+howto = nodes[0]
+assert howto.signature[1] == cce.Component("slot", "who", slot_type="string")
+assert howto.body[0].kind == "command"
+assert howto.command is None
+
+command = nodes[1].command
+assert [c.text for c in command.components] == ["greet", "Bob"]
+assert command.modifiers[0].name == "tone"
+assert repr(command.modifiers[0].args[0]) == "Component(kind='literal', text='warm', slot_type=None)"
+assert nodes == cce.parse("howto greet %who:string?\n- say hello to %who\n\ngreet 'Bob' | with tone = 'warm'.")
+"#));
+}
+
+#[test]
+fn test_py_parse_error() {
+  run(c_str!(r#"
+parser = cce.Parser("greet 'Bob'.\n'oops'.")
+assert next(parser).kind == "command"
+
+try:
+  next(parser)
+  raise AssertionError("expected a ParseError")
+except cce.ParseError as err:
+  assert err.args[1:] == (2, 1)
+"#));
+}
+
+#[test]
+fn test_py_convert() {
+  run(c_str!(r#"
+program = cce.convert(cce.Parser("whatis a greeting?\n- = say hello\n- $$wave$$\n\n"))
+
+assert len(program) == 1
+whatis = program[0]
+assert whatis.kind == "whatis"
+assert whatis.alternatives[0].canonical
+assert whatis.alternatives[1].final_sequence == "wave"
+assert cce.convert(cce.parse(str(whatis)))[0] == whatis
+assert len(whatis.digest) == 18
+"#));
+}
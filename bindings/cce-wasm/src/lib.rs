@@ -0,0 +1,165 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Browser bindings for the parser and linter. `parse` and `check` return
+// plain JS objects built from the serializable results below.
+
+use std::ops::Range;
+
+use cce_ast::{Document, ParseNode, Parser, ParserError, Span, Suggestion};
+use cce_infer_ast::{convert, ProgramNode};
+use cce_lint::{LintContext, LintRegistry, Severity};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+  pub severity: &'static str,
+  pub lint: Option<&'static str>,
+  pub code: Option<&'static str>,
+  pub message: String,
+  pub span: Option<Span>,
+  pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseResult {
+  pub nodes: Vec<ParseNode>,
+  pub errors: Vec<Diagnostic>,
+}
+
+impl From<&ParserError> for Diagnostic {
+  fn from(err: &ParserError) -> Self {
+    Diagnostic {
+      severity: "error",
+      lint: None,
+      code: Some(err.code()),
+      message: err.to_string(),
+      span: err.span(),
+      suggestions: err.suggestion().into_iter().collect(),
+    }
+  }
+}
+
+// Parses as many statements as possible, stopping at the first error
+pub fn parse_source(source: &str) -> ParseResult {
+  let mut parser = Parser::from(source);
+  let mut result = ParseResult { nodes: Vec::new(), errors: Vec::new() };
+
+  loop {
+    match parser.next() {
+      Ok(Some(node)) => result.nodes.push(node),
+      Ok(None) => break,
+      Err(err) => {
+        result.errors.push(Diagnostic::from(&err));
+        break;
+      }
+    }
+  }
+
+  result
+}
+
+pub fn check_source(source: &str) -> Vec<Diagnostic> {
+  let document: Document = match Document::parse(source.to_string()) {
+    Ok(document) => document,
+    Err(err) => return vec![Diagnostic::from(&err)],
+  };
+
+  let files: Vec<Vec<ProgramNode>> = vec![convert(document.nodes().cloned().collect())];
+
+  LintRegistry::default()
+    .run(&LintContext::new(&files))
+    .into_iter()
+    .map(|diagnostic| {
+      let range: Option<Range<usize>> = document
+        .statements()
+        .get(diagnostic.location.node)
+        .map(|statement| statement.range.clone());
+
+      Diagnostic {
+        severity: match diagnostic.severity {
+          Severity::Warning => "warning",
+          Severity::Error => "error",
+        },
+        lint: Some(diagnostic.lint),
+        code: diagnostic.code,
+        message: diagnostic.message,
+        span: range.clone().map(|range| statement_span(source, range)),
+        // Lint suggestions only name a word, so find it in the statement
+        // to give tooling a span to replace
+        suggestions: diagnostic
+          .suggestions
+          .into_iter()
+          .map(|suggestion| match (&suggestion.span, &range) {
+            (None, Some(range)) => Suggestion { span: word_span(source, range.clone(), &suggestion.original), ..suggestion },
+            _ => suggestion,
+          })
+          .collect(),
+      }
+    })
+    .collect()
+}
+
+// The statement's range, starting at its first non-blank character
+fn statement_span(source: &str, range: Range<usize>) -> Span {
+  let text: &str = &source[range.clone()];
+  let start: usize = range.start + (text.len() - text.trim_start().len());
+
+  span_at(source, start..range.end)
+}
+
+// The first occurrence of `word` in the range that is a whole word
+fn word_span(source: &str, range: Range<usize>, word: &str) -> Option<Span> {
+  let text: &str = &source[range.clone()];
+  let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+  text
+    .match_indices(word)
+    .map(|(i, _)| range.start + i)
+    .find(|&start| {
+      !source[..start].ends_with(is_word) && !source[start + word.len()..].starts_with(is_word)
+    })
+    .map(|start| span_at(source, start..start + word.len()))
+}
+
+fn span_at(source: &str, range: Range<usize>) -> Span {
+  let before: &str = &source[..range.start];
+  let line_start: usize = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+  Span {
+    start: range.start,
+    end: range.end,
+    line: before.matches('\n').count() + 1,
+    column: before[line_start..].chars().count() + 1,
+  }
+}
+
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsValue, JsValue> {
+  Ok(serde_wasm_bindgen::to_value(&parse_source(source))?)
+}
+
+#[wasm_bindgen]
+pub fn check(source: &str) -> Result<JsValue, JsValue> {
+  Ok(serde_wasm_bindgen::to_value(&check_source(source))?)
+}
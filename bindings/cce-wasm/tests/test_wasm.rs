@@ -0,0 +1,84 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::Span;
+use cce_wasm::*;
+
+
+#[test]
+fn test_wasm_parse() {
+  let result: ParseResult = parse_source("howto greet %who?\n- say hello to %who\n\ngreet 'Bob'.");
+
+  assert_eq!(result.nodes.len(), 2);
+  assert_eq!(result.errors, vec![]);
+
+  let json = serde_json::to_value(&result).unwrap();
+  assert!(json["nodes"][0]["HowToStatement"]["signature"].is_array());
+}
+
+#[test]
+fn test_wasm_parse_error() {
+  let result: ParseResult = parse_source("greet 'Bob'.\n'oops'.");
+
+  assert_eq!(result.nodes.len(), 1);
+  assert_eq!(result.errors.len(), 1);
+  assert_eq!(result.errors[0].severity, "error");
+  assert_eq!(result.errors[0].span.map(|span| (span.line, span.column)), Some((2, 1)));
+}
+
+#[test]
+fn test_wasm_check() {
+  let diagnostics: Vec<Diagnostic> = check_source("greet 'Bob'.\n\nhowto wave?\n- move arm\n\n");
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].lint, Some("unused_howto"));
+  assert_eq!(diagnostics[0].severity, "warning");
+  assert_eq!(
+    diagnostics[0].span.map(|span: Span| (span.line, span.column)),
+    Some((3, 1))
+  );
+}
+
+#[test]
+fn test_wasm_check_parse_error() {
+  let diagnostics: Vec<Diagnostic> = check_source("howto wave\n- move arm\n\n");
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].lint, None);
+}
+
+#[test]
+fn test_wasm_check_suggestions() {
+  let source: &str = "howto greet %who?\n- wave to %who\n\ngreet 'Ann'.\ngret 'Bob'.\n";
+  let diagnostics: Vec<Diagnostic> = check_source(source);
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].code, Some("CCE0305"));
+
+  let suggestion = &diagnostics[0].suggestions[0];
+  assert_eq!(suggestion.span.map(|span: Span| (span.line, span.column)), Some((5, 1)));
+  assert_eq!(suggestion.apply(source).unwrap(), "howto greet %who?\n- wave to %who\n\ngreet 'Ann'.\ngreet 'Bob'.\n");
+
+  let diagnostics: Vec<Diagnostic> = check_source("whatsi stdout?\n- the console\n");
+
+  assert_eq!(diagnostics[0].code, Some("CCE0109"));
+  assert_eq!(diagnostics[0].suggestions[0].replacement, "whatis");
+}
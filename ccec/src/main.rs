@@ -19,16 +19,31 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 */
 
 
-use std::io::prelude::*;
-use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::time::Duration;
 
-use clap::Parser as ClapParser;
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
 
-use cce_ast::{Parser, ParseNode};
-use cce_infer_ast::convert;
-use cce_infer::Deducer;
+use cce_analysis::{corpus_stats_with, CorpusStats};
+use cce_ast::{DefinitionLocation, ParseNode, WorkspaceParseResult};
+use cce_config::{ConfigError, Project};
+use cce_driver::{Output, ParseCache, Session, SessionConfig, Stage, Watcher};
+use cce_infer::{Conflict, DefGraph, SessionLog};
+use cce_infer_ast::NormalizeOptions;
+use cce_lint::Severity;
+use circelang_db::{InstalledPackage, Package, PackageError, Store};
+
+
+// Lets `--timings` report peak memory per stage, at the cost of counting
+// every allocation
+#[cfg(feature = "alloc-tracking")]
+#[global_allocator]
+static ALLOCATOR: cce_driver::TrackingAllocator = cce_driver::TrackingAllocator;
+
+// How often `check --watch` looks for changes when filesystem notifications
+// are not available
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 
 #[derive(ClapParser)]
@@ -36,45 +51,360 @@ use cce_infer::Deducer;
 #[command(about = "The Circe Compiler", long_about = None)]
 #[command(version = "0.1.0")]
 #[command(author = "Carlos Kieliszewski")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-  filename: String
+  #[command(subcommand)]
+  command: Option<Command>,
+
+  /// Files to compile, or the project in the nearest circe.toml if none
+  filenames: Vec<PathBuf>,
+
+  /// Report time, node counts and peak memory for each compiler stage
+  #[arg(long, global = true, value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "text")]
+  timings: Option<TimingsFormat>,
+
+  /// Write every decision expansion makes to a session log
+  #[arg(long)]
+  record: Option<PathBuf>,
+
+  /// Expand the program as a session log written by --record says
+  #[arg(long)]
+  replay: Option<PathBuf>,
+
+  /// Lowercase keywords, drop articles and collapse whitespace in literals
+  /// before matching commands against definitions
+  #[arg(long)]
+  normalize: bool
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TimingsFormat {
+  Text,
+  Json
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Parse and lint the given files without compiling them
+  Check {
+    filenames: Vec<PathBuf>,
+    /// Recheck whenever a file changes, searching directories for .cce files
+    #[arg(long)]
+    watch: bool
+  },
+  /// Print which definitions use which others as a Graphviz graph
+  Graph {
+    filenames: Vec<PathBuf>
+  },
+  /// Report definition counts, slot usage, vocabulary and unresolved commands
+  Stats {
+    filenames: Vec<PathBuf>
+  },
+  /// Bundle a package directory into a single file
+  Pack {
+    dir: PathBuf,
+    #[arg(short, long)]
+    output: Option<PathBuf>
+  },
+  /// Extract a package bundle into a directory
+  Unpack {
+    bundle: PathBuf,
+    dir: PathBuf
+  },
+  /// Install a package bundle or directory into the definition store
+  Install {
+    path: PathBuf
+  },
+  /// Describe a diagnostic code such as CCE0001
+  Explain {
+    code: String
+  },
+  /// Print the grammar the parser accepts as EBNF
+  Grammar {
+    /// Print railroad diagram data as JSON instead
+    #[arg(long)]
+    json: bool
+  }
 }
 
 
 fn main() {
   let cli = Cli::parse();
 
-  let path: &Path = Path::new(&cli.filename);
-  if !path.exists() {
-    println!("File not found");
+  match cli.command {
+    Some(Command::Check { filenames, watch: false }) => check(&filenames, cli.timings),
+    Some(Command::Check { filenames, watch: true }) => watch(filenames, cli.timings),
+    Some(Command::Graph { filenames }) => graph(&filenames, cli.timings),
+    Some(Command::Stats { filenames }) => stats(&filenames, cli.timings),
+    Some(Command::Pack { dir, output }) => or_exit(pack(&dir, output)),
+    Some(Command::Unpack { bundle, dir }) => or_exit(unpack(&bundle, &dir)),
+    Some(Command::Install { path }) => or_exit(install(&path)),
+    Some(Command::Explain { code }) => explain(&code),
+    Some(Command::Grammar { json: false }) => print!("{}", cce_ast::grammar()),
+    Some(Command::Grammar { json: true }) => println!("{}", cce_ast::grammar().to_json()),
+    None => compile(&cli.filenames, cli.record.as_deref(), cli.replay.as_deref(), cli.normalize, cli.timings)
   }
+}
 
-  let mut file = File::open(path).unwrap();
-  let mut contents = String::new();
-  file.read_to_string(&mut contents).unwrap();
+fn or_exit(result: Result<(), PackageError>) {
+  if let Err(err) = result {
+    println!("Error: {}", err);
+    exit(1);
+  }
+}
 
-  let contents = contents.as_str();
+fn explain(code: &str) {
+  match cce_driver::explain(code) {
+    Some(explanation) => print!("{}", explanation),
+    None => {
+      println!("Error: unknown code {}", code);
+      exit(1);
+    }
+  }
+}
 
-  let mut parser = Parser::from(contents);
-  let mut nodes: Vec<ParseNode> = Vec::new();
+fn read_package(path: &Path) -> Result<Package, PackageError> {
+  if path.is_dir() {
+    Package::read_dir(path)
+  } else {
+    Package::unpack(&std::fs::read_to_string(path)?)
+  }
+}
 
-  loop {
-    match parser.next() {
-      Ok(Some(node)) => nodes.push(node),
-      Ok(None) => break,
-      Err(err) => {
-        println!("Error: {}", err);
-        exit(1);
+fn pack(dir: &Path, output: Option<PathBuf>) -> Result<(), PackageError> {
+  let package: Package = Package::read_dir(dir)?;
+  let output: PathBuf = output.unwrap_or_else(|| PathBuf::from(package.bundle_name()));
+
+  std::fs::write(&output, package.pack())?;
+  println!("Packed {} {} into {}", package.manifest.name, package.manifest.version, output.display());
+
+  Ok(())
+}
+
+fn unpack(bundle: &Path, dir: &Path) -> Result<(), PackageError> {
+  let package: Package = read_package(bundle)?;
+
+  package.write_dir(dir)?;
+  println!("Unpacked {} {} into {}", package.manifest.name, package.manifest.version, dir.display());
+
+  Ok(())
+}
+
+fn install(path: &Path) -> Result<(), PackageError> {
+  let package: Package = read_package(path)?;
+  let store: Store = Store::open_default()?;
+
+  let installed: InstalledPackage = store.install(&package)?;
+  println!("Installed {} {} ({} definitions) into {}", installed.name, installed.version, installed.definitions.len(), store.root().display());
+
+  Ok(())
+}
+
+// Without file names, the project in the nearest circe.toml is opened and
+// its source paths are returned instead
+fn open(filenames: &[PathBuf], config: SessionConfig) -> (Session, Vec<PathBuf>) {
+  if !filenames.is_empty() {
+    return (Session::new(config), filenames.to_vec());
+  }
+
+  let project: Project = match std::env::current_dir().map_err(ConfigError::from).and_then(|dir| Project::discover(&dir)) {
+    Ok(project) => project,
+    Err(err) => {
+      println!("Error: {}", err);
+      exit(1);
+    }
+  };
+
+  let sources: Vec<PathBuf> = project.sources.clone();
+  (Session::from_project(project, config), sources)
+}
+
+// Runs the pipeline, reporting parse errors and exiting if there are any
+fn run(filenames: &[PathBuf], config: SessionConfig, timings: Option<TimingsFormat>) -> Output {
+  run_replaying(filenames, config, None, timings)
+}
+
+fn run_replaying(filenames: &[PathBuf], config: SessionConfig, replay: Option<SessionLog>, timings: Option<TimingsFormat>) -> Output {
+  let config: SessionConfig = SessionConfig { timings: timings.is_some(), ..config };
+  let (mut session, paths) = open(filenames, config);
+
+  if let Some(log) = replay {
+    session.set_replay(log);
+  }
+
+  let files: Vec<PathBuf> = match session.project().map(Project::files) {
+    Some(Ok(files)) => files,
+    Some(Err(err)) => {
+      println!("Error: {}", err);
+      exit(1);
+    }
+    None => paths,
+  };
+
+  let output: Output = run_in(&mut session, &files, timings);
+
+  if output.failed == Some(Stage::Parse) {
+    exit(1);
+  }
+
+  output
+}
+
+// Timings go to stderr so they do not mix with the output
+fn run_in(session: &mut Session, filenames: &[PathBuf], timings: Option<TimingsFormat>) -> Output {
+  let output: Output = match session.compile(filenames) {
+    Ok(output) => output,
+    Err(err) => {
+      println!("Error: {}", err);
+      exit(1);
+    }
+  };
+
+  match (timings, &output.timings) {
+    (Some(TimingsFormat::Text), Some(report)) => eprint!("{}", report),
+    (Some(TimingsFormat::Json), Some(report)) => eprintln!("{}", report.to_json()),
+    _ => {}
+  }
+
+  for file in &output.workspace.files {
+    for err in &file.errors {
+      match err.span() {
+        Some(span) => println!("Error[{}]: {}:{}:{}: {}", err.code(), file.path.display(), span.line, span.column, err),
+        None => println!("Error[{}]: {}: {}", err.code(), file.path.display(), err)
+      }
+
+      if let Some(suggestion) = err.suggestion() {
+        println!("  Help: replace '{}' with '{}'", suggestion.original, suggestion.replacement);
       }
     }
   }
 
-  let ast = convert(nodes);
-  let mut deducer = Deducer::new();
-  for node in ast {
-    deducer.add_node(node);
+  output
+}
+
+fn check(filenames: &[PathBuf], timings: Option<TimingsFormat>) {
+  let output: Output = run(filenames, SessionConfig { stop_after: Some(Stage::Lint), ..SessionConfig::default() }, timings);
+
+  if report_diagnostics(&output) {
+    exit(1);
+  }
+}
+
+// Only files whose contents changed are parsed again
+fn watch(roots: Vec<PathBuf>, timings: Option<TimingsFormat>) {
+  let (mut session, roots) = open(&roots, SessionConfig { stop_after: Some(Stage::Lint), timings: timings.is_some(), ..SessionConfig::default() });
+  session.enable_parse_cache();
+
+  let mut watcher: Watcher = Watcher::new(roots);
+
+  loop {
+    if let Err(err) = watcher.wait(POLL_INTERVAL) {
+      println!("Error: {}", err);
+      exit(1);
+    }
+
+    let output: Output = run_in(&mut session, &watcher.files(), timings);
+
+    if output.failed != Some(Stage::Parse) {
+      report_diagnostics(&output);
+    }
+
+    let cache: &ParseCache = session.parse_cache().expect("parse cache is enabled");
+    println!("Checked {} files ({} parsed), watching for changes...", cache.hits() + cache.misses(), cache.misses());
+  }
+}
+
+// Returns whether any diagnostic is an error
+fn report_diagnostics(output: &Output) -> bool {
+  let workspace: &WorkspaceParseResult = &output.workspace;
+
+  for diagnostic in &output.diagnostics {
+    let path = workspace.files[diagnostic.location.file].path.display();
+    let code: String = diagnostic.code.map(|code| format!("[{}]", code)).unwrap_or_default();
+    println!("{}{}: {}: statement {}: {} [{}]", diagnostic.severity, code, path, diagnostic.location.node + 1, diagnostic.message, diagnostic.lint);
+
+    for related in &diagnostic.related {
+      let path = workspace.files[related.file].path.display();
+      println!("  Note: see {}: statement {}", path, related.node + 1);
+    }
+
+    for suggestion in &diagnostic.suggestions {
+      println!("  Help: replace '{}' with '{}'", suggestion.original, suggestion.replacement);
+    }
+  }
+
+  output.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+fn report_conflicts(output: &Output) {
+  let workspace: &WorkspaceParseResult = &output.workspace;
+
+  for Conflict { existing, new } in &output.conflicts {
+    println!("Warning: {}: statement {}: conflicts with the definition at {}: statement {}",
+      workspace.files[new.file].path.display(), new.node + 1,
+      workspace.files[existing.file].path.display(), existing.node + 1);
+  }
+}
+
+// The graph goes to stdout so it can be piped into `dot`
+fn graph(filenames: &[PathBuf], timings: Option<TimingsFormat>) {
+  let output: Output = run(filenames, SessionConfig { stop_after: Some(Stage::Resolve), ..SessionConfig::default() }, timings);
+  let graph: DefGraph = DefGraph::build(&output.knowledge);
+
+  for cycle in graph.cycles() {
+    let sites: Vec<String> = cycle
+      .iter()
+      .map(|&i| {
+        let site: DefinitionLocation = graph.entry(i).site;
+        format!("{}: statement {}", output.workspace.files[site.file].path.display(), site.node + 1)
+      })
+      .collect();
+
+    eprintln!("Warning: definitions depend on each other: {}", sites.join(", "));
   }
 
-  let result = deducer.deduce();
-  println!("{:?}", result);
-}
\ No newline at end of file
+  print!("{}", graph.to_dot());
+}
+
+// Commands count as resolved if any visible definition matches them,
+// including those from `std` and installed packages
+fn stats(filenames: &[PathBuf], timings: Option<TimingsFormat>) {
+  let output: Output = run(filenames, SessionConfig { stop_after: Some(Stage::Resolve), ..SessionConfig::default() }, timings);
+  let nodes: Vec<ParseNode> = output.workspace.files.iter().flat_map(|file| file.nodes.iter().cloned()).collect();
+  let stats: CorpusStats = corpus_stats_with(&nodes, &output.knowledge);
+
+  print!("{}", stats);
+}
+
+fn read_log(path: &Path) -> SessionLog {
+  let log = std::fs::read_to_string(path).map_err(|err| err.to_string()).and_then(|text| SessionLog::parse(&text).map_err(|err| err.to_string()));
+
+  match log {
+    Ok(log) => log,
+    Err(err) => {
+      println!("Error: {}: {}", path.display(), err);
+      exit(1);
+    }
+  }
+}
+
+fn compile(filenames: &[PathBuf], record: Option<&Path>, replay: Option<&Path>, normalize: bool, timings: Option<TimingsFormat>) {
+  let config: SessionConfig = SessionConfig { lint: false, record_decisions: record.is_some(), normalization: normalize.then(NormalizeOptions::default), ..SessionConfig::default() };
+  let output: Output = run_replaying(filenames, config, replay.map(read_log), timings);
+
+  report_conflicts(&output);
+
+  if let Some(path) = record {
+    if let Err(err) = std::fs::write(path, output.decisions.to_string()) {
+      println!("Error: {}: {}", path.display(), err);
+      exit(1);
+    }
+  }
+
+  if let Some(err) = &output.expansion_error {
+    println!("Error[{}]: {}", err.code(), err);
+    exit(1);
+  }
+
+  println!("{:?}", output.program);
+}
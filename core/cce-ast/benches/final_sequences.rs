@@ -0,0 +1,98 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Parses a file of whatis statements with multi-kilobyte final sequences
+// and reports how many bytes the parser allocates for every byte of input.
+// Run with `cargo bench -p cce-ast --bench final_sequences`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use cce_ast::{ParseNode, Parser};
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const STATEMENTS: usize = 64;
+const SEQUENCE_LEN: usize = 8 * 1024;
+const RUNS: usize = 20;
+
+fn source() -> String {
+    let code: String = "x".repeat(SEQUENCE_LEN);
+
+    (0..STATEMENTS)
+        .map(|i| {
+            format!(
+                "whatis step{}?\n- $$rust\n{}$$\n- $${}$$\n\n",
+                i, code, code
+            )
+        })
+        .collect()
+}
+
+fn parse(source: &str) -> usize {
+    let mut parser: Parser = Parser::from(source);
+    let mut nodes: Vec<ParseNode> = Vec::new();
+
+    while let Some(node) = parser.next().unwrap() {
+        nodes.push(node);
+    }
+
+    nodes.len()
+}
+
+fn main() {
+    let source: String = source();
+    assert_eq!(parse(&source), STATEMENTS);
+
+    let before: usize = ALLOCATED.load(Ordering::Relaxed);
+    let start: Instant = Instant::now();
+
+    for _ in 0..RUNS {
+        parse(&source);
+    }
+
+    let elapsed = start.elapsed() / RUNS as u32;
+    let allocated: usize = (ALLOCATED.load(Ordering::Relaxed) - before) / RUNS;
+
+    println!(
+        "final_sequences: {} bytes of input, {} bytes allocated ({:.2} per input byte), {:.3} ms per parse",
+        source.len(),
+        allocated,
+        allocated as f64 / source.len() as f64,
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
@@ -0,0 +1,346 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Proptest strategies for random, valid ASTs. Every generated node can be
+// printed as Circe source and parsed back to an equal node, so they are
+// suitable for round-trip tests.
+//
+// Back references are never generated since the lexer has no syntax for
+// them yet.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::Union;
+
+use crate::grammar::{grammar, Expr};
+use crate::lexer::TokenKind;
+use crate::parser::*;
+
+// Words the parser treats specially when they appear as keywords
+const RESERVED: &[&str] = &[
+    "howto", "whatis", "module", "end", "when", "with", "true", "false",
+];
+
+pub fn identifier() -> impl Strategy<Value = String> {
+    "[a-zA-Z_][a-zA-Z0-9_]{0,7}"
+        .prop_filter("reserved word", |ident| !RESERVED.contains(&ident.as_str()))
+}
+
+// Other line breaks read as `\n`, so generated text never holds them
+pub fn literal() -> impl Strategy<Value = String> {
+    "[^'\r\n\u{85}\u{2028}\u{2029}]{0,12}"
+}
+
+pub fn slot_type() -> impl Strategy<Value = SlotType> {
+    prop_oneof![
+        Just(SlotType::String),
+        Just(SlotType::Number),
+        Just(SlotType::Boolean),
+        // `%name:type` is only an annotation if the type starts with a letter
+        "[a-zA-Z][a-zA-Z0-9_]{0,7}"
+            .prop_filter("builtin type", |name| {
+                !["string", "number", "boolean"].contains(&name.as_str())
+            })
+            .prop_map(SlotType::Other),
+    ]
+}
+
+pub fn command_component() -> impl Strategy<Value = CommandComponent> {
+    prop_oneof![
+        3 => identifier().prop_map(|word| CommandComponent::Keyword(word.into())),
+        1 => literal().prop_map(CommandComponent::Literal),
+        // The default lexer config has no units
+        1 => "[0-9]{1,4}(\\.[0-9]{1,3})?"
+            .prop_map(|value| CommandComponent::Number { value, unit: None }),
+        1 => any::<bool>().prop_map(CommandComponent::Boolean),
+        1 => (identifier(), proptest::option::of(slot_type()))
+            .prop_map(|(name, ty)| CommandComponent::Slot { name: name.into(), ty }),
+    ]
+}
+
+pub fn components() -> impl Strategy<Value = Vec<CommandComponent>> {
+    vec(command_component(), 1..5)
+}
+
+pub fn modifier() -> impl Strategy<Value = Modifier> {
+    prop_oneof![
+        (identifier(), components()).prop_map(|(name, args)| Modifier::Named { name, args }),
+        components().prop_map(Modifier::FreeForm),
+    ]
+}
+
+pub fn command() -> impl Strategy<Value = Command> {
+    (components(), vec(modifier(), 0..3)).prop_map(|(components, modifiers)| Command {
+        components,
+        modifiers,
+    })
+}
+
+// A command that can start a statement, which means its first component is
+// a keyword
+pub fn statement_command() -> impl Strategy<Value = Command> {
+    (identifier(), command()).prop_map(|(first, mut command)| {
+        command
+            .components
+            .insert(0, CommandComponent::Keyword(first.into()));
+        command
+    })
+}
+
+pub fn howto_command() -> impl Strategy<Value = HowToCommand> {
+    command()
+        .prop_map(HowToCommand::Command)
+        .prop_recursive(3, 16, 3, |inner| {
+            prop_oneof![
+                2 => command().prop_map(HowToCommand::Command),
+                1 => (command(), vec(inner.clone(), 1..3))
+                    .prop_map(|(head, body)| HowToCommand::Block { head, body }),
+                1 => (command(), vec(inner, 1..3))
+                    .prop_map(|(condition, body)| HowToCommand::When { condition, body }),
+            ]
+        })
+}
+
+pub fn howto_statement() -> impl Strategy<Value = HowToStatement> {
+    (components(), vec(howto_command(), 1..4))
+        .prop_map(|(signature, body)| HowToStatement { signature, body })
+}
+
+// Final sequences may contain dollars, just not at either end where they
+// would run into the fence. Untagged code stays on one line so its first line
+// is never mistaken for a language tag.
+pub fn final_sequence() -> impl Strategy<Value = FinalSequence> {
+    prop_oneof![
+        "[^$\r\n\u{85}\u{2028}\u{2029}]([^\r\n\u{85}\u{2028}\u{2029}]{0,10}[^$\r\n\u{85}\u{2028}\u{2029}])?"
+            .prop_map(|code| FinalSequence::new(None, code)),
+        (
+            "[a-z][a-z0-9+#-]{0,5}",
+            "([^\r\u{85}\u{2028}\u{2029}]{0,10}[^$\r\u{85}\u{2028}\u{2029}])?"
+        )
+            .prop_map(|(language, code)| FinalSequence::new(Some(language), code)),
+    ]
+}
+
+pub fn whatis_command() -> impl Strategy<Value = WhatIsCommand> {
+    prop_oneof![
+        3 => command().prop_map(WhatIsCommand::Command),
+        1 => final_sequence().prop_map(WhatIsCommand::Final),
+    ]
+}
+
+pub fn whatis_statement() -> impl Strategy<Value = WhatIsStatement> {
+    (
+        components(),
+        vec(whatis_command(), 1..4),
+        any::<prop::sample::Index>(),
+    )
+        .prop_map(|(signature, commands, canonical)| {
+            // At most one alternative may be canonical
+            let canonical: Option<usize> = match canonical.index(commands.len() + 1) {
+                0 => None,
+                i => Some(i - 1),
+            };
+
+            let alternatives: Vec<WhatIsAlternative> = commands
+                .into_iter()
+                .enumerate()
+                .map(|(i, command)| WhatIsAlternative {
+                    canonical: canonical == Some(i),
+                    command,
+                })
+                .collect();
+
+            WhatIsStatement {
+                signature,
+                alternatives,
+            }
+        })
+}
+
+pub fn definition() -> impl Strategy<Value = ParseNode> {
+    prop_oneof![
+        howto_statement().prop_map(ParseNode::HowToStatement),
+        whatis_statement().prop_map(ParseNode::WhatIsStatement),
+    ]
+}
+
+pub fn module_statement() -> impl Strategy<Value = ModuleStatement> {
+    (identifier(), vec(definition(), 0..3)).prop_map(|(name, body)| ModuleStatement { name, body })
+}
+
+pub fn parse_node() -> impl Strategy<Value = ParseNode> {
+    prop_oneof![
+        3 => statement_command().prop_map(ParseNode::Command),
+        3 => howto_statement().prop_map(ParseNode::HowToStatement),
+        3 => whatis_statement().prop_map(ParseNode::WhatIsStatement),
+        1 => module_statement().prop_map(ParseNode::Module),
+    ]
+}
+
+pub fn program() -> impl Strategy<Value = Vec<ParseNode>> {
+    vec(parse_node(), 0..6)
+}
+
+// Source text for the grammar rule `name`, expanded from `grammar()` rather
+// than built from the AST, so the parser can be checked against the table.
+// The table says nothing about layout, so statements are separated by a
+// blank line, each step and alternative starts a line, nested steps are
+// indented further, and slots and back references are written without
+// spaces. A command statement starts with a word, and a whatis has at most
+// one canonical alternative.
+pub fn sentence(name: &'static str) -> BoxedStrategy<String> {
+    expand_rule(name, 0, 0, " ")
+}
+
+// Rules nest at most this deep. Past it, options and repeats are left out
+// and choices take their last alternative, which in every rule is the one
+// that does not recurse.
+const MAX_DEPTH: usize = 8;
+
+fn expand(
+    expr: &'static Expr,
+    depth: usize,
+    indent: usize,
+    sep: &'static str,
+) -> BoxedStrategy<String> {
+    let limit: bool = depth >= MAX_DEPTH;
+
+    match expr {
+        Expr::Word { text, .. } if *text == "end" => Just(format!("\n{}", text)).boxed(),
+        Expr::Word { text, .. } => Just(text.to_string()).boxed(),
+        Expr::Token(kind) => terminal(*kind, indent),
+        Expr::Rule(name) => expand_rule(name, depth + 1, indent, sep),
+        Expr::Seq(items) => items
+            .iter()
+            .map(|item| expand(item, depth, indent, sep))
+            .collect::<Vec<BoxedStrategy<String>>>()
+            .prop_map(move |parts| join(parts, sep))
+            .boxed(),
+        Expr::Choice(items) if limit => expand(&items[items.len() - 1], depth, indent, sep),
+        Expr::Choice(items) => {
+            Union::new(items.iter().map(|item| expand(item, depth, indent, sep))).boxed()
+        }
+        Expr::Optional(_) | Expr::Repeat(_) if limit => Just(String::new()).boxed(),
+        Expr::Optional(item) => proptest::option::of(expand(item, depth, indent, sep))
+            .prop_map(Option::unwrap_or_default)
+            .boxed(),
+        Expr::Repeat(item) => vec(expand(item, depth, indent, sep), 0..3)
+            .prop_map(move |parts| join(parts, sep))
+            .boxed(),
+    }
+}
+
+fn expand_rule(
+    name: &'static str,
+    depth: usize,
+    indent: usize,
+    sep: &'static str,
+) -> BoxedStrategy<String> {
+    let body: &'static Expr = &grammar()
+        .rule(name)
+        .unwrap_or_else(|| panic!("unknown rule {}", name))
+        .expr;
+
+    match (name, body) {
+        ("slot" | "backref", _) => expand(body, depth, indent, ""),
+        ("steps", _) => expand(body, depth, indent + 2, sep),
+        ("statement", Expr::Choice(items)) => Union::new(items.iter().map(|item| {
+            let statement: BoxedStrategy<String> = expand(item, depth, indent, sep);
+
+            match item {
+                Expr::Rule("command") => (identifier(), statement)
+                    .prop_map(move |(word, command)| join(vec![word, command], sep))
+                    .boxed(),
+                _ => statement,
+            }
+            .prop_map(|statement| format!("\n\n{}", statement))
+            .boxed()
+        }))
+        .boxed(),
+        ("whatis", _) => expand(body, depth, indent, sep)
+            .prop_filter("more than one canonical alternative", |whatis| {
+                whatis.matches("\n- =").count() <= 1
+            })
+            .prop_map(|statement| format!("\n\n{}", statement))
+            .boxed(),
+        ("howto", _) => expand(body, depth, indent, sep)
+            .prop_map(|statement| format!("\n\n{}", statement))
+            .boxed(),
+        _ => expand(body, depth, indent, sep),
+    }
+}
+
+fn terminal(kind: TokenKind, indent: usize) -> BoxedStrategy<String> {
+    match kind {
+        TokenKind::Identifier => identifier().boxed(),
+        TokenKind::Keyword => proptest::sample::select(grammar().keywords())
+            .prop_map(String::from)
+            .boxed(),
+        TokenKind::Literal => literal().prop_map(|text| format!("'{}'", text)).boxed(),
+        TokenKind::Number => "[0-9]{1,4}(\\.[0-9]{1,3})?".boxed(),
+        TokenKind::FinalSequence => "[^$\r\n\u{85}\u{2028}\u{2029}]{1,10}"
+            .prop_filter("dollar at the end", |code| !code.ends_with('$'))
+            .prop_map(|code| format!("$${}$$", code))
+            .boxed(),
+        TokenKind::Punctuation('-') => Just(format!("\n{}-", " ".repeat(indent))).boxed(),
+        TokenKind::Punctuation(c) => Just(c.to_string()).boxed(),
+        TokenKind::Question => Just("?".into()).boxed(),
+        TokenKind::Dot => Just(".".into()).boxed(),
+        TokenKind::Colon => Just(":".into()).boxed(),
+        TokenKind::Equals => Just("=".into()).boxed(),
+        TokenKind::Percent => Just("%".into()).boxed(),
+        TokenKind::Ampersand => Just("&".into()).boxed(),
+        TokenKind::Newline => Just("\n".into()).boxed(),
+    }
+}
+
+// Puts `sep` between parts that are not already split by a line break. A
+// part starting with a line break after one only adds a blank line if it
+// starts with two.
+fn join(parts: Vec<String>, sep: &str) -> String {
+    let mut text: String = String::new();
+
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        if text.is_empty() || text.ends_with('\n') {
+            text.push_str(
+                part.strip_prefix('\n')
+                    .filter(|_| !text.is_empty())
+                    .unwrap_or(part),
+            );
+        } else {
+            if !part.starts_with('\n') {
+                text.push_str(sep);
+            }
+
+            text.push_str(part);
+        }
+    }
+
+    text
+}
+
+impl Arbitrary for ParseNode {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<ParseNode>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        parse_node().boxed()
+    }
+}
@@ -0,0 +1,482 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// A lossless syntax tree. Every byte of the source belongs to exactly one
+// token, including the whitespace the lexer skips, so printing the tree
+// gives back the original text. The tree is built from the same parser as
+// the AST, which records where each node starts and finishes.
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::intern::Symbol;
+use crate::lexer::{Span, TokenKind};
+use crate::parser::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Root,
+    Command,
+    HowTo,
+    WhatIs,
+    Module,
+    Signature,
+    Slot,
+    BackRef,
+    Modifier,
+    Step,
+    Alternative,
+    Token(TokenKind),
+    Whitespace,
+    // Source after a syntax error, kept as a single token
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub range: Range<usize>,
+    pub children: Vec<SyntaxElement>,
+}
+
+#[derive(Debug)]
+pub struct CstParseResult {
+    pub root: SyntaxNode,
+    pub error: Option<ParserError>,
+}
+
+// Recorded by the parser. Positions count the tokens consumed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Event {
+    Start(SyntaxKind, usize),
+    Finish(usize),
+}
+
+impl SyntaxElement {
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.range.clone(),
+            SyntaxElement::Token(token) => token.range.clone(),
+        }
+    }
+}
+
+impl SyntaxNode {
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // Every token below this node, in source order
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens: Vec<&SyntaxToken> = Vec::new();
+
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+
+        tokens
+    }
+
+    // The statements of a root node as AST nodes
+    pub fn lower(&self) -> Vec<ParseNode> {
+        self.nodes().filter_map(lower_statement).collect()
+    }
+
+    pub(crate) fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    pub(crate) fn child(&self, kind: SyntaxKind) -> Option<&SyntaxNode> {
+        self.nodes().find(|node| node.kind == kind)
+    }
+
+    fn has_token(&self, kind: TokenKind) -> bool {
+        self.child_tokens()
+            .any(|token| token.kind == SyntaxKind::Token(kind))
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{}", token.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn parse_cst(source: &str) -> CstParseResult {
+    let mut parser: Parser = Parser::from(source);
+    parser.lexer.log = Some(Vec::new());
+    parser.events = Some(Vec::new());
+
+    // Everything recorded for a statement that fails to parse is dropped, and
+    // its source becomes an error token instead
+    let mut committed: (usize, usize) = (0, 0);
+    let mut error: Option<ParserError> = None;
+
+    loop {
+        match parser.next() {
+            Ok(Some(_)) => {
+                committed = (
+                    parser.events.as_ref().map_or(0, Vec::len),
+                    parser.lexer.log.as_ref().map_or(0, Vec::len),
+                );
+            }
+            Ok(None) => break,
+            Err(err) => {
+                error = Some(err);
+                break;
+            }
+        }
+    }
+
+    let mut events: Vec<Event> = parser.events.take().unwrap_or_default();
+    let mut tokens: Vec<(TokenKind, Span)> = parser.lexer.log.take().unwrap_or_default();
+
+    if error.is_some() {
+        events.truncate(committed.0);
+        tokens.truncate(committed.1);
+    } else {
+        committed.1 = tokens.len();
+    }
+
+    let mut builder = Builder {
+        source,
+        tokens: &tokens,
+        next: 0,
+        offset: 0,
+        stack: vec![SyntaxNode {
+            kind: SyntaxKind::Root,
+            range: 0..source.len(),
+            children: Vec::new(),
+        }],
+    };
+
+    for event in events {
+        match event {
+            Event::Start(kind, position) => {
+                builder.tokens_until(position);
+
+                if let Some((_, span)) = builder.tokens.get(builder.next) {
+                    builder.gap(span.start);
+                }
+
+                let offset: usize = builder.offset;
+                builder.stack.push(SyntaxNode {
+                    kind,
+                    range: offset..offset,
+                    children: Vec::new(),
+                });
+            }
+            Event::Finish(position) => {
+                builder.tokens_until(position);
+
+                let mut node: SyntaxNode = builder.stack.pop().expect("unbalanced CST events");
+                node.range.end = builder.offset;
+                builder.push(SyntaxElement::Node(node));
+            }
+        }
+    }
+
+    builder.tokens_until(committed.1);
+
+    if error.is_some() && builder.offset < source.len() {
+        let offset: usize = builder.offset;
+        builder.push(SyntaxElement::Token(SyntaxToken {
+            kind: SyntaxKind::Error,
+            range: offset..source.len(),
+            text: source[offset..].to_string(),
+        }));
+        builder.offset = source.len();
+    } else {
+        builder.gap(source.len());
+    }
+
+    CstParseResult {
+        root: builder.stack.pop().expect("unbalanced CST events"),
+        error,
+    }
+}
+
+struct Builder<'a> {
+    source: &'a str,
+    tokens: &'a [(TokenKind, Span)],
+    next: usize,
+    offset: usize,
+    stack: Vec<SyntaxNode>,
+}
+
+impl<'a> Builder<'a> {
+    fn push(&mut self, element: SyntaxElement) {
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children.push(element);
+        }
+    }
+
+    // Whitespace the lexer skipped before `end`
+    fn gap(&mut self, end: usize) {
+        if self.offset < end {
+            let range: Range<usize> = self.offset..end;
+            self.push(SyntaxElement::Token(SyntaxToken {
+                kind: SyntaxKind::Whitespace,
+                text: self.source[range.clone()].to_string(),
+                range,
+            }));
+            self.offset = end;
+        }
+    }
+
+    fn tokens_until(&mut self, position: usize) {
+        while self.next < position.min(self.tokens.len()) {
+            let (kind, span) = self.tokens[self.next];
+            self.gap(span.start);
+
+            self.push(SyntaxElement::Token(SyntaxToken {
+                kind: SyntaxKind::Token(kind),
+                range: span.start..span.end,
+                text: self.source[span.start..span.end].to_string(),
+            }));
+
+            self.offset = span.end;
+            self.next += 1;
+        }
+    }
+}
+
+fn lower_statement(node: &SyntaxNode) -> Option<ParseNode> {
+    match node.kind {
+        SyntaxKind::Command => Some(ParseNode::Command(lower_command(node))),
+        SyntaxKind::HowTo => Some(ParseNode::HowToStatement(HowToStatement {
+            signature: lower_signature(node),
+            body: node
+                .nodes()
+                .filter(|node| node.kind == SyntaxKind::Step)
+                .map(lower_step)
+                .collect(),
+        })),
+        SyntaxKind::WhatIs => Some(ParseNode::WhatIsStatement(WhatIsStatement {
+            signature: lower_signature(node),
+            alternatives: node
+                .nodes()
+                .filter(|node| node.kind == SyntaxKind::Alternative)
+                .map(lower_alternative)
+                .collect(),
+        })),
+        SyntaxKind::Module => Some(ParseNode::Module(ModuleStatement {
+            name: node
+                .child_tokens()
+                .find(|token| token.kind == SyntaxKind::Token(TokenKind::Identifier))
+                .map(|token| token.text.clone())
+                .unwrap_or_default(),
+            body: node.nodes().filter_map(lower_statement).collect(),
+        })),
+        _ => None,
+    }
+}
+
+fn lower_signature(node: &SyntaxNode) -> Vec<CommandComponent> {
+    node.child(SyntaxKind::Signature)
+        .map(|signature| lower_components(&signature.children))
+        .unwrap_or_default()
+}
+
+fn lower_components(elements: &[SyntaxElement]) -> Vec<CommandComponent> {
+    components_with_ranges(elements)
+        .into_iter()
+        .map(|(component, _)| component)
+        .collect()
+}
+
+// The components among `elements`, with the source range each came from
+pub(crate) fn components_with_ranges(
+    elements: &[SyntaxElement],
+) -> Vec<(CommandComponent, Range<usize>)> {
+    elements
+        .iter()
+        .filter_map(|element| {
+            match element {
+                SyntaxElement::Token(token) => match token.kind {
+                    SyntaxKind::Token(TokenKind::Identifier) => {
+                        Some(CommandComponent::word(token.text.clone()))
+                    }
+                    SyntaxKind::Token(TokenKind::Keyword) => {
+                        Some(CommandComponent::Keyword(Symbol::intern(&token.text)))
+                    }
+                    SyntaxKind::Token(TokenKind::Number) => {
+                        // The unit, if any, is in the same token
+                        let mut words = token.text.split_whitespace();
+
+                        Some(CommandComponent::Number {
+                            value: words.next().unwrap_or_default().to_string(),
+                            unit: words.next().map(Symbol::intern),
+                        })
+                    }
+                    SyntaxKind::Token(TokenKind::Literal) => {
+                        // Quotes may be any configured char, not just one byte
+                        let mut chars = token.text.chars();
+                        chars.next();
+                        chars.next_back();
+
+                        Some(CommandComponent::Literal(chars.as_str().to_string()))
+                    }
+                    _ => None,
+                },
+                SyntaxElement::Node(node) if node.kind == SyntaxKind::Slot => {
+                    let mut names = node
+                        .child_tokens()
+                        .filter(|token| token.kind == SyntaxKind::Token(TokenKind::Identifier))
+                        .map(|token| token.text.clone());
+
+                    Some(CommandComponent::Slot {
+                        name: names.next().unwrap_or_default().into(),
+                        ty: names.next().map(SlotType::from),
+                    })
+                }
+                SyntaxElement::Node(node) if node.kind == SyntaxKind::BackRef => node
+                    .child_tokens()
+                    .find(|token| token.kind == SyntaxKind::Token(TokenKind::Identifier))
+                    .map(|token| CommandComponent::BackRef(token.text.as_str().into())),
+                SyntaxElement::Node(_) => None,
+            }
+            .map(|component| (component, element.range()))
+        })
+        .collect()
+}
+
+fn lower_command(node: &SyntaxNode) -> Command {
+    Command {
+        components: lower_components(&node.children),
+        modifiers: node
+            .nodes()
+            .filter(|node| node.kind == SyntaxKind::Modifier)
+            .map(lower_modifier)
+            .collect(),
+    }
+}
+
+fn lower_modifier(node: &SyntaxNode) -> Modifier {
+    let equals: Option<usize> = node.children.iter().position(|child| {
+        matches!(child, SyntaxElement::Token(token) if token.kind == SyntaxKind::Token(TokenKind::Equals))
+    });
+
+    match equals {
+        Some(equals) => {
+            let head: Vec<CommandComponent> = lower_components(&node.children[..equals]);
+            let name: String = match head.get(1) {
+                Some(CommandComponent::Keyword(name)) => name.to_string(),
+                _ => String::new(),
+            };
+
+            Modifier::Named {
+                name,
+                args: lower_components(&node.children[equals + 1..]),
+            }
+        }
+        None => Modifier::FreeForm(lower_components(&node.children)),
+    }
+}
+
+fn lower_step(node: &SyntaxNode) -> HowToCommand {
+    let mut command: Command = node
+        .child(SyntaxKind::Command)
+        .map(lower_command)
+        .unwrap_or(Command {
+            components: Vec::new(),
+            modifiers: Vec::new(),
+        });
+    let body: Vec<HowToCommand> = node
+        .nodes()
+        .filter(|node| node.kind == SyntaxKind::Step)
+        .map(lower_step)
+        .collect();
+
+    if body.is_empty() {
+        return HowToCommand::Command(command);
+    }
+
+    match command.components.first() {
+        Some(CommandComponent::Keyword(kw)) if kw == "when" => {
+            command.components.remove(0);
+
+            HowToCommand::When {
+                condition: command,
+                body,
+            }
+        }
+        _ => HowToCommand::Block {
+            head: command,
+            body,
+        },
+    }
+}
+
+fn lower_alternative(node: &SyntaxNode) -> WhatIsAlternative {
+    let sequence: Option<&SyntaxToken> = node
+        .child_tokens()
+        .find(|token| token.kind == SyntaxKind::Token(TokenKind::FinalSequence));
+
+    let command: WhatIsCommand = match sequence {
+        Some(token) => {
+            let fence: usize = token.text.chars().take_while(|c| *c == '$').count();
+            WhatIsCommand::Final(
+                token.text[fence..token.text.len() - fence]
+                    .to_string()
+                    .into(),
+            )
+        }
+        None => WhatIsCommand::Command(
+            node.child(SyntaxKind::Command)
+                .map(lower_command)
+                .unwrap_or(Command {
+                    components: Vec::new(),
+                    modifiers: Vec::new(),
+                }),
+        ),
+    };
+
+    WhatIsAlternative {
+        canonical: node.has_token(TokenKind::Equals),
+        command,
+    }
+}
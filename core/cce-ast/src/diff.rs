@@ -0,0 +1,196 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Compares two versions of a program definition by definition, for review
+// tooling and for updating a knowledge base without rebuilding it. Commands
+// are not definitions and are left out, and members of modules are
+// compared wherever they are nested.
+//
+// Definitions are paired up in three passes: ones that are identical, then
+// ones with the same signature but a different body, then ones of the same
+// kind whose signatures are similar or whose bodies are identical, which
+// is what a rename looks like.
+
+use crate::parser::{CommandComponent, ParseNode};
+use circelang_hash::CirceHash;
+
+// How much of a signature must stay the same for an edited definition to
+// count as modified rather than removed and added again
+const MIN_SIMILARITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AstDiff<'a> {
+    // In the order of the new program
+    pub added: Vec<&'a ParseNode>,
+    // In the order of the old program
+    pub removed: Vec<&'a ParseNode>,
+    // In the order of the new program
+    pub modified: Vec<ModifiedDefinition<'a>>,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModifiedDefinition<'a> {
+    pub old: &'a ParseNode,
+    pub new: &'a ParseNode,
+}
+
+impl ModifiedDefinition<'_> {
+    pub fn signature_changed(&self) -> bool {
+        self.old.signature() != self.new.signature()
+    }
+}
+
+impl AstDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+pub fn diff<'a>(old: &'a [ParseNode], new: &'a [ParseNode]) -> AstDiff<'a> {
+    let old: Vec<Definition> = definitions(old);
+    let new: Vec<Definition> = definitions(new);
+
+    // The old definition each new one is paired with
+    let mut pairs: Vec<Option<usize>> = vec![None; new.len()];
+    let mut paired: Vec<bool> = vec![false; old.len()];
+
+    let passes: [fn(&Definition, &Definition) -> bool; 3] = [
+        |old, new| old.hash == new.hash,
+        |old, new| old.signature_hash == new.signature_hash,
+        |old, new| {
+            old.body_hash == new.body_hash
+                || similarity(old.signature(), new.signature()) >= MIN_SIMILARITY
+        },
+    ];
+
+    for pass in passes {
+        for (i, definition) in new.iter().enumerate() {
+            if pairs[i].is_some() {
+                continue;
+            }
+
+            let candidate = old.iter().enumerate().position(|(j, other)| {
+                !paired[j] && other.is_howto == definition.is_howto && pass(other, definition)
+            });
+
+            if let Some(j) = candidate {
+                pairs[i] = Some(j);
+                paired[j] = true;
+            }
+        }
+    }
+
+    let mut diff: AstDiff = AstDiff::default();
+
+    for (definition, pair) in new.iter().zip(&pairs) {
+        match pair {
+            Some(j) if old[*j].hash == definition.hash => diff.unchanged += 1,
+            Some(j) => diff.modified.push(ModifiedDefinition {
+                old: old[*j].node,
+                new: definition.node,
+            }),
+            None => diff.added.push(definition.node),
+        }
+    }
+
+    for (definition, paired) in old.iter().zip(&paired) {
+        if !paired {
+            diff.removed.push(definition.node);
+        }
+    }
+
+    diff
+}
+
+struct Definition<'a> {
+    node: &'a ParseNode,
+    is_howto: bool,
+    hash: u64,
+    signature_hash: u64,
+    body_hash: u64,
+}
+
+impl Definition<'_> {
+    fn signature(&self) -> &[CommandComponent] {
+        self.node.signature().unwrap_or_default()
+    }
+}
+
+fn definitions(nodes: &[ParseNode]) -> Vec<Definition<'_>> {
+    nodes
+        .iter()
+        .flat_map(ParseNode::flatten)
+        .filter_map(|node| {
+            let (is_howto, signature_hash, body_hash) = match node {
+                ParseNode::HowToStatement(howto) => {
+                    (true, howto.signature.hash(), howto.body.hash())
+                }
+                ParseNode::WhatIsStatement(whatis) => {
+                    (false, whatis.signature.hash(), whatis.alternatives.hash())
+                }
+                ParseNode::Command(_) | ParseNode::Module(_) => return None,
+            };
+
+            Some(Definition {
+                node,
+                is_howto,
+                hash: node.hash(),
+                signature_hash,
+                body_hash,
+            })
+        })
+        .collect()
+}
+
+// One minus the edit distance between two signatures, counted in
+// components and scaled by the longer one. Slots are alike whatever they
+// are named.
+fn similarity(a: &[CommandComponent], b: &[CommandComponent]) -> f64 {
+    let longest: usize = a.len().max(b.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let alike = |a: &CommandComponent, b: &CommandComponent| match (a, b) {
+        (CommandComponent::Slot { .. }, CommandComponent::Slot { .. }) => true,
+        _ => a == b,
+    };
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, x) in a.iter().enumerate() {
+        let mut current: Vec<usize> = vec![i + 1];
+
+        for (j, y) in b.iter().enumerate() {
+            let cost: usize = usize::from(!alike(x, y));
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
@@ -0,0 +1,482 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// The grammar the parser accepts, as data. The parser is written by hand,
+// but takes its statement keywords and the tokens it reports as expected
+// from this table, and tests check that the two agree. Docs, fuzzers and
+// editors can use `grammar()` instead of keeping a copy of the syntax.
+//
+// Terminals are token kinds as the default `LexerConfig` lexes them. The
+// table leaves out layout: howto steps nest by indentation, and a whatis
+// ends at a blank line. Nor can it say that only a word, not any
+// component, can start a command statement, or that at most one whatis
+// alternative is canonical.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::lexer::TokenKind;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    // A token of `kind` that reads exactly `text`, like the keyword `howto`
+    // or the identifier `end`
+    Word { text: &'static str, kind: TokenKind },
+    // Any token of a kind
+    Token(TokenKind),
+    // Another rule, by name
+    Rule(&'static str),
+    Seq(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Optional(Box<Expr>),
+    // Zero or more times
+    Repeat(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: &'static str,
+    pub expr: Expr,
+}
+
+// The first rule is the whole program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    pub rules: Vec<Rule>,
+}
+
+pub fn grammar() -> &'static Grammar {
+    static GRAMMAR: OnceLock<Grammar> = OnceLock::new();
+    GRAMMAR.get_or_init(build)
+}
+
+fn keyword(text: &'static str) -> Expr {
+    Expr::Word {
+        text,
+        kind: TokenKind::Keyword,
+    }
+}
+
+fn word(text: &'static str) -> Expr {
+    Expr::Word {
+        text,
+        kind: TokenKind::Identifier,
+    }
+}
+
+fn token(kind: TokenKind) -> Expr {
+    Expr::Token(kind)
+}
+
+fn punctuation(c: char) -> Expr {
+    Expr::Token(TokenKind::Punctuation(c))
+}
+
+fn rule(name: &'static str) -> Expr {
+    Expr::Rule(name)
+}
+
+fn seq(items: Vec<Expr>) -> Expr {
+    Expr::Seq(items)
+}
+
+fn choice(items: Vec<Expr>) -> Expr {
+    Expr::Choice(items)
+}
+
+fn optional(item: Expr) -> Expr {
+    Expr::Optional(Box::new(item))
+}
+
+fn repeat(item: Expr) -> Expr {
+    Expr::Repeat(Box::new(item))
+}
+
+fn build() -> Grammar {
+    let rules: Vec<(&'static str, Expr)> = vec![
+        (
+            "program",
+            repeat(choice(vec![rule("statement"), token(TokenKind::Newline)])),
+        ),
+        (
+            "statement",
+            choice(vec![
+                rule("howto"),
+                rule("whatis"),
+                rule("module"),
+                rule("command"),
+            ]),
+        ),
+        (
+            "howto",
+            seq(vec![
+                keyword("howto"),
+                rule("signature"),
+                token(TokenKind::Question),
+                token(TokenKind::Newline),
+                rule("steps"),
+                optional(token(TokenKind::Dot)),
+            ]),
+        ),
+        (
+            "whatis",
+            seq(vec![
+                keyword("whatis"),
+                rule("signature"),
+                token(TokenKind::Question),
+                token(TokenKind::Newline),
+                rule("alternative"),
+                repeat(seq(vec![
+                    optional(token(TokenKind::Newline)),
+                    rule("alternative"),
+                ])),
+            ]),
+        ),
+        (
+            "module",
+            seq(vec![
+                keyword("module"),
+                token(TokenKind::Identifier),
+                token(TokenKind::Colon),
+                repeat(choice(vec![
+                    token(TokenKind::Newline),
+                    rule("howto"),
+                    rule("whatis"),
+                ])),
+                word("end"),
+                optional(token(TokenKind::Dot)),
+            ]),
+        ),
+        ("signature", repeat(rule("component"))),
+        (
+            "command",
+            seq(vec![
+                repeat(rule("component")),
+                repeat(choice(vec![
+                    seq(vec![punctuation('|'), rule("modifier")]),
+                    token(TokenKind::Newline),
+                ])),
+                optional(token(TokenKind::Dot)),
+            ]),
+        ),
+        (
+            "component",
+            choice(vec![
+                token(TokenKind::Identifier),
+                token(TokenKind::Keyword),
+                token(TokenKind::Literal),
+                token(TokenKind::Number),
+                rule("slot"),
+                rule("backref"),
+            ]),
+        ),
+        (
+            "slot",
+            seq(vec![
+                token(TokenKind::Percent),
+                token(TokenKind::Identifier),
+                optional(seq(vec![
+                    token(TokenKind::Colon),
+                    token(TokenKind::Identifier),
+                ])),
+            ]),
+        ),
+        (
+            "backref",
+            seq(vec![
+                token(TokenKind::Ampersand),
+                token(TokenKind::Identifier),
+            ]),
+        ),
+        (
+            "modifier",
+            choice(vec![
+                seq(vec![
+                    word("with"),
+                    token(TokenKind::Identifier),
+                    token(TokenKind::Equals),
+                    rule("component"),
+                    repeat(rule("component")),
+                ]),
+                repeat(rule("component")),
+            ]),
+        ),
+        ("steps", seq(vec![rule("step"), repeat(rule("step"))])),
+        (
+            "step",
+            seq(vec![
+                punctuation('-'),
+                choice(vec![
+                    seq(vec![word("when"), rule("command"), rule("steps")]),
+                    seq(vec![
+                        rule("command"),
+                        optional(seq(vec![
+                            optional(seq(vec![
+                                token(TokenKind::Colon),
+                                repeat(token(TokenKind::Newline)),
+                            ])),
+                            rule("steps"),
+                        ])),
+                    ]),
+                ]),
+            ]),
+        ),
+        (
+            "alternative",
+            seq(vec![
+                punctuation('-'),
+                optional(token(TokenKind::Equals)),
+                choice(vec![token(TokenKind::FinalSequence), rule("command")]),
+            ]),
+        ),
+    ];
+
+    Grammar {
+        rules: rules
+            .into_iter()
+            .map(|(name, expr)| Rule { name, expr })
+            .collect(),
+    }
+}
+
+impl Grammar {
+    pub fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.name == name)
+    }
+
+    // Words lexed as keywords, in the order the rules use them
+    pub fn keywords(&self) -> Vec<&'static str> {
+        let mut keywords: Vec<&'static str> = Vec::new();
+
+        for rule in &self.rules {
+            rule.expr.walk(&mut |expr| {
+                if let Expr::Word {
+                    text,
+                    kind: TokenKind::Keyword,
+                } = expr
+                {
+                    if !keywords.contains(text) {
+                        keywords.push(text);
+                    }
+                }
+            });
+        }
+
+        keywords
+    }
+
+    // Kinds of the tokens that can start the rule, in the order they
+    // appear in it. Empty for an unknown rule.
+    pub fn first(&self, name: &str) -> Vec<TokenKind> {
+        let mut kinds: Vec<TokenKind> = Vec::new();
+
+        if let Some(rule) = self.rule(name) {
+            self.first_of(&rule.expr, &mut kinds);
+        }
+
+        kinds
+    }
+
+    // Adds the first tokens of `expr` to `kinds`, and returns whether it
+    // can match no tokens at all
+    fn first_of(&self, expr: &Expr, kinds: &mut Vec<TokenKind>) -> bool {
+        let mut add = |kind: TokenKind| {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        };
+
+        match expr {
+            Expr::Word { kind, .. } | Expr::Token(kind) => {
+                add(*kind);
+                false
+            }
+            Expr::Rule(name) => match self.rule(name) {
+                Some(rule) => self.first_of(&rule.expr, kinds),
+                None => true,
+            },
+            Expr::Seq(items) => items.iter().all(|item| self.first_of(item, kinds)),
+            Expr::Choice(items) => {
+                // Every alternative adds its tokens, so no short-circuiting
+                let mut nullable: bool = false;
+
+                for item in items {
+                    nullable |= self.first_of(item, kinds);
+                }
+
+                nullable
+            }
+            Expr::Optional(item) | Expr::Repeat(item) => {
+                self.first_of(item, kinds);
+                true
+            }
+        }
+    }
+
+    // Input for railroad diagram renderers: every rule as a tree of
+    // `sequence`, `choice`, `optional` and `zero_or_more` nodes, with
+    // `terminal` text, `token` kinds and `rule` references at the leaves
+    pub fn to_json(&self) -> String {
+        let rules: Vec<String> = self
+            .rules
+            .iter()
+            .map(|rule| {
+                format!(
+                    "{{\"name\":\"{}\",\"diagram\":{}}}",
+                    rule.name,
+                    rule.expr.to_json()
+                )
+            })
+            .collect();
+
+        format!("{{\"rules\":[{}]}}", rules.join(","))
+    }
+}
+
+impl Expr {
+    fn walk(&self, f: &mut impl FnMut(&Expr)) {
+        f(self);
+
+        match self {
+            Expr::Seq(items) | Expr::Choice(items) => {
+                items.iter().for_each(|item| item.walk(f));
+            }
+            Expr::Optional(item) | Expr::Repeat(item) => item.walk(f),
+            Expr::Word { .. } | Expr::Token(_) | Expr::Rule(_) => {}
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let items = |items: &[Expr]| -> String {
+            items
+                .iter()
+                .map(Expr::to_json)
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+
+        match self {
+            Expr::Word { text, .. } => {
+                format!("{{\"type\":\"terminal\",\"text\":{}}}", json_string(text))
+            }
+            Expr::Token(TokenKind::Punctuation(c)) => format!(
+                "{{\"type\":\"terminal\",\"text\":{}}}",
+                json_string(&c.to_string())
+            ),
+            Expr::Token(kind) => match symbol(*kind) {
+                Some(text) => format!("{{\"type\":\"terminal\",\"text\":{}}}", json_string(text)),
+                None => format!("{{\"type\":\"token\",\"text\":\"{}\"}}", class(*kind)),
+            },
+            Expr::Rule(name) => format!("{{\"type\":\"rule\",\"text\":\"{}\"}}", name),
+            Expr::Seq(list) => format!("{{\"type\":\"sequence\",\"items\":[{}]}}", items(list)),
+            Expr::Choice(list) => format!("{{\"type\":\"choice\",\"items\":[{}]}}", items(list)),
+            Expr::Optional(item) => {
+                format!("{{\"type\":\"optional\",\"item\":{}}}", item.to_json())
+            }
+            Expr::Repeat(item) => {
+                format!("{{\"type\":\"zero_or_more\",\"item\":{}}}", item.to_json())
+            }
+        }
+    }
+}
+
+// The text of tokens that are always written the same way
+fn symbol(kind: TokenKind) -> Option<&'static str> {
+    match kind {
+        TokenKind::Question => Some("?"),
+        TokenKind::Dot => Some("."),
+        TokenKind::Percent => Some("%"),
+        TokenKind::Ampersand => Some("&"),
+        TokenKind::Colon => Some(":"),
+        TokenKind::Equals => Some("="),
+        _ => None,
+    }
+}
+
+// Names for the other kinds, as EBNF writes them
+fn class(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Identifier => "identifier",
+        TokenKind::Keyword => "keyword",
+        TokenKind::Literal => "literal",
+        TokenKind::Number => "number",
+        TokenKind::FinalSequence => "final_sequence",
+        TokenKind::Newline => "newline",
+        _ => "punctuation",
+    }
+}
+
+fn json_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// EBNF, one rule per line:
+//
+//   slot = "%" identifier [ ":" identifier ] ;
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rule in &self.rules {
+            writeln!(f, "{} = {} ;", rule.name, rule.expr)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Word { text, .. } => write!(f, "\"{}\"", text),
+            Expr::Token(TokenKind::Punctuation(c)) => write!(f, "\"{}\"", c),
+            Expr::Token(kind) => match symbol(*kind) {
+                Some(text) => write!(f, "\"{}\"", text),
+                None => write!(f, "{}", class(*kind)),
+            },
+            Expr::Rule(name) => write!(f, "{}", name),
+            Expr::Seq(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+
+                    // Alternatives inside a sequence need grouping
+                    match item {
+                        Expr::Choice(_) => write!(f, "( {} )", item)?,
+                        _ => write!(f, "{}", item)?,
+                    }
+                }
+
+                Ok(())
+            }
+            Expr::Choice(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+
+                Ok(())
+            }
+            Expr::Optional(item) => write!(f, "[ {} ]", item),
+            Expr::Repeat(item) => write!(f, "{{ {} }}", item),
+        }
+    }
+}
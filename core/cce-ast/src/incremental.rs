@@ -0,0 +1,199 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::ops::Range;
+
+use crate::lexer::{Lexer, Span};
+use crate::parser::{ParseNode, Parser, ParserError};
+use cce_stream::InputStream;
+use circelang_hash::CirceHash;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub range: Range<usize>,
+    pub hash: u64,
+    pub node: ParseNode,
+}
+
+// A parsed source file that keeps the byte range of every statement, so
+// that an edit only reparses the statements around it. Ranges tile the
+// source: each one starts where the previous one ended, and includes any
+// blank lines in front of the statement.
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    statements: Vec<Statement>,
+}
+
+impl Document {
+    pub fn parse(source: String) -> Result<Document, ParserError> {
+        let statements: Vec<Statement> = parse_statements(&source, 0, None)?.0;
+
+        Ok(Document { source, statements })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &ParseNode> {
+        self.statements.iter().map(|statement| &statement.node)
+    }
+
+    // Applies the edit and returns the indices of the statements that are
+    // new or changed. On error the document is left as it was.
+    pub fn apply(&mut self, edit: &TextEdit) -> Result<Vec<usize>, ParserError> {
+        if edit.range.start > edit.range.end
+            || edit.range.end > self.source.len()
+            || !self.source.is_char_boundary(edit.range.start)
+            || !self.source.is_char_boundary(edit.range.end)
+        {
+            return Err(ParserError::InvalidEdit {
+                range: edit.range.clone(),
+            });
+        }
+
+        let mut source: String = self.source.clone();
+        source.replace_range(edit.range.clone(), &edit.replacement);
+
+        // The statement before the edit is reparsed too, since it may have
+        // ended because of the token the edit replaced
+        let first: usize = self
+            .statements
+            .iter()
+            .position(|statement| statement.range.end >= edit.range.start)
+            .unwrap_or(self.statements.len())
+            .saturating_sub(1);
+        let start: usize = self
+            .statements
+            .get(first)
+            .map(|statement| statement.range.start)
+            .unwrap_or(0);
+
+        let resync = Resync {
+            edit,
+            statements: &self.statements[first..],
+        };
+        let (mut statements, reused) = parse_statements(&source, start, Some(resync))?;
+
+        let inserted: usize = edit.replacement.len();
+        let removed: usize = edit.range.len();
+        let changed: Vec<usize> = statements
+            .iter()
+            .enumerate()
+            .filter(|(i, statement)| {
+                self.statements.get(first + i).map(|old| old.hash) != Some(statement.hash)
+            })
+            .map(|(i, _)| first + i)
+            .collect();
+
+        if let Some(reused) = reused {
+            statements.extend(
+                self.statements[first + reused..]
+                    .iter()
+                    .map(|old| Statement {
+                        range: (old.range.start + inserted - removed)
+                            ..(old.range.end + inserted - removed),
+                        hash: old.hash,
+                        node: old.node.clone(),
+                    }),
+            );
+        }
+
+        self.statements.truncate(first);
+        self.statements.append(&mut statements);
+        self.source = source;
+
+        Ok(changed)
+    }
+}
+
+struct Resync<'d> {
+    edit: &'d TextEdit,
+    statements: &'d [Statement],
+}
+
+impl<'d> Resync<'d> {
+    // Once parsing is past the edit and lands on the end of an old
+    // statement, everything after it is unchanged text and can be reused.
+    // Returns the index of the first old statement to reuse.
+    fn check(&self, end: usize) -> Option<usize> {
+        let edit_end: usize = self.edit.range.start + self.edit.replacement.len();
+
+        if end < edit_end {
+            return None;
+        }
+
+        let old_end: usize = end - self.edit.replacement.len() + self.edit.range.len();
+
+        self.statements
+            .iter()
+            .position(|statement| statement.range.end == old_end)
+            .map(|i| i + 1)
+    }
+}
+
+fn parse_statements(
+    source: &str,
+    start: usize,
+    resync: Option<Resync>,
+) -> Result<(Vec<Statement>, Option<usize>), ParserError> {
+    let rest: &str = &source[start..];
+    // Spans of errors count from the start of the document, not of the
+    // reparsed text
+    let mut parser = Parser::new(Lexer::from(rest).starting_at(span_at(source, start)));
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut offset: usize = start;
+
+    while let Some(node) = parser.next()? {
+        let end: usize = start + parser.lexer.consumed(rest);
+
+        statements.push(Statement {
+            range: offset..end,
+            hash: node.hash(),
+            node,
+        });
+        offset = end;
+
+        if let Some(reused) = resync.as_ref().and_then(|resync| resync.check(end)) {
+            return Ok((statements, Some(reused)));
+        }
+    }
+
+    Ok((statements, None))
+}
+
+// Where `offset` falls in `source`, counting lines as the lexer does
+fn span_at(source: &str, offset: usize) -> Span {
+    let mut stream = InputStream::new(&source[..offset]);
+    stream.by_ref().for_each(drop);
+
+    Span::at(offset, stream.line, stream.column)
+}
@@ -0,0 +1,280 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+// Interned words. Every parse in the process shares one interner, so a word
+// that appears thousands of times across a workspace is only stored once,
+// and two symbols compare by id rather than by their text.
+//
+// Interned text lives until the process exits, so the interner is bounded
+// by the number of distinct words the process ever parses, not by how much
+// it parses: re-parsing the same files interns nothing new, but a
+// long-running process fed ever-new words grows with them, by the length of
+// each word plus a few dozen bytes. To keep that in check the interner stops
+// at `MAX_INTERNED` words, or the limit set with `Symbol::set_interned_limit`;
+// words first seen after that are stored in the symbols themselves and freed
+// with them, at the cost of comparing by text. `Symbol::interned` reports
+// the count.
+//
+// Only interning a new word takes the write lock. Reading a symbol's text
+// takes no lock at all, since words are stored in chunks that never move.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use circelang_hash::CirceHash;
+
+#[derive(Clone)]
+pub struct Symbol(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Interned(u32),
+    Owned(Arc<str>),
+}
+
+// Distinct words kept for the life of the process, unless changed with
+// `Symbol::set_interned_limit`
+pub const MAX_INTERNED: usize = 1 << 20;
+
+static LIMIT: AtomicUsize = AtomicUsize::new(MAX_INTERNED);
+
+// Chunk `k` holds the words with ids `2^k - 1` to `2^(k+1) - 2`, so 32
+// chunks cover every id and a full chunk is never copied to grow
+const CHUNKS: usize = 32;
+
+type Chunk = Box<[OnceLock<&'static str>]>;
+
+struct Interner {
+    ids: RwLock<HashMap<&'static str, u32>>,
+    words: [OnceLock<Chunk>; CHUNKS],
+}
+
+fn interner() -> &'static Interner {
+    static INTERNER: OnceLock<Interner> = OnceLock::new();
+    INTERNER.get_or_init(|| Interner {
+        ids: RwLock::default(),
+        words: std::array::from_fn(|_| OnceLock::new()),
+    })
+}
+
+// The chunk of the word with `id`, and its index in the chunk
+fn locate(id: u32) -> (usize, usize) {
+    let position: u64 = id as u64 + 1;
+    let chunk: u32 = u64::BITS - 1 - position.leading_zeros();
+
+    (chunk as usize, (position - (1 << chunk)) as usize)
+}
+
+impl Symbol {
+    pub fn intern(word: &str) -> Symbol {
+        let interner: &Interner = interner();
+
+        if let Some(id) = interner.ids.read().unwrap().get(word) {
+            return Symbol(Repr::Interned(*id));
+        }
+
+        let mut ids = interner.ids.write().unwrap();
+
+        // Another thread may have interned the word since the read lock was
+        // released
+        if let Some(id) = ids.get(word) {
+            return Symbol(Repr::Interned(*id));
+        }
+
+        if ids.len() >= LIMIT.load(Ordering::Relaxed) {
+            return Symbol(Repr::Owned(word.into()));
+        }
+
+        let id: u32 = ids.len() as u32;
+        let word: &'static str = Box::leak(word.into());
+        let (chunk, index) = locate(id);
+
+        interner.words[chunk]
+            .get_or_init(|| (0..1usize << chunk).map(|_| OnceLock::new()).collect())[index]
+            .get_or_init(|| word);
+        ids.insert(word, id);
+
+        Symbol(Repr::Interned(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        let id: u32 = match &self.0 {
+            Repr::Interned(id) => *id,
+            Repr::Owned(word) => return word,
+        };
+        let (chunk, index) = locate(id);
+
+        // A symbol only exists once `intern` has stored its word
+        interner().words[chunk]
+            .get()
+            .and_then(|words| words[index].get())
+            .expect("symbol without a word")
+    }
+
+    // `None` for words seen after the interner filled up
+    pub fn id(&self) -> Option<u32> {
+        match &self.0 {
+            Repr::Interned(id) => Some(*id),
+            Repr::Owned(_) => None,
+        }
+    }
+
+    // Number of distinct words interned so far
+    pub fn interned() -> usize {
+        interner().ids.read().unwrap().len()
+    }
+
+    // Words already interned stay interned. Lowering the limit below
+    // `interned` only stops the interner from growing.
+    pub fn set_interned_limit(words: usize) {
+        LIMIT.store(words, Ordering::Relaxed);
+    }
+}
+
+// A word may be interned after symbols owning it were made, if the limit
+// was raised, so those compare by text
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Interned(a), Repr::Interned(b)) => a == b,
+            _ => self.as_str() == other.as_str(),
+        }
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(self.as_str(), state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(word: &str) -> Self {
+        Symbol::intern(word)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(word: String) -> Self {
+        Symbol::intern(&word)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(word: &String) -> Self {
+        Symbol::intern(word)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+// Ordered by text, not by id, so sorting does not depend on which word
+// happened to be interned first
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other {
+            return std::cmp::Ordering::Equal;
+        }
+
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+// Hashes the text, so a symbol hashes the same as the `String` it replaced
+// and the same in every process
+impl CirceHash for Symbol {
+    fn hash(&self) -> u64 {
+        CirceHash::hash(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Symbol {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let word: std::borrow::Cow<'de, str> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(Symbol::intern(&word))
+    }
+}
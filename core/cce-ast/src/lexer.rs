@@ -18,13 +18,115 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
-use cce_stream::InputStream;
+use crate::grammar::grammar;
+use cce_stream::{Checkpoint, InputStream};
+
+#[cfg(feature = "tokio")]
+use cce_stream::AsyncInputStream;
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncRead;
+
+use std::collections::VecDeque;
+use std::fmt;
 
 use thiserror::Error;
 
 pub struct Lexer<'s> {
     pub(crate) stream: InputStream<'s>,
-    pub(crate) peeked: Option<Token>,
+    // Tokens lexed ahead of `next`, oldest first
+    pub(crate) peeked: VecDeque<Peeked>,
+    pub(crate) last_span: Span,
+    // Every token returned by `next`, recorded while building a CST
+    pub(crate) log: Option<Vec<(TokenKind, Span)>>,
+    config: LexerConfig,
+    source_len: usize,
+    // Tokens returned by `next` so far
+    pub(crate) tokens: usize,
+    pub(crate) max_tokens: Option<usize>,
+    // Whether an unterminated literal or final sequence at the end of the
+    // input is returned as is, instead of being an error
+    pub(crate) partial: bool,
+    cut_off: bool,
+}
+
+// Everything needed to rewind a lexer, so a parser can try one reading of
+// the input and go back if it does not work out
+#[derive(Debug, Clone)]
+pub struct LexerCheckpoint<'s> {
+    stream: Checkpoint<'s>,
+    peeked: VecDeque<Peeked>,
+    last_span: Span,
+    log: usize,
+    tokens: usize,
+    cut_off: bool,
+}
+
+// A token lexed by `peek_n` but not yet returned by `next`
+#[derive(Debug, Clone)]
+pub(crate) struct Peeked {
+    token: Token,
+    span: Span,
+    // Bytes of the input left before the token was lexed
+    remaining: usize,
+}
+
+// What the lexer recognizes, for experimenting with the syntax. Words in
+// `keywords` become keyword tokens, a literal can be enclosed in any of
+// the `quotes`, and each of the `punctuation` chars is its own token. A
+// number followed by one of the `units`, as in `5 seconds`, is lexed with
+// the unit as one token. The `terminator` ends a statement and is lexed as
+// `Token::Dot` whatever char it is; a `.` between digits still reads as a
+// decimal point.
+//
+// Only the lexer is configured. The parser knows what to do with the
+// keywords in `grammar()` and no others, so a new keyword is lexed as one
+// but cannot start a statement until the parser supports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexerConfig {
+    pub keywords: Vec<String>,
+    pub quotes: Vec<char>,
+    pub punctuation: Vec<char>,
+    pub units: Vec<String>,
+    pub terminator: char,
+}
+
+impl Default for LexerConfig {
+    fn default() -> Self {
+        LexerConfig {
+            keywords: grammar()
+                .keywords()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            quotes: vec!['\''],
+            punctuation: vec!['-', '|'],
+            units: Vec::new(),
+            terminator: '.',
+        }
+    }
+}
+
+// Location of a token in the source. `start` and `end` are byte offsets,
+// `line` and `column` are where the token starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    // An empty span, the position `offset` bytes into the source
+    pub(crate) fn at(offset: usize, line: usize, column: usize) -> Span {
+        Span {
+            start: offset,
+            end: offset,
+            line,
+            column,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,6 +134,8 @@ pub enum Token {
     Identifier(String),
     Keyword(String),
     Literal(String),
+    // Digits with an optional fraction, such as `42` or `3.14`
+    Number { value: String, unit: Option<String> },
     Punctuation(char),
     FinalSequence(String),
     Newline,
@@ -39,6 +143,65 @@ pub enum Token {
     Dot,
     Percent,
     Ampersand,
+    Colon,
+    Equals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier,
+    Keyword,
+    Literal,
+    Number,
+    Punctuation(char),
+    FinalSequence,
+    Newline,
+    Question,
+    Dot,
+    Percent,
+    Ampersand,
+    Colon,
+    Equals,
+}
+
+impl Token {
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Keyword(_) => TokenKind::Keyword,
+            Token::Literal(_) => TokenKind::Literal,
+            Token::Number { .. } => TokenKind::Number,
+            Token::Punctuation(c) => TokenKind::Punctuation(*c),
+            Token::FinalSequence(_) => TokenKind::FinalSequence,
+            Token::Newline => TokenKind::Newline,
+            Token::Question => TokenKind::Question,
+            Token::Dot => TokenKind::Dot,
+            Token::Percent => TokenKind::Percent,
+            Token::Ampersand => TokenKind::Ampersand,
+            Token::Colon => TokenKind::Colon,
+            Token::Equals => TokenKind::Equals,
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Identifier => write!(f, "identifier"),
+            TokenKind::Keyword => write!(f, "keyword"),
+            TokenKind::Literal => write!(f, "literal"),
+            TokenKind::Number => write!(f, "number"),
+            TokenKind::Punctuation(c) => write!(f, "'{}'", c),
+            TokenKind::FinalSequence => write!(f, "final sequence"),
+            TokenKind::Newline => write!(f, "newline"),
+            TokenKind::Question => write!(f, "'?'"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::Percent => write!(f, "'%'"),
+            TokenKind::Ampersand => write!(f, "'&'"),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Equals => write!(f, "'='"),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -49,16 +212,117 @@ pub enum LexerError {
     UnexpectedEndOfStream,
     #[error("Unexpected character: {0}")]
     UnexpectedCharacter(char),
+    #[error("Input has more than {0} tokens")]
+    TokenLimit(usize),
+}
+
+impl LexerError {
+    // Stable code for `ccec explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerError::InputStreamError(_) => "CCE0001",
+            LexerError::UnexpectedEndOfStream => "CCE0002",
+            LexerError::UnexpectedCharacter(_) => "CCE0003",
+            LexerError::TokenLimit(_) => "CCE0004",
+        }
+    }
 }
 
 impl<'s> Lexer<'s> {
-    pub fn new(stream: InputStream) -> Lexer {
+    pub fn new(stream: InputStream<'s>) -> Lexer<'s> {
+        Lexer::with_config(stream, LexerConfig::default())
+    }
+
+    pub fn with_config(stream: InputStream<'s>, config: LexerConfig) -> Lexer<'s> {
+        let source_len: usize = stream.remaining().len();
+
         Lexer {
             stream,
-            peeked: None,
+            peeked: VecDeque::new(),
+            last_span: Span::default(),
+            log: None,
+            config,
+            source_len,
+            tokens: 0,
+            max_tokens: None,
+            partial: false,
+            cut_off: false,
+        }
+    }
+
+    // Spans as if the input started at `at` in a longer one, for lexing
+    // input that arrives a piece at a time
+    pub(crate) fn starting_at(mut self, at: Span) -> Lexer<'s> {
+        self.source_len += at.start;
+        self.stream.line = at.line;
+        self.stream.column = at.column;
+        self.last_span = at;
+        self
+    }
+
+    // Accepts input that ends in the middle of a token, as while it is being
+    // typed
+    pub fn allow_partial(mut self) -> Lexer<'s> {
+        self.partial = true;
+        self
+    }
+
+    // Whether the token most recently lexed, including a peeked one, ran
+    // into the end of the input. A word there may still continue.
+    pub fn is_cut_off(&self) -> bool {
+        self.cut_off
+    }
+
+    pub fn config(&self) -> &LexerConfig {
+        &self.config
+    }
+
+    pub fn checkpoint(&self) -> LexerCheckpoint<'s> {
+        LexerCheckpoint {
+            stream: self.stream.checkpoint(),
+            peeked: self.peeked.clone(),
+            last_span: self.last_span,
+            log: self.log.as_ref().map_or(0, Vec::len),
+            tokens: self.tokens,
+            cut_off: self.cut_off,
+        }
+    }
+
+    // Rewinds to `checkpoint`, which must have been taken from this lexer.
+    // Tokens returned since are returned again.
+    pub fn restore(&mut self, checkpoint: LexerCheckpoint<'s>) {
+        self.stream.restore(checkpoint.stream);
+        self.peeked = checkpoint.peeked;
+        self.last_span = checkpoint.last_span;
+        self.tokens = checkpoint.tokens;
+        self.cut_off = checkpoint.cut_off;
+
+        if let Some(log) = &mut self.log {
+            log.truncate(checkpoint.log);
         }
     }
 
+    // Span of the token most recently returned by `next`
+    pub fn span(&self) -> Span {
+        self.last_span
+    }
+
+    // Span of the peeked token, or an empty span at the current position
+    pub fn peek_span(&mut self) -> Result<Span, LexerError> {
+        self.peek()?;
+
+        match self.peeked.front() {
+            Some(peeked) => Ok(peeked.span),
+            None => Ok(self.position()),
+        }
+    }
+
+    fn position(&self) -> Span {
+        let offset: usize = self.source_len - self.stream.remaining().len();
+
+        Span::at(offset, self.stream.line, self.stream.column)
+    }
+
     fn create_ident_or_keyword(&mut self) -> Result<Token, LexerError> {
         let mut ident = String::new();
         let mut c: Option<char> = self.stream.peek();
@@ -73,19 +337,22 @@ impl<'s> Lexer<'s> {
             }
         }
 
-        match ident.as_str() {
-            "howto" | "whatis" => Ok(Token::Keyword(ident)),
-            _ => Ok(Token::Identifier(ident)),
+        self.cut_off = c.is_none();
+
+        if self.config.keywords.contains(&ident) {
+            Ok(Token::Keyword(ident))
+        } else {
+            Ok(Token::Identifier(ident))
         }
     }
 
-    fn create_string_literal(&mut self) -> Result<Token, LexerError> {
+    fn create_string_literal(&mut self, quote: char) -> Result<Token, LexerError> {
         let mut literal: String = String::new();
         let mut c: Option<char> = self.stream.peek();
 
         loop {
             if let Some(ch) = c {
-                if ch == '\'' {
+                if ch == quote {
                     self.stream.next();
                     break;
                 } else {
@@ -93,6 +360,9 @@ impl<'s> Lexer<'s> {
                     self.stream.next();
                     c = self.stream.peek();
                 }
+            } else if self.partial {
+                self.cut_off = true;
+                break;
             } else {
                 return Err(LexerError::UnexpectedEndOfStream);
             }
@@ -101,58 +371,164 @@ impl<'s> Lexer<'s> {
         Ok(Token::Literal(literal))
     }
 
+    fn create_number(&mut self) -> Result<Token, LexerError> {
+        let mut value: String = self.digits();
+
+        // A dot only starts a fraction if a digit follows, so `wait 5.`
+        // still ends the statement
+        if self.stream.peek() == Some('.') {
+            let checkpoint: Checkpoint = self.stream.checkpoint();
+            self.stream.next();
+
+            if self.stream.peek().is_some_and(|c| c.is_ascii_digit()) {
+                value.push('.');
+                value.push_str(&self.digits());
+            } else {
+                self.stream.restore(checkpoint);
+            }
+        }
+
+        self.cut_off = self.stream.peek().is_none();
+
+        Ok(Token::Number {
+            value,
+            unit: self.unit(),
+        })
+    }
+
+    fn digits(&mut self) -> String {
+        let mut digits: String = String::new();
+
+        while let Some(c) = self.stream.peek().filter(char::is_ascii_digit) {
+            digits.push(c);
+            self.stream.next();
+        }
+
+        digits
+    }
+
+    // The word after a number, if it is one of the configured units
+    fn unit(&mut self) -> Option<String> {
+        if self.config.units.is_empty() {
+            return None;
+        }
+
+        let checkpoint: Checkpoint = self.stream.checkpoint();
+
+        while self
+            .stream
+            .peek()
+            .is_some_and(|c| c.is_whitespace() && c != '\n')
+        {
+            self.stream.next();
+        }
+
+        if self
+            .stream
+            .peek()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+        {
+            if let Ok(Token::Identifier(word)) = self.create_ident_or_keyword() {
+                if self.config.units.contains(&word) {
+                    return Some(word);
+                }
+            }
+        }
+
+        self.stream.restore(checkpoint);
+        self.cut_off = self.stream.peek().is_none();
+        None
+    }
+
     fn create_final_sequence(&mut self) -> Result<Token, LexerError> {
-        let mut sequence: String = String::new();
-        let mut c: Option<char> = self.stream.peek();
-        let mut dollars = 0;
-        let mut dollars_after = 0;
-        let mut at_start = true;
+        let mut dollars: usize = 0;
 
-        loop {
-            if let Some(ch) = c {
-                if ch == '$' && at_start {
-                    self.stream.next();
-                    dollars += 1;
-                    c = self.stream.peek();
-                } else if ch == '$' {
-                    self.stream.next();
-                    dollars_after += 1;
+        while self.stream.peek() == Some('$') {
+            self.stream.next();
+            dollars += 1;
+        }
 
-                    if dollars_after == dollars {
-                        break;
-                    } else {
-                        c = self.stream.peek();
-                    }
-                } else {
-                    at_start = false;
+        // Sequences can run to kilobytes, so the code gets a buffer big
+        // enough for all of it up front instead of growing a char at a time
+        let closing: String = "$".repeat(dollars);
+        let rest: &str = self.stream.remaining();
+        let mut sequence: String =
+            String::with_capacity(rest.find(closing.as_str()).unwrap_or(rest.len()));
+        let mut dollars_after: usize = 0;
 
-                    if dollars_after > 0 {
-                        sequence.push_str(&"$".repeat(dollars_after));
-                        dollars_after = 0;
-                    }
+        for ch in self.stream.by_ref() {
+            if ch == '$' {
+                dollars_after += 1;
 
-                    sequence.push(ch);
-                    self.stream.next();
-                    c = self.stream.peek();
+                if dollars_after == dollars {
+                    break;
                 }
+            } else {
+                sequence.push_str(&closing[..dollars_after]);
+                dollars_after = 0;
+                sequence.push(ch);
             }
         }
 
         if dollars_after == dollars {
             Ok(Token::FinalSequence(sequence))
+        } else if self.partial {
+            self.cut_off = true;
+            sequence.push_str(&closing[..dollars_after]);
+            Ok(Token::FinalSequence(sequence))
         } else {
             Err(LexerError::UnexpectedEndOfStream)
         }
     }
 
     // TODO: Move this to an iterator
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<Token>, LexerError> {
-        if self.peeked.is_some() {
-            let tok = self.peeked.clone();
-            self.peeked = None;
-            return Ok(tok);
+        let token: Option<Token> = self.advance()?;
+
+        if let (Some(log), Some(token)) = (&mut self.log, &token) {
+            log.push((token.kind(), self.last_span));
+        }
+
+        if token.is_some() {
+            self.tokens += 1;
+
+            if self.max_tokens.is_some_and(|limit| self.tokens > limit) {
+                return Err(LexerError::TokenLimit(self.tokens - 1));
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Some(token) = &token {
+            tracing::trace!(
+                kind = %token.kind(),
+                line = self.last_span.line,
+                column = self.last_span.column,
+                "token"
+            );
+        }
+
+        Ok(token)
+    }
+
+    fn advance(&mut self) -> Result<Option<Token>, LexerError> {
+        if let Some(peeked) = self.peeked.pop_front() {
+            self.last_span = peeked.span;
+            return Ok(Some(peeked.token));
         };
 
+        let token: Option<Token> = self.lex()?;
+
+        if token.is_some() {
+            self.last_span.end = self.source_len - self.stream.remaining().len();
+        }
+
+        Ok(token)
+    }
+
+    fn lex(&mut self) -> Result<Option<Token>, LexerError> {
+        self.cut_off = false;
+
         let mut c: char = match self.stream.peek() {
             Some(c) => c,
             None => return Ok(None),
@@ -166,17 +542,21 @@ impl<'s> Lexer<'s> {
             };
         }
 
+        self.last_span = self.position();
+
         match c {
-            'a'..='z' | 'A'..='Z' | '_' => Ok(Some(self.create_ident_or_keyword()?)),
-            '\'' => {
+            // Any alphabetic char starts a word, not only ASCII letters
+            c if c.is_alphabetic() || c == '_' => Ok(Some(self.create_ident_or_keyword()?)),
+            c if c.is_ascii_digit() => Ok(Some(self.create_number()?)),
+            c if self.config.quotes.contains(&c) => {
                 self.stream.next();
-                Ok(Some(self.create_string_literal()?))
+                Ok(Some(self.create_string_literal(c)?))
             }
-            '-' | '|' => {
+            c if self.config.punctuation.contains(&c) => {
                 self.stream.next();
                 Ok(Some(Token::Punctuation(c)))
             }
-            '.' => {
+            c if c == self.config.terminator => {
                 self.stream.next();
                 Ok(Some(Token::Dot))
             }
@@ -184,13 +564,25 @@ impl<'s> Lexer<'s> {
                 self.stream.next();
                 Ok(Some(Token::Percent))
             }
+            '&' => {
+                self.stream.next();
+                Ok(Some(Token::Ampersand))
+            }
             '?' => {
                 self.stream.next();
                 Ok(Some(Token::Question))
             }
+            ':' => {
+                self.stream.next();
+                Ok(Some(Token::Colon))
+            }
+            '=' => {
+                self.stream.next();
+                Ok(Some(Token::Equals))
+            }
             '$' => {
                 let final_ = self.create_final_sequence();
-                final_.map(|v| Some(v))
+                final_.map(Some)
             }
             '\n' => {
                 self.stream.next();
@@ -200,12 +592,81 @@ impl<'s> Lexer<'s> {
         }
     }
 
-    pub fn peek(&mut self) -> Result<Option<Token>, LexerError> {
-        if self.peeked.is_none() {
-            self.peeked = self.next()?;
-        };
+    // Peeked tokens are lent out rather than cloned, as a literal or final
+    // sequence may be kilobytes long and is often peeked several times
+    pub fn peek(&mut self) -> Result<Option<&Token>, LexerError> {
+        self.peek_n(0)
+    }
 
-        Ok(self.peeked.clone())
+    // Kind of the peeked token, for checks that should not hold on to it
+    pub fn peek_kind(&mut self) -> Result<Option<TokenKind>, LexerError> {
+        Ok(self.peek()?.map(Token::kind))
+    }
+
+    // The token `k` places after the next one, without consuming any.
+    // `peek_n(0)` is the same as `peek`.
+    pub fn peek_n(&mut self, k: usize) -> Result<Option<&Token>, LexerError> {
+        while self.peeked.len() <= k {
+            let last_span: Span = self.last_span;
+            let remaining: usize = self.stream.remaining().len();
+
+            let token: Option<Token> = self.lex()?;
+            let mut span: Span = self.last_span;
+            span.end = self.source_len - self.stream.remaining().len();
+            self.last_span = last_span;
+
+            match token {
+                Some(token) => self.peeked.push_back(Peeked {
+                    token,
+                    span,
+                    remaining,
+                }),
+                None => return Ok(None),
+            }
+        }
+
+        Ok(self.peeked.get(k).map(|peeked| &peeked.token))
+    }
+
+    // Bytes of the input that have been consumed, not counting peeked tokens
+    pub(crate) fn consumed(&self, source: &str) -> usize {
+        match self.peeked.front() {
+            Some(peeked) => source.len() - peeked.remaining,
+            None => source.len() - self.stream.remaining().len(),
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn next_async<R: AsyncRead + Unpin>(
+        stream: &mut AsyncInputStream<R>,
+    ) -> Result<Option<Token>, LexerError> {
+        loop {
+            let source: &str = stream.buffered();
+            let mut lexer =
+                Lexer::from(source).starting_at(Span::at(stream.pos, stream.line, stream.column));
+            let result = lexer.next();
+
+            // Words, numbers and unterminated sequences may continue in the
+            // next read
+            let incomplete: bool = !stream.is_eof()
+                && match &result {
+                    Ok(None) => true,
+                    Ok(Some(_)) => lexer.is_cut_off(),
+                    Err(LexerError::UnexpectedEndOfStream) => true,
+                    _ => false,
+                };
+
+            if incomplete {
+                stream.grow().await?;
+                continue;
+            }
+
+            let consumed: usize = lexer.consumed(source);
+            let token: Option<Token> = result?;
+            stream.consume(consumed);
+
+            return Ok(token);
+        }
     }
 }
 
@@ -18,11 +18,41 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+mod cst;
+mod diff;
+mod grammar;
+mod incremental;
+mod intern;
 mod lexer;
 mod parser;
-
-pub use lexer::{Lexer, LexerError, Token};
+mod partial;
+mod refactor;
+mod suggest;
+mod template;
+pub mod visit;
+mod workspace;
+
+pub use cst::{parse_cst, CstParseResult, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+pub use diff::{diff, AstDiff, ModifiedDefinition};
+pub use grammar::{grammar, Expr, Grammar, Rule};
+pub use incremental::{Document, Statement, TextEdit};
+pub use intern::{Symbol, MAX_INTERNED};
+pub use lexer::{Lexer, LexerCheckpoint, LexerConfig, LexerError, Span, Token, TokenKind};
 pub use parser::{
-    Command, CommandComponent, HowToStatement, ParseNode, Parser, ParserError, WhatIsCommand,
-    WhatIsStatement,
+    parse_all, Command, CommandComponent, FinalSequence, HowToCommand, HowToStatement, Modifier,
+    ModuleStatement, ParseNode, ParseOptions, Parser, ParserError, ParserLimits, SlotType,
+    WhatIsAlternative, WhatIsCommand, WhatIsStatement,
+};
+pub use partial::{parse_partial, PartialParse, PartialToken};
+pub use refactor::{rename_keyword, rename_slot, RenameError};
+pub use suggest::{closest, edit_distance, Suggestion};
+pub use template::{parse_template, render, validate_template, TemplateError, TemplatePart};
+pub use visit::{Visit, VisitMut};
+pub use workspace::{
+    parse_file, parse_file_with, parse_file_with_limits, parse_file_with_options,
+    parse_source_with, parse_source_with_limits, parse_workspace, parse_workspace_with,
+    parse_workspace_with_limits, parse_workspace_with_options, read_source, DefinitionLocation,
+    FileParseResult, WorkspaceError, WorkspaceParseResult,
 };
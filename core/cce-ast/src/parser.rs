@@ -18,163 +18,700 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
-use crate::lexer::{Lexer, LexerError, Token};
+use crate::cst::{Event, SyntaxKind};
+use crate::grammar::grammar;
+use crate::intern::Symbol;
+use crate::lexer::{Lexer, LexerCheckpoint, LexerConfig, LexerError, Span, Token, TokenKind};
+use crate::suggest::{closest, Suggestion};
+use cce_stream::{DecodeOptions, InputStream, ReadInputStream};
 use circelang_hash::CirceHash;
 
+#[cfg(feature = "tokio")]
+use cce_stream::AsyncInputStream;
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncRead;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Read;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
 use thiserror::Error;
 
 pub struct Parser<'s> {
     pub(crate) lexer: Lexer<'s>,
-    pub(crate) peeked: Option<ParseNode>,
+    // Statements parsed ahead of `next`, oldest first
+    pub(crate) peeked: VecDeque<ParseNode>,
+    // Where CST nodes start and finish, recorded while building a CST
+    pub(crate) events: Option<Vec<Event>>,
+    limits: ParserLimits,
+    // Statements and commands parsed so far
+    nodes: usize,
+    // Howto step levels the parser is inside of
+    depth: usize,
+    // Whether the parser is inside a `module`, where `end` closes it
+    in_module: bool,
+}
+
+// Bounds on what one parser will accept, so untrusted input cannot make it
+// run out of memory or stack. `None` means unlimited. Depth is bounded by
+// default, since every level of nested steps takes stack, and workspace
+// parses run on threads with small stacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserLimits {
+    pub max_tokens: Option<usize>,
+    // Statements plus the commands inside them
+    pub max_nodes: Option<usize>,
+    // Levels of nested howto steps
+    pub max_depth: Option<usize>,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_tokens: None,
+            max_nodes: None,
+            max_depth: Some(256),
+        }
+    }
+}
+
+// How the parser and lexer are set up for each statement of a stream, or
+// each file of a workspace. Limits apply to each of them separately.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub limits: ParserLimits,
+    pub lexer: LexerConfig,
+    // How workspace files are decoded. Streams are always UTF-8.
+    pub decode: DecodeOptions,
 }
 
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParseNode {
     Command(Command),
     HowToStatement(HowToStatement),
     WhatIsStatement(WhatIsStatement),
+    Module(ModuleStatement),
+}
+
+impl ParseNode {
+    // `None` for commands and modules
+    pub fn signature(&self) -> Option<&[CommandComponent]> {
+        match self {
+            ParseNode::HowToStatement(howto) => Some(&howto.signature),
+            ParseNode::WhatIsStatement(whatis) => Some(&whatis.signature),
+            ParseNode::Command(_) | ParseNode::Module(_) => None,
+        }
+    }
+
+    // The node, then the members of a module after it. Definition locations
+    // count nodes in this order, so the first node after a module is
+    // numbered past its members.
+    pub fn flatten(&self) -> Vec<&ParseNode> {
+        let mut nodes: Vec<&ParseNode> = vec![self];
+
+        if let ParseNode::Module(module) = self {
+            nodes.extend(module.body.iter().flat_map(ParseNode::flatten));
+        }
+
+        nodes
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Command {
     pub components: Vec<CommandComponent>,
-    pub modifiers: Vec<Vec<CommandComponent>>,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl Command {
+    pub fn modifier(&self, name: &str) -> Option<&Modifier> {
+        self.modifiers
+            .iter()
+            .find(|modifier| modifier.name() == Some(name))
+    }
 }
 
+// `| with <name> = <value>` is parsed as a named modifier, anything else
+// after a `|` is kept as free-form components
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Modifier {
+    Named {
+        name: String,
+        args: Vec<CommandComponent>,
+    },
+    FreeForm(Vec<CommandComponent>),
+}
+
+impl Modifier {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Modifier::Named { name, .. } => Some(name),
+            Modifier::FreeForm(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CommandComponent {
     Literal(String),
-    Keyword(String),
-    Slot(String),
-    BackRef(String),
+    Keyword(Symbol),
+    Slot { name: Symbol, ty: Option<SlotType> },
+    BackRef(Symbol),
+    // Written without quotes. The digits are kept as written, so `1.50`
+    // prints back as it was.
+    Number { value: String, unit: Option<Symbol> },
+    Boolean(bool),
+}
+
+impl CommandComponent {
+    // A word of a command: `true` and `false` are booleans, anything else
+    // a keyword
+    pub fn word(word: String) -> CommandComponent {
+        match word.as_str() {
+            "true" => CommandComponent::Boolean(true),
+            "false" => CommandComponent::Boolean(false),
+            _ => CommandComponent::Keyword(word.into()),
+        }
+    }
+}
+
+// Kind of value a slot accepts, written as `%name:type`
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SlotType {
+    String,
+    Number,
+    Other(String),
+    Boolean,
+}
+
+impl From<String> for SlotType {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "string" => SlotType::String,
+            "number" => SlotType::Number,
+            "boolean" => SlotType::Boolean,
+            _ => SlotType::Other(name),
+        }
+    }
+}
+
+impl fmt::Display for SlotType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlotType::String => write!(f, "string"),
+            SlotType::Number => write!(f, "number"),
+            SlotType::Boolean => write!(f, "boolean"),
+            SlotType::Other(name) => write!(f, "{}", name),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HowToCommand {
+    Command(Command),
+    Block {
+        head: Command,
+        body: Vec<HowToCommand>,
+    },
+    When {
+        condition: Command,
+        body: Vec<HowToCommand>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HowToStatement {
     pub signature: Vec<CommandComponent>,
-    pub body: Vec<Command>,
+    pub body: Vec<HowToCommand>,
 }
 
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WhatIsCommand {
     Command(Command),
-    Final(String),
+    Final(FinalSequence),
+}
+
+// Body of a `$$ ... $$` block. A first line holding a single word, as in
+// `$$rust\n...$$`, is taken as the language tag and is not part of the code.
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FinalSequence {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+impl FinalSequence {
+    pub fn new(language: Option<String>, code: String) -> Self {
+        FinalSequence { language, code }
+    }
+
+    pub fn is_for(&self, language: &str) -> bool {
+        self.language
+            .as_deref()
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(language))
+    }
 }
 
+// The tag is cut off the front of `raw` in place, so the code, which may be
+// long, is not copied
+impl From<String> for FinalSequence {
+    fn from(mut raw: String) -> Self {
+        if let Some(newline) = raw.find('\n') {
+            if is_language_tag(&raw[..newline]) {
+                let tag: String = raw[..newline].to_string();
+                raw.drain(..=newline);
+                return FinalSequence::new(Some(tag), raw);
+            }
+        }
+
+        FinalSequence::new(None, raw)
+    }
+}
+
+pub(crate) fn is_language_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | '#'))
+}
+
+// One '-' item of a whatis body. At most one alternative is marked as
+// canonical with a leading '='.
 #[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhatIsAlternative {
+    pub canonical: bool,
+    pub command: WhatIsCommand,
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WhatIsStatement {
     pub signature: Vec<CommandComponent>,
-    pub body: Vec<WhatIsCommand>,
+    pub alternatives: Vec<WhatIsAlternative>,
+}
+
+impl WhatIsStatement {
+    // The alternative marked canonical, or the first one if none is
+    pub fn canonical(&self) -> Option<&WhatIsAlternative> {
+        self.alternatives
+            .iter()
+            .find(|alternative| alternative.canonical)
+            .or(self.alternatives.first())
+    }
+
+    // The final sequence tagged with `language`, falling back to the first
+    // untagged one so single-target definitions keep working
+    pub fn final_for(&self, language: &str) -> Option<&FinalSequence> {
+        let finals = || {
+            self.alternatives
+                .iter()
+                .filter_map(|alternative| match &alternative.command {
+                    WhatIsCommand::Final(sequence) => Some(sequence),
+                    WhatIsCommand::Command(_) => None,
+                })
+        };
+
+        finals()
+            .find(|sequence| sequence.is_for(language))
+            .or_else(|| finals().find(|sequence| sequence.language.is_none()))
+    }
+}
+
+// `module <name>:` followed by definitions and a closing `end`. The
+// parser only lets howto and whatis statements into the body.
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleStatement {
+    pub name: String,
+    pub body: Vec<ParseNode>,
 }
 
 #[derive(Error, Debug)]
 pub enum ParserError {
     #[error("{0}")]
     LexerError(#[from] LexerError),
-    #[error("Syntax error: {0}")]
-    SyntaxError(String),
+    #[error("Syntax error: Expected {expected}, found {}", describe(.found))]
+    ExpectedToken {
+        expected: TokenKind,
+        found: Option<Token>,
+        span: Span,
+    },
+    #[error("Syntax error: Expected {}, found {}", describe_kinds(.expected), describe(.found))]
+    ExpectedOneOf {
+        expected: Vec<TokenKind>,
+        found: Option<Token>,
+        span: Span,
+    },
+    #[error("Syntax error: Final sequences are not allowed here")]
+    UnexpectedFinalSequence { span: Span },
+    #[error("Syntax error: A statement cannot start with {}", .found.kind())]
+    UnexpectedStatementStart { found: Token, span: Span },
+    #[error("Syntax error: Step is not aligned with the steps before it")]
+    InconsistentIndentation { span: Span },
+    #[error("Syntax error: Only one alternative can be marked canonical")]
+    DuplicateCanonical { span: Span },
+    #[error("Syntax error: No statement starts with '{keyword}'")]
+    UnknownStatement { keyword: String, span: Span },
+    // A command followed by `?`, whose first word is close to a keyword
+    #[error("Syntax error: '{word}' is not a keyword, did you mean '{keyword}'?")]
+    MisspelledKeyword {
+        word: String,
+        keyword: String,
+        span: Span,
+    },
+    #[error("Syntax error: Only howto and whatis statements can go in a module")]
+    UnexpectedInModule { span: Span },
+    #[error("Syntax error: Module '{name}' is missing its 'end'")]
+    UnclosedModule { name: String, span: Span },
+    #[error("Limit exceeded: More than {limit} statements and commands")]
+    NodeLimit { limit: usize, span: Span },
+    #[error("Limit exceeded: Steps nested more than {limit} levels deep")]
+    DepthLimit { limit: usize, span: Span },
+    #[error("Syntax error: The steps of a block must be indented further than its head")]
+    UnindentedBlock { span: Span },
+    // A `TextEdit` whose range is not inside the document, or splits a
+    // character
+    #[error("Invalid edit: {}..{} is not a range of the document", .range.start, .range.end)]
+    InvalidEdit { range: Range<usize> },
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+fn describe(found: &Option<Token>) -> String {
+    match found {
+        Some(token) => token.kind().to_string(),
+        None => "end of input".to_string(),
+    }
+}
+
+fn describe_kinds(kinds: &[TokenKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| kind.to_string())
+        .collect::<Vec<String>>()
+        .join(" or ")
+}
+
+impl ParserError {
+    // Stable code for `ccec explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::LexerError(err) => err.code(),
+            ParserError::ExpectedToken { .. } => "CCE0100",
+            ParserError::ExpectedOneOf { .. } => "CCE0101",
+            ParserError::UnexpectedFinalSequence { .. } => "CCE0102",
+            ParserError::UnexpectedStatementStart { .. } => "CCE0103",
+            ParserError::InconsistentIndentation { .. } => "CCE0104",
+            ParserError::DuplicateCanonical { .. } => "CCE0105",
+            ParserError::UnknownStatement { .. } => "CCE0106",
+            ParserError::MisspelledKeyword { .. } => "CCE0109",
+            ParserError::UnexpectedInModule { .. } => "CCE0110",
+            ParserError::UnclosedModule { .. } => "CCE0111",
+            ParserError::NodeLimit { .. } => "CCE0107",
+            ParserError::DepthLimit { .. } => "CCE0108",
+            ParserError::UnindentedBlock { .. } => "CCE0112",
+            ParserError::InvalidEdit { .. } => "CCE0113",
+            ParserError::InternalError(_) => "CCE0199",
+        }
+    }
+
+    // A fix for the error that tooling can apply, if there is an obvious one
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        let (word, keyword, span) = match self {
+            ParserError::MisspelledKeyword {
+                word,
+                keyword,
+                span,
+            } => (word, keyword.as_str(), span),
+            ParserError::UnknownStatement { keyword, span } => {
+                (keyword, closest(keyword, grammar().keywords())?, span)
+            }
+            _ => return None,
+        };
+
+        Some(Suggestion {
+            original: word.clone(),
+            replacement: keyword.to_string(),
+            span: Some(*span),
+        })
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParserError::ExpectedToken { span, .. }
+            | ParserError::ExpectedOneOf { span, .. }
+            | ParserError::UnexpectedFinalSequence { span }
+            | ParserError::UnexpectedStatementStart { span, .. }
+            | ParserError::InconsistentIndentation { span }
+            | ParserError::DuplicateCanonical { span }
+            | ParserError::UnknownStatement { span, .. }
+            | ParserError::MisspelledKeyword { span, .. }
+            | ParserError::UnexpectedInModule { span }
+            | ParserError::UnclosedModule { span, .. }
+            | ParserError::NodeLimit { span, .. }
+            | ParserError::DepthLimit { span, .. }
+            | ParserError::UnindentedBlock { span } => Some(*span),
+            ParserError::LexerError(_)
+            | ParserError::InvalidEdit { .. }
+            | ParserError::InternalError(_) => None,
+        }
+    }
+}
+
 impl<'s> Parser<'s> {
-    pub fn new(lexer: Lexer<'s>) -> Parser {
+    pub fn new(lexer: Lexer<'s>) -> Parser<'s> {
         Parser {
             lexer,
-            peeked: None,
+            peeked: VecDeque::new(),
+            events: None,
+            limits: ParserLimits::default(),
+            nodes: 0,
+            depth: 0,
+            in_module: false,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ParserLimits) -> Parser<'s> {
+        self.lexer.max_tokens = limits.max_tokens;
+        self.limits = limits;
+        self
+    }
+
+    fn count_node(&mut self) -> Result<(), ParserError> {
+        self.nodes += 1;
+
+        match self.limits.max_nodes {
+            Some(limit) if self.nodes > limit => Err(ParserError::NodeLimit {
+                limit,
+                span: self.lexer.peek_span()?,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn start(&mut self, kind: SyntaxKind) {
+        let position: usize = self.lexer.log.as_ref().map_or(0, Vec::len);
+
+        if let Some(events) = &mut self.events {
+            events.push(Event::Start(kind, position));
+        }
+    }
+
+    fn finish(&mut self) {
+        let position: usize = self.lexer.log.as_ref().map_or(0, Vec::len);
+
+        if let Some(events) = &mut self.events {
+            events.push(Event::Finish(position));
         }
     }
 
+    fn expected<T>(&mut self, expected: TokenKind) -> Result<T, ParserError> {
+        let found: Option<Token> = self.lexer.peek()?.cloned();
+        let span: Span = self.lexer.peek_span()?;
+
+        Err(ParserError::ExpectedToken {
+            expected,
+            found,
+            span,
+        })
+    }
+
+    fn expected_one_of<T>(&mut self, expected: &[TokenKind]) -> Result<T, ParserError> {
+        let found: Option<Token> = self.lexer.peek()?.cloned();
+        let span: Span = self.lexer.peek_span()?;
+
+        Err(ParserError::ExpectedOneOf {
+            expected: expected.to_vec(),
+            found,
+            span,
+        })
+    }
+
     fn parse_vec_command_component(&mut self) -> Result<Vec<CommandComponent>, ParserError> {
         let mut components: Vec<CommandComponent> = Vec::new();
 
-        let mut tok: Option<Token> = self.lexer.peek()?;
-
-        while let Some(token) = tok.clone() {
-            match token {
-                Token::Identifier(ident) => {
-                    components.push(CommandComponent::Keyword(ident));
+        while let Some(kind) = self.lexer.peek_kind()? {
+            match kind {
+                TokenKind::Identifier => {
+                    components.push(CommandComponent::word(self.take_text()?));
                 }
-                Token::Keyword(kw) => {
-                    components.push(CommandComponent::Keyword(kw));
+                TokenKind::Keyword => {
+                    let word: String = self.take_text()?;
+                    components.push(CommandComponent::Keyword(word.into()));
                 }
-                Token::Literal(lit) => {
-                    components.push(CommandComponent::Literal(lit));
+                TokenKind::Literal => {
+                    components.push(CommandComponent::Literal(self.take_text()?));
+                }
+                TokenKind::Number => {
+                    if let Some(Token::Number { value, unit }) = self.lexer.next()? {
+                        components.push(CommandComponent::Number {
+                            value,
+                            unit: unit.map(Symbol::from),
+                        });
+                    }
                 }
-                Token::Percent => {
+                TokenKind::Percent => {
+                    self.start(SyntaxKind::Slot);
                     self.lexer.next()?;
-                    tok = self.lexer.peek()?;
 
-                    if let Some(Token::Identifier(ident)) = tok {
-                        components.push(CommandComponent::Slot(ident));
+                    if self.lexer.peek_kind()? != Some(TokenKind::Identifier) {
+                        return self.expected(TokenKind::Identifier);
+                    }
+
+                    let name: String = self.take_text()?;
+
+                    // Only `%name:type` with no spaces is an annotation, so
+                    // a step ending in `%name:` still opens a nested block.
+                    // The colon is taken speculatively and given back if no
+                    // type follows it.
+                    let end: usize = self.lexer.span().end;
+                    let checkpoint: LexerCheckpoint = self.lexer.checkpoint();
+
+                    let ty: Option<String> = if self.lexer.peek()? == Some(&Token::Colon)
+                        && self.lexer.peek_span()?.start == end
+                    {
+                        let colon: Span = self.lexer.peek_span()?;
+                        self.lexer.next()?;
+
+                        match self.lexer.peek_kind()? {
+                            Some(TokenKind::Identifier)
+                                if self.lexer.peek_span()?.start == colon.end =>
+                            {
+                                Some(self.take_text()?)
+                            }
+                            // Partial input may end before the type is typed
+                            None if self.lexer.partial => {
+                                return self.expected(TokenKind::Identifier);
+                            }
+                            _ => None,
+                        }
                     } else {
-                        return Err(ParserError::SyntaxError("Expected identifier".to_string()));
+                        None
+                    };
+
+                    if ty.is_none() {
+                        self.lexer.restore(checkpoint);
                     }
+
+                    components.push(CommandComponent::Slot {
+                        name: name.into(),
+                        ty: ty.map(SlotType::from),
+                    });
+
+                    self.finish();
                 }
-                Token::Ampersand => {
+                TokenKind::Ampersand => {
+                    self.start(SyntaxKind::BackRef);
                     self.lexer.next()?;
-                    tok = self.lexer.peek()?;
 
-                    if let Some(Token::Identifier(ident)) = tok {
-                        components.push(CommandComponent::BackRef(ident));
-                    } else {
-                        return Err(ParserError::SyntaxError("Expected identifier".to_string()));
+                    if self.lexer.peek_kind()? != Some(TokenKind::Identifier) {
+                        return self.expected(TokenKind::Identifier);
                     }
+
+                    components.push(CommandComponent::BackRef(self.take_text()?.into()));
+                    self.finish();
                 }
-                Token::Punctuation(_) => {
-                    break;
-                }
-                Token::FinalSequence(_) => {
-                    return Err(ParserError::SyntaxError(
-                        "Final sequences are not allowed here".to_string(),
-                    ));
-                }
-                Token::Newline => {
-                    break;
-                }
-                Token::Question => {
-                    break;
+                TokenKind::FinalSequence => {
+                    return Err(ParserError::UnexpectedFinalSequence {
+                        span: self.lexer.peek_span()?,
+                    });
                 }
-                Token::Dot => {
+                TokenKind::Punctuation(_)
+                | TokenKind::Newline
+                | TokenKind::Question
+                | TokenKind::Dot
+                | TokenKind::Colon
+                | TokenKind::Equals => {
                     break;
                 }
             }
-
-            self.lexer.next()?;
-            tok = self.lexer.peek()?;
         }
 
         Ok(components)
     }
 
-    fn parse_command(&mut self) -> Result<Command, ParserError> {
+    // Takes the peeked token and moves its text out, instead of cloning it
+    fn take_text(&mut self) -> Result<String, ParserError> {
+        match self.lexer.next()? {
+            Some(
+                Token::Identifier(text)
+                | Token::Keyword(text)
+                | Token::Literal(text)
+                | Token::FinalSequence(text),
+            ) => Ok(text),
+            found => Err(ParserError::InternalError(format!(
+                "expected a token with text, found {:?}",
+                found
+            ))),
+        }
+    }
+
+    fn parse_modifier(&mut self) -> Result<Modifier, ParserError> {
         let components: Vec<CommandComponent> = self.parse_vec_command_component()?;
-        let mut modifiers: Vec<Vec<CommandComponent>> = Vec::new();
 
-        let mut tok: Option<Token> = self.lexer.peek()?;
+        if self.lexer.peek()? != Some(&Token::Equals) {
+            return Ok(Modifier::FreeForm(components));
+        }
+
+        match components.as_slice() {
+            [CommandComponent::Keyword(with), CommandComponent::Keyword(name)]
+                if with == "with" =>
+            {
+                let name: String = name.to_string();
 
-        while let Some(token) = tok.clone() {
-            match token {
-                Token::Punctuation(punc) => match punc {
+                self.lexer.next()?;
+                let args: Vec<CommandComponent> = self.parse_vec_command_component()?;
+
+                if args.is_empty() {
+                    return self.expected_one_of(&grammar().first("component"));
+                }
+
+                Ok(Modifier::Named { name, args })
+            }
+            _ => Ok(Modifier::FreeForm(components)),
+        }
+    }
+
+    fn parse_command(&mut self) -> Result<Command, ParserError> {
+        self.count_node()?;
+        self.start(SyntaxKind::Command);
+        let components: Vec<CommandComponent> = self.parse_vec_command_component()?;
+        let mut modifiers: Vec<Modifier> = Vec::new();
+
+        while let Some(kind) = self.lexer.peek_kind()? {
+            match kind {
+                TokenKind::Punctuation(punc) => match punc {
                     '|' => {
+                        self.start(SyntaxKind::Modifier);
                         self.lexer.next()?;
-                        modifiers.push(self.parse_vec_command_component()?);
-                        tok = self.lexer.peek()?;
+                        modifiers.push(self.parse_modifier()?);
+                        self.finish();
                     }
                     '-' => {
                         break;
                     }
                     _ => {
-                        return Err(ParserError::SyntaxError("Expected '|'".to_string()));
+                        return self.expected(TokenKind::Punctuation('|'));
                     }
                 },
-                Token::Dot => {
+                TokenKind::Dot => {
                     self.lexer.next()?;
                     break;
                 }
-                Token::Newline => {
+                TokenKind::Newline => {
                     self.lexer.next()?;
-                    tok = self.lexer.peek()?;
                 }
                 _ => {
                     break;
@@ -182,6 +719,8 @@ impl<'s> Parser<'s> {
             }
         }
 
+        self.finish();
+
         Ok(Command {
             components,
             modifiers,
@@ -189,115 +728,235 @@ impl<'s> Parser<'s> {
     }
 
     fn parse_whatis_command(&mut self) -> Result<WhatIsCommand, ParserError> {
-        let tok: Option<Token> = self.lexer.peek()?;
-
-        match tok {
-            Some(Token::FinalSequence(seq)) => {
-                self.lexer.next()?;
-
-                Ok(WhatIsCommand::Final(seq))
-            }
-            _ => Ok(WhatIsCommand::Command(self.parse_command()?)),
+        if self.lexer.peek_kind()? == Some(TokenKind::FinalSequence) {
+            return Ok(WhatIsCommand::Final(self.take_text()?.into()));
         }
+
+        Ok(WhatIsCommand::Command(self.parse_command()?))
     }
 
     fn parse_howto_statement(&mut self) -> Result<HowToStatement, ParserError> {
+        self.start(SyntaxKind::Signature);
         let signature: Vec<CommandComponent> = self.parse_vec_command_component()?;
+        self.finish();
 
-        if self.lexer.peek()? != Some(Token::Question) {
-            return Err(ParserError::SyntaxError("Expected '?'".to_string()));
+        if self.lexer.peek()? != Some(&Token::Question) {
+            return self.expected(TokenKind::Question);
         }
 
         self.lexer.next()?;
 
-        if self.lexer.peek()? != Some(Token::Newline) {
-            return Err(ParserError::SyntaxError("Expected newline".to_string()));
+        if self.lexer.peek()? != Some(&Token::Newline) {
+            return self.expected(TokenKind::Newline);
         }
 
         self.lexer.next()?;
 
-        let mut body: Vec<Command> = Vec::new();
+        if self.lexer.peek()? != Some(&Token::Punctuation('-')) {
+            return self.expected(TokenKind::Punctuation('-'));
+        }
 
-        let mut tok: Option<Token> = self.lexer.peek()?;
+        let column: usize = self.lexer.peek_span()?.column;
+        let body: Vec<HowToCommand> = self.parse_howto_steps(column)?;
 
-        match tok {
-            Some(Token::Punctuation(punc)) => {
-                if punc == '-' {
-                    self.lexer.next()?;
-                } else {
-                    return Err(ParserError::SyntaxError("Expected '-'".to_string()));
-                }
+        match self.lexer.peek()? {
+            Some(Token::Dot) => {
+                self.lexer.next()?;
             }
-            _ => {
-                return Err(ParserError::SyntaxError("Expected '-'".to_string()));
+            Some(Token::Punctuation('-')) => {
+                return Err(ParserError::InconsistentIndentation {
+                    span: self.lexer.peek_span()?,
+                });
             }
-        };
+            Some(Token::Punctuation(_)) => {
+                return self.expected_one_of(&[TokenKind::Punctuation('-'), TokenKind::Dot]);
+            }
+            _ => {}
+        }
+
+        Ok(HowToStatement { signature, body })
+    }
+
+    // Whether the peeked token is the first one on its line
+    fn peek_starts_line(&mut self) -> Result<bool, ParserError> {
+        Ok(self.lexer.peek_span()?.line > self.lexer.span().line)
+    }
+
+    // Parses the '-' steps of one level of a howto body, all starting at
+    // `column`. Steps on a following line that are indented further belong
+    // to the step above them; the level ends at a step indented less.
+    fn parse_howto_steps(&mut self, column: usize) -> Result<Vec<HowToCommand>, ParserError> {
+        self.depth += 1;
+
+        if let Some(limit) = self.limits.max_depth.filter(|limit| self.depth > *limit) {
+            return Err(ParserError::DepthLimit {
+                limit,
+                span: self.lexer.peek_span()?,
+            });
+        }
+
+        let mut steps: Vec<HowToCommand> = Vec::new();
 
         loop {
-            let cmd: Command = self.parse_command()?;
-            body.push(cmd);
+            self.start(SyntaxKind::Step);
+            self.lexer.next()?;
+            steps.push(self.parse_howto_step(column)?);
+            self.finish();
 
-            tok = self.lexer.peek()?;
-            match tok {
-                Some(Token::Punctuation('-')) => {
-                    self.lexer.next()?;
-                }
-                Some(Token::Dot) => {
-                    self.lexer.next()?;
-                    break;
-                }
-                Some(Token::Punctuation(_)) => {
-                    return Err(ParserError::SyntaxError("Expected '-' or '.'".to_string()));
-                }
-                _ => break,
+            if self.lexer.peek()? != Some(&Token::Punctuation('-')) {
+                break;
+            }
+
+            let next: Span = self.lexer.peek_span()?;
+
+            if self.peek_starts_line()? && next.column != column {
+                break;
             }
         }
 
-        Ok(HowToStatement { signature, body })
+        self.depth -= 1;
+        Ok(steps)
+    }
+
+    fn parse_howto_step(&mut self, column: usize) -> Result<HowToCommand, ParserError> {
+        let command: Command = self.parse_command()?;
+
+        // A command ended by `.` leaves the line break after it, which is
+        // skipped when another step follows, as between whatis alternatives
+        let mut breaks: usize = 0;
+
+        while self.lexer.peek_n(breaks)? == Some(&Token::Newline) {
+            breaks += 1;
+        }
+
+        if breaks > 0 && self.lexer.peek_n(breaks)? == Some(&Token::Punctuation('-')) {
+            for _ in 0..breaks {
+                self.lexer.next()?;
+            }
+        }
+
+        let colon: bool = self.lexer.peek()? == Some(&Token::Colon);
+
+        if colon {
+            self.lexer.next()?;
+
+            while self.lexer.peek()? == Some(&Token::Newline) {
+                self.lexer.next()?;
+            }
+        }
+
+        let nested: bool = self.lexer.peek()? == Some(&Token::Punctuation('-'))
+            && self.peek_starts_line()?
+            && self.lexer.peek_span()?.column > column;
+
+        let body: Vec<HowToCommand> = if nested {
+            let column: usize = self.lexer.peek_span()?.column;
+            self.parse_howto_steps(column)?
+        } else {
+            Vec::new()
+        };
+
+        let is_when: bool = matches!(
+            command.components.first(),
+            Some(CommandComponent::Keyword(kw)) if kw == "when"
+        );
+
+        // A `when` without steps is an ordinary command that starts with the
+        // word, but a `:` promises steps
+        if colon && body.is_empty() {
+            let unindented: bool =
+                self.lexer.peek()? == Some(&Token::Punctuation('-')) && self.peek_starts_line()?;
+
+            if unindented {
+                return Err(ParserError::UnindentedBlock {
+                    span: self.lexer.peek_span()?,
+                });
+            }
+
+            return self.expected(TokenKind::Punctuation('-'));
+        }
+
+        if is_when && !body.is_empty() {
+            let Command {
+                mut components,
+                modifiers,
+            } = command;
+            components.remove(0);
+
+            Ok(HowToCommand::When {
+                condition: Command {
+                    components,
+                    modifiers,
+                },
+                body,
+            })
+        } else if !body.is_empty() {
+            Ok(HowToCommand::Block {
+                head: command,
+                body,
+            })
+        } else {
+            Ok(HowToCommand::Command(command))
+        }
     }
 
     fn parse_whatis_statement(&mut self) -> Result<WhatIsStatement, ParserError> {
+        self.start(SyntaxKind::Signature);
         let signature: Vec<CommandComponent> = self.parse_vec_command_component()?;
+        self.finish();
 
-        if self.lexer.peek()? != Some(Token::Question) {
-            return Err(ParserError::SyntaxError("Expected '?'".to_string()));
+        if self.lexer.peek()? != Some(&Token::Question) {
+            return self.expected(TokenKind::Question);
         }
 
         self.lexer.next()?;
 
-        if self.lexer.peek()? != Some(Token::Newline) {
-            return Err(ParserError::SyntaxError("Expected newline".to_string()));
+        if self.lexer.peek()? != Some(&Token::Newline) {
+            return self.expected(TokenKind::Newline);
         }
 
         self.lexer.next()?;
 
-        let mut body: Vec<WhatIsCommand> = Vec::new();
+        let mut alternatives: Vec<WhatIsAlternative> = Vec::new();
 
-        let mut tok: Option<Token> = self.lexer.peek()?;
+        if self.lexer.peek_kind()? != Some(TokenKind::Punctuation('-')) {
+            return self.expected(TokenKind::Punctuation('-'));
+        }
 
-        match tok {
-            Some(Token::Punctuation(punc)) => {
-                if punc == '-' {
-                    self.lexer.next()?;
-                } else {
-                    return Err(ParserError::SyntaxError("Expected '-'".to_string()));
+        self.start(SyntaxKind::Alternative);
+        self.lexer.next()?;
+
+        loop {
+            let canonical: bool = self.lexer.peek()? == Some(&Token::Equals);
+
+            if canonical {
+                if alternatives.iter().any(|alternative| alternative.canonical) {
+                    return Err(ParserError::DuplicateCanonical {
+                        span: self.lexer.peek_span()?,
+                    });
                 }
+
+                self.lexer.next()?;
             }
-            _ => {
-                return Err(ParserError::SyntaxError("Expected '-'".to_string()));
-            }
-        };
 
-        loop {
-            let cmd: WhatIsCommand = self.parse_whatis_command()?;
-            body.push(cmd);
+            let command: WhatIsCommand = self.parse_whatis_command()?;
+            alternatives.push(WhatIsAlternative { canonical, command });
+            self.finish();
 
-            tok = self.lexer.peek()?;
-            match tok {
-                Some(Token::Punctuation('-')) => {
+            match self.lexer.peek_kind()? {
+                Some(TokenKind::Punctuation('-')) => {
+                    self.start(SyntaxKind::Alternative);
                     self.lexer.next()?;
                 }
-                Some(Token::Newline) => {
+                // A `-` on the next line is another alternative
+                Some(TokenKind::Newline)
+                    if self.lexer.peek_n(1)? == Some(&Token::Punctuation('-')) =>
+                {
+                    self.lexer.next()?;
+                    self.start(SyntaxKind::Alternative);
+                    self.lexer.next()?;
+                }
+                Some(TokenKind::Newline) => {
                     self.lexer.next()?;
 
                     match self.lexer.peek()? {
@@ -305,80 +964,357 @@ impl<'s> Parser<'s> {
                             self.lexer.next()?;
                             break;
                         }
-                        Some(Token::Punctuation('-')) => {
-                            self.lexer.next()?;
-                        }
                         None => {
                             break;
                         }
+                        Some(Token::Identifier(word)) if self.in_module && word == "end" => {
+                            break;
+                        }
                         _ => {
-                            return Err(ParserError::SyntaxError(
-                                "Expected newline or '-'".to_string(),
-                            ));
+                            return self.expected_one_of(&[
+                                TokenKind::Newline,
+                                TokenKind::Punctuation('-'),
+                            ]);
                         }
                     }
                 }
-                Some(Token::Punctuation(_)) => {
-                    return Err(ParserError::SyntaxError("Expected '-'".to_string()));
+                Some(TokenKind::Punctuation(_)) => {
+                    return self.expected(TokenKind::Punctuation('-'));
                 }
                 _ => break,
             }
         }
 
-        Ok(WhatIsStatement { signature, body })
+        Ok(WhatIsStatement {
+            signature,
+            alternatives,
+        })
+    }
+
+    fn parse_module_statement(&mut self) -> Result<ModuleStatement, ParserError> {
+        if self.lexer.peek_kind()? != Some(TokenKind::Identifier) {
+            return self.expected(TokenKind::Identifier);
+        }
+
+        let name: String = self.take_text()?;
+
+        if self.lexer.peek()? != Some(&Token::Colon) {
+            return self.expected(TokenKind::Colon);
+        }
+
+        self.lexer.next()?;
+
+        let mut body: Vec<ParseNode> = Vec::new();
+
+        loop {
+            match self.lexer.peek()? {
+                Some(Token::Newline) => {
+                    self.lexer.next()?;
+                }
+                Some(Token::Identifier(word)) if word == "end" => {
+                    self.lexer.next()?;
+
+                    if self.lexer.peek()? == Some(&Token::Dot) {
+                        self.lexer.next()?;
+                    }
+
+                    break;
+                }
+                Some(Token::Keyword(kw)) if kw == "howto" || kw == "whatis" => {
+                    // Not `next`, which would hand back statements peeked
+                    // before the module
+                    self.in_module = true;
+                    let node: Option<ParseNode> = self.parse_statement()?;
+                    self.in_module = false;
+
+                    body.extend(node);
+                }
+                None => {
+                    return Err(ParserError::UnclosedModule {
+                        name,
+                        span: self.lexer.peek_span()?,
+                    });
+                }
+                Some(_) => {
+                    return Err(ParserError::UnexpectedInModule {
+                        span: self.lexer.peek_span()?,
+                    });
+                }
+            }
+        }
+
+        Ok(ModuleStatement { name, body })
+    }
+
+    fn trace_end(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(tokens = self.lexer.tokens, "end of input");
     }
 
     // TODO: Move this to an iterator
+    #[allow(clippy::should_implement_trait)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", name = "statement", skip_all, err)
+    )]
     pub fn next(&mut self) -> Result<Option<ParseNode>, ParserError> {
-        if self.peeked.is_some() {
-            let peeked: Option<ParseNode> = self.peeked.clone();
-            self.peeked = None;
-            return Ok(peeked);
+        if let Some(peeked) = self.peeked.pop_front() {
+            return Ok(Some(peeked));
         }
 
-        let mut token: Token = match self.lexer.peek()? {
-            Some(tok) => tok,
+        self.parse_statement()
+    }
+
+    fn parse_statement(&mut self) -> Result<Option<ParseNode>, ParserError> {
+        while self.lexer.peek_kind()? == Some(TokenKind::Newline) {
+            self.lexer.next()?;
+        }
+
+        // Statements start with a keyword or a word, so this is cheap
+        let token: Token = match self.lexer.peek()? {
+            Some(tok) => tok.clone(),
             None => {
+                self.trace_end();
                 return Ok(None);
             }
         };
 
-        while token == Token::Newline {
-            self.lexer.next()?;
-            token = match self.lexer.peek()? {
-                Some(tok) => tok,
-                None => {
-                    return Ok(None);
-                }
-            };
-        }
-
         match token {
             Token::Keyword(kw) => match kw.as_str() {
                 "howto" => {
+                    self.count_node()?;
+                    self.start(SyntaxKind::HowTo);
                     self.lexer.next()?;
                     let howto: HowToStatement = self.parse_howto_statement()?;
+                    self.finish();
                     Ok(Some(ParseNode::HowToStatement(howto)))
                 }
                 "whatis" => {
+                    self.count_node()?;
+                    self.start(SyntaxKind::WhatIs);
                     self.lexer.next()?;
                     let whatis: WhatIsStatement = self.parse_whatis_statement()?;
+                    self.finish();
                     Ok(Some(ParseNode::WhatIsStatement(whatis)))
                 }
-                _ => Err(ParserError::InternalError("Unexpected keyword".to_string())),
+                "module" => {
+                    self.count_node()?;
+                    self.start(SyntaxKind::Module);
+                    self.lexer.next()?;
+                    let module: ModuleStatement = self.parse_module_statement()?;
+                    self.finish();
+                    Ok(Some(ParseNode::Module(module)))
+                }
+                _ => Err(ParserError::UnknownStatement {
+                    span: self.lexer.peek_span()?,
+                    keyword: kw,
+                }),
             },
-            Token::Identifier(_) => Ok(Some(ParseNode::Command(self.parse_command()?))),
-            _ => Err(ParserError::InternalError("Unexpected token".to_string())),
+            Token::Identifier(word) => {
+                let span: Span = self.lexer.peek_span()?;
+                let command: Command = self.parse_command()?;
+
+                // `hwoto greet?` reads as the command `hwoto greet`, which
+                // cannot be followed by the `?` of a signature
+                if self.lexer.peek()? == Some(&Token::Question) {
+                    if let Some(keyword) = closest(&word, grammar().keywords()) {
+                        return Err(ParserError::MisspelledKeyword {
+                            word,
+                            keyword: keyword.to_string(),
+                            span,
+                        });
+                    }
+                }
+
+                Ok(Some(ParseNode::Command(command)))
+            }
+            _ => Err(ParserError::UnexpectedStatementStart {
+                span: self.lexer.peek_span()?,
+                found: token,
+            }),
+        }
+    }
+
+    pub fn peek(&mut self) -> Result<Option<&ParseNode>, ParserError> {
+        self.peek_n(0)
+    }
+
+    // The statement `k` places after the next one, without consuming any.
+    // `peek_n(0)` is the same as `peek`.
+    pub fn peek_n(&mut self, k: usize) -> Result<Option<&ParseNode>, ParserError> {
+        while self.peeked.len() <= k {
+            match self.parse_statement()? {
+                Some(node) => self.peeked.push_back(node),
+                None => break,
+            }
+        }
+
+        Ok(self.peeked.get(k))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn next_async<R: AsyncRead + Unpin>(
+        stream: &mut AsyncInputStream<R>,
+    ) -> Result<Option<ParseNode>, ParserError> {
+        Parser::next_async_with(stream, &ParseOptions::default()).await
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn next_async_with<R: AsyncRead + Unpin>(
+        stream: &mut AsyncInputStream<R>,
+        options: &ParseOptions,
+    ) -> Result<Option<ParseNode>, ParserError> {
+        loop {
+            let at: Span = Span::at(stream.pos, stream.line, stream.column);
+
+            match Parser::next_buffered(stream.buffered(), stream.is_eof(), at, options) {
+                Some((result, consumed)) => {
+                    let node: Option<ParseNode> = result?;
+                    stream.consume(consumed);
+
+                    return Ok(node);
+                }
+                None => {
+                    stream.grow().await.map_err(LexerError::from)?;
+                }
+            }
+        }
+    }
+
+    // Blocking version of `next_async`, for readers such as stdin or a pipe
+    pub fn next_from<R: Read>(
+        stream: &mut ReadInputStream<R>,
+    ) -> Result<Option<ParseNode>, ParserError> {
+        Parser::next_from_with(stream, &ParseOptions::default())
+    }
+
+    pub fn next_from_with<R: Read>(
+        stream: &mut ReadInputStream<R>,
+        options: &ParseOptions,
+    ) -> Result<Option<ParseNode>, ParserError> {
+        loop {
+            let at: Span = Span::at(stream.pos, stream.line, stream.column);
+
+            match Parser::next_buffered(stream.buffered(), stream.is_eof(), at, options) {
+                Some((result, consumed)) => {
+                    let node: Option<ParseNode> = result?;
+                    stream.consume(consumed);
+
+                    return Ok(node);
+                }
+                None => {
+                    stream.grow().map_err(LexerError::from)?;
+                }
+            }
         }
     }
 
-    pub fn peek(&mut self) -> Result<Option<ParseNode>, ParserError> {
-        if self.peeked.is_none() {
-            self.peeked = self.next()?;
+    // Calls `f` with each statement as soon as it is complete. More input is
+    // only read once `f` returns, so a slow consumer holds back the reader.
+    pub fn for_each_statement<R: Read, F: FnMut(ParseNode)>(
+        reader: R,
+        f: F,
+    ) -> Result<(), ParserError> {
+        Parser::for_each_statement_with(reader, &ParseOptions::default(), f)
+    }
+
+    pub fn for_each_statement_with<R: Read, F: FnMut(ParseNode)>(
+        reader: R,
+        options: &ParseOptions,
+        mut f: F,
+    ) -> Result<(), ParserError> {
+        let mut stream: ReadInputStream<R> = ReadInputStream::new(reader);
+
+        while let Some(node) = Parser::next_from_with(&mut stream, options)? {
+            f(node);
         }
 
-        Ok(self.peeked.clone())
+        Ok(())
+    }
+
+    // Parses on a thread of its own, sending each statement as soon as it is
+    // complete. Once `bound` statements are waiting in the channel the parser
+    // stops reading until the receiver catches up. Parsing ends after the
+    // first error, which is sent as the last message, or once the receiver
+    // is dropped.
+    pub fn spawn_statements<R: Read + Send + 'static>(
+        reader: R,
+        bound: usize,
+    ) -> Receiver<Result<ParseNode, ParserError>> {
+        Parser::spawn_statements_with(reader, bound, ParseOptions::default())
     }
+
+    pub fn spawn_statements_with<R: Read + Send + 'static>(
+        reader: R,
+        bound: usize,
+        options: ParseOptions,
+    ) -> Receiver<Result<ParseNode, ParserError>> {
+        let (sender, receiver) = mpsc::sync_channel(bound);
+
+        thread::spawn(move || {
+            let mut stream: ReadInputStream<R> = ReadInputStream::new(reader);
+
+            loop {
+                let message: Result<ParseNode, ParserError> =
+                    match Parser::next_from_with(&mut stream, &options) {
+                        Ok(Some(node)) => Ok(node),
+                        Ok(None) => break,
+                        Err(err) => Err(err),
+                    };
+                let failed: bool = message.is_err();
+
+                if sender.send(message).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        receiver
+    }
+
+    // Parses the first statement of `source`, everything received so far,
+    // which starts `at` in the whole input. Returns `None` if the statement
+    // might go on in input that has not arrived yet, otherwise the result
+    // and the bytes it used up.
+    fn next_buffered(
+        source: &str,
+        eof: bool,
+        at: Span,
+        options: &ParseOptions,
+    ) -> Option<(Result<Option<ParseNode>, ParserError>, usize)> {
+        let lexer = Lexer::with_config(InputStream::new(source), options.lexer.clone());
+        let mut parser = Parser::new(lexer.starting_at(at)).with_limits(options.limits);
+        let result = parser.next();
+
+        // A statement is only known to be finished once the parser has
+        // looked past it, or the input has run out for good
+        let lexer: &Lexer = &parser.lexer;
+        let at_end: bool = lexer.peeked.is_empty() && lexer.stream.remaining().is_empty();
+        let incomplete: bool = !eof
+            && match &result {
+                Ok(None) => true,
+                Ok(Some(_)) => at_end,
+                Err(_) => at_end,
+            };
+
+        if incomplete {
+            return None;
+        }
+
+        Some((result, lexer.consumed(source)))
+    }
+}
+
+// Every statement of `source`, stopping at the first error
+pub fn parse_all(source: &str) -> Result<Vec<ParseNode>, ParserError> {
+    let mut parser: Parser = Parser::from(source);
+    let mut nodes: Vec<ParseNode> = Vec::new();
+
+    while let Some(node) = parser.next()? {
+        nodes.push(node);
+    }
+
+    Ok(nodes)
 }
 
 impl<'s> From<&'s str> for Parser<'s> {
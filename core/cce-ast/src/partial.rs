@@ -0,0 +1,217 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+// Parsing input that is still being typed, for completion. Besides what
+// parses so far, this works out which tokens could legally come next by
+// appending a sample of each kind and checking whether the parser rejects
+// it right where it starts.
+
+use std::ops::Range;
+
+use crate::lexer::{Lexer, LexerConfig, Span, Token, TokenKind};
+use crate::parser::{ParseNode, Parser, ParserError};
+
+// A token the input ends in the middle of. `text` is what the token holds
+// so far: the word, or the literal or final sequence without its quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialToken {
+    pub kind: TokenKind,
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct PartialParse {
+    pub nodes: Vec<ParseNode>,
+    // Source of the last statement, if it cannot be parsed until more of it
+    // is typed
+    pub pending: Option<Range<usize>>,
+    pub partial: Option<PartialToken>,
+    // Kinds of token that could start where `partial` does, or at the end of
+    // the input, in the order `TokenKind` lists them
+    pub expected: Vec<TokenKind>,
+    // Keywords that could come there, and `end` where it closes a module
+    pub keywords: Vec<String>,
+    // An error before the end of the input, which no more typing can fix
+    pub error: Option<ParserError>,
+}
+
+pub fn parse_partial(source: &str) -> PartialParse {
+    let partial: Option<PartialToken> = last_token(source);
+    let head: &str = &source[..partial
+        .as_ref()
+        .map_or(source.len(), |token| token.span.start)];
+
+    let mut parser: Parser = Parser::new(Lexer::from(source).allow_partial());
+    let mut nodes: Vec<ParseNode> = Vec::new();
+    let mut pending: Option<Range<usize>> = None;
+    let mut error: Option<ParserError> = None;
+    let mut end: usize = 0;
+
+    loop {
+        match parser.next() {
+            Ok(Some(node)) => {
+                nodes.push(node);
+                end = parser.lexer.consumed(source);
+            }
+            Ok(None) => break,
+            Err(err) if at_end(&err, source.len()) => {
+                let start: usize = source.len() - source[end..].trim_start().len();
+                pending = Some(start..source.len());
+                break;
+            }
+            Err(err) => {
+                error = Some(err);
+                break;
+            }
+        }
+    }
+
+    let (expected, keywords) = match error {
+        Some(_) => (Vec::new(), Vec::new()),
+        None => next_tokens(head),
+    };
+
+    PartialParse {
+        nodes,
+        pending,
+        partial,
+        expected,
+        keywords,
+        error,
+    }
+}
+
+// The last token of `source`, if it runs into the end of the input
+fn last_token(source: &str) -> Option<PartialToken> {
+    let mut lexer: Lexer = Lexer::from(source).allow_partial();
+    let mut last: Option<PartialToken> = None;
+
+    while let Ok(Some(token)) = lexer.next() {
+        last = match &token {
+            Token::Identifier(text)
+            | Token::Keyword(text)
+            | Token::Literal(text)
+            | Token::Number {
+                value: text,
+                unit: None,
+            }
+            | Token::Number {
+                unit: Some(text), ..
+            }
+            | Token::FinalSequence(text)
+                if lexer.is_cut_off() =>
+            {
+                Some(PartialToken {
+                    kind: token.kind(),
+                    text: text.clone(),
+                    span: lexer.span(),
+                })
+            }
+            _ => None,
+        };
+    }
+
+    last
+}
+
+// Whether `err` is only the parser running out of input
+fn at_end(err: &ParserError, len: usize) -> bool {
+    err.span().is_some_and(|span| span.start >= len)
+}
+
+fn next_tokens(head: &str) -> (Vec<TokenKind>, Vec<String>) {
+    let config: LexerConfig = LexerConfig::default();
+    let fits = |sample: &str| -> bool { accepts(head, sample) };
+
+    let mut expected: Vec<TokenKind> = Vec::new();
+
+    let mut keywords: Vec<String> = config
+        .keywords
+        .iter()
+        .filter(|keyword| fits(keyword))
+        .cloned()
+        .collect();
+
+    // `end` is only worth suggesting where other words cannot go
+    let identifier: bool = fits("x");
+    let end: bool = !identifier && fits("end");
+
+    if identifier || end {
+        expected.push(TokenKind::Identifier);
+    }
+
+    if !keywords.is_empty() {
+        expected.push(TokenKind::Keyword);
+    }
+
+    if end {
+        keywords.push("end".to_string());
+    }
+
+    let quote: char = config.quotes.first().copied().unwrap_or('\'');
+    let mut samples: Vec<(TokenKind, String)> = vec![
+        (TokenKind::Literal, format!("{quote}x{quote}")),
+        (TokenKind::Number, "1".to_string()),
+    ];
+    samples.extend(
+        config
+            .punctuation
+            .iter()
+            .map(|c| (TokenKind::Punctuation(*c), c.to_string())),
+    );
+    samples.extend(
+        [
+            (TokenKind::FinalSequence, "$$x$$"),
+            (TokenKind::Newline, "\n"),
+            (TokenKind::Question, "?"),
+            (TokenKind::Dot, &config.terminator.to_string()),
+            (TokenKind::Percent, "%"),
+            (TokenKind::Ampersand, "&"),
+            (TokenKind::Colon, ":"),
+            (TokenKind::Equals, "="),
+        ]
+        .map(|(kind, sample)| (kind, sample.to_string())),
+    );
+
+    expected.extend(
+        samples
+            .into_iter()
+            .filter(|(_, sample)| fits(sample))
+            .map(|(kind, _)| kind),
+    );
+
+    (expected, keywords)
+}
+
+// Whether the parser gets past `sample` put after `head`. Running out of
+// input after it is fine, but an error where it starts means it cannot go
+// there.
+fn accepts(head: &str, sample: &str) -> bool {
+    let source: String = format!("{}{}", head, sample);
+    let mut parser: Parser = Parser::from(source.as_str());
+
+    loop {
+        match parser.next() {
+            Ok(Some(_)) => {}
+            Ok(None) => return true,
+            Err(err) => return err.span().is_some_and(|span| span.start > head.len()),
+        }
+    }
+}
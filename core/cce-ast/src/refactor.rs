@@ -0,0 +1,300 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Renames for editor tooling. Each rename updates the AST in place and
+// returns the text edits that make the same change to the source the tree
+// was parsed from. `nodes` must be the lowered form of `tree`.
+//
+// Edits are sorted by position and never overlap, so they can be applied
+// to the source back to front.
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::cst::{components_with_ranges, SyntaxKind, SyntaxNode};
+use crate::incremental::TextEdit;
+use crate::intern::Symbol;
+use crate::lexer::TokenKind;
+use crate::parser::*;
+use crate::visit::VisitMut;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RenameError {
+    #[error("Not a valid name: '{0}'")]
+    InvalidName(String),
+}
+
+pub fn rename_slot(
+    nodes: &mut [ParseNode],
+    tree: &SyntaxNode,
+    old: &str,
+    new: &str,
+) -> Result<Vec<TextEdit>, RenameError> {
+    if !is_identifier(new) {
+        return Err(RenameError::InvalidName(new.to_string()));
+    }
+
+    let mut renamer = SlotRenamer { old, new };
+    for node in nodes.iter_mut() {
+        renamer.visit_parse_node_mut(node);
+    }
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    collect_slot_edits(tree, old, new, &mut edits);
+
+    Ok(edits)
+}
+
+// Renames a phrase of keywords in every howto and whatis signature, and in
+// the commands that call one of those signatures. A command calls a
+// signature if it has the same length and the same keywords wherever the
+// signature does not have a slot.
+pub fn rename_keyword(
+    nodes: &mut [ParseNode],
+    tree: &SyntaxNode,
+    old: &str,
+    new: &str,
+) -> Result<Vec<TextEdit>, RenameError> {
+    let old_words: Vec<&str> = old.split_whitespace().collect();
+    let new_words: Vec<&str> = new.split_whitespace().collect();
+
+    if old_words.is_empty() {
+        return Err(RenameError::InvalidName(old.to_string()));
+    }
+
+    if new_words.is_empty() || !new_words.iter().all(|word| is_keyword(word)) {
+        return Err(RenameError::InvalidName(new.to_string()));
+    }
+
+    let signatures: Vec<Vec<CommandComponent>> = nodes
+        .iter()
+        .flat_map(ParseNode::flatten)
+        .filter_map(|node| node.signature().map(<[CommandComponent]>::to_vec))
+        .filter(|signature| !phrase_positions(signature, &old_words).is_empty())
+        .collect();
+
+    let phrase = Phrase {
+        old: &old_words,
+        new: &new_words,
+        signatures: &signatures,
+    };
+
+    let mut renamer = KeywordRenamer { phrase: &phrase };
+    for node in nodes.iter_mut() {
+        renamer.visit_parse_node_mut(node);
+    }
+
+    let mut edits: Vec<TextEdit> = Vec::new();
+    collect_keyword_edits(tree, &phrase, &mut edits);
+    edits.sort_by_key(|edit| edit.range.start);
+
+    Ok(edits)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// Keywords that would start a statement are not allowed
+fn is_keyword(word: &str) -> bool {
+    is_identifier(word) && !matches!(word, "howto" | "whatis" | "module")
+}
+
+struct SlotRenamer<'a> {
+    old: &'a str,
+    new: &'a str,
+}
+
+// Back-references name the slot they refer to, so they are renamed with it
+impl VisitMut for SlotRenamer<'_> {
+    fn visit_command_component_mut(&mut self, component: &mut CommandComponent) {
+        if let CommandComponent::Slot { name, .. } | CommandComponent::BackRef(name) = component {
+            if *name == self.old {
+                *name = self.new.into();
+            }
+        }
+    }
+}
+
+fn collect_slot_edits(node: &SyntaxNode, old: &str, new: &str, edits: &mut Vec<TextEdit>) {
+    if matches!(node.kind, SyntaxKind::Slot | SyntaxKind::BackRef) {
+        let name = node
+            .child_tokens()
+            .find(|token| token.kind == SyntaxKind::Token(TokenKind::Identifier));
+
+        if let Some(name) = name.filter(|name| name.text == old) {
+            edits.push(TextEdit {
+                range: name.range.clone(),
+                replacement: new.to_string(),
+            });
+        }
+
+        return;
+    }
+
+    for child in node.nodes() {
+        collect_slot_edits(child, old, new, edits);
+    }
+}
+
+struct Phrase<'a> {
+    old: &'a [&'a str],
+    new: &'a [&'a str],
+    // Signatures that contained the phrase before the rename
+    signatures: &'a [Vec<CommandComponent>],
+}
+
+impl Phrase<'_> {
+    // Where the phrase starts in a command. Calls are renamed at the same
+    // positions as the signature they call.
+    fn call_positions(&self, command: &[CommandComponent]) -> Vec<usize> {
+        self.signatures
+            .iter()
+            .find(|signature| calls(signature, command))
+            .map(|signature| phrase_positions(signature, self.old))
+            .unwrap_or_default()
+    }
+
+    fn rename(&self, components: &mut Vec<CommandComponent>, positions: &[usize]) {
+        for &position in positions.iter().rev() {
+            components.splice(
+                position..position + self.old.len(),
+                self.new
+                    .iter()
+                    .map(|word| CommandComponent::Keyword(Symbol::intern(word))),
+            );
+        }
+    }
+}
+
+fn calls(signature: &[CommandComponent], command: &[CommandComponent]) -> bool {
+    signature.len() == command.len()
+        && signature.iter().zip(command).all(|(expected, found)| {
+            matches!(expected, CommandComponent::Slot { .. }) || expected == found
+        })
+}
+
+// Non-overlapping starting positions of `phrase` as consecutive keywords
+fn phrase_positions(components: &[CommandComponent], phrase: &[&str]) -> Vec<usize> {
+    let mut positions: Vec<usize> = Vec::new();
+    let mut i: usize = 0;
+
+    while i + phrase.len() <= components.len() {
+        let found: bool = components[i..i + phrase.len()].iter().zip(phrase).all(
+            |(component, word)| matches!(component, CommandComponent::Keyword(kw) if kw == word),
+        );
+
+        if found {
+            positions.push(i);
+            i += phrase.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    positions
+}
+
+struct KeywordRenamer<'a> {
+    phrase: &'a Phrase<'a>,
+}
+
+impl VisitMut for KeywordRenamer<'_> {
+    fn visit_signature_mut(&mut self, signature: &mut Vec<CommandComponent>) {
+        let positions: Vec<usize> = phrase_positions(signature, self.phrase.old);
+        self.phrase.rename(signature, &positions);
+    }
+
+    fn visit_command_mut(&mut self, command: &mut Command) {
+        let positions: Vec<usize> = self.phrase.call_positions(&command.components);
+        self.phrase.rename(&mut command.components, &positions);
+    }
+}
+
+fn collect_keyword_edits(node: &SyntaxNode, phrase: &Phrase, edits: &mut Vec<TextEdit>) {
+    match node.kind {
+        SyntaxKind::Signature => {
+            let components = components_with_ranges(&node.children);
+            let positions: Vec<usize> = phrase_positions(&without_ranges(&components), phrase.old);
+            push_phrase_edits(&components, &positions, phrase, edits);
+        }
+        SyntaxKind::Step => {
+            let mut components = node
+                .child(SyntaxKind::Command)
+                .map(|command| components_with_ranges(&command.children))
+                .unwrap_or_default();
+
+            let nested: bool = node.child(SyntaxKind::Step).is_some();
+            let when: bool = matches!(
+                components.first(),
+                Some((CommandComponent::Keyword(kw), _)) if kw == "when"
+            );
+
+            if nested && when {
+                components.remove(0);
+            }
+
+            let positions: Vec<usize> = phrase.call_positions(&without_ranges(&components));
+            push_phrase_edits(&components, &positions, phrase, edits);
+
+            for step in node.nodes().filter(|node| node.kind == SyntaxKind::Step) {
+                collect_keyword_edits(step, phrase, edits);
+            }
+        }
+        SyntaxKind::Command => {
+            let components = components_with_ranges(&node.children);
+            let positions: Vec<usize> = phrase.call_positions(&without_ranges(&components));
+            push_phrase_edits(&components, &positions, phrase, edits);
+        }
+        _ => {
+            for child in node.nodes() {
+                collect_keyword_edits(child, phrase, edits);
+            }
+        }
+    }
+}
+
+fn without_ranges(components: &[(CommandComponent, Range<usize>)]) -> Vec<CommandComponent> {
+    components
+        .iter()
+        .map(|(component, _)| component.clone())
+        .collect()
+}
+
+fn push_phrase_edits(
+    components: &[(CommandComponent, Range<usize>)],
+    positions: &[usize],
+    phrase: &Phrase,
+    edits: &mut Vec<TextEdit>,
+) {
+    for &position in positions {
+        let start: usize = components[position].1.start;
+        let end: usize = components[position + phrase.old.len() - 1].1.end;
+
+        edits.push(TextEdit {
+            range: start..end,
+            replacement: phrase.new.join(" "),
+        });
+    }
+}
@@ -0,0 +1,114 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+// "Did you mean" suggestions for misspelled words. Candidates are ranked by
+// edit distance, counting a swap of two neighbouring chars as one edit so
+// `hwoto` is as close to `howto` as `howt` is.
+
+use std::fmt;
+
+use crate::lexer::Span;
+
+// A replacement for one word that tooling can apply as is. `span` is where
+// `original` is in the source, if known; otherwise it is the first
+// occurrence of `original` in the statement the diagnostic points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suggestion {
+    pub original: String,
+    pub replacement: String,
+    pub span: Option<Span>,
+}
+
+impl Suggestion {
+    // The source with the suggestion applied, or `None` if `span` is unknown
+    // or no longer covers `original`
+    pub fn apply(&self, source: &str) -> Option<String> {
+        let span: Span = self.span?;
+
+        if source.get(span.start..span.end)? != self.original {
+            return None;
+        }
+
+        Some(format!(
+            "{}{}{}",
+            &source[..span.start],
+            self.replacement,
+            &source[span.end..]
+        ))
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "did you mean '{}'?", self.replacement)
+    }
+}
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Three rows, as a transposition looks two rows back
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut current: Vec<usize> = vec![i; b.len() + 1];
+
+        for j in 1..=b.len() {
+            let cost: usize = usize::from(a[i - 1] != b[j - 1]);
+
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+
+        before = std::mem::replace(&mut previous, current);
+    }
+
+    previous[b.len()]
+}
+
+// Words further than this from every candidate get no suggestion, so short
+// words are not "corrected" into unrelated ones
+fn max_distance(word: &str) -> usize {
+    (word.chars().count() / 3).max(1)
+}
+
+// The candidate closest to `word`, if any is close enough. Candidates equal
+// to `word` are skipped, as there is nothing to correct; ties go to the
+// first candidate.
+pub fn closest<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit: usize = max_distance(word);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|(distance, _)| (1..=limit).contains(distance))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
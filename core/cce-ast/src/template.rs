@@ -0,0 +1,196 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+// Templates inside final sequences. `$name` inserts the value bound to the
+// signature slot `%name`; `${name:spec}` does the same and applies a format
+// spec to the value first. A `$` that does not start a placeholder is kept
+// as is.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::parser::{CommandComponent, FinalSequence};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    Text(String),
+    Placeholder { name: String, spec: Option<String> },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    #[error("Template error: Unterminated placeholder at offset {offset}")]
+    Unterminated { offset: usize },
+    #[error("Template error: Invalid placeholder '{text}' at offset {offset}")]
+    InvalidPlaceholder { text: String, offset: usize },
+    #[error("Template error: '${0}' does not match any slot in the signature")]
+    UnknownPlaceholder(String),
+    #[error("Template error: Unknown format spec '{0}'")]
+    UnknownSpec(String),
+    #[error("Template error: No value bound for '${0}'")]
+    Unbound(String),
+}
+
+const SPECS: &[&str] = &["upper", "lower", "trim"];
+
+pub fn parse_template(code: &str) -> Result<Vec<TemplatePart>, TemplateError> {
+    let mut parts: Vec<TemplatePart> = Vec::new();
+    let mut text: String = String::new();
+    let mut rest: &str = code;
+
+    while let Some(dollar) = rest.find('$') {
+        text.push_str(&rest[..dollar]);
+        let offset: usize = code.len() - rest.len() + dollar;
+        let after: &str = &rest[dollar + 1..];
+
+        let (placeholder, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            let close: usize = braced
+                .find('}')
+                .ok_or(TemplateError::Unterminated { offset })?;
+            let inner: &str = &braced[..close];
+            let (name, spec) = match inner.split_once(':') {
+                Some((name, spec)) => (name, Some(spec.to_string())),
+                None => (inner, None),
+            };
+
+            if name.is_empty() || identifier_len(name) != name.len() {
+                return Err(TemplateError::InvalidPlaceholder {
+                    text: format!("${{{}}}", inner),
+                    offset,
+                });
+            }
+
+            (
+                Some(TemplatePart::Placeholder {
+                    name: name.to_string(),
+                    spec,
+                }),
+                close + 2,
+            )
+        } else {
+            match identifier_len(after) {
+                0 => (None, 0),
+                len => (
+                    Some(TemplatePart::Placeholder {
+                        name: after[..len].to_string(),
+                        spec: None,
+                    }),
+                    len,
+                ),
+            }
+        };
+
+        match placeholder {
+            Some(placeholder) => {
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(placeholder);
+            }
+            None => text.push('$'),
+        }
+
+        rest = &after[consumed..];
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+
+    Ok(parts)
+}
+
+// Checks that every placeholder names a slot of `signature` and only uses
+// known format specs
+pub fn validate_template(
+    parts: &[TemplatePart],
+    signature: &[CommandComponent],
+) -> Result<(), TemplateError> {
+    for part in parts {
+        if let TemplatePart::Placeholder { name, spec } = part {
+            let is_slot = signature.iter().any(|component| {
+                matches!(component, CommandComponent::Slot { name: slot, .. } if slot == name)
+            });
+
+            if !is_slot {
+                return Err(TemplateError::UnknownPlaceholder(name.clone()));
+            }
+
+            if let Some(spec) = spec {
+                if !SPECS.contains(&spec.as_str()) {
+                    return Err(TemplateError::UnknownSpec(spec.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn render(
+    parts: &[TemplatePart],
+    bindings: &HashMap<String, String>,
+) -> Result<String, TemplateError> {
+    let mut output: String = String::new();
+
+    for part in parts {
+        match part {
+            TemplatePart::Text(text) => output.push_str(text),
+            TemplatePart::Placeholder { name, spec } => {
+                let value: &str = bindings
+                    .get(name)
+                    .ok_or_else(|| TemplateError::Unbound(name.clone()))?;
+
+                match spec.as_deref() {
+                    None => output.push_str(value),
+                    Some("upper") => output.push_str(&value.to_uppercase()),
+                    Some("lower") => output.push_str(&value.to_lowercase()),
+                    Some("trim") => output.push_str(value.trim()),
+                    Some(spec) => return Err(TemplateError::UnknownSpec(spec.to_string())),
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+impl FinalSequence {
+    pub fn template(&self) -> Result<Vec<TemplatePart>, TemplateError> {
+        parse_template(&self.code)
+    }
+}
+
+// Length in bytes of the identifier at the start of `s`, using the same
+// rules as the lexer
+fn identifier_len(s: &str) -> usize {
+    let mut chars = s.char_indices();
+
+    match chars.next() {
+        Some((_, c)) if c.is_alphabetic() || c == '_' => {}
+        _ => return 0,
+    }
+
+    chars
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map(|(i, _)| i)
+        .unwrap_or(s.len())
+}
@@ -0,0 +1,278 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Traversal over the AST. Each `visit_*` method defaults to the matching
+// `walk_*` function, which visits the node's children; override a method to
+// act on a node, and call the walk function from it to keep descending.
+
+use crate::parser::{
+    Command, CommandComponent, FinalSequence, HowToCommand, HowToStatement, Modifier,
+    ModuleStatement, ParseNode, WhatIsAlternative, WhatIsCommand, WhatIsStatement,
+};
+
+pub trait Visit {
+    fn visit_parse_node(&mut self, node: &ParseNode) {
+        walk_parse_node(self, node);
+    }
+
+    fn visit_command(&mut self, command: &Command) {
+        walk_command(self, command);
+    }
+
+    fn visit_modifier(&mut self, modifier: &Modifier) {
+        walk_modifier(self, modifier);
+    }
+
+    fn visit_command_component(&mut self, _component: &CommandComponent) {}
+
+    fn visit_howto_statement(&mut self, howto: &HowToStatement) {
+        walk_howto_statement(self, howto);
+    }
+
+    fn visit_howto_command(&mut self, command: &HowToCommand) {
+        walk_howto_command(self, command);
+    }
+
+    fn visit_whatis_statement(&mut self, whatis: &WhatIsStatement) {
+        walk_whatis_statement(self, whatis);
+    }
+
+    fn visit_whatis_alternative(&mut self, alternative: &WhatIsAlternative) {
+        self.visit_whatis_command(&alternative.command);
+    }
+
+    fn visit_whatis_command(&mut self, command: &WhatIsCommand) {
+        walk_whatis_command(self, command);
+    }
+
+    fn visit_signature(&mut self, signature: &[CommandComponent]) {
+        walk_components(self, signature);
+    }
+
+    fn visit_final(&mut self, _sequence: &FinalSequence) {}
+
+    fn visit_module(&mut self, module: &ModuleStatement) {
+        walk_module(self, module);
+    }
+}
+
+pub fn walk_parse_node<V: Visit + ?Sized>(v: &mut V, node: &ParseNode) {
+    match node {
+        ParseNode::Command(command) => v.visit_command(command),
+        ParseNode::HowToStatement(howto) => v.visit_howto_statement(howto),
+        ParseNode::WhatIsStatement(whatis) => v.visit_whatis_statement(whatis),
+        ParseNode::Module(module) => v.visit_module(module),
+    }
+}
+
+pub fn walk_module<V: Visit + ?Sized>(v: &mut V, module: &ModuleStatement) {
+    for node in &module.body {
+        v.visit_parse_node(node);
+    }
+}
+
+pub fn walk_command<V: Visit + ?Sized>(v: &mut V, command: &Command) {
+    walk_components(v, &command.components);
+
+    for modifier in &command.modifiers {
+        v.visit_modifier(modifier);
+    }
+}
+
+pub fn walk_modifier<V: Visit + ?Sized>(v: &mut V, modifier: &Modifier) {
+    match modifier {
+        Modifier::Named { args, .. } => walk_components(v, args),
+        Modifier::FreeForm(components) => walk_components(v, components),
+    }
+}
+
+pub fn walk_components<V: Visit + ?Sized>(v: &mut V, components: &[CommandComponent]) {
+    for component in components {
+        v.visit_command_component(component);
+    }
+}
+
+pub fn walk_howto_statement<V: Visit + ?Sized>(v: &mut V, howto: &HowToStatement) {
+    v.visit_signature(&howto.signature);
+
+    for command in &howto.body {
+        v.visit_howto_command(command);
+    }
+}
+
+pub fn walk_howto_command<V: Visit + ?Sized>(v: &mut V, command: &HowToCommand) {
+    match command {
+        HowToCommand::Command(command) => v.visit_command(command),
+        HowToCommand::Block { head, body } => {
+            v.visit_command(head);
+
+            for command in body {
+                v.visit_howto_command(command);
+            }
+        }
+        HowToCommand::When { condition, body } => {
+            v.visit_command(condition);
+
+            for command in body {
+                v.visit_howto_command(command);
+            }
+        }
+    }
+}
+
+pub fn walk_whatis_statement<V: Visit + ?Sized>(v: &mut V, whatis: &WhatIsStatement) {
+    v.visit_signature(&whatis.signature);
+
+    for alternative in &whatis.alternatives {
+        v.visit_whatis_alternative(alternative);
+    }
+}
+
+pub fn walk_whatis_command<V: Visit + ?Sized>(v: &mut V, command: &WhatIsCommand) {
+    match command {
+        WhatIsCommand::Command(command) => v.visit_command(command),
+        WhatIsCommand::Final(sequence) => v.visit_final(sequence),
+    }
+}
+
+pub trait VisitMut {
+    fn visit_parse_node_mut(&mut self, node: &mut ParseNode) {
+        walk_parse_node_mut(self, node);
+    }
+
+    fn visit_command_mut(&mut self, command: &mut Command) {
+        walk_command_mut(self, command);
+    }
+
+    fn visit_modifier_mut(&mut self, modifier: &mut Modifier) {
+        walk_modifier_mut(self, modifier);
+    }
+
+    fn visit_command_component_mut(&mut self, _component: &mut CommandComponent) {}
+
+    fn visit_howto_statement_mut(&mut self, howto: &mut HowToStatement) {
+        walk_howto_statement_mut(self, howto);
+    }
+
+    fn visit_howto_command_mut(&mut self, command: &mut HowToCommand) {
+        walk_howto_command_mut(self, command);
+    }
+
+    fn visit_whatis_statement_mut(&mut self, whatis: &mut WhatIsStatement) {
+        walk_whatis_statement_mut(self, whatis);
+    }
+
+    fn visit_whatis_alternative_mut(&mut self, alternative: &mut WhatIsAlternative) {
+        self.visit_whatis_command_mut(&mut alternative.command);
+    }
+
+    fn visit_whatis_command_mut(&mut self, command: &mut WhatIsCommand) {
+        walk_whatis_command_mut(self, command);
+    }
+
+    fn visit_signature_mut(&mut self, signature: &mut Vec<CommandComponent>) {
+        walk_components_mut(self, signature);
+    }
+
+    fn visit_final_mut(&mut self, _sequence: &mut FinalSequence) {}
+
+    fn visit_module_mut(&mut self, module: &mut ModuleStatement) {
+        walk_module_mut(self, module);
+    }
+}
+
+pub fn walk_parse_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut ParseNode) {
+    match node {
+        ParseNode::Command(command) => v.visit_command_mut(command),
+        ParseNode::HowToStatement(howto) => v.visit_howto_statement_mut(howto),
+        ParseNode::WhatIsStatement(whatis) => v.visit_whatis_statement_mut(whatis),
+        ParseNode::Module(module) => v.visit_module_mut(module),
+    }
+}
+
+pub fn walk_module_mut<V: VisitMut + ?Sized>(v: &mut V, module: &mut ModuleStatement) {
+    for node in &mut module.body {
+        v.visit_parse_node_mut(node);
+    }
+}
+
+pub fn walk_command_mut<V: VisitMut + ?Sized>(v: &mut V, command: &mut Command) {
+    walk_components_mut(v, &mut command.components);
+
+    for modifier in &mut command.modifiers {
+        v.visit_modifier_mut(modifier);
+    }
+}
+
+pub fn walk_modifier_mut<V: VisitMut + ?Sized>(v: &mut V, modifier: &mut Modifier) {
+    match modifier {
+        Modifier::Named { args, .. } => walk_components_mut(v, args),
+        Modifier::FreeForm(components) => walk_components_mut(v, components),
+    }
+}
+
+pub fn walk_components_mut<V: VisitMut + ?Sized>(v: &mut V, components: &mut [CommandComponent]) {
+    for component in components {
+        v.visit_command_component_mut(component);
+    }
+}
+
+pub fn walk_howto_statement_mut<V: VisitMut + ?Sized>(v: &mut V, howto: &mut HowToStatement) {
+    v.visit_signature_mut(&mut howto.signature);
+
+    for command in &mut howto.body {
+        v.visit_howto_command_mut(command);
+    }
+}
+
+pub fn walk_howto_command_mut<V: VisitMut + ?Sized>(v: &mut V, command: &mut HowToCommand) {
+    match command {
+        HowToCommand::Command(command) => v.visit_command_mut(command),
+        HowToCommand::Block { head, body } => {
+            v.visit_command_mut(head);
+
+            for command in body {
+                v.visit_howto_command_mut(command);
+            }
+        }
+        HowToCommand::When { condition, body } => {
+            v.visit_command_mut(condition);
+
+            for command in body {
+                v.visit_howto_command_mut(command);
+            }
+        }
+    }
+}
+
+pub fn walk_whatis_statement_mut<V: VisitMut + ?Sized>(v: &mut V, whatis: &mut WhatIsStatement) {
+    v.visit_signature_mut(&mut whatis.signature);
+
+    for alternative in &mut whatis.alternatives {
+        v.visit_whatis_alternative_mut(alternative);
+    }
+}
+
+pub fn walk_whatis_command_mut<V: VisitMut + ?Sized>(v: &mut V, command: &mut WhatIsCommand) {
+    match command {
+        WhatIsCommand::Command(command) => v.visit_command_mut(command),
+        WhatIsCommand::Final(sequence) => v.visit_final_mut(sequence),
+    }
+}
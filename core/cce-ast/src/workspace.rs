@@ -0,0 +1,304 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use crate::lexer::{Lexer, Span};
+use crate::parser::{ParseNode, ParseOptions, Parser, ParserError, ParserLimits};
+use crate::suggest::Suggestion;
+use cce_stream::{DecodeOptions, DecodeWarning, Decoded, InputStream, InputStreamError};
+use circelang_hash::CirceHash;
+
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct WorkspaceParseResult {
+    pub files: Vec<FileParseResult>,
+    pub definitions: HashMap<u64, Vec<DefinitionLocation>>,
+}
+
+#[derive(Debug)]
+pub struct FileParseResult {
+    pub path: PathBuf,
+    pub nodes: Vec<ParseNode>,
+    pub errors: Vec<WorkspaceError>,
+    // Invalid input replaced while decoding the file in lossy mode
+    pub warnings: Vec<DecodeWarning>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefinitionLocation {
+    pub file: usize,
+    pub node: usize,
+}
+
+// Counts from one, like `ccec` does when it reports a statement
+impl fmt::Display for DefinitionLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "statement {} of file {}", self.node + 1, self.file + 1)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WorkspaceError {
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    ParserError(#[from] ParserError),
+    #[error("{0}")]
+    DecodeError(InputStreamError),
+}
+
+impl WorkspaceError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WorkspaceError::IoError(_) => "CCE0005",
+            WorkspaceError::ParserError(err) => err.code(),
+            WorkspaceError::DecodeError(_) => "CCE0001",
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            WorkspaceError::IoError(_) | WorkspaceError::DecodeError(_) => None,
+            WorkspaceError::ParserError(err) => err.span(),
+        }
+    }
+
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        match self {
+            WorkspaceError::IoError(_) | WorkspaceError::DecodeError(_) => None,
+            WorkspaceError::ParserError(err) => err.suggestion(),
+        }
+    }
+}
+
+impl WorkspaceParseResult {
+    // Indexes the definitions of files parsed one by one
+    pub fn from_files(files: Vec<FileParseResult>) -> WorkspaceParseResult {
+        let mut definitions: HashMap<u64, Vec<DefinitionLocation>> = HashMap::new();
+
+        for (file, result) in files.iter().enumerate() {
+            for (node, parsed) in result.nodes.iter().flat_map(ParseNode::flatten).enumerate() {
+                let Some(signature) = parsed.signature() else {
+                    continue;
+                };
+
+                definitions
+                    .entry(signature.hash())
+                    .or_default()
+                    .push(DefinitionLocation { file, node });
+            }
+        }
+
+        WorkspaceParseResult { files, definitions }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.files.iter().any(|file| !file.errors.is_empty())
+    }
+
+    // Nodes are counted as `ParseNode::flatten` orders them, so definitions
+    // inside modules have locations of their own
+    pub fn lookup(&self, location: DefinitionLocation) -> Option<&ParseNode> {
+        self.files
+            .get(location.file)?
+            .nodes
+            .iter()
+            .flat_map(ParseNode::flatten)
+            .nth(location.node)
+    }
+}
+
+pub fn parse_file(path: &Path) -> FileParseResult {
+    parse_file_with(path, &ParseOptions::default())
+}
+
+// Limits apply to each file separately
+pub fn parse_file_with_limits(path: &Path, limits: ParserLimits) -> FileParseResult {
+    parse_file_with(
+        path,
+        &ParseOptions {
+            limits,
+            ..ParseOptions::default()
+        },
+    )
+}
+
+pub fn parse_file_with_options(
+    path: &Path,
+    limits: ParserLimits,
+    decode: DecodeOptions,
+) -> FileParseResult {
+    parse_file_with(
+        path,
+        &ParseOptions {
+            limits,
+            decode,
+            ..ParseOptions::default()
+        },
+    )
+}
+
+pub fn parse_file_with(path: &Path, options: &ParseOptions) -> FileParseResult {
+    match read_source(path, options.decode) {
+        Ok(decoded) => FileParseResult {
+            warnings: decoded.warnings,
+            ..parse_source_with(path, &decoded.text, options)
+        },
+        Err(err) => FileParseResult {
+            path: path.to_path_buf(),
+            nodes: Vec::new(),
+            errors: vec![err],
+            warnings: Vec::new(),
+        },
+    }
+}
+
+// Reads a source file in whichever encoding it was saved in
+pub fn read_source(path: &Path, decode: DecodeOptions) -> Result<Decoded, WorkspaceError> {
+    InputStream::open(path, decode).map_err(|err| match err {
+        InputStreamError::ReadError(err) => WorkspaceError::IoError(err),
+        err => WorkspaceError::DecodeError(err),
+    })
+}
+
+pub fn parse_source_with_limits(
+    path: &Path,
+    contents: &str,
+    limits: ParserLimits,
+) -> FileParseResult {
+    parse_source_with(
+        path,
+        contents,
+        &ParseOptions {
+            limits,
+            ..ParseOptions::default()
+        },
+    )
+}
+
+// Parses `contents` as if it had been read from `path`, so
+// `options.decode` goes unused
+pub fn parse_source_with(path: &Path, contents: &str, options: &ParseOptions) -> FileParseResult {
+    let mut result = FileParseResult {
+        path: path.to_path_buf(),
+        nodes: Vec::new(),
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let lexer = Lexer::with_config(InputStream::new(contents), options.lexer.clone());
+    let mut parser = Parser::new(lexer).with_limits(options.limits);
+
+    loop {
+        match parser.next() {
+            Ok(Some(node)) => result.nodes.push(node),
+            Ok(None) => break,
+            Err(err) => {
+                result.errors.push(err.into());
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+pub fn parse_workspace(paths: &[PathBuf]) -> WorkspaceParseResult {
+    parse_workspace_with(paths, &ParseOptions::default())
+}
+
+pub fn parse_workspace_with_limits(
+    paths: &[PathBuf],
+    limits: ParserLimits,
+) -> WorkspaceParseResult {
+    parse_workspace_with(
+        paths,
+        &ParseOptions {
+            limits,
+            ..ParseOptions::default()
+        },
+    )
+}
+
+pub fn parse_workspace_with_options(
+    paths: &[PathBuf],
+    limits: ParserLimits,
+    decode: DecodeOptions,
+) -> WorkspaceParseResult {
+    parse_workspace_with(
+        paths,
+        &ParseOptions {
+            limits,
+            decode,
+            ..ParseOptions::default()
+        },
+    )
+}
+
+pub fn parse_workspace_with(paths: &[PathBuf], options: &ParseOptions) -> WorkspaceParseResult {
+    let threads: usize = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(paths.len())
+        .max(1);
+    let chunk_size: usize = paths.len().div_ceil(threads).max(1);
+
+    // Without threads to spare (or at all, as on wasm32), parse in place
+    let files: Vec<FileParseResult> = if threads == 1 {
+        paths
+            .iter()
+            .map(|path| parse_file_with(path, options))
+            .collect()
+    } else {
+        parse_parallel(paths, chunk_size, options)
+    };
+
+    WorkspaceParseResult::from_files(files)
+}
+
+fn parse_parallel(
+    paths: &[PathBuf],
+    chunk_size: usize,
+    options: &ParseOptions,
+) -> Vec<FileParseResult> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|path| parse_file_with(path, options))
+                        .collect::<Vec<FileParseResult>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("parser thread panicked"))
+            .collect()
+    })
+}
@@ -0,0 +1,139 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+#![cfg(feature = "tokio")]
+
+use cce_ast::*;
+use cce_stream::AsyncInputStream;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn test_lexer_async() {
+    let mut stream = AsyncInputStream::new("howto hello world".as_bytes());
+
+    let next_token = Lexer::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(next_token, Token::Keyword("howto".to_string()));
+
+    let next_token = Lexer::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(next_token, Token::Identifier("hello".to_string()));
+
+    let next_token = Lexer::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(next_token, Token::Identifier("world".to_string()));
+
+    let next_token = Lexer::next_async(&mut stream).await.unwrap();
+    assert_eq!(next_token, None);
+}
+
+#[tokio::test]
+async fn test_lexer_async_number_across_reads() {
+    let (mut writer, reader) = tokio::io::duplex(64);
+    let mut stream = AsyncInputStream::new(reader);
+
+    writer.write_all(b"wait 12").await.unwrap();
+    Lexer::next_async(&mut stream).await.unwrap().unwrap();
+
+    let sender = tokio::spawn(async move {
+        writer.write_all(b"34 times").await.unwrap();
+    });
+
+    let next_token = Lexer::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(
+        next_token,
+        Token::Number {
+            value: "1234".to_string(),
+            unit: None
+        }
+    );
+
+    sender.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_parser_async_chunked() {
+    let (mut writer, reader) = tokio::io::duplex(4);
+    let mut stream = AsyncInputStream::new(reader);
+
+    let sender = tokio::spawn(async move {
+        for chunk in ["say hel", "lo world", "\nsay 'good", "bye'", " | loudly."] {
+            writer.write_all(chunk.as_bytes()).await.unwrap();
+            tokio::task::yield_now().await;
+        }
+    });
+
+    let next_node = Parser::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(
+        next_node,
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Keyword("hello".into()),
+                CommandComponent::Keyword("world".into()),
+            ],
+            modifiers: vec![],
+        })
+    );
+
+    let next_node = Parser::next_async(&mut stream).await.unwrap().unwrap();
+    assert_eq!(
+        next_node,
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Literal("goodbye".to_string()),
+            ],
+            modifiers: vec![Modifier::FreeForm(vec![CommandComponent::Keyword(
+                "loudly".into()
+            )])],
+        })
+    );
+
+    sender.await.unwrap();
+
+    let next_node = Parser::next_async(&mut stream).await.unwrap();
+    assert_eq!(next_node, None);
+    assert_eq!(stream.line, 2);
+}
+
+#[tokio::test]
+async fn test_parser_async_howto() {
+    let source =
+        "howto say hello world?\n- say hello world\n| do not say goodbye\n\nsay hello world";
+    let mut stream = AsyncInputStream::new(source.as_bytes());
+
+    let next_node = Parser::next_async(&mut stream).await.unwrap().unwrap();
+    assert!(matches!(next_node, ParseNode::HowToStatement(_)));
+
+    let next_node = Parser::next_async(&mut stream).await.unwrap().unwrap();
+    assert!(matches!(next_node, ParseNode::Command(_)));
+
+    assert_eq!(Parser::next_async(&mut stream).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_parser_async_error_span() {
+    let mut stream = AsyncInputStream::new("say héllo.\nwave.\n'oops'.\n".as_bytes());
+
+    Parser::next_async(&mut stream).await.unwrap().unwrap();
+    Parser::next_async(&mut stream).await.unwrap().unwrap();
+
+    let err: ParserError = Parser::next_async(&mut stream).await.unwrap_err();
+    let span: Span = err.span().unwrap();
+    assert_eq!((span.line, span.column, span.start), (3, 1, 18));
+}
@@ -0,0 +1,132 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+const SOURCE: &str = "print  'hi'   | with color = 'red'.\n\n\
+howto greet %who:string?\n\
+- say hello to   %who\n\
+- when %who is 'Bob'\n  - wave\n\
+- prepare:\n  - smile | add warmth\n\n\
+whatis a greeting?\n\
+- = say hello\n\
+- $$echo hi$$\n\n  ";
+
+#[test]
+fn test_cst_lossless() {
+    let result: CstParseResult = parse_cst(SOURCE);
+
+    assert!(result.error.is_none());
+    assert_eq!(result.root.to_string(), SOURCE);
+    assert_eq!(result.root.range, 0..SOURCE.len());
+
+    // Tokens tile the source with no gaps or overlaps
+    let mut offset: usize = 0;
+    for token in result.root.tokens() {
+        assert_eq!(token.range.start, offset);
+        assert_eq!(&SOURCE[token.range.clone()], token.text);
+        offset = token.range.end;
+    }
+    assert_eq!(offset, SOURCE.len());
+}
+
+#[test]
+fn test_cst_structure() {
+    let root: SyntaxNode = parse_cst(SOURCE).root;
+    let statements: Vec<&SyntaxNode> = root.nodes().collect();
+
+    assert_eq!(
+        statements.iter().map(|node| node.kind).collect::<Vec<_>>(),
+        vec![SyntaxKind::Command, SyntaxKind::HowTo, SyntaxKind::WhatIs]
+    );
+
+    let command: &SyntaxNode = statements[0];
+    assert_eq!(
+        &SOURCE[command.range.clone()],
+        "print  'hi'   | with color = 'red'."
+    );
+
+    let modifier: &SyntaxNode = command.nodes().next().unwrap();
+    assert_eq!(modifier.kind, SyntaxKind::Modifier);
+    assert_eq!(modifier.to_string(), "| with color = 'red'");
+
+    let steps: Vec<&SyntaxNode> = statements[1]
+        .nodes()
+        .filter(|node| node.kind == SyntaxKind::Step)
+        .collect();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(
+        steps[2]
+            .nodes()
+            .filter(|node| node.kind == SyntaxKind::Step)
+            .count(),
+        1
+    );
+
+    let slot: &SyntaxNode = statements[1]
+        .nodes()
+        .find(|node| node.kind == SyntaxKind::Signature)
+        .and_then(|signature| signature.nodes().next())
+        .unwrap();
+    assert_eq!(slot.kind, SyntaxKind::Slot);
+    assert_eq!(slot.to_string(), "%who:string");
+}
+
+#[test]
+fn test_cst_lower() {
+    assert_eq!(parse_cst(SOURCE).root.lower(), parse_all(SOURCE).unwrap());
+
+    let source: &str = include_str!("./examples/hello.cce");
+    let result: CstParseResult = parse_cst(source);
+
+    assert_eq!(result.root.to_string(), source);
+    assert_eq!(result.root.lower(), parse_all(source).unwrap());
+}
+
+#[test]
+fn test_cst_lower_backref() {
+    let source: &str = "howto greet %who?\n- say hello to &who\n\n";
+
+    assert_eq!(parse_cst(source).root.lower(), parse_all(source).unwrap());
+}
+
+#[test]
+fn test_cst_lower_numbers_and_booleans() {
+    let source: &str = "howto wait %time:number?\n- sleep %time\n\nwait 2.5 | with loud = true.\n";
+
+    assert_eq!(parse_cst(source).root.lower(), parse_all(source).unwrap());
+}
+
+#[test]
+fn test_cst_error() {
+    let source: &str = "greet 'Bob'.\nhowto wave\n- move arm\n";
+    let result: CstParseResult = parse_cst(source);
+
+    assert!(matches!(
+        result.error,
+        Some(ParserError::ExpectedToken { .. })
+    ));
+    assert_eq!(result.root.to_string(), source);
+    assert_eq!(result.root.lower().len(), 1);
+
+    let last: &SyntaxElement = result.root.children.last().unwrap();
+    assert!(matches!(last, SyntaxElement::Token(token) if token.kind == SyntaxKind::Error));
+    assert_eq!(last.range(), 12..source.len());
+}
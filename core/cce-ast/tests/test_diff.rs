@@ -0,0 +1,77 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+const OLD: &str = "howto greet %name?\n- say hello\n\n\
+whatis say hello?\n- $$echo hello$$\n\n\
+howto wave at %who?\n- lift arm\n\n\
+howto dance?\n- move\n\n\
+greet 'bob'.";
+
+#[test]
+fn test_diff_unchanged() {
+    let old: Vec<ParseNode> = parse_all(OLD).unwrap();
+    let new: Vec<ParseNode> = parse_all(&OLD.replace("greet 'bob'.", "greet 'ann'.")).unwrap();
+
+    let diff: AstDiff = diff(&old, &new);
+    assert!(diff.is_empty());
+    assert_eq!(diff.unchanged, 4);
+}
+
+#[test]
+fn test_diff_changes() {
+    let old: Vec<ParseNode> = parse_all(OLD).unwrap();
+    let new: Vec<ParseNode> = parse_all(
+        "howto greet %person?\n- say hello\n\n\
+whatis say hello?\n- $$echo hi$$\n\n\
+howto wave at %who with %hand?\n- lift arm\n\n\
+howto sing?\n- open mouth",
+    )
+    .unwrap();
+
+    let diff: AstDiff = diff(&old, &new);
+    assert_eq!(diff.unchanged, 0);
+
+    // A renamed slot or an added one changes the signature, but not by
+    // enough to make it a different definition
+    let modified: Vec<(&ParseNode, bool)> = diff
+        .modified
+        .iter()
+        .map(|modified| (modified.old, modified.signature_changed()))
+        .collect();
+    assert_eq!(
+        modified,
+        vec![(&old[0], true), (&old[1], false), (&old[2], true)]
+    );
+
+    assert_eq!(diff.added, vec![&new[3]]);
+    assert_eq!(diff.removed, vec![&old[3]]);
+}
+
+#[test]
+fn test_diff_modules() {
+    let old: Vec<ParseNode> = parse_all("howto dance?\n- move\n\n").unwrap();
+    let new: Vec<ParseNode> = parse_all("module party:\nhowto dance?\n- move\n\nend\n").unwrap();
+
+    let diff: AstDiff = diff(&old, &new);
+    assert!(diff.is_empty());
+    assert_eq!(diff.unchanged, 1);
+}
@@ -27,48 +27,51 @@ fn test_parser_example_helloworld() {
     let expected_output = vec![
         ParseNode::Command(Command {
             components: vec![
-                CommandComponent::Keyword("print".to_string()),
+                CommandComponent::Keyword("print".into()),
                 CommandComponent::Literal("Hello, world!".to_string()),
-                CommandComponent::Keyword("to".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("console".to_string()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("console".into()),
             ],
             modifiers: vec![],
         }),
         ParseNode::HowToStatement(HowToStatement {
             signature: vec![
-                CommandComponent::Keyword("print".to_string()),
-                CommandComponent::Keyword("a".to_string()),
-                CommandComponent::Keyword("string".to_string()),
-                CommandComponent::Keyword("to".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("console".to_string()),
+                CommandComponent::Keyword("print".into()),
+                CommandComponent::Keyword("a".into()),
+                CommandComponent::Keyword("string".into()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("console".into()),
             ],
-            body: vec![Command {
+            body: vec![HowToCommand::Command(Command {
                 components: vec![
-                    CommandComponent::Keyword("write".to_string()),
-                    CommandComponent::Keyword("the".to_string()),
-                    CommandComponent::Keyword("string".to_string()),
-                    CommandComponent::Keyword("to".to_string()),
-                    CommandComponent::Keyword("stdout".to_string()),
+                    CommandComponent::Keyword("write".into()),
+                    CommandComponent::Keyword("the".into()),
+                    CommandComponent::Keyword("string".into()),
+                    CommandComponent::Keyword("to".into()),
+                    CommandComponent::Keyword("stdout".into()),
                 ],
-                modifiers: vec![vec![
-                    CommandComponent::Keyword("add".to_string()),
-                    CommandComponent::Keyword("a".to_string()),
-                    CommandComponent::Keyword("newline".to_string()),
-                ]],
-            }],
+                modifiers: vec![Modifier::FreeForm(vec![
+                    CommandComponent::Keyword("add".into()),
+                    CommandComponent::Keyword("a".into()),
+                    CommandComponent::Keyword("newline".into()),
+                ])],
+            })],
         }),
         ParseNode::WhatIsStatement(WhatIsStatement {
             signature: vec![CommandComponent::Literal("stdout".to_string())],
-            body: vec![WhatIsCommand::Command(Command {
-                components: vec![
-                    CommandComponent::Keyword("a".to_string()),
-                    CommandComponent::Keyword("file".to_string()),
-                    CommandComponent::Keyword("stream".to_string()),
-                ],
-                modifiers: vec![],
-            })],
+            alternatives: vec![WhatIsAlternative {
+                canonical: false,
+                command: WhatIsCommand::Command(Command {
+                    components: vec![
+                        CommandComponent::Keyword("a".into()),
+                        CommandComponent::Keyword("file".into()),
+                        CommandComponent::Keyword("stream".into()),
+                    ],
+                    modifiers: vec![],
+                }),
+            }],
         }),
     ];
 
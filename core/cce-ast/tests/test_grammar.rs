@@ -0,0 +1,133 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+fn references(expr: &Expr, names: &mut Vec<&'static str>) {
+    match expr {
+        Expr::Rule(name) => names.push(name),
+        Expr::Seq(items) | Expr::Choice(items) => {
+            items.iter().for_each(|item| references(item, names));
+        }
+        Expr::Optional(item) | Expr::Repeat(item) => references(item, names),
+        Expr::Word { .. } | Expr::Token(_) => {}
+    }
+}
+
+#[test]
+fn test_grammar_rules_resolve() {
+    let grammar: &Grammar = grammar();
+    assert_eq!(grammar.rules[0].name, "program");
+
+    for rule in &grammar.rules {
+        let mut names: Vec<&'static str> = Vec::new();
+        references(&rule.expr, &mut names);
+
+        for name in names {
+            assert!(
+                grammar.rule(name).is_some(),
+                "{} refers to unknown rule {}",
+                rule.name,
+                name
+            );
+        }
+    }
+
+    assert!(grammar.rule("sentence").is_none());
+    assert!(grammar.first("sentence").is_empty());
+}
+
+#[test]
+fn test_grammar_ebnf() {
+    let ebnf: String = grammar().to_string();
+
+    assert!(ebnf.starts_with("program = { statement | newline } ;\n"));
+    assert!(ebnf.contains("\nslot = \"%\" identifier [ \":\" identifier ] ;\n"));
+    assert!(
+        ebnf.contains("\ncomponent = identifier | keyword | literal | number | slot | backref ;\n")
+    );
+    assert!(ebnf.contains("\nalternative = \"-\" [ \"=\" ] ( final_sequence | command ) ;\n"));
+    assert_eq!(ebnf.lines().count(), grammar().rules.len());
+}
+
+#[test]
+fn test_grammar_json() {
+    let json: String = grammar().to_json();
+
+    assert!(json
+        .starts_with("{\"rules\":[{\"name\":\"program\",\"diagram\":{\"type\":\"zero_or_more\""));
+    assert!(json.contains(
+        "{\"name\":\"backref\",\"diagram\":{\"type\":\"sequence\",\"items\":[{\"type\":\"terminal\",\"text\":\"&\"},{\"type\":\"token\",\"text\":\"identifier\"}]}}"
+    ));
+    assert!(json.ends_with("]}"));
+}
+
+#[test]
+fn test_grammar_keywords() {
+    assert_eq!(grammar().keywords(), vec!["howto", "whatis", "module"]);
+    assert_eq!(
+        LexerConfig::default().keywords,
+        vec!["howto", "whatis", "module"]
+    );
+}
+
+#[test]
+fn test_grammar_first() {
+    assert_eq!(
+        grammar().first("component"),
+        vec![
+            TokenKind::Identifier,
+            TokenKind::Keyword,
+            TokenKind::Literal,
+            TokenKind::Number,
+            TokenKind::Percent,
+            TokenKind::Ampersand,
+        ]
+    );
+    assert_eq!(
+        grammar().first("alternative"),
+        vec![TokenKind::Punctuation('-')]
+    );
+
+    // A command can be empty, so what follows it can start one too
+    assert_eq!(grammar().first("step"), vec![TokenKind::Punctuation('-')]);
+    assert!(grammar()
+        .first("command")
+        .contains(&TokenKind::Punctuation('|')));
+}
+
+#[test]
+fn test_grammar_matches_parser_errors() {
+    let mut parser = Parser::from("print 'hi' | with color = .");
+
+    match parser.next().unwrap_err() {
+        ParserError::ExpectedOneOf { expected, .. } => {
+            assert_eq!(expected, grammar().first("component"))
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+
+    match Parser::from("hwoto greet?\n- wave\n").next().unwrap_err() {
+        ParserError::MisspelledKeyword { keyword, .. } => {
+            assert!(grammar().keywords().contains(&keyword.as_str()))
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
@@ -0,0 +1,169 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::ops::Range;
+
+use cce_ast::*;
+
+const SOURCE: &str = "say hello.\nsay goodbye.\n\nhowto say hello?\n- wave\n\nsay hello world.\n";
+
+fn full_parse(source: &str) -> Vec<ParseNode> {
+    let mut parser = Parser::from(source);
+    let mut nodes: Vec<ParseNode> = Vec::new();
+    while let Some(node) = parser.next().unwrap() {
+        nodes.push(node);
+    }
+    nodes
+}
+
+fn replace(document: &mut Document, old: &str, new: &str) -> Vec<usize> {
+    let start: usize = document.source().find(old).unwrap();
+
+    document
+        .apply(&TextEdit {
+            range: start..start + old.len(),
+            replacement: new.to_string(),
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_document_parse() {
+    let document = Document::parse(SOURCE.to_string()).unwrap();
+
+    assert_eq!(
+        document.nodes().cloned().collect::<Vec<_>>(),
+        full_parse(SOURCE)
+    );
+
+    let statements: &[Statement] = document.statements();
+    assert_eq!(statements[0].range.start, 0);
+    for pair in statements.windows(2) {
+        assert_eq!(pair[0].range.end, pair[1].range.start);
+    }
+}
+
+#[test]
+fn test_document_edit_statement() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+
+    let changed: Vec<usize> = replace(&mut document, "goodbye", "farewell");
+
+    assert_eq!(changed, vec![1]);
+    assert_eq!(
+        document.nodes().cloned().collect::<Vec<_>>(),
+        full_parse(&SOURCE.replace("goodbye", "farewell"))
+    );
+}
+
+#[test]
+fn test_document_edit_shifts_ranges() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+
+    replace(&mut document, "say hello.", "say hello there.");
+
+    let reparsed = Document::parse(document.source().to_string()).unwrap();
+    assert_eq!(document.statements(), reparsed.statements());
+}
+
+#[test]
+fn test_document_edit_joins_statements() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+
+    let changed: Vec<usize> = replace(&mut document, "hello.\nsay", "hello\n| then say");
+
+    assert_eq!(changed, vec![0]);
+    assert_eq!(document.statements().len(), 3);
+    assert_eq!(
+        document.nodes().cloned().collect::<Vec<_>>(),
+        full_parse(document.source())
+    );
+}
+
+#[test]
+fn test_document_edit_append() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+    let end: usize = document.source().len();
+
+    let changed: Vec<usize> = document
+        .apply(&TextEdit {
+            range: end..end,
+            replacement: "say more.\n".to_string(),
+        })
+        .unwrap();
+
+    assert_eq!(changed, vec![4]);
+    assert_eq!(
+        document.nodes().cloned().collect::<Vec<_>>(),
+        full_parse(document.source())
+    );
+}
+
+#[test]
+fn test_document_edit_error() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+
+    assert!(document
+        .apply(&TextEdit {
+            range: 0..3,
+            replacement: "?".to_string(),
+        })
+        .is_err());
+    assert_eq!(document.source(), SOURCE);
+}
+
+#[test]
+fn test_document_edit_error_span() {
+    let mut document = Document::parse(SOURCE.to_string()).unwrap();
+    let start: usize = SOURCE.find("say hello world").unwrap();
+    let edit = TextEdit {
+        range: start..start + "say hello world".len(),
+        replacement: "'oops'".to_string(),
+    };
+
+    let err: ParserError = document.apply(&edit).unwrap_err();
+
+    let mut source: String = SOURCE.to_string();
+    source.replace_range(edit.range.clone(), &edit.replacement);
+    assert_eq!(err.span(), Document::parse(source).unwrap_err().span());
+
+    let span: Span = err.span().unwrap();
+    assert_eq!((span.line, span.column, span.start), (7, 1, start));
+}
+
+#[test]
+fn test_document_edit_invalid_range() {
+    let mut document = Document::parse("say héllo.\n".to_string()).unwrap();
+
+    let reversed: Range<usize> = Range { start: 5, end: 4 };
+
+    for range in [reversed, 0..100, 6..7] {
+        let err: ParserError = document
+            .apply(&TextEdit {
+                range: range.clone(),
+                replacement: "x".to_string(),
+            })
+            .unwrap_err();
+
+        assert!(matches!(err, ParserError::InvalidEdit { range: ref found } if *found == range));
+        assert_eq!(err.code(), "CCE0113");
+    }
+    assert_eq!(document.source(), "say héllo.\n");
+}
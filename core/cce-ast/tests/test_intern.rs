@@ -0,0 +1,136 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::thread;
+
+use cce_ast::*;
+use circelang_hash::CirceHash;
+
+fn keywords(source: &str) -> Vec<Symbol> {
+    let mut parser: Parser = Parser::from(source);
+    let mut keywords: Vec<Symbol> = Vec::new();
+
+    while let Some(node) = parser.next().unwrap() {
+        if let ParseNode::Command(command) = node {
+            for component in command.components {
+                if let CommandComponent::Keyword(keyword) = component {
+                    keywords.push(keyword);
+                }
+            }
+        }
+    }
+
+    keywords
+}
+
+#[test]
+fn test_intern_same_word_same_symbol() {
+    let print: Symbol = Symbol::intern("print");
+
+    assert_eq!(Symbol::intern("print"), print);
+    assert_eq!(Symbol::from("print".to_string()).id(), print.id());
+    assert_ne!(Symbol::intern("console"), print);
+    assert_eq!(print.as_str(), "print");
+    assert_eq!(print, "print");
+    assert_eq!(print.to_string(), "print");
+    assert_eq!(format!("{:?}", print), "\"print\"");
+}
+
+#[test]
+fn test_intern_shared_across_parses() {
+    let first: Vec<Symbol> = keywords("print to the console.\n");
+    let second: Vec<Symbol> = keywords("print the console output.\n");
+
+    assert_eq!(first[0].id(), second[0].id());
+    assert_eq!(first[2].id(), second[1].id());
+    assert_eq!(first[3].id(), second[2].id());
+}
+
+#[test]
+fn test_intern_across_threads() {
+    let handles: Vec<thread::JoinHandle<Vec<Symbol>>> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                ["alpha", "beta", "gamma"]
+                    .iter()
+                    .map(|word| Symbol::intern(word))
+                    .collect()
+            })
+        })
+        .collect();
+
+    let symbols: Vec<Vec<Symbol>> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    for other in &symbols[1..] {
+        assert_eq!(other, &symbols[0]);
+    }
+}
+
+#[test]
+fn test_intern_order_and_hash() {
+    // Interned in the opposite order to how they sort
+    let zebra: Symbol = Symbol::intern("zebra-order");
+    let apple: Symbol = Symbol::intern("apple-order");
+
+    let mut sorted: Vec<Symbol> = vec![zebra.clone(), apple.clone()];
+    sorted.sort();
+    assert_eq!(sorted, vec![apple, zebra.clone()]);
+
+    assert_eq!(
+        CirceHash::hash(&zebra),
+        CirceHash::hash(&"zebra-order".to_string())
+    );
+}
+
+#[test]
+fn test_intern_many_words() {
+    // Enough words to fill several chunks of the word table
+    let symbols: Vec<Symbol> = (0..5000)
+        .map(|i| Symbol::intern(&format!("many-{}", i)))
+        .collect();
+
+    for (i, symbol) in symbols.iter().enumerate() {
+        assert_eq!(symbol.as_str(), format!("many-{}", i));
+        assert_eq!(Symbol::intern(symbol), *symbol);
+    }
+
+    assert!(Symbol::interned() >= 5000);
+}
+
+#[test]
+fn test_intern_slot_names() {
+    let mut parser: Parser = Parser::from("howto greet %who?\n- say hi to &who\n");
+
+    match parser.next().unwrap() {
+        Some(ParseNode::HowToStatement(howto)) => {
+            assert_eq!(
+                howto.signature[1],
+                CommandComponent::Slot {
+                    name: Symbol::intern("who"),
+                    ty: None
+                }
+            );
+        }
+        node => panic!("unexpected node: {:?}", node),
+    }
+}
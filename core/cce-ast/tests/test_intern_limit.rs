@@ -0,0 +1,51 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// The limit is global, so this runs in a process of its own
+
+use std::collections::HashSet;
+
+use cce_ast::*;
+use circelang_hash::CirceHash;
+
+#[test]
+fn test_intern_limit() {
+    let kept: Symbol = Symbol::intern("kept");
+    Symbol::set_interned_limit(Symbol::interned());
+
+    let owned: Symbol = Symbol::intern("owned");
+    assert_eq!(owned.id(), None);
+    assert_eq!(owned, Symbol::intern("owned"));
+    assert_eq!(owned.as_str(), "owned");
+    assert_eq!(Symbol::intern("kept"), kept);
+    assert!(kept.id().is_some());
+
+    // Raising the limit interns the word, which still equals the symbols
+    // made before
+    Symbol::set_interned_limit(MAX_INTERNED);
+    let interned: Symbol = Symbol::intern("owned");
+    assert!(interned.id().is_some());
+    assert_eq!(interned, owned);
+    assert!(HashSet::from([owned]).contains(&interned));
+    assert_eq!(
+        CirceHash::hash(&interned),
+        CirceHash::hash(&"owned".to_string())
+    );
+}
@@ -18,7 +18,8 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
-use cce_ast::{Lexer, Token};
+use cce_ast::{Lexer, LexerConfig, LexerError, Parser, ParserError, Span, Token};
+use cce_stream::InputStream;
 
 #[test]
 fn test_lexer_basic() {
@@ -76,13 +77,13 @@ fn test_lexer_peek() {
     let mut lexer = Lexer::from("howto hello world");
 
     let next_token = lexer.peek().unwrap().unwrap();
-    assert_eq!(next_token, Token::Keyword("howto".to_string()));
+    assert_eq!(next_token, &Token::Keyword("howto".to_string()));
 
     let next_token = lexer.next().unwrap().unwrap();
     assert_eq!(next_token, Token::Keyword("howto".to_string()));
 
     let next_token = lexer.peek().unwrap().unwrap();
-    assert_eq!(next_token, Token::Identifier("hello".to_string()));
+    assert_eq!(next_token, &Token::Identifier("hello".to_string()));
 
     let next_token = lexer.next().unwrap().unwrap();
     assert_eq!(next_token, Token::Identifier("hello".to_string()));
@@ -129,3 +130,310 @@ fn test_lexer_slot() {
     let next_token = lexer.next().unwrap().unwrap();
     assert_eq!(next_token, Token::Identifier("hello".to_string()));
 }
+
+#[test]
+fn test_lexer_span() {
+    let mut lexer = Lexer::from("howto\n  'hello'");
+
+    lexer.next().unwrap();
+    assert_eq!(
+        lexer.span(),
+        Span {
+            start: 0,
+            end: 5,
+            line: 1,
+            column: 1
+        }
+    );
+
+    assert_eq!(lexer.peek().unwrap(), Some(&Token::Newline));
+    assert_eq!(lexer.span().start, 0);
+    assert_eq!(lexer.peek_span().unwrap().start, 5);
+
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+    assert_eq!(
+        lexer.span(),
+        Span {
+            start: 8,
+            end: 15,
+            line: 2,
+            column: 3
+        }
+    );
+}
+
+#[test]
+fn test_lexer_config() {
+    let config = LexerConfig {
+        keywords: vec!["howto".to_string(), "define".to_string()],
+        quotes: vec!['\'', '"'],
+        punctuation: vec!['-', '|', ';'],
+        units: Vec::new(),
+        terminator: '.',
+    };
+    let mut lexer = Lexer::with_config(InputStream::new("define \"it's\" ; whatis"), config);
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Keyword("define".to_string()))
+    );
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Literal("it's".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), Some(Token::Punctuation(';')));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("whatis".to_string()))
+    );
+}
+
+#[test]
+fn test_lexer_config_terminator() {
+    let config = LexerConfig {
+        terminator: ';',
+        ..LexerConfig::default()
+    };
+    let mut lexer = Lexer::with_config(InputStream::new("wait 2.5; go."), config);
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("wait".to_string()))
+    );
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Number {
+            value: "2.5".to_string(),
+            unit: None
+        })
+    );
+    assert_eq!(lexer.next().unwrap(), Some(Token::Dot));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("go".to_string()))
+    );
+    assert!(matches!(
+        lexer.next(),
+        Err(LexerError::UnexpectedCharacter('.'))
+    ));
+}
+
+#[test]
+fn test_lexer_config_default() {
+    let mut lexer = Lexer::from("\"hi\"");
+
+    assert!(matches!(
+        lexer.next(),
+        Err(LexerError::UnexpectedCharacter('"'))
+    ));
+}
+
+#[test]
+fn test_parser_unknown_statement() {
+    let config = LexerConfig {
+        keywords: vec![
+            "howto".to_string(),
+            "whatis".to_string(),
+            "define".to_string(),
+        ],
+        ..LexerConfig::default()
+    };
+    let mut parser = Parser::new(Lexer::with_config(
+        InputStream::new("define hello?"),
+        config,
+    ));
+
+    let err: ParserError = parser.next().unwrap_err();
+    assert!(
+        matches!(err, ParserError::UnknownStatement { ref keyword, .. } if keyword == "define")
+    );
+    assert_eq!(err.span().map(|span| span.start), Some(0));
+}
+
+#[test]
+fn test_lexer_unicode() {
+    let mut lexer = Lexer::from("grüße 'naïve ✓' 日本\n- $$ünï$$");
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("grüße".to_string()))
+    );
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Literal("naïve ✓".to_string()))
+    );
+
+    // Columns count chars, while start and end are byte offsets
+    assert_eq!(
+        lexer.span(),
+        Span {
+            start: 8,
+            end: 20,
+            line: 1,
+            column: 7
+        }
+    );
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("日本".to_string()))
+    );
+    assert_eq!(lexer.span().column, 17);
+
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::FinalSequence("ünï".to_string()))
+    );
+    assert_eq!(lexer.span().column, 3);
+}
+
+#[test]
+fn test_lexer_checkpoint() {
+    let mut lexer = Lexer::from("howto greet %name:string");
+    lexer.next().unwrap();
+
+    let span: Span = lexer.span();
+    assert_eq!(
+        lexer.peek().unwrap(),
+        Some(&Token::Identifier("greet".to_string()))
+    );
+
+    let checkpoint = lexer.checkpoint();
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+    assert_eq!(lexer.next().unwrap(), Some(Token::Colon));
+
+    lexer.restore(checkpoint);
+    assert_eq!(lexer.span(), span);
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("greet".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), Some(Token::Percent));
+}
+
+#[test]
+fn test_lexer_peek_n() {
+    let mut lexer = Lexer::from("- a\n- b");
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+
+    assert_eq!(lexer.peek_n(1).unwrap(), Some(&Token::Punctuation('-')));
+    assert_eq!(lexer.peek_n(3).unwrap(), None);
+    assert_eq!(
+        lexer.peek_span().unwrap(),
+        Span {
+            start: 3,
+            end: 4,
+            line: 1,
+            column: 4
+        }
+    );
+
+    assert_eq!(lexer.next().unwrap(), Some(Token::Newline));
+    assert_eq!(lexer.span().start, 3);
+    assert_eq!(lexer.next().unwrap(), Some(Token::Punctuation('-')));
+    assert_eq!(lexer.span().line, 2);
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("b".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), None);
+}
+
+#[test]
+fn test_lexer_line_endings() {
+    let mut lexer = Lexer::from("say hi\r\n\u{2028}say 'a\r\nb'");
+    lexer.next().unwrap();
+    lexer.next().unwrap();
+
+    assert_eq!(lexer.next().unwrap(), Some(Token::Newline));
+    assert_eq!(lexer.span().end, 8);
+    assert_eq!(lexer.next().unwrap(), Some(Token::Newline));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("say".to_string()))
+    );
+    assert_eq!((lexer.span().line, lexer.span().column), (3, 1));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Literal("a\nb".to_string()))
+    );
+}
+
+#[test]
+fn test_lexer_numbers() {
+    let mut lexer = Lexer::from("wait 5. add 3.14 to 2x");
+
+    let number = |value: &str| Token::Number {
+        value: value.to_string(),
+        unit: None,
+    };
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("wait".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), Some(number("5")));
+    // A dot without digits after it ends the statement
+    assert_eq!(lexer.next().unwrap(), Some(Token::Dot));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("add".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), Some(number("3.14")));
+    assert_eq!(lexer.span().start, 12);
+    assert_eq!(lexer.span().end, 16);
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("to".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), Some(number("2")));
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("x".to_string()))
+    );
+    assert_eq!(lexer.next().unwrap(), None);
+}
+
+#[test]
+fn test_lexer_units() {
+    let config = LexerConfig {
+        units: vec!["seconds".to_string(), "ms".to_string()],
+        ..LexerConfig::default()
+    };
+    let mut lexer = Lexer::with_config(InputStream::new("wait 5 seconds then 10 times"), config);
+
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("wait".to_string()))
+    );
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Number {
+            value: "5".to_string(),
+            unit: Some("seconds".to_string()),
+        })
+    );
+    assert_eq!(lexer.span().end, 14);
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("then".to_string()))
+    );
+    // Other words after a number are left for the next token
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Number {
+            value: "10".to_string(),
+            unit: None,
+        })
+    );
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("times".to_string()))
+    );
+}
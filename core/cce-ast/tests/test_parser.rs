@@ -19,6 +19,7 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 */
 
 use cce_ast::*;
+use cce_stream::InputStream;
 
 #[test]
 fn test_parser_basic() {
@@ -27,9 +28,9 @@ fn test_parser_basic() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::Command(Command {
         components: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
         modifiers: vec![],
     });
@@ -44,7 +45,7 @@ fn test_parser_literal() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::Command(Command {
         components: vec![
-            CommandComponent::Keyword("say".to_string()),
+            CommandComponent::Keyword("say".into()),
             CommandComponent::Literal("hello world".to_string()),
         ],
         modifiers: vec![],
@@ -60,15 +61,15 @@ fn test_parser_modifier() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::Command(Command {
         components: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
-        modifiers: vec![vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
-        ]],
+        modifiers: vec![Modifier::FreeForm(vec![
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
+        ])],
     });
 
     assert_eq!(next_node, expected_node);
@@ -81,21 +82,21 @@ fn test_parser_modifier_multiple() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::Command(Command {
         components: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
         modifiers: vec![
-            vec![
-                CommandComponent::Keyword("say".to_string()),
-                CommandComponent::Keyword("hello".to_string()),
-                CommandComponent::Keyword("world".to_string()),
-            ],
-            vec![
-                CommandComponent::Keyword("say".to_string()),
-                CommandComponent::Keyword("hello".to_string()),
-                CommandComponent::Keyword("world".to_string()),
-            ],
+            Modifier::FreeForm(vec![
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Keyword("hello".into()),
+                CommandComponent::Keyword("world".into()),
+            ]),
+            Modifier::FreeForm(vec![
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Keyword("hello".into()),
+                CommandComponent::Keyword("world".into()),
+            ]),
         ],
     });
 
@@ -110,23 +111,23 @@ fn test_parser_howto() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
         signature: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
-        body: vec![Command {
+        body: vec![HowToCommand::Command(Command {
             components: vec![
-                CommandComponent::Keyword("say".to_string()),
-                CommandComponent::Keyword("hello".to_string()),
-                CommandComponent::Keyword("world".to_string()),
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Keyword("hello".into()),
+                CommandComponent::Keyword("world".into()),
             ],
-            modifiers: vec![vec![
-                CommandComponent::Keyword("do".to_string()),
-                CommandComponent::Keyword("not".to_string()),
-                CommandComponent::Keyword("say".to_string()),
-                CommandComponent::Keyword("goodbye".to_string()),
-            ]],
-        }],
+            modifiers: vec![Modifier::FreeForm(vec![
+                CommandComponent::Keyword("do".into()),
+                CommandComponent::Keyword("not".into()),
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Keyword("goodbye".into()),
+            ])],
+        })],
     });
 
     assert_eq!(next_node, expected_node);
@@ -141,33 +142,33 @@ fn test_parser_howto_multiple() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
         signature: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
         body: vec![
-            Command {
+            HowToCommand::Command(Command {
                 components: vec![
-                    CommandComponent::Keyword("say".to_string()),
-                    CommandComponent::Keyword("hello".to_string()),
-                    CommandComponent::Keyword("world".to_string()),
+                    CommandComponent::Keyword("say".into()),
+                    CommandComponent::Keyword("hello".into()),
+                    CommandComponent::Keyword("world".into()),
                 ],
-                modifiers: vec![vec![
-                    CommandComponent::Keyword("do".to_string()),
-                    CommandComponent::Keyword("not".to_string()),
-                    CommandComponent::Keyword("say".to_string()),
-                    CommandComponent::Keyword("goodbye".to_string()),
-                ]],
-            },
-            Command {
+                modifiers: vec![Modifier::FreeForm(vec![
+                    CommandComponent::Keyword("do".into()),
+                    CommandComponent::Keyword("not".into()),
+                    CommandComponent::Keyword("say".into()),
+                    CommandComponent::Keyword("goodbye".into()),
+                ])],
+            }),
+            HowToCommand::Command(Command {
                 components: vec![
-                    CommandComponent::Keyword("say".to_string()),
-                    CommandComponent::Keyword("hello".to_string()),
-                    CommandComponent::Keyword("world".to_string()),
-                    CommandComponent::Keyword("again".to_string()),
+                    CommandComponent::Keyword("say".into()),
+                    CommandComponent::Keyword("hello".into()),
+                    CommandComponent::Keyword("world".into()),
+                    CommandComponent::Keyword("again".into()),
                 ],
                 modifiers: vec![],
-            },
+            }),
         ],
     });
 
@@ -181,20 +182,23 @@ fn test_parser_whatis() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::WhatIsStatement(WhatIsStatement {
         signature: vec![
-            CommandComponent::Keyword("the".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("the".into()),
+            CommandComponent::Keyword("world".into()),
         ],
-        body: vec![WhatIsCommand::Command(Command {
-            components: vec![
-                CommandComponent::Keyword("a".to_string()),
-                CommandComponent::Keyword("planet".to_string()),
-            ],
-            modifiers: vec![vec![
-                CommandComponent::Keyword("in".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("universe".to_string()),
-            ]],
-        })],
+        alternatives: vec![WhatIsAlternative {
+            canonical: false,
+            command: WhatIsCommand::Command(Command {
+                components: vec![
+                    CommandComponent::Keyword("a".into()),
+                    CommandComponent::Keyword("planet".into()),
+                ],
+                modifiers: vec![Modifier::FreeForm(vec![
+                    CommandComponent::Keyword("in".into()),
+                    CommandComponent::Keyword("the".into()),
+                    CommandComponent::Keyword("universe".into()),
+                ])],
+            }),
+        }],
     });
 
     assert_eq!(next_node, expected_node);
@@ -209,32 +213,38 @@ fn test_parser_whatis_multiple() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::WhatIsStatement(WhatIsStatement {
         signature: vec![
-            CommandComponent::Keyword("the".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("the".into()),
+            CommandComponent::Keyword("world".into()),
         ],
-        body: vec![
-            WhatIsCommand::Command(Command {
-                components: vec![
-                    CommandComponent::Keyword("a".to_string()),
-                    CommandComponent::Keyword("planet".to_string()),
-                ],
-                modifiers: vec![vec![
-                    CommandComponent::Keyword("in".to_string()),
-                    CommandComponent::Keyword("the".to_string()),
-                    CommandComponent::Keyword("universe".to_string()),
-                ]],
-            }),
-            WhatIsCommand::Command(Command {
-                components: vec![
-                    CommandComponent::Keyword("a".to_string()),
-                    CommandComponent::Keyword("planet".to_string()),
-                    CommandComponent::Keyword("in".to_string()),
-                    CommandComponent::Keyword("the".to_string()),
-                    CommandComponent::Keyword("solar".to_string()),
-                    CommandComponent::Keyword("system".to_string()),
-                ],
-                modifiers: vec![],
-            }),
+        alternatives: vec![
+            WhatIsAlternative {
+                canonical: false,
+                command: WhatIsCommand::Command(Command {
+                    components: vec![
+                        CommandComponent::Keyword("a".into()),
+                        CommandComponent::Keyword("planet".into()),
+                    ],
+                    modifiers: vec![Modifier::FreeForm(vec![
+                        CommandComponent::Keyword("in".into()),
+                        CommandComponent::Keyword("the".into()),
+                        CommandComponent::Keyword("universe".into()),
+                    ])],
+                }),
+            },
+            WhatIsAlternative {
+                canonical: false,
+                command: WhatIsCommand::Command(Command {
+                    components: vec![
+                        CommandComponent::Keyword("a".into()),
+                        CommandComponent::Keyword("planet".into()),
+                        CommandComponent::Keyword("in".into()),
+                        CommandComponent::Keyword("the".into()),
+                        CommandComponent::Keyword("solar".into()),
+                        CommandComponent::Keyword("system".into()),
+                    ],
+                    modifiers: vec![],
+                }),
+            },
         ],
     });
 
@@ -249,28 +259,28 @@ fn test_parser_howto_multiple_nomod() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
         signature: vec![
-            CommandComponent::Keyword("say".to_string()),
-            CommandComponent::Keyword("hello".to_string()),
-            CommandComponent::Keyword("world".to_string()),
+            CommandComponent::Keyword("say".into()),
+            CommandComponent::Keyword("hello".into()),
+            CommandComponent::Keyword("world".into()),
         ],
         body: vec![
-            Command {
+            HowToCommand::Command(Command {
                 components: vec![
-                    CommandComponent::Keyword("say".to_string()),
-                    CommandComponent::Keyword("hello".to_string()),
-                    CommandComponent::Keyword("world".to_string()),
+                    CommandComponent::Keyword("say".into()),
+                    CommandComponent::Keyword("hello".into()),
+                    CommandComponent::Keyword("world".into()),
                 ],
                 modifiers: vec![],
-            },
-            Command {
+            }),
+            HowToCommand::Command(Command {
                 components: vec![
-                    CommandComponent::Keyword("say".to_string()),
-                    CommandComponent::Keyword("hello".to_string()),
-                    CommandComponent::Keyword("world".to_string()),
-                    CommandComponent::Keyword("again".to_string()),
+                    CommandComponent::Keyword("say".into()),
+                    CommandComponent::Keyword("hello".into()),
+                    CommandComponent::Keyword("world".into()),
+                    CommandComponent::Keyword("again".into()),
                 ],
                 modifiers: vec![],
-            },
+            }),
         ],
     });
 
@@ -284,8 +294,11 @@ fn test_parser_slot() {
     let next_node: ParseNode = parser.next().unwrap().unwrap();
     let expected_node: ParseNode = ParseNode::Command(Command {
         components: vec![
-            CommandComponent::Keyword("read".to_string()),
-            CommandComponent::Slot("hello".to_string()),
+            CommandComponent::Keyword("read".into()),
+            CommandComponent::Slot {
+                name: "hello".into(),
+                ty: None,
+            },
         ],
         modifiers: vec![],
     });
@@ -300,3 +313,677 @@ fn test_parser_openslot() {
 
     parser.next().unwrap().unwrap();
 }
+
+#[test]
+fn test_parser_expected_token() {
+    let mut parser = Parser::from("howto say hello\n- say hello");
+
+    let err: ParserError = parser.next().unwrap_err();
+
+    match &err {
+        ParserError::ExpectedToken {
+            expected,
+            found,
+            span,
+        } => {
+            assert_eq!(*expected, TokenKind::Question);
+            assert_eq!(*found, Some(Token::Newline));
+            assert_eq!((span.line, span.column), (1, 16));
+            assert_eq!((span.start, span.end), (15, 16));
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+
+    assert_eq!(err.to_string(), "Syntax error: Expected '?', found newline");
+}
+
+#[test]
+fn test_parser_expected_one_of() {
+    let mut parser = Parser::from("whatis the world?\n-$$ planet $$\n say");
+
+    let err: ParserError = parser.next().unwrap_err();
+
+    assert!(matches!(err, ParserError::ExpectedOneOf { .. }));
+    assert_eq!(err.span().map(|span| span.line), Some(3));
+    assert_eq!(
+        err.to_string(),
+        "Syntax error: Expected newline or '-', found identifier"
+    );
+}
+
+#[test]
+fn test_parser_unexpected_statement_start() {
+    let mut parser = Parser::from("\n\n- say hello");
+
+    let err: ParserError = parser.next().unwrap_err();
+
+    match err {
+        ParserError::UnexpectedStatementStart { found, span } => {
+            assert_eq!(found, Token::Punctuation('-'));
+            assert_eq!((span.line, span.column), (3, 1));
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+}
+
+fn keywords(words: &str) -> Command {
+    Command {
+        components: words
+            .split(' ')
+            .map(|word| CommandComponent::Keyword(word.into()))
+            .collect(),
+        modifiers: vec![],
+    }
+}
+
+#[test]
+fn test_parser_howto_nested() {
+    let mut parser = Parser::from(
+        "howto greet everyone?\n- for each person:\n  - say hello\n  - wave\n- sit down",
+    );
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
+        signature: keywords("greet everyone").components,
+        body: vec![
+            HowToCommand::Block {
+                head: keywords("for each person"),
+                body: vec![
+                    HowToCommand::Command(keywords("say hello")),
+                    HowToCommand::Command(keywords("wave")),
+                ],
+            },
+            HowToCommand::Command(keywords("sit down")),
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+}
+
+#[test]
+fn test_parser_howto_nested_indent_only() {
+    let mut parser =
+        Parser::from("howto greet?\n- say hello\n    - loudly\n        - very loudly\n- wave");
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
+        signature: keywords("greet").components,
+        body: vec![
+            HowToCommand::Block {
+                head: keywords("say hello"),
+                body: vec![HowToCommand::Block {
+                    head: keywords("loudly"),
+                    body: vec![HowToCommand::Command(keywords("very loudly"))],
+                }],
+            },
+            HowToCommand::Command(keywords("wave")),
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+}
+
+#[test]
+fn test_parser_howto_when() {
+    let mut parser = Parser::from(
+        "howto leave?\n- when the door is closed\n  - open the door\n- walk out\n\nleave.",
+    );
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
+        signature: keywords("leave").components,
+        body: vec![
+            HowToCommand::When {
+                condition: keywords("the door is closed"),
+                body: vec![HowToCommand::Command(keywords("open the door"))],
+            },
+            HowToCommand::Command(keywords("walk out")),
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+    assert_eq!(
+        parser.next().unwrap().unwrap(),
+        ParseNode::Command(keywords("leave"))
+    );
+}
+
+#[test]
+fn test_parser_howto_inline_steps() {
+    let mut parser = Parser::from("howto greet?\n- say hello - wave");
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
+        signature: keywords("greet").components,
+        body: vec![
+            HowToCommand::Command(keywords("say hello")),
+            HowToCommand::Command(keywords("wave")),
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+}
+
+#[test]
+fn test_parser_howto_when_without_body() {
+    // Without nested steps, `when` starts an ordinary command
+    for source in [
+        "howto leave?\n- when the door is closed\n- walk out",
+        "howto leave?\n- when the door is closed\n- walk out\n\n",
+    ] {
+        let mut parser = Parser::from(source);
+
+        let next_node: ParseNode = parser.next().unwrap().unwrap();
+        let expected_node: ParseNode = ParseNode::HowToStatement(HowToStatement {
+            signature: keywords("leave").components,
+            body: vec![
+                HowToCommand::Command(keywords("when the door is closed")),
+                HowToCommand::Command(keywords("walk out")),
+            ],
+        });
+
+        assert_eq!(next_node, expected_node);
+    }
+
+    let mut parser = Parser::from("howto greet?\n- when done\n\n");
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::HowToStatement(howto)) if howto.body == vec![HowToCommand::Command(keywords("when done"))]
+    ));
+
+    // A `:` still needs steps after it
+    let mut parser = Parser::from("howto leave?\n- when the door is closed:\n\n");
+    assert!(matches!(
+        parser.next().unwrap_err(),
+        ParserError::ExpectedToken {
+            expected: TokenKind::Punctuation('-'),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_parser_howto_unindented_block() {
+    for source in [
+        "howto cook?\n- prepare:\n- chop onions\n\n",
+        "howto leave?\n  - when the door is closed:\n- walk out\n\n",
+    ] {
+        let mut parser = Parser::from(source);
+
+        match parser.next().unwrap_err() {
+            ParserError::UnindentedBlock { span } => assert_eq!(span.line, 3),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
+
+#[test]
+fn test_parser_howto_step_after_dot() {
+    let mut parser = Parser::from("howto greet?\n- say hello.\n\n- wave.\nsmile");
+
+    match parser.next().unwrap() {
+        Some(ParseNode::HowToStatement(statement)) => assert_eq!(statement.body.len(), 2),
+        node => panic!("unexpected node: {:?}", node),
+    }
+
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(_))
+    ));
+}
+
+#[test]
+fn test_parser_howto_inconsistent_indentation() {
+    let mut parser = Parser::from("howto greet?\n- say hello\n    - loudly\n  - wave");
+
+    let err: ParserError = parser.next().unwrap_err();
+
+    match err {
+        ParserError::InconsistentIndentation { span } => {
+            assert_eq!((span.line, span.column), (4, 3));
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_parser_whatis_canonical() {
+    let mut parser =
+        Parser::from("whatis stdout?\n- the console output\n- = file descriptor '1'\n\n");
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::WhatIsStatement(WhatIsStatement {
+        signature: keywords("stdout").components,
+        alternatives: vec![
+            WhatIsAlternative {
+                canonical: false,
+                command: WhatIsCommand::Command(keywords("the console output")),
+            },
+            WhatIsAlternative {
+                canonical: true,
+                command: WhatIsCommand::Command(Command {
+                    components: vec![
+                        CommandComponent::Keyword("file".into()),
+                        CommandComponent::Keyword("descriptor".into()),
+                        CommandComponent::Literal("1".to_string()),
+                    ],
+                    modifiers: vec![],
+                }),
+            },
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+
+    if let ParseNode::WhatIsStatement(whatis) = next_node {
+        assert_eq!(whatis.canonical(), Some(&whatis.alternatives[1]));
+    }
+}
+
+#[test]
+fn test_parser_whatis_canonical_default() {
+    let mut parser = Parser::from("whatis stdout?\n- the console output\n- a stream\n\n");
+
+    if let Some(ParseNode::WhatIsStatement(whatis)) = parser.next().unwrap() {
+        assert_eq!(whatis.canonical(), Some(&whatis.alternatives[0]));
+    } else {
+        panic!("expected a whatis statement");
+    }
+}
+
+#[test]
+fn test_parser_whatis_duplicate_canonical() {
+    let mut parser = Parser::from("whatis stdout?\n- = the console output\n- = a stream\n\n");
+
+    let err: ParserError = parser.next().unwrap_err();
+
+    match err {
+        ParserError::DuplicateCanonical { span } => {
+            assert_eq!((span.line, span.column), (3, 3));
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_parser_typed_slot() {
+    let mut parser =
+        Parser::from("howto greet %name:string %count:number %who times?\n- say hi\n\n");
+
+    if let Some(ParseNode::HowToStatement(howto)) = parser.next().unwrap() {
+        assert_eq!(
+            howto.signature,
+            vec![
+                CommandComponent::Keyword("greet".into()),
+                CommandComponent::Slot {
+                    name: "name".into(),
+                    ty: Some(SlotType::String),
+                },
+                CommandComponent::Slot {
+                    name: "count".into(),
+                    ty: Some(SlotType::Number),
+                },
+                CommandComponent::Slot {
+                    name: "who".into(),
+                    ty: None,
+                },
+                CommandComponent::Keyword("times".into()),
+            ]
+        );
+    } else {
+        panic!("expected a howto statement");
+    }
+}
+
+#[test]
+fn test_parser_slot_before_block_colon() {
+    let mut parser = Parser::from("howto greet %name?\n- for each %name:\n  - say hi\n\n");
+
+    if let Some(ParseNode::HowToStatement(howto)) = parser.next().unwrap() {
+        match &howto.body[0] {
+            HowToCommand::Block { head, body } => {
+                assert_eq!(
+                    head.components.last(),
+                    Some(&CommandComponent::Slot {
+                        name: "name".into(),
+                        ty: None,
+                    })
+                );
+                assert_eq!(body.len(), 1);
+            }
+            other => panic!("expected a block, found {:?}", other),
+        }
+    } else {
+        panic!("expected a howto statement");
+    }
+}
+
+#[test]
+fn test_parser_named_modifier() {
+    let mut parser = Parser::from("print 'hi' | with color = 'red' | add a newline.");
+
+    let next_node: ParseNode = parser.next().unwrap().unwrap();
+    let expected_node: ParseNode = ParseNode::Command(Command {
+        components: vec![
+            CommandComponent::Keyword("print".into()),
+            CommandComponent::Literal("hi".to_string()),
+        ],
+        modifiers: vec![
+            Modifier::Named {
+                name: "color".to_string(),
+                args: vec![CommandComponent::Literal("red".to_string())],
+            },
+            Modifier::FreeForm(vec![
+                CommandComponent::Keyword("add".into()),
+                CommandComponent::Keyword("a".into()),
+                CommandComponent::Keyword("newline".into()),
+            ]),
+        ],
+    });
+
+    assert_eq!(next_node, expected_node);
+
+    if let ParseNode::Command(command) = next_node {
+        assert_eq!(command.modifier("color"), Some(&command.modifiers[0]));
+        assert_eq!(command.modifier("size"), None);
+    }
+}
+
+#[test]
+fn test_parser_named_modifier_missing_value() {
+    let mut parser = Parser::from("print 'hi' | with color = .");
+
+    match parser.next().unwrap_err() {
+        ParserError::ExpectedOneOf { found, .. } => assert_eq!(found, Some(Token::Dot)),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_parser_unicode() {
+    let mut parser = Parser::from("grüße %wer:zahl mit 'Grüßen'.");
+
+    assert_eq!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("grüße".into()),
+                CommandComponent::Slot {
+                    name: "wer".into(),
+                    ty: Some(SlotType::Other("zahl".to_string())),
+                },
+                CommandComponent::Keyword("mit".into()),
+                CommandComponent::Literal("Grüßen".to_string()),
+            ],
+            modifiers: vec![],
+        }))
+    );
+}
+
+#[test]
+fn test_parser_final_language_tags() {
+    let mut parser = Parser::from(
+        "whatis a greeting?\n- $$rust\nprintln!(\"hi\");$$\n- $$python\nprint('hi')$$\n- $$echo hi$$\n",
+    );
+
+    let whatis: WhatIsStatement = match parser.next().unwrap().unwrap() {
+        ParseNode::WhatIsStatement(whatis) => whatis,
+        node => panic!("unexpected node: {:?}", node),
+    };
+
+    let finals: Vec<FinalSequence> = whatis
+        .alternatives
+        .iter()
+        .filter_map(|alternative| match &alternative.command {
+            WhatIsCommand::Final(sequence) => Some(sequence.clone()),
+            WhatIsCommand::Command(_) => None,
+        })
+        .collect();
+
+    assert_eq!(
+        finals,
+        vec![
+            FinalSequence::new(Some("rust".to_string()), "println!(\"hi\");".to_string()),
+            FinalSequence::new(Some("python".to_string()), "print('hi')".to_string()),
+            FinalSequence::new(None, "echo hi".to_string()),
+        ]
+    );
+
+    assert_eq!(whatis.final_for("Python"), Some(&finals[1]));
+    assert_eq!(whatis.final_for("go"), Some(&finals[2]));
+}
+
+#[test]
+fn test_parser_limits() {
+    let source: &str = "howto greet?\n- say hello\n  - loudly\n\nwave.\nnod.";
+
+    let mut parser = Parser::from(source).with_limits(ParserLimits {
+        max_tokens: Some(4),
+        ..ParserLimits::default()
+    });
+    assert!(matches!(
+        parser.next(),
+        Err(ParserError::LexerError(LexerError::TokenLimit(4)))
+    ));
+
+    let mut parser = Parser::from(source).with_limits(ParserLimits {
+        max_nodes: Some(4),
+        ..ParserLimits::default()
+    });
+    parser.next().unwrap();
+    parser.next().unwrap();
+    let err: ParserError = parser.next().unwrap_err();
+    assert!(matches!(err, ParserError::NodeLimit { limit: 4, .. }));
+    assert_eq!(err.span().map(|span| span.line), Some(6));
+
+    let mut parser = Parser::from(source).with_limits(ParserLimits {
+        max_depth: Some(1),
+        ..ParserLimits::default()
+    });
+    let err: ParserError = parser.next().unwrap_err();
+    assert!(matches!(err, ParserError::DepthLimit { limit: 1, .. }));
+    assert_eq!(
+        err.span().map(|span| (span.line, span.column)),
+        Some((3, 3))
+    );
+
+    let mut parser = Parser::from(source).with_limits(ParserLimits {
+        max_tokens: Some(100),
+        max_nodes: Some(5),
+        max_depth: Some(2),
+    });
+    while parser.next().unwrap().is_some() {}
+}
+
+#[test]
+fn test_parser_module() {
+    let mut parser = Parser::from(
+        "module console:\nhowto print %what?\n- write %what\n\nwhatis write %what?\n- $$print($what)$$\nend\n\nprint 'hi'.",
+    );
+
+    let Some(ParseNode::Module(module)) = parser.next().unwrap() else {
+        panic!("expected a module");
+    };
+
+    assert_eq!(module.name, "console");
+    assert!(matches!(
+        module.body.as_slice(),
+        [ParseNode::HowToStatement(_), ParseNode::WhatIsStatement(_)]
+    ));
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(_))
+    ));
+    assert!(parser.next().unwrap().is_none());
+}
+
+#[test]
+fn test_parser_module_errors() {
+    let err: ParserError = Parser::from("module console:\nprint 'hi'.\nend\n")
+        .next()
+        .unwrap_err();
+    assert!(matches!(err, ParserError::UnexpectedInModule { .. }));
+    assert_eq!(err.code(), "CCE0110");
+    assert_eq!(err.span().map(|span| span.line), Some(2));
+
+    let err: ParserError = Parser::from("module console:\nhowto print?\n- write\n\n")
+        .next()
+        .unwrap_err();
+    assert!(matches!(err, ParserError::UnclosedModule { ref name, .. } if name == "console"));
+    assert_eq!(err.code(), "CCE0111");
+
+    let err: ParserError = Parser::from("module outer:\nmodule inner:\nend\nend\n")
+        .next()
+        .unwrap_err();
+    assert!(matches!(err, ParserError::UnexpectedInModule { .. }));
+}
+
+#[test]
+fn test_parser_peek_n() {
+    let mut parser = Parser::from("say hi.\nsay bye.\nsay done.");
+
+    assert_eq!(
+        parser.peek_n(2).unwrap(),
+        Some(&ParseNode::Command(keywords("say done")))
+    );
+    assert_eq!(parser.peek_n(3).unwrap(), None);
+    assert_eq!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(keywords("say hi")))
+    );
+    assert_eq!(
+        parser.peek().unwrap(),
+        Some(&ParseNode::Command(keywords("say bye")))
+    );
+    assert_eq!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(keywords("say bye")))
+    );
+    assert_eq!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(keywords("say done")))
+    );
+    assert_eq!(parser.next().unwrap(), None);
+}
+
+#[test]
+fn test_parser_peek_before_module() {
+    let mut parser = Parser::from("greet.\nmodule m:\nhowto x?\n- y.\nend\n");
+
+    match parser.peek_n(1).unwrap() {
+        Some(ParseNode::Module(module)) => {
+            assert_eq!(module.name, "m");
+            assert_eq!(module.body.len(), 1);
+            assert!(matches!(module.body[0], ParseNode::HowToStatement(_)));
+        }
+        other => panic!("expected a module, found {:?}", other),
+    }
+    assert_eq!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(keywords("greet")))
+    );
+    assert!(matches!(parser.next().unwrap(), Some(ParseNode::Module(_))));
+    assert_eq!(parser.next().unwrap(), None);
+}
+
+#[test]
+fn test_final_sequence_from_tagged() {
+    let sequence = FinalSequence::from("ünï\nprint()".to_string());
+
+    assert_eq!(sequence.language.as_deref(), Some("ünï"));
+    assert_eq!(sequence.code, "print()");
+}
+
+#[test]
+fn test_parser_numbers_and_booleans() {
+    let mut parser = Parser::from(
+        "set volume to 7.5 | with muted = false.\nhowto toggle %on:boolean?\n- set %on to true\n\n",
+    );
+
+    assert_eq!(
+        parser.next().unwrap().unwrap(),
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("set".into()),
+                CommandComponent::Keyword("volume".into()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Number {
+                    value: "7.5".to_string(),
+                    unit: None,
+                },
+            ],
+            modifiers: vec![Modifier::Named {
+                name: "muted".to_string(),
+                args: vec![CommandComponent::Boolean(false)],
+            }],
+        })
+    );
+
+    if let Some(ParseNode::HowToStatement(howto)) = parser.next().unwrap() {
+        assert_eq!(
+            howto.signature[1],
+            CommandComponent::Slot {
+                name: "on".into(),
+                ty: Some(SlotType::Boolean),
+            }
+        );
+        assert!(matches!(
+            &howto.body[0],
+            HowToCommand::Command(step) if step.components[3] == CommandComponent::Boolean(true)
+        ));
+    } else {
+        panic!("expected a howto statement");
+    }
+}
+
+#[test]
+fn test_parser_units() {
+    let config = LexerConfig {
+        units: vec!["seconds".to_string()],
+        ..LexerConfig::default()
+    };
+    let mut parser = Parser::new(Lexer::with_config(
+        InputStream::new("wait 5 seconds."),
+        config,
+    ));
+
+    assert_eq!(
+        parser.next().unwrap().unwrap(),
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("wait".into()),
+                CommandComponent::Number {
+                    value: "5".to_string(),
+                    unit: Some("seconds".into()),
+                },
+            ],
+            modifiers: vec![],
+        })
+    );
+}
+
+#[test]
+fn test_parser_terminator() {
+    let config = LexerConfig {
+        terminator: ';',
+        ..LexerConfig::default()
+    };
+    let mut parser = Parser::new(Lexer::with_config(
+        InputStream::new("wait 5; howto greet?\n- wave;\nsmile;"),
+        config,
+    ));
+
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(_))
+    ));
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::HowToStatement(_))
+    ));
+    assert!(matches!(
+        parser.next().unwrap(),
+        Some(ParseNode::Command(_))
+    ));
+    assert_eq!(parser.next().unwrap(), None);
+}
@@ -0,0 +1,148 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+#[test]
+fn test_partial_command() {
+    let result: PartialParse = parse_partial("print 'hi' to the");
+
+    assert!(result.error.is_none());
+    assert_eq!(result.pending, None);
+    assert_eq!(
+        result.nodes,
+        vec![ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("print".into()),
+                CommandComponent::Literal("hi".to_string()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Keyword("the".into()),
+            ],
+            modifiers: vec![],
+        })]
+    );
+
+    // The last word may still continue, so what is expected is what could
+    // go in its place
+    let partial: PartialToken = result.partial.unwrap();
+    assert_eq!(partial.kind, TokenKind::Identifier);
+    assert_eq!(partial.text, "the");
+    assert_eq!(partial.span.start, 14);
+    assert_eq!(
+        result.expected,
+        vec![
+            TokenKind::Identifier,
+            TokenKind::Keyword,
+            TokenKind::Literal,
+            TokenKind::Number,
+            TokenKind::Punctuation('|'),
+            TokenKind::Newline,
+            TokenKind::Dot,
+            TokenKind::Percent,
+            TokenKind::Ampersand,
+        ]
+    );
+}
+
+#[test]
+fn test_partial_pending_statement() {
+    let result: PartialParse = parse_partial("say hi.\nhowto greet %who ");
+
+    assert_eq!(result.nodes.len(), 1);
+    assert_eq!(result.pending, Some(8..25));
+    assert_eq!(result.partial, None);
+    assert_eq!(
+        result.expected,
+        vec![
+            TokenKind::Identifier,
+            TokenKind::Keyword,
+            TokenKind::Literal,
+            TokenKind::Number,
+            TokenKind::Question,
+            TokenKind::Percent,
+            TokenKind::Ampersand,
+        ]
+    );
+
+    // The type of an annotation has yet to be typed
+    let result: PartialParse = parse_partial("howto greet %who:");
+    assert!(result.error.is_none());
+    assert_eq!(result.expected, vec![TokenKind::Identifier]);
+}
+
+#[test]
+fn test_partial_tokens() {
+    let result: PartialParse = parse_partial("print 'hi");
+    assert_eq!(result.partial.unwrap().text, "hi");
+    assert_eq!(
+        result.nodes[0],
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("print".into()),
+                CommandComponent::Literal("hi".to_string()),
+            ],
+            modifiers: vec![],
+        })
+    );
+
+    let result: PartialParse = parse_partial("whatis hello?\n- $$rust");
+    let partial: PartialToken = result.partial.unwrap();
+    assert_eq!(partial.kind, TokenKind::FinalSequence);
+    assert_eq!(partial.text, "rust");
+    assert!(result.expected.contains(&TokenKind::FinalSequence));
+
+    let mut lexer: Lexer = Lexer::from("say 'hi").allow_partial();
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Identifier("say".to_string()))
+    );
+    assert!(!lexer.is_cut_off());
+    assert_eq!(
+        lexer.next().unwrap(),
+        Some(Token::Literal("hi".to_string()))
+    );
+    assert!(lexer.is_cut_off());
+}
+
+#[test]
+fn test_partial_keywords_and_errors() {
+    let result: PartialParse = parse_partial("");
+    assert_eq!(
+        result.expected,
+        vec![
+            TokenKind::Identifier,
+            TokenKind::Keyword,
+            TokenKind::Newline
+        ]
+    );
+    assert_eq!(result.keywords, vec!["howto", "whatis", "module"]);
+
+    let result: PartialParse = parse_partial("module greetings:\nhowto greet?\n- wave\n");
+    assert_eq!(result.pending, Some(0..38));
+    assert_eq!(result.keywords, vec!["howto", "whatis", "end"]);
+
+    // More typing cannot fix an error before the end
+    let result: PartialParse = parse_partial("print 'hi' | ?");
+    assert!(matches!(
+        result.error,
+        Some(ParserError::UnexpectedStatementStart { .. })
+    ));
+    assert!(result.expected.is_empty());
+}
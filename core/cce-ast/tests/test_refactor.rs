@@ -0,0 +1,115 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+const SOURCE: &str = "howto say  hello to %who?\n\
+- print 'hello' | with name = %who\n\n\
+whatis a greeting for %who?\n\
+- = say hello to %who\n\n\
+say hello to 'Bob'.\n\
+say hello loudly.\n";
+
+fn apply(source: &str, edits: &[TextEdit]) -> String {
+    let mut source: String = source.to_string();
+
+    for edit in edits.iter().rev() {
+        source.replace_range(edit.range.clone(), &edit.replacement);
+    }
+
+    source
+}
+
+#[test]
+fn test_rename_slot() {
+    let tree: SyntaxNode = parse_cst(SOURCE).root;
+    let mut nodes: Vec<ParseNode> = tree.lower();
+
+    let edits: Vec<TextEdit> = rename_slot(&mut nodes, &tree, "who", "person").unwrap();
+    assert_eq!(edits.len(), 4);
+    assert!(edits.iter().all(|edit| edit.replacement == "person"));
+
+    let renamed: String = apply(SOURCE, &edits);
+    assert!(!renamed.contains("%who"));
+    assert_eq!(parse_cst(&renamed).root.lower(), nodes);
+}
+
+#[test]
+fn test_rename_slot_back_references() {
+    let source: &str = "howto greet %who?\n- say hi to &who\n- wave at &who\n";
+    let tree: SyntaxNode = parse_cst(source).root;
+    let mut nodes: Vec<ParseNode> = tree.lower();
+
+    let edits: Vec<TextEdit> = rename_slot(&mut nodes, &tree, "who", "person").unwrap();
+    assert_eq!(edits.len(), 3);
+
+    let renamed: String = apply(source, &edits);
+    assert_eq!(
+        renamed,
+        "howto greet %person?\n- say hi to &person\n- wave at &person\n"
+    );
+    assert_eq!(parse_cst(&renamed).root.lower(), nodes);
+}
+
+#[test]
+fn test_rename_keyword() {
+    let tree: SyntaxNode = parse_cst(SOURCE).root;
+    let mut nodes: Vec<ParseNode> = tree.lower();
+
+    let edits: Vec<TextEdit> = rename_keyword(&mut nodes, &tree, "say hello", "greet").unwrap();
+
+    // The signature, the whatis alternative and the first call, but not the
+    // command that has the words without calling the howto
+    assert_eq!(edits.len(), 3);
+    assert_eq!(&SOURCE[edits[0].range.clone()], "say  hello");
+
+    let renamed: String = apply(SOURCE, &edits);
+    assert!(renamed.contains("greet to 'Bob'."));
+    assert!(renamed.contains("say hello loudly."));
+    assert_eq!(parse_cst(&renamed).root.lower(), nodes);
+}
+
+#[test]
+fn test_rename_when_condition() {
+    let source: &str = "howto it rains?\n- look outside\n\n\
+howto walk?\n- when it rains\n  - take an umbrella\n";
+    let tree: SyntaxNode = parse_cst(source).root;
+    let mut nodes: Vec<ParseNode> = tree.lower();
+
+    let edits: Vec<TextEdit> = rename_keyword(&mut nodes, &tree, "rains", "pours").unwrap();
+    assert_eq!(edits.len(), 2);
+    assert_eq!(parse_cst(&apply(source, &edits)).root.lower(), nodes);
+}
+
+#[test]
+fn test_rename_invalid() {
+    let tree: SyntaxNode = parse_cst(SOURCE).root;
+    let mut nodes: Vec<ParseNode> = tree.lower();
+
+    assert_eq!(
+        rename_slot(&mut nodes, &tree, "who", "two words"),
+        Err(RenameError::InvalidName("two words".to_string()))
+    );
+    assert_eq!(
+        rename_keyword(&mut nodes, &tree, "say", "howto"),
+        Err(RenameError::InvalidName("howto".to_string()))
+    );
+    assert_eq!(nodes, tree.lower());
+}
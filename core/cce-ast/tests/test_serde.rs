@@ -0,0 +1,58 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+#![cfg(feature = "serde")]
+
+use cce_ast::*;
+
+#[test]
+fn test_serde_round_trip() {
+    let source = include_str!("./examples/hello.cce");
+    let mut parser = Parser::from(source);
+
+    let mut nodes: Vec<ParseNode> = Vec::new();
+    while let Some(node) = parser.next().unwrap() {
+        nodes.push(node);
+    }
+
+    let json: String = serde_json::to_string(&nodes).unwrap();
+    let parsed: Vec<ParseNode> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed, nodes);
+}
+
+#[test]
+fn test_serde_shape() {
+    let mut parser = Parser::from("greet %who:string.");
+    let node: ParseNode = parser.next().unwrap().unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&node).unwrap(),
+        serde_json::json!({
+            "Command": {
+                "components": [
+                    { "Keyword": "greet" },
+                    { "Slot": { "name": "who", "ty": "String" } }
+                ],
+                "modifiers": []
+            }
+        })
+    );
+}
@@ -0,0 +1,230 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::cell::Cell;
+use std::io::Read;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cce_ast::*;
+use cce_stream::ReadInputStream;
+
+// Hands out one line per read, like a terminal or a line-buffered pipe
+struct Lines<C> {
+    lines: Vec<String>,
+    reads: C,
+}
+
+impl<C: Fn()> Read for Lines<C> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.lines.is_empty() {
+            return Ok(0);
+        }
+
+        (self.reads)();
+
+        let line: String = self.lines.remove(0);
+        buf[..line.len()].copy_from_slice(line.as_bytes());
+        Ok(line.len())
+    }
+}
+
+fn lines<C>(source: &str, reads: C) -> Lines<C> {
+    Lines {
+        lines: source.split_inclusive('\n').map(str::to_string).collect(),
+        reads,
+    }
+}
+
+fn keywords(node: &ParseNode) -> String {
+    match node {
+        ParseNode::Command(command) => command
+            .components
+            .iter()
+            .map(|component| match component {
+                CommandComponent::Keyword(word) => word.to_string(),
+                CommandComponent::Literal(literal) => format!("'{}'", literal),
+                _ => "?".to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(" "),
+        ParseNode::HowToStatement(_) => "howto".to_string(),
+        ParseNode::WhatIsStatement(_) => "whatis".to_string(),
+        ParseNode::Module(module) => format!("module {}", module.name),
+    }
+}
+
+#[test]
+fn test_parser_next_from() {
+    let mut stream = ReadInputStream::new(lines("say hello.\nsay 'goodbye'\n | loudly.\n", || {}));
+
+    let node: ParseNode = Parser::next_from(&mut stream).unwrap().unwrap();
+    assert_eq!(keywords(&node), "say hello");
+
+    let node: ParseNode = Parser::next_from(&mut stream).unwrap().unwrap();
+    match &node {
+        ParseNode::Command(command) => assert_eq!(command.modifiers.len(), 1),
+        _ => panic!("expected a command"),
+    }
+
+    assert_eq!(Parser::next_from(&mut stream).unwrap(), None);
+}
+
+#[test]
+fn test_parser_for_each_statement_incremental() {
+    let reads: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+    let counter: Rc<Cell<usize>> = reads.clone();
+    let source: &str = "say hello.\nwave.\nhowto greet?\n- wave\n\nsay bye.\n";
+
+    let mut seen: Vec<(String, usize)> = Vec::new();
+    Parser::for_each_statement(
+        lines(source, move || counter.set(counter.get() + 1)),
+        |node| seen.push((keywords(&node), reads.get())),
+    )
+    .unwrap();
+
+    // Commands ending in `.` are passed on as soon as their line arrives; a
+    // howto has to wait for the line after its last step
+    assert_eq!(
+        seen,
+        vec![
+            ("say hello".to_string(), 1),
+            ("wave".to_string(), 2),
+            ("howto".to_string(), 6),
+            ("say bye".to_string(), 6),
+        ]
+    );
+}
+
+#[test]
+fn test_parser_for_each_statement_error() {
+    let mut seen: Vec<String> = Vec::new();
+    let err: ParserError = Parser::for_each_statement("say hello.\n- wave\n".as_bytes(), |node| {
+        seen.push(keywords(&node))
+    })
+    .unwrap_err();
+
+    assert_eq!(seen, vec!["say hello"]);
+    assert_eq!(err.code(), "CCE0103");
+}
+
+#[test]
+fn test_parser_for_each_statement_error_span() {
+    let source: &str = "say héllo.\nwave.\nnod.\n'oops'.\n";
+
+    let mut parser = Parser::from(source);
+    let expected: ParserError = loop {
+        if let Err(err) = parser.next() {
+            break err;
+        }
+    };
+
+    // Spans count from the start of the input, not of the buffer the
+    // statement was parsed from
+    let err: ParserError = Parser::for_each_statement(source.as_bytes(), |_| {}).unwrap_err();
+    let span: Span = err.span().unwrap();
+    assert_eq!(Some(span), expected.span());
+    assert_eq!((span.line, span.column, span.start), (4, 1, 23));
+
+    let spawned: ParserError = Parser::spawn_statements(source.as_bytes(), 4)
+        .iter()
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(spawned.span(), expected.span());
+}
+
+#[test]
+fn test_parser_spawn_statements() {
+    let source: &'static str = "say hello.\nwave.\n'oops'.\nsay bye.\n";
+    let messages: Vec<Result<ParseNode, ParserError>> =
+        Parser::spawn_statements(source.as_bytes(), 4)
+            .iter()
+            .collect();
+
+    assert_eq!(messages.len(), 3);
+    assert_eq!(keywords(messages[0].as_ref().unwrap()), "say hello");
+    assert_eq!(keywords(messages[1].as_ref().unwrap()), "wave");
+    assert!(messages[2].is_err());
+}
+
+#[test]
+fn test_parser_spawn_statements_backpressure() {
+    let reads: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let counter: Arc<AtomicUsize> = reads.clone();
+    let source: String = "wave.\n".repeat(20);
+
+    let receiver = Parser::spawn_statements(
+        lines(&source, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }),
+        1,
+    );
+
+    receiver.recv().unwrap().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    // One statement taken, one waiting in the channel and one waiting to be
+    // sent; the parser reads nothing more until the receiver catches up
+    assert!(reads.load(Ordering::SeqCst) <= 3);
+    assert_eq!(receiver.iter().count(), 19);
+}
+
+#[test]
+fn test_parser_streaming_options() {
+    let options = ParseOptions {
+        limits: ParserLimits {
+            max_depth: Some(1),
+            ..ParserLimits::default()
+        },
+        lexer: LexerConfig {
+            units: vec!["seconds".to_string()],
+            ..LexerConfig::default()
+        },
+        ..ParseOptions::default()
+    };
+    let source: &'static str = "wait 5 seconds.\nhowto nap?\n- rest:\n  - sleep\n\n";
+
+    let mut stream = ReadInputStream::new(lines(source, || {}));
+    let node: ParseNode = Parser::next_from_with(&mut stream, &options)
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        &node,
+        ParseNode::Command(command) if matches!(
+            &command.components[1],
+            CommandComponent::Number { unit: Some(unit), .. } if *unit == "seconds"
+        )
+    ));
+
+    let err: ParserError = Parser::next_from_with(&mut stream, &options).unwrap_err();
+    assert!(matches!(err, ParserError::DepthLimit { limit: 1, .. }));
+
+    let messages: Vec<Result<ParseNode, ParserError>> =
+        Parser::spawn_statements_with(source.as_bytes(), 4, options)
+            .iter()
+            .collect();
+    assert_eq!(messages[0].as_ref().ok(), Some(&node));
+    assert!(matches!(
+        messages[1],
+        Err(ParserError::DepthLimit { limit: 1, .. })
+    ));
+}
@@ -0,0 +1,98 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::*;
+
+#[test]
+fn test_suggest_edit_distance() {
+    assert_eq!(edit_distance("howto", "howto"), 0);
+    assert_eq!(edit_distance("hwoto", "howto"), 1);
+    assert_eq!(edit_distance("howt", "howto"), 1);
+    assert_eq!(edit_distance("whatsi", "whatis"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "say"), 3);
+}
+
+#[test]
+fn test_suggest_closest() {
+    let keywords: Vec<&str> = vec!["howto", "whatis"];
+
+    assert_eq!(closest("hwoto", keywords.iter().copied()), Some("howto"));
+    assert_eq!(closest("whatsi", keywords.iter().copied()), Some("whatis"));
+    assert_eq!(closest("howto", keywords.iter().copied()), None);
+    assert_eq!(closest("greet", keywords.iter().copied()), None);
+    // Short words only get suggestions one edit away
+    assert_eq!(closest("ab", ["ba", "abc"]), Some("ba"));
+    assert_eq!(closest("ab", ["xyz"]), None);
+}
+
+#[test]
+fn test_parser_misspelled_keyword() {
+    let source: &str = "hwoto greet?\n- say hello\n";
+    let err: ParserError = Parser::from(source).next().unwrap_err();
+
+    assert_eq!(err.code(), "CCE0109");
+    assert_eq!(
+        err.to_string(),
+        "Syntax error: 'hwoto' is not a keyword, did you mean 'howto'?"
+    );
+
+    let suggestion: Suggestion = err.suggestion().unwrap();
+    assert_eq!(suggestion.to_string(), "did you mean 'howto'?");
+    assert_eq!(
+        suggestion.apply(source).as_deref(),
+        Some("howto greet?\n- say hello\n")
+    );
+
+    // Only a signature gives the word away
+    let mut parser: Parser = Parser::from("hwoto greet.\n");
+    assert!(matches!(parser.next(), Ok(Some(ParseNode::Command(_)))));
+
+    // Words nowhere near a keyword keep the old error
+    let mut parser: Parser = Parser::from("greet?\n");
+    assert!(matches!(parser.next(), Ok(Some(ParseNode::Command(_)))));
+    let err: ParserError = parser.next().unwrap_err();
+    assert!(matches!(err, ParserError::UnexpectedStatementStart { .. }));
+    assert_eq!(err.suggestion(), None);
+}
+
+#[test]
+fn test_suggest_apply_stale() {
+    let suggestion: Suggestion = Suggestion {
+        original: "hwoto".to_string(),
+        replacement: "howto".to_string(),
+        span: Some(Span {
+            start: 0,
+            end: 5,
+            line: 1,
+            column: 1,
+        }),
+    };
+
+    assert_eq!(suggestion.apply("greet."), None);
+    assert_eq!(
+        Suggestion {
+            span: None,
+            ..suggestion
+        }
+        .apply("hwoto greet?"),
+        None
+    );
+}
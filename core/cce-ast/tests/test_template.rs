@@ -0,0 +1,106 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::collections::HashMap;
+
+use cce_ast::*;
+
+fn placeholder(name: &str, spec: Option<&str>) -> TemplatePart {
+    TemplatePart::Placeholder {
+        name: name.to_string(),
+        spec: spec.map(str::to_string),
+    }
+}
+
+fn text(text: &str) -> TemplatePart {
+    TemplatePart::Text(text.to_string())
+}
+
+#[test]
+fn test_template_parse() {
+    assert_eq!(
+        parse_template("echo $who costs $5, ${who:upper}!").unwrap(),
+        vec![
+            text("echo "),
+            placeholder("who", None),
+            text(" costs $5, "),
+            placeholder("who", Some("upper")),
+            text("!"),
+        ]
+    );
+
+    assert_eq!(parse_template("$ $").unwrap(), vec![text("$ $")]);
+    assert_eq!(parse_template("").unwrap(), vec![]);
+}
+
+#[test]
+fn test_template_parse_errors() {
+    assert_eq!(
+        parse_template("say ${who"),
+        Err(TemplateError::Unterminated { offset: 4 })
+    );
+    assert_eq!(
+        parse_template("${:upper}"),
+        Err(TemplateError::InvalidPlaceholder {
+            text: "${:upper}".to_string(),
+            offset: 0,
+        })
+    );
+}
+
+#[test]
+fn test_template_validate() {
+    let mut parser = Parser::from("whatis greeting for %who?\n- $$echo hello $who$$\n");
+
+    let whatis: WhatIsStatement = match parser.next().unwrap().unwrap() {
+        ParseNode::WhatIsStatement(whatis) => whatis,
+        node => panic!("unexpected node: {:?}", node),
+    };
+    let parts: Vec<TemplatePart> = whatis.final_for("sh").unwrap().template().unwrap();
+
+    assert_eq!(validate_template(&parts, &whatis.signature), Ok(()));
+    assert_eq!(
+        validate_template(&parse_template("$whom").unwrap(), &whatis.signature),
+        Err(TemplateError::UnknownPlaceholder("whom".to_string()))
+    );
+    assert_eq!(
+        validate_template(&parse_template("${who:bold}").unwrap(), &whatis.signature),
+        Err(TemplateError::UnknownSpec("bold".to_string()))
+    );
+}
+
+#[test]
+fn test_template_render() {
+    let parts: Vec<TemplatePart> = parse_template("hello ${who:upper}, from ${me:trim}").unwrap();
+    let mut bindings: HashMap<String, String> = HashMap::new();
+    bindings.insert("who".to_string(), "world".to_string());
+
+    assert_eq!(
+        render(&parts, &bindings),
+        Err(TemplateError::Unbound("me".to_string()))
+    );
+
+    bindings.insert("me".to_string(), "  circe ".to_string());
+
+    assert_eq!(
+        render(&parts, &bindings).unwrap(),
+        "hello WORLD, from circe"
+    );
+}
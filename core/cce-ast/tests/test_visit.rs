@@ -0,0 +1,87 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::visit::{walk_command_mut, Visit, VisitMut};
+use cce_ast::*;
+
+struct SlotCounter {
+    slots: Vec<String>,
+    finals: usize,
+}
+
+impl Visit for SlotCounter {
+    fn visit_command_component(&mut self, component: &CommandComponent) {
+        if let CommandComponent::Slot { name, .. } = component {
+            self.slots.push(name.to_string());
+        }
+    }
+
+    fn visit_final(&mut self, _sequence: &FinalSequence) {
+        self.finals += 1;
+    }
+}
+
+struct Renamer;
+
+impl VisitMut for Renamer {
+    fn visit_command_mut(&mut self, command: &mut Command) {
+        command.modifiers.clear();
+        walk_command_mut(self, command);
+    }
+
+    fn visit_command_component_mut(&mut self, component: &mut CommandComponent) {
+        if let CommandComponent::Keyword(keyword) = component {
+            if keyword == "hello" {
+                *keyword = "goodbye".into();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_visit() {
+    let nodes = parse_all("howto say %greeting?\n- say %greeting | to %person\n\nwhatis a greeting?\n-$$ hi $$\n\nsay hello.").unwrap();
+    let mut counter = SlotCounter {
+        slots: Vec::new(),
+        finals: 0,
+    };
+
+    for node in &nodes {
+        counter.visit_parse_node(node);
+    }
+
+    assert_eq!(counter.slots, vec!["greeting", "greeting", "person"]);
+    assert_eq!(counter.finals, 1);
+}
+
+#[test]
+fn test_visit_mut() {
+    let mut nodes =
+        parse_all("howto say hello?\n- say hello | loudly\n\nsay hello world.").unwrap();
+
+    for node in &mut nodes {
+        Renamer.visit_parse_node_mut(node);
+    }
+
+    assert_eq!(
+        nodes,
+        parse_all("howto say goodbye?\n- say goodbye\n\nsay goodbye world.").unwrap()
+    );
+}
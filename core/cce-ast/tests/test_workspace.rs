@@ -0,0 +1,233 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use std::path::PathBuf;
+
+use cce_ast::*;
+
+fn example(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("examples")
+        .join(name)
+}
+
+#[test]
+fn test_workspace_parse() {
+    let paths = vec![example("hello.cce"), example("print_smth.cce")];
+    let result: WorkspaceParseResult = parse_workspace(&paths);
+
+    assert_eq!(result.files.len(), 2);
+    assert_eq!(result.files[0].path, paths[0]);
+    assert_eq!(result.files[0].nodes.len(), 3);
+    assert!(result.files[0].errors.is_empty());
+
+    assert!(matches!(
+        result.files[1].errors.as_slice(),
+        [WorkspaceError::ParserError(_)]
+    ));
+    assert!(result.has_errors());
+}
+
+#[test]
+fn test_workspace_definitions() {
+    let paths = vec![example("hello.cce"), example("hello.cce")];
+    let result: WorkspaceParseResult = parse_workspace(&paths);
+
+    // Two definitions per file, each one defined in both files
+    assert_eq!(result.definitions.len(), 2);
+
+    for locations in result.definitions.values() {
+        assert_eq!(locations.len(), 2);
+        assert_eq!(result.lookup(locations[0]), result.lookup(locations[1]));
+    }
+}
+
+#[test]
+fn test_workspace_module_locations() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("circe-workspace-{}-module.cce", std::process::id()));
+    std::fs::write(
+        &path,
+        "module greetings:\nhowto greet?\n- wave\n\nhowto part?\n- bow\nend\n\nhowto nod?\n- nod\n\n",
+    )
+    .unwrap();
+
+    let result: WorkspaceParseResult = parse_workspace(std::slice::from_ref(&path));
+    std::fs::remove_file(&path).unwrap();
+
+    // The module is node 0 and its members come next
+    let mut nodes: Vec<usize> = result
+        .definitions
+        .values()
+        .flatten()
+        .map(|location| location.node)
+        .collect();
+    nodes.sort();
+    assert_eq!(nodes, vec![1, 2, 3]);
+
+    assert!(matches!(
+        result.lookup(DefinitionLocation { file: 0, node: 0 }),
+        Some(ParseNode::Module(_))
+    ));
+    assert_eq!(
+        result
+            .lookup(DefinitionLocation { file: 0, node: 2 })
+            .and_then(ParseNode::signature),
+        Some(&[CommandComponent::Keyword("part".into())][..])
+    );
+}
+
+#[test]
+fn test_workspace_missing_file() {
+    let result: WorkspaceParseResult = parse_workspace(&[example("missing.cce")]);
+
+    assert!(matches!(
+        result.files[0].errors.as_slice(),
+        [WorkspaceError::IoError(_)]
+    ));
+}
+
+#[test]
+fn test_workspace_depth_limit() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("circe-depth-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let nested = |levels: usize| -> String {
+        let steps: String = (0..levels)
+            .map(|level| format!("{}- a:\n", "  ".repeat(level)))
+            .collect();
+        format!("howto deep?\n{}{}- b\n\n", steps, "  ".repeat(levels))
+    };
+
+    let deep: PathBuf = dir.join("deep.cce");
+    std::fs::write(&deep, nested(5000)).unwrap();
+    let shallow: PathBuf = dir.join("shallow.cce");
+    std::fs::write(&shallow, nested(255)).unwrap();
+
+    // The deepest nesting the default limit allows still fits on a worker
+    // thread's stack
+    let result: WorkspaceParseResult = parse_workspace(&[deep, shallow]);
+    assert!(matches!(
+        result.files[0].errors.as_slice(),
+        [WorkspaceError::ParserError(ParserError::DepthLimit {
+            limit: 256,
+            ..
+        })]
+    ));
+    assert_eq!(result.files[1].nodes.len(), 1);
+    assert!(result.files[1].errors.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_workspace_encodings() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("circe-encodings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let utf16: PathBuf = dir.join("utf16.cce");
+    let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+    bytes.extend(
+        "howto greet?\r\n- wave\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes),
+    );
+    std::fs::write(&utf16, bytes).unwrap();
+
+    let bom: PathBuf = dir.join("bom.cce");
+    std::fs::write(&bom, b"\xEF\xBB\xBFgreet.\n").unwrap();
+
+    let latin1: PathBuf = dir.join("latin1.cce");
+    std::fs::write(&latin1, b"say 'gr\xFC\xDFe'.\n").unwrap();
+
+    let paths: Vec<PathBuf> = vec![utf16, bom, latin1];
+    let result: WorkspaceParseResult = parse_workspace(&paths);
+
+    assert_eq!(result.files[0].nodes.len(), 1);
+    assert!(result.files[0].errors.is_empty());
+    assert_eq!(result.files[1].nodes.len(), 1);
+    assert!(result.files[1].errors.is_empty());
+    assert_eq!(result.files[2].errors[0].code(), "CCE0001");
+
+    // Lossy decoding replaces what it cannot read and warns instead
+    let lossy = cce_stream::DecodeOptions {
+        encoding: None,
+        lossy: true,
+    };
+    let result: WorkspaceParseResult =
+        parse_workspace_with_options(&paths, ParserLimits::default(), lossy);
+
+    assert!(!result.has_errors());
+    assert_eq!(
+        result.files[2].nodes[0],
+        ParseNode::Command(Command {
+            components: vec![
+                CommandComponent::Keyword("say".into()),
+                CommandComponent::Literal("gr\u{FFFD}\u{FFFD}e".to_string()),
+            ],
+            modifiers: vec![],
+        })
+    );
+    assert_eq!(result.files[2].warnings.len(), 2);
+    assert!(result.files[0].warnings.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_workspace_parse_options() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("circe-options-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let file: PathBuf = dir.join("terminated.cce");
+    std::fs::write(&file, "say hi;\nsay bye;\n").unwrap();
+    let paths: Vec<PathBuf> = vec![file.clone(), file];
+
+    assert!(parse_workspace(&paths).has_errors());
+
+    // The lexer config and limits reach every file, on every thread
+    let options = ParseOptions {
+        lexer: LexerConfig {
+            terminator: ';',
+            ..LexerConfig::default()
+        },
+        ..ParseOptions::default()
+    };
+    let result: WorkspaceParseResult = parse_workspace_with(&paths, &options);
+    assert!(!result.has_errors());
+    assert_eq!(result.files[0].nodes.len(), 2);
+    assert_eq!(result.files[1].nodes.len(), 2);
+
+    let limited = ParseOptions {
+        limits: ParserLimits {
+            max_nodes: Some(1),
+            ..ParserLimits::default()
+        },
+        ..options
+    };
+    let result: WorkspaceParseResult = parse_workspace_with(&paths, &limited);
+    assert!(result
+        .files
+        .iter()
+        .all(|file| matches!(file.errors.as_slice(), [WorkspaceError::ParserError(_)])));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,100 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{advance, decode_pending, finish_pending, InputStreamError, READ_CHUNK};
+
+pub struct AsyncInputStream<R> {
+  reader: R,
+  buffer: String,
+  pending: Vec<u8>,
+  eof: bool,
+  pub line: usize,
+  pub column: usize,
+  /// Bytes consumed so far.
+  pub pos: usize
+}
+
+impl<R: AsyncRead + Unpin> AsyncInputStream<R> {
+  pub fn new(reader: R) -> Self {
+    AsyncInputStream {
+      reader,
+      buffer: String::new(),
+      pending: Vec::new(),
+      eof: false,
+      line: 1,
+      column: 1,
+      pos: 0
+    }
+  }
+
+  /// Input that has been received but not yet consumed.
+  pub fn buffered(&self) -> &str {
+    &self.buffer
+  }
+
+  pub fn is_eof(&self) -> bool {
+    self.eof
+  }
+
+  /// Waits for more input from the reader and appends it to the buffer.
+  /// Returns the number of bytes read, which is zero once the reader is done.
+  pub async fn fill(&mut self) -> Result<usize, InputStreamError> {
+    if self.eof {
+      return Ok(0);
+    }
+
+    let mut chunk = [0u8; READ_CHUNK];
+    let read = self.reader.read(&mut chunk).await?;
+
+    if read == 0 {
+      self.eof = true;
+
+      finish_pending(&mut self.pending, &mut self.buffer)?;
+
+      return Ok(0);
+    }
+
+    self.pending.extend_from_slice(&chunk[..read]);
+    decode_pending(&mut self.pending, &mut self.buffer)?;
+
+    Ok(read)
+  }
+
+  /// Reads until the buffer has at least doubled or the reader is done, so
+  /// a caller that rescans the buffer after each call does linear work in
+  /// total rather than rescanning once per chunk.
+  pub async fn grow(&mut self) -> Result<(), InputStreamError> {
+    let wanted: usize = (self.buffer.len() * 2).max(1);
+
+    while self.buffer.len() < wanted && self.fill().await? > 0 {}
+
+    Ok(())
+  }
+
+  /// Drops `len` bytes from the front of the buffer, advancing the position.
+  pub fn consume(&mut self, len: usize) {
+    advance(&self.buffer[..len], &mut self.line, &mut self.column, &mut self.pos);
+
+    self.buffer.drain(..len);
+  }
+}
@@ -0,0 +1,201 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+// Files arrive in more than one encoding: editors on Windows save UTF-8
+// with a byte order mark, or UTF-16. Decoding takes the encoding from the
+// byte order mark, or from the zero bytes ASCII text has in UTF-16, unless
+// it is given. Invalid input fails decoding, or in lossy mode is replaced
+// with U+FFFD and reported as a warning.
+
+use std::fmt;
+
+use crate::{InputStream, InputStreamError};
+
+// Bytes looked at to tell UTF-16 without a byte order mark from UTF-8
+const SNIFF_LEN: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be
+}
+
+impl Encoding {
+  fn bom(&self) -> &'static [u8] {
+    match self {
+      Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+      Encoding::Utf16Le => &[0xFF, 0xFE],
+      Encoding::Utf16Be => &[0xFE, 0xFF]
+    }
+  }
+}
+
+impl fmt::Display for Encoding {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Encoding::Utf8 => write!(f, "UTF-8"),
+      Encoding::Utf16Le => write!(f, "UTF-16LE"),
+      Encoding::Utf16Be => write!(f, "UTF-16BE")
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecodeOptions {
+  // `None` detects the encoding
+  pub encoding: Option<Encoding>,
+  // Replace invalid input and warn instead of failing
+  pub lossy: bool
+}
+
+// Invalid input replaced in lossy mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeWarning {
+  // Byte offset from the start of the input, byte order mark included
+  pub offset: usize,
+  pub len: usize
+}
+
+impl fmt::Display for DecodeWarning {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "replaced {} invalid byte(s) at offset {} with U+FFFD", self.len, self.offset)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+  pub text: String,
+  pub encoding: Encoding,
+  // Whether the input started with a byte order mark. It is not part of
+  // `text`.
+  pub bom: bool,
+  pub warnings: Vec<DecodeWarning>
+}
+
+impl Decoded {
+  pub fn stream(&self) -> InputStream<'_> {
+    InputStream::new(&self.text)
+  }
+}
+
+pub fn decode(bytes: &[u8], options: DecodeOptions) -> Result<Decoded, InputStreamError> {
+  let bom: Option<Encoding> = [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+    .into_iter()
+    .find(|encoding| bytes.starts_with(encoding.bom()));
+
+  let encoding: Encoding = options.encoding.or(bom).unwrap_or_else(|| sniff(bytes));
+
+  // A byte order mark for another encoding than the one given is content
+  let start: usize = match bom {
+    Some(bom) if bom == encoding => bom.bom().len(),
+    _ => 0
+  };
+
+  let mut warnings: Vec<DecodeWarning> = Vec::new();
+
+  let text: String = match encoding {
+    Encoding::Utf8 => decode_utf8(&bytes[start..], start, options.lossy, &mut warnings)?,
+    Encoding::Utf16Le => decode_utf16(&bytes[start..], start, u16::from_le_bytes, options.lossy, &mut warnings)?,
+    Encoding::Utf16Be => decode_utf16(&bytes[start..], start, u16::from_be_bytes, options.lossy, &mut warnings)?
+  };
+
+  Ok(Decoded { text, encoding, bom: start > 0, warnings })
+}
+
+// ASCII text in UTF-16 has a zero in every other byte: the high byte of
+// each unit, which comes second in little endian order
+fn sniff(bytes: &[u8]) -> Encoding {
+  let sample: &[u8] = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+  let units: usize = sample.len() / 2;
+
+  if units == 0 {
+    return Encoding::Utf8;
+  }
+
+  let zeros = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|byte| **byte == 0).count();
+  let (even, odd) = (zeros(0), zeros(1));
+
+  match (even, odd) {
+    (0, odd) if odd * 2 >= units => Encoding::Utf16Le,
+    (even, 0) if even * 2 >= units => Encoding::Utf16Be,
+    _ => Encoding::Utf8
+  }
+}
+
+fn decode_utf8(bytes: &[u8], offset: usize, lossy: bool, warnings: &mut Vec<DecodeWarning>) -> Result<String, InputStreamError> {
+  let mut text: String = String::with_capacity(bytes.len());
+  let mut rest: &[u8] = bytes;
+  let mut offset: usize = offset;
+
+  loop {
+    let err = match std::str::from_utf8(rest) {
+      Ok(valid) => {
+        text.push_str(valid);
+        return Ok(text);
+      }
+      Err(_) if !lossy => return Err(InputStreamError::Utf8Error),
+      Err(err) => err
+    };
+
+    let valid: usize = err.valid_up_to();
+    let len: usize = err.error_len().unwrap_or(rest.len() - valid);
+
+    text.push_str(std::str::from_utf8(&rest[..valid]).map_err(|_| InputStreamError::Utf8Error)?);
+    text.push(char::REPLACEMENT_CHARACTER);
+    warnings.push(DecodeWarning { offset: offset + valid, len });
+
+    rest = &rest[valid + len..];
+    offset += valid + len;
+  }
+}
+
+fn decode_utf16(bytes: &[u8], offset: usize, unit: fn([u8; 2]) -> u16, lossy: bool, warnings: &mut Vec<DecodeWarning>) -> Result<String, InputStreamError> {
+  let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+  let mut text: String = String::with_capacity(bytes.len() / 2);
+  let mut offset: usize = offset;
+
+  for decoded in char::decode_utf16(units) {
+    match decoded {
+      Ok(c) => {
+        text.push(c);
+        offset += c.len_utf16() * 2;
+      }
+      Err(_) if !lossy => return Err(InputStreamError::Utf16Error),
+      Err(_) => {
+        text.push(char::REPLACEMENT_CHARACTER);
+        warnings.push(DecodeWarning { offset, len: 2 });
+        offset += 2;
+      }
+    }
+  }
+
+  // Half a unit left over at the end
+  if bytes.len() % 2 == 1 {
+    if !lossy {
+      return Err(InputStreamError::Utf16Error);
+    }
+
+    text.push(char::REPLACEMENT_CHARACTER);
+    warnings.push(DecodeWarning { offset, len: 1 });
+  }
+
+  Ok(text)
+}
@@ -19,8 +19,22 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 */
 
 
+use std::path::Path;
+
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+mod async_stream;
+mod decode;
+mod read_stream;
+
+#[cfg(feature = "tokio")]
+pub use async_stream::AsyncInputStream;
+pub use decode::{decode, DecodeOptions, DecodeWarning, Decoded, Encoding};
+pub use read_stream::ReadInputStream;
+
+pub(crate) const READ_CHUNK: usize = 4096;
+
 pub struct InputStream<'s> {
   pub(crate) data: &'s str,
   pub line: usize,
@@ -28,10 +42,23 @@ pub struct InputStream<'s> {
   pub pos: usize
 }
 
+// A position in an `InputStream` to go back to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint<'s> {
+  data: &'s str,
+  line: usize,
+  column: usize,
+  pos: usize
+}
+
 #[derive(Error, Debug)]
 pub enum InputStreamError {
   #[error("Failed to read from stream")]
-  ReadError(#[from] std::io::Error)
+  ReadError(#[from] std::io::Error),
+  #[error("Stream is not valid UTF-8")]
+  Utf8Error,
+  #[error("Stream is not valid UTF-16")]
+  Utf16Error
 }
 
 impl<'s> InputStream<'s> {
@@ -44,12 +71,50 @@ impl<'s> InputStream<'s> {
     }
   }
 
+  // `InputStream` borrows its text, so opening a file gives the decoded
+  // text to stream from with `Decoded::stream`
+  pub fn open(path: impl AsRef<Path>, options: DecodeOptions) -> Result<Decoded, InputStreamError> {
+    decode(&std::fs::read(path)?, options)
+  }
+
+  // Any line break reads as a single '\n'
   pub fn peek(&self) -> Option<char> {
-    self.data.chars().next()
+    match line_break(self.data) {
+      Some(_) => Some('\n'),
+      None => self.data.chars().next()
+    }
   }
 
   pub fn peek_n(&self, n: usize) -> Option<char> {
-    self.data.chars().nth(n)
+    let mut rest: &str = self.data;
+
+    for _ in 0..n {
+      let c = rest.chars().next()?;
+      rest = &rest[line_break(rest).unwrap_or(c.len_utf8())..];
+    }
+
+    InputStream::new(rest).peek()
+  }
+
+  pub fn remaining(&self) -> &'s str {
+    self.data
+  }
+
+  pub fn checkpoint(&self) -> Checkpoint<'s> {
+    Checkpoint {
+      data: self.data,
+      line: self.line,
+      column: self.column,
+      pos: self.pos
+    }
+  }
+
+  // Rewinds to `checkpoint`, which must have been taken from this stream
+  pub fn restore(&mut self, checkpoint: Checkpoint<'s>) {
+    self.data = checkpoint.data;
+    self.line = checkpoint.line;
+    self.column = checkpoint.column;
+    self.pos = checkpoint.pos;
   }
 }
 
@@ -57,18 +122,82 @@ impl<'s> Iterator for InputStream<'s> {
   type Item = char;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let c = self.peek()?;
-
-    self.pos += 1;
-    self.column += 1;
+    let c = self.data.chars().next()?;
 
-    if c == '\n' {
+    if let Some(len) = line_break(self.data) {
+      self.pos += self.data[..len].chars().count();
       self.line += 1;
       self.column = 1;
+      self.data = &self.data[len..];
+
+      return Some('\n');
     }
 
+    self.pos += 1;
+    self.column += 1;
     self.data = &self.data[c.len_utf8()..];
 
     Some(c)
   }
 }
+
+// Length in bytes of the line break `data` starts with, if any. `\r\n`
+// is one break, as are a lone `\r` and the Unicode line separators.
+pub(crate) fn line_break(data: &str) -> Option<usize> {
+  if data.starts_with("\r\n") {
+    return Some(2);
+  }
+
+  match data.chars().next()? {
+    c @ ('\n' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}') => Some(c.len_utf8()),
+    _ => None
+  }
+}
+
+// A multi-byte character may be split across reads, so only the valid
+// prefix is moved into the buffer and the rest waits for the next read.
+// So does a trailing `\r`, which may be the start of a `\r\n`.
+pub(crate) fn decode_pending(pending: &mut Vec<u8>, buffer: &mut String) -> Result<(), InputStreamError> {
+  let mut valid = match std::str::from_utf8(pending) {
+    Ok(s) => s.len(),
+    Err(e) if e.error_len().is_none() => e.valid_up_to(),
+    Err(_) => return Err(InputStreamError::Utf8Error)
+  };
+
+  if valid > 0 && pending[valid - 1] == b'\r' {
+    valid -= 1;
+  }
+
+  let bytes: Vec<u8> = pending.drain(..valid).collect();
+  buffer.push_str(std::str::from_utf8(&bytes).map_err(|_| InputStreamError::Utf8Error)?);
+
+  Ok(())
+}
+
+// Once the reader is done, whatever is still pending must be a lone `\r`
+pub(crate) fn finish_pending(pending: &mut Vec<u8>, buffer: &mut String) -> Result<(), InputStreamError> {
+  match pending.as_slice() {
+    [] => {}
+    [b'\r'] => buffer.push('\r'),
+    _ => return Err(InputStreamError::Utf8Error)
+  }
+
+  pending.clear();
+
+  Ok(())
+}
+
+// Advances a position over `consumed`, counting lines the same way
+// `InputStream` does. Unlike `InputStream::pos`, `pos` counts bytes, to line
+// up with the offsets of the spans parsed from the buffer.
+pub(crate) fn advance(consumed: &str, line: &mut usize, column: &mut usize, pos: &mut usize) {
+  let mut stream = InputStream::new(consumed);
+  stream.line = *line;
+  stream.column = *column;
+
+  stream.by_ref().for_each(drop);
+
+  *line = stream.line;
+  *column = stream.column;
+  *pos += consumed.len();
+}
@@ -0,0 +1,78 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+#![cfg(feature = "tokio")]
+
+use cce_stream::AsyncInputStream;
+use tokio::io::AsyncReadExt;
+
+#[tokio::test]
+async fn test_async_input_stream() {
+  let mut stream = AsyncInputStream::new("Hello!\nWorld".as_bytes());
+
+  while stream.fill().await.unwrap() > 0 {}
+
+  assert!(stream.is_eof());
+  assert_eq!(stream.buffered(), "Hello!\nWorld");
+
+  stream.consume(7);
+  assert_eq!(stream.buffered(), "World");
+  assert_eq!(stream.line, 2);
+  assert_eq!(stream.column, 1);
+  assert_eq!(stream.pos, 7);
+}
+
+#[tokio::test]
+async fn test_async_input_stream_split_char() {
+  let bytes: &[u8] = "é".as_bytes();
+  let (first, second) = bytes.split_at(1);
+  let mut stream = AsyncInputStream::new(first.chain(second));
+
+  stream.fill().await.unwrap();
+  assert_eq!(stream.buffered(), "");
+
+  stream.fill().await.unwrap();
+  assert_eq!(stream.buffered(), "é");
+}
+
+#[tokio::test]
+async fn test_async_input_stream_invalid() {
+  let mut stream = AsyncInputStream::new(&[0xffu8, 0xfe][..]);
+
+  assert!(stream.fill().await.is_err());
+}
+
+#[tokio::test]
+async fn test_async_input_stream_grow() {
+  let mut stream = AsyncInputStream::new("ab".as_bytes().chain("cd".as_bytes()).chain("efgh".as_bytes()));
+
+  stream.grow().await.unwrap();
+  assert_eq!(stream.buffered(), "ab");
+
+  stream.grow().await.unwrap();
+  assert_eq!(stream.buffered(), "abcd");
+
+  stream.grow().await.unwrap();
+  assert_eq!(stream.buffered(), "abcdefgh");
+  assert!(!stream.is_eof());
+
+  stream.grow().await.unwrap();
+  assert!(stream.is_eof());
+}
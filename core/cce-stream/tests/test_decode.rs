@@ -0,0 +1,136 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::PathBuf;
+
+use cce_stream::*;
+
+fn utf16le(text: &str) -> Vec<u8> {
+  text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn utf16be(text: &str) -> Vec<u8> {
+  text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+#[test]
+fn test_decode_utf8() {
+  let decoded: Decoded = decode("say 'grüße'.".as_bytes(), DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.text, "say 'grüße'.");
+  assert_eq!(decoded.encoding, Encoding::Utf8);
+  assert!(!decoded.bom);
+
+  let decoded: Decoded = decode(b"\xEF\xBB\xBFsay hi.", DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.text, "say hi.");
+  assert!(decoded.bom);
+  assert_eq!(decoded.stream().next(), Some('s'));
+
+  assert_eq!(decode(b"", DecodeOptions::default()).unwrap().text, "");
+}
+
+#[test]
+fn test_decode_utf16() {
+  let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+  bytes.extend(utf16le("say 'hé' 🎉.\n"));
+  let decoded: Decoded = decode(&bytes, DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.text, "say 'hé' 🎉.\n");
+  assert_eq!(decoded.encoding, Encoding::Utf16Le);
+  assert!(decoded.bom);
+
+  let mut bytes: Vec<u8> = vec![0xFE, 0xFF];
+  bytes.extend(utf16be("say hi."));
+  let decoded: Decoded = decode(&bytes, DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.text, "say hi.");
+  assert_eq!(decoded.encoding, Encoding::Utf16Be);
+
+  // Without a byte order mark, from the zero bytes
+  let decoded: Decoded = decode(&utf16le("howto greet?\n- wave\n"), DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.encoding, Encoding::Utf16Le);
+  assert_eq!(decoded.text, "howto greet?\n- wave\n");
+  assert!(!decoded.bom);
+
+  assert_eq!(decode(&utf16be("say hi."), DecodeOptions::default()).unwrap().encoding, Encoding::Utf16Be);
+}
+
+#[test]
+fn test_decode_override() {
+  let options: DecodeOptions = DecodeOptions { encoding: Some(Encoding::Utf16Be), lossy: false };
+  assert_eq!(decode(&utf16be("hi"), options).unwrap().text, "hi");
+
+  // A byte order mark for another encoding is read as content
+  let options: DecodeOptions = DecodeOptions { encoding: Some(Encoding::Utf8), lossy: false };
+  assert!(matches!(decode(b"\xFF\xFEhi", options), Err(InputStreamError::Utf8Error)));
+
+  let decoded: Decoded = decode(b"\xEF\xBB\xBFhi", DecodeOptions { encoding: Some(Encoding::Utf8), lossy: false }).unwrap();
+  assert_eq!(decoded.text, "hi");
+  assert!(decoded.bom);
+}
+
+#[test]
+fn test_decode_invalid() {
+  assert!(matches!(decode(b"say \xFF hi.", DecodeOptions::default()), Err(InputStreamError::Utf8Error)));
+  assert!(matches!(decode(b"\xFF\xFEh\x00i", DecodeOptions::default()), Err(InputStreamError::Utf16Error)));
+
+  // An unpaired surrogate
+  let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+  bytes.extend(utf16le("a"));
+  bytes.extend([0x00, 0xD8]);
+  bytes.extend(utf16le("b"));
+  assert!(matches!(decode(&bytes, DecodeOptions::default()), Err(InputStreamError::Utf16Error)));
+}
+
+#[test]
+fn test_decode_lossy() {
+  let options: DecodeOptions = DecodeOptions { encoding: None, lossy: true };
+
+  let decoded: Decoded = decode(b"\xEF\xBB\xBFsay \xFF\xFE hi \xE2\x82", options).unwrap();
+  assert_eq!(decoded.text, "say \u{FFFD}\u{FFFD} hi \u{FFFD}");
+  assert_eq!(decoded.warnings, vec![
+    DecodeWarning { offset: 7, len: 1 },
+    DecodeWarning { offset: 8, len: 1 },
+    DecodeWarning { offset: 13, len: 2 }
+  ]);
+  assert_eq!(decoded.warnings[2].to_string(), "replaced 2 invalid byte(s) at offset 13 with U+FFFD");
+
+  let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+  bytes.extend(utf16le("a"));
+  bytes.extend([0x00, 0xD8]);
+  bytes.extend(utf16le("b"));
+  bytes.push(b'c');
+  let decoded: Decoded = decode(&bytes, options).unwrap();
+  assert_eq!(decoded.text, "a\u{FFFD}b\u{FFFD}");
+  assert_eq!(decoded.warnings, vec![DecodeWarning { offset: 4, len: 2 }, DecodeWarning { offset: 8, len: 1 }]);
+}
+
+#[test]
+fn test_input_stream_open() {
+  let path: PathBuf = std::env::temp_dir().join(format!("circe-decode-{}.cce", std::process::id()));
+  let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+  bytes.extend(utf16le("say hi."));
+  fs::write(&path, bytes).unwrap();
+
+  let decoded: Decoded = InputStream::open(&path, DecodeOptions::default()).unwrap();
+  assert_eq!(decoded.stream().collect::<String>(), "say hi.");
+
+  fs::remove_file(&path).unwrap();
+  assert!(matches!(InputStream::open(&path, DecodeOptions::default()), Err(InputStreamError::ReadError(_))));
+}
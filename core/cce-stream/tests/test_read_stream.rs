@@ -0,0 +1,105 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::io::Read;
+
+use cce_stream::ReadInputStream;
+
+#[test]
+fn test_read_input_stream() {
+  let mut stream = ReadInputStream::new("Hello!\nWorld".as_bytes());
+
+  while stream.fill().unwrap() > 0 {}
+
+  assert!(stream.is_eof());
+  assert_eq!(stream.buffered(), "Hello!\nWorld");
+
+  stream.consume(7);
+  assert_eq!(stream.buffered(), "World");
+  assert_eq!(stream.line, 2);
+  assert_eq!(stream.column, 1);
+  assert_eq!(stream.pos, 7);
+}
+
+#[test]
+fn test_read_input_stream_pos_in_bytes() {
+  let mut stream = ReadInputStream::new("héllo
+wörld".as_bytes());
+
+  while stream.fill().unwrap() > 0 {}
+
+  stream.consume("héllo
+w".len());
+  assert_eq!(stream.buffered(), "örld");
+  assert_eq!((stream.line, stream.column, stream.pos), (2, 2, 8));
+}
+
+#[test]
+fn test_read_input_stream_split_char() {
+  let bytes: &[u8] = "é".as_bytes();
+  let (first, second) = bytes.split_at(1);
+  let mut stream = ReadInputStream::new(first.chain(second));
+
+  stream.fill().unwrap();
+  assert_eq!(stream.buffered(), "");
+
+  stream.fill().unwrap();
+  assert_eq!(stream.buffered(), "é");
+}
+
+#[test]
+fn test_read_input_stream_invalid() {
+  let mut stream = ReadInputStream::new(&[0xffu8, 0xfe][..]);
+
+  assert!(stream.fill().is_err());
+}
+
+#[test]
+fn test_read_input_stream_split_crlf() {
+  let mut stream = ReadInputStream::new("a\r".as_bytes().chain("\nb\r".as_bytes()));
+
+  stream.fill().unwrap();
+  assert_eq!(stream.buffered(), "a");
+
+  while stream.fill().unwrap() > 0 {}
+  assert_eq!(stream.buffered(), "a\r\nb\r");
+
+  stream.consume(3);
+  assert_eq!((stream.line, stream.column, stream.pos), (2, 1, 3));
+}
+
+#[test]
+fn test_read_input_stream_grow() {
+  let mut stream = ReadInputStream::new("ab".as_bytes().chain("cd".as_bytes()).chain("efgh".as_bytes()));
+
+  stream.grow().unwrap();
+  assert_eq!(stream.buffered(), "ab");
+
+  stream.grow().unwrap();
+  assert_eq!(stream.buffered(), "abcd");
+
+  stream.grow().unwrap();
+  assert_eq!(stream.buffered(), "abcdefgh");
+  assert!(!stream.is_eof());
+
+  stream.grow().unwrap();
+  assert!(stream.is_eof());
+}
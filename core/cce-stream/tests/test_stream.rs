@@ -50,4 +50,28 @@ fn test_input_stream_none() {
 
   let next_char: Option<char> = stream.next();
   assert_eq!(next_char, None);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_input_stream_checkpoint() {
+  let mut stream = InputStream::new("ab\ncd");
+  stream.next();
+
+  let checkpoint = stream.checkpoint();
+  assert_eq!(stream.by_ref().take(3).collect::<String>(), "b\nc");
+  assert_eq!((stream.line, stream.column), (2, 2));
+
+  stream.restore(checkpoint);
+  assert_eq!((stream.line, stream.column, stream.pos), (1, 2, 1));
+  assert_eq!(stream.remaining(), "b\ncd");
+}
+
+#[test]
+fn test_input_stream_line_endings() {
+  let mut stream = InputStream::new("a\r\nb\rc\u{2028}d");
+
+  assert_eq!(stream.peek_n(1), Some('\n'));
+  assert_eq!(stream.peek_n(2), Some('b'));
+  assert_eq!(stream.by_ref().collect::<String>(), "a\nb\nc\nd");
+  assert_eq!((stream.line, stream.column, stream.pos), (4, 2, 8));
+}
@@ -24,133 +24,146 @@ use proc_macro::TokenStream;
 use quote::quote;
 
 use syn::{
-    Ident, DeriveInput,
-    Data, DataStruct, FieldsNamed, FieldsUnnamed, DataEnum
+    parse_quote, Ident, DeriveInput, Generics,
+    Data, DataStruct, DataEnum, Field, Fields
 };
 
 
-#[proc_macro_derive(CirceHash)]
+#[proc_macro_derive(CirceHash, attributes(circe_hash))]
 pub fn circehash_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse_macro_input!(input as DeriveInput);
     let name = ast.ident.clone();
 
-    match ast.data {
-        Data::Struct(data) => circehash_derive_struct(name, data),
-        Data::Enum(data) => circehash_derive_enum(name, data),
-        Data::Union(_) => panic!("Unions are not supported")
-    }
-}
+    let body = match ast.data {
+        Data::Struct(data) => circehash_derive_struct(data),
+        Data::Enum(data) => circehash_derive_enum(data),
+        Data::Union(_) => Err(syn::Error::new_spanned(&ast.ident, "Unions are not supported"))
+    };
 
-fn circehash_derive_struct(name: Ident, data: DataStruct) -> TokenStream {
-    match data.fields {
-        syn::Fields::Named(f) => circehash_derive_struct_named(name, f),
-        syn::Fields::Unnamed(f) => circehash_derive_struct_unnamed(name, f),
-        syn::Fields::Unit => circehash_derive_struct_unit(name)
-    }
-}
+    let body = match body {
+        Ok(body) => body,
+        Err(err) => return err.to_compile_error().into()
+    };
 
-fn circehash_derive_struct_named(name: Ident, f: FieldsNamed) -> TokenStream {
-    let (fields_i, fields): (Vec<usize>, Vec<Ident>) = f.named.iter().map(|f| {
-        f.ident.clone().unwrap()
-    }).enumerate().unzip();
+    let generics = add_trait_bounds(ast.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
-        impl ::circelang_hash::CirceHash for #name {
+        impl #impl_generics ::circelang_hash::CirceHash for #name #ty_generics #where_clause {
             fn hash(&self) -> u64 {
-                let mut hash: u64 = 0;
-
-                #(hash ^= self.#fields.hash().rotate_right(#fields_i as u32 % 64);)*
-
-                hash
+                #body
             }
         }
     }.into()
 }
 
-fn circehash_derive_struct_unnamed(name: Ident, f: FieldsUnnamed) -> TokenStream {
-    let fields_i = (0..f.unnamed.len()).collect::<Vec<usize>>();
-
-    quote! {
-        impl ::circelang_hash::CirceHash for #name {
-            fn hash(&self) -> u64 {
-                let mut hash: u64 = 0;
+fn add_trait_bounds(mut generics: Generics) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::circelang_hash::CirceHash));
+    }
 
-                #(hash ^= self.#fields_i.hash().rotate_right(#fields_i as u32 % 64);)*
+    generics
+}
 
-                hash
+// `#[circe_hash(skip)]` leaves a field out of the hash
+fn is_skipped(field: &Field) -> syn::Result<bool> {
+    let mut skip = false;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("circe_hash")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported circe_hash attribute"))
             }
-        }
-    }.into()
+        })?;
+    }
+
+    Ok(skip)
 }
 
-fn circehash_derive_struct_unit(name: Ident) -> TokenStream {
+// Each field is rotated by its position, so reordering fields changes the hash
+fn combine(fields: &[(usize, proc_macro2::TokenStream)], seed: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let (fields_i, fields): (Vec<usize>, Vec<proc_macro2::TokenStream>) = fields.iter().cloned().unzip();
+
     quote! {
-        impl ::circelang_hash::CirceHash for #name {
-            fn hash(&self) -> u64 {
-                0
-            }
-        }
-    }.into()
+        let mut hash: u64 = #seed;
+
+        #(hash ^= ::circelang_hash::CirceHash::hash(#fields).rotate_right(#fields_i as u32 % 64);)*
+
+        hash
+    }
 }
 
-fn circehash_derive_enum(name: Ident, data: DataEnum) -> TokenStream {
-    let variants = data.variants.iter().map(|v| {
-        let ident = v.ident.clone();
-        let data = v.fields.clone();
+fn circehash_derive_struct(data: DataStruct) -> syn::Result<proc_macro2::TokenStream> {
+    let mut fields: Vec<(usize, proc_macro2::TokenStream)> = Vec::new();
 
-        match data {
-            syn::Fields::Named(f) => circehash_derive_enum_variant_named(ident, f),
-            syn::Fields::Unnamed(f) => circehash_derive_enum_variant_unnamed(ident, f),
-            syn::Fields::Unit => circehash_derive_enum_variant_unit(ident)
+    for (i, field) in data.fields.iter().enumerate() {
+        if is_skipped(field)? {
+            continue;
         }
-    });
 
-    quote! {
-        impl ::circelang_hash::CirceHash for #name {
-            fn hash(&self) -> u64 {
-                match self {
-                    #(#variants,)*
-                }
+        let access = match &field.ident {
+            Some(ident) => quote! { &self.#ident },
+            None => {
+                let index = syn::Index::from(i);
+                quote! { &self.#index }
             }
-        }
-    }.into()
-}
+        };
 
-fn circehash_derive_enum_variant_named(name: Ident, f: FieldsNamed) -> proc_macro2::TokenStream {
-    let (fields_i, fields): (Vec<usize>, Vec<Ident>) = f.named.iter().map(|f| {
-        f.ident.clone().unwrap()
-    }).enumerate().unzip();
+        fields.push((i, access));
+    }
 
-    quote! {
-        Self::#name { #(#fields,)* } => {
-            let mut hash: u64 = 0;
+    Ok(combine(&fields, quote! { 0 }))
+}
 
-            #(hash ^= #fields.hash().rotate_right(#fields_i as u32 % 64);)*
+fn circehash_derive_enum(data: DataEnum) -> syn::Result<proc_macro2::TokenStream> {
+    let mut variants: Vec<proc_macro2::TokenStream> = Vec::new();
 
-            hash
-        }
-    }
-}
+    for (variant_i, v) in data.variants.iter().enumerate() {
+        let ident = v.ident.clone();
+        // Mixing in the variant index keeps `A(x)` and `B(x)` apart
+        let seed = quote! { (#variant_i as u64).rotate_right(32) };
 
-fn circehash_derive_enum_variant_unnamed(name: Ident, f: FieldsUnnamed) -> proc_macro2::TokenStream {
-    let fields_i = (0..f.unnamed.len()).collect::<Vec<usize>>();
-    let fields = (0..f.unnamed.len()).map(|i| {
-        Ident::new(&format!("f{}", i), proc_macro2::Span::call_site())
-    }).collect::<Vec<Ident>>();
+        let mut fields: Vec<(usize, proc_macro2::TokenStream)> = Vec::new();
+        let mut bindings: Vec<proc_macro2::TokenStream> = Vec::new();
 
-    quote! {
-        Self::#name ( #(#fields,)* ) => {
-            let mut hash: u64 = 0;
+        for (i, field) in v.fields.iter().enumerate() {
+            let skipped = is_skipped(field)?;
+
+            let binding = match &field.ident {
+                Some(ident) => ident.clone(),
+                None => Ident::new(&format!("f{}", i), proc_macro2::Span::call_site())
+            };
 
-            #(hash ^= #fields.hash().rotate_right(#fields_i as u32 % 64);)*
+            match (&field.ident, skipped) {
+                (Some(_), true) => continue,
+                (None, true) => bindings.push(quote! { _ }),
+                (_, false) => bindings.push(quote! { #binding })
+            }
 
-            hash
+            if !skipped {
+                fields.push((i, quote! { #binding }));
+            }
         }
+
+        let body = combine(&fields, seed);
+
+        variants.push(match v.fields {
+            Fields::Named(_) => quote! { Self::#ident { #(#bindings,)* .. } => { #body } },
+            Fields::Unnamed(_) => quote! { Self::#ident ( #(#bindings,)* ) => { #body } },
+            Fields::Unit => quote! { Self::#ident => { #body } }
+        });
     }
-}
 
-fn circehash_derive_enum_variant_unit(name: Ident) -> proc_macro2::TokenStream {
-    quote! {
-        Self::#name => 0
+    if variants.is_empty() {
+        return Ok(quote! { match *self {} });
     }
+
+    Ok(quote! {
+        match self {
+            #(#variants,)*
+        }
+    })
 }
@@ -0,0 +1,135 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+// Version of the hashing algorithm. Digests persisted with another version
+// cannot be compared with the ones computed now.
+pub const HASH_VERSION: u8 = 1;
+
+// A `CirceHash` value tagged with the algorithm version. It serializes as
+// the version byte followed by the hash in big-endian order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digest {
+    version: u8,
+    value: u64,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DigestError {
+    #[error("Expected {expected} bytes, found {found}")]
+    InvalidLength { expected: usize, found: usize },
+    #[error("Invalid hex digit: {0}")]
+    InvalidHex(char),
+    #[error("Unsupported hash version: {0}")]
+    UnsupportedVersion(u8),
+}
+
+impl Digest {
+    pub const SIZE: usize = 9;
+
+    pub fn new(value: u64) -> Self {
+        Self {
+            version: HASH_VERSION,
+            value,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn to_bytes(&self) -> [u8; Digest::SIZE] {
+        let mut bytes = [0; Digest::SIZE];
+
+        bytes[0] = self.version;
+        bytes[1..].copy_from_slice(&self.value.to_be_bytes());
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DigestError> {
+        if bytes.len() != Digest::SIZE {
+            return Err(DigestError::InvalidLength {
+                expected: Digest::SIZE,
+                found: bytes.len(),
+            });
+        }
+
+        if bytes[0] != HASH_VERSION {
+            return Err(DigestError::UnsupportedVersion(bytes[0]));
+        }
+
+        let mut value = [0; 8];
+        value.copy_from_slice(&bytes[1..]);
+
+        Ok(Self {
+            version: bytes[0],
+            value: u64::from_be_bytes(value),
+        })
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.to_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, DigestError> {
+        let digits: Vec<char> = hex.chars().collect();
+
+        if digits.len() != Digest::SIZE * 2 {
+            return Err(DigestError::InvalidLength {
+                expected: Digest::SIZE * 2,
+                found: digits.len(),
+            });
+        }
+
+        let mut bytes = [0; Digest::SIZE];
+
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            let high = pair[0].to_digit(16).ok_or(DigestError::InvalidHex(pair[0]))?;
+            let low = pair[1].to_digit(16).ok_or(DigestError::InvalidHex(pair[1]))?;
+
+            *byte = (high * 16 + low) as u8;
+        }
+
+        Digest::from_bytes(&bytes)
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for Digest {
+    type Err = DigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Digest::from_hex(s)
+    }
+}
@@ -18,11 +18,24 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
 pub use circelang_hash_proc::CirceHash;
 
+mod digest;
+pub use digest::{Digest, DigestError, HASH_VERSION};
+
 
+// The algorithm is described in docs/hashing.md. Changing the value any impl
+// produces requires bumping HASH_VERSION.
 pub trait CirceHash {
     fn hash(&self) -> u64;
+
+    fn digest(&self) -> Digest {
+        Digest::new(self.hash())
+    }
 }
 
 macro_rules! hash_is_identity {
@@ -46,7 +59,7 @@ macro_rules! hash_is_shifted_identity {
         $(
             impl CirceHash for $t {
                 fn hash(&self) -> u64 {
-                    (*self + $s) as u64
+                    self.wrapping_add($s) as u64
                 }
             }
         )*
@@ -77,6 +90,12 @@ impl CirceHash for char {
 }
 
 impl CirceHash for String {
+    fn hash(&self) -> u64 {
+        self.as_str().hash()
+    }
+}
+
+impl CirceHash for str {
     fn hash(&self) -> u64 {
         let mut hash: u64 = 0;
 
@@ -100,15 +119,19 @@ impl CirceHash for f64 {
     }
 }
 
-#[cfg(target_pointer_width = "32")]
-hash_is_identity![usize];
-#[cfg(target_pointer_width = "64")]
-hash_is_identity![usize];
+// Pointer-sized integers hash as their 64-bit counterparts, so the hash is
+// the same on 32-bit and 64-bit targets
+impl CirceHash for usize {
+    fn hash(&self) -> u64 {
+        (*self as u64).hash()
+    }
+}
 
-#[cfg(target_pointer_width = "32")]
-hash_is_shifted_identity![isize, isize::MAX / 2];
-#[cfg(target_pointer_width = "64")]
-hash_is_shifted_identity![isize, isize::MAX / 2];
+impl CirceHash for isize {
+    fn hash(&self) -> u64 {
+        (*self as i64).hash()
+    }
+}
 
 impl<T: CirceHash> CirceHash for Option<T> {
     fn hash(&self) -> u64 {
@@ -155,14 +178,88 @@ impl<T: CirceHash, const N: usize> CirceHash for [T; N] {
     }
 }
 
-impl<T: CirceHash> CirceHash for Box<T> {
+impl<T: CirceHash + ?Sized> CirceHash for Box<T> {
     fn hash(&self) -> u64 {
         self.as_ref().hash()
     }
 }
 
-impl<T: CirceHash> CirceHash for &T {
+impl<T: CirceHash + ?Sized> CirceHash for Rc<T> {
+    fn hash(&self) -> u64 {
+        self.as_ref().hash()
+    }
+}
+
+impl<T: CirceHash + ?Sized> CirceHash for Arc<T> {
+    fn hash(&self) -> u64 {
+        self.as_ref().hash()
+    }
+}
+
+impl<T: CirceHash + ?Sized> CirceHash for &T {
     fn hash(&self) -> u64 {
         (*self).hash()
     }
 }
+
+impl CirceHash for () {
+    fn hash(&self) -> u64 {
+        0
+    }
+}
+
+macro_rules! hash_tuple {
+    ( $( ( $( $t:ident $i:tt ),+ ) );* $(;)? ) => {
+        $(
+            impl<$( $t: CirceHash ),+> CirceHash for ( $( $t, )+ ) {
+                fn hash(&self) -> u64 {
+                    let mut hash: u64 = 0;
+
+                    $( hash ^= self.$i.hash().rotate_right($i as u32 % 64); )+
+
+                    hash
+                }
+            }
+        )*
+    }
+}
+
+hash_tuple![
+    (A 0);
+    (A 0, B 1);
+    (A 0, B 1, C 2);
+    (A 0, B 1, C 2, D 3);
+    (A 0, B 1, C 2, D 3, E 4);
+    (A 0, B 1, C 2, D 3, E 4, F 5);
+];
+
+// Maps and sets combine their entries with a wrapping sum, so the hash does
+// not depend on iteration order and equal collections hash the same
+// regardless of their type
+fn hash_unordered<I: Iterator<Item = u64>>(hashes: I) -> u64 {
+    hashes.fold(0, |hash, entry| hash.wrapping_add(entry))
+}
+
+impl<K: CirceHash, V: CirceHash, S> CirceHash for HashMap<K, V, S> {
+    fn hash(&self) -> u64 {
+        hash_unordered(self.iter().map(|entry| entry.hash()))
+    }
+}
+
+impl<K: CirceHash, V: CirceHash> CirceHash for BTreeMap<K, V> {
+    fn hash(&self) -> u64 {
+        hash_unordered(self.iter().map(|entry| entry.hash()))
+    }
+}
+
+impl<T: CirceHash, S> CirceHash for HashSet<T, S> {
+    fn hash(&self) -> u64 {
+        hash_unordered(self.iter().map(|t| t.hash()))
+    }
+}
+
+impl<T: CirceHash> CirceHash for BTreeSet<T> {
+    fn hash(&self) -> u64 {
+        hash_unordered(self.iter().map(|t| t.hash()))
+    }
+}
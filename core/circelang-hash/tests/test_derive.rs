@@ -0,0 +1,109 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::collections::{BTreeMap, HashMap};
+
+use circelang_hash::CirceHash;
+
+#[derive(CirceHash)]
+struct Wrapper<T> {
+    inner: T,
+    #[circe_hash(skip)]
+    #[allow(dead_code)]
+    cache: Option<u64>,
+}
+
+#[derive(CirceHash)]
+struct Pair<'a, A, B>(&'a A, B);
+
+#[derive(CirceHash)]
+enum Component {
+    Literal(String),
+    Keyword(String),
+    Slot {
+        name: String,
+        #[circe_hash(skip)]
+        #[allow(dead_code)]
+        note: String,
+    },
+    Empty,
+}
+
+#[test]
+fn test_hash_generic_struct() {
+    let a = Wrapper { inner: vec!["a".to_string()], cache: None };
+    let b = Wrapper { inner: vec!["a".to_string()], cache: Some(42) };
+    let c = Wrapper { inner: vec!["b".to_string()], cache: None };
+
+    assert_eq!(a.hash(), b.hash());
+    assert_ne!(a.hash(), c.hash());
+}
+
+#[test]
+fn test_hash_generic_tuple_struct() {
+    let value: u32 = 7;
+
+    assert_eq!(Pair(&value, "x".to_string()).hash(), (&value, "x".to_string()).hash());
+}
+
+#[test]
+fn test_hash_enum_variants_differ() {
+    let literal = Component::Literal("x".to_string());
+    let keyword = Component::Keyword("x".to_string());
+
+    assert_ne!(literal.hash(), keyword.hash());
+    assert_ne!(Component::Empty.hash(), Component::Literal(String::new()).hash());
+}
+
+#[test]
+fn test_hash_enum_skip() {
+    let a = Component::Slot { name: "who".to_string(), note: "first".to_string() };
+    let b = Component::Slot { name: "who".to_string(), note: "second".to_string() };
+
+    assert_eq!(a.hash(), b.hash());
+}
+
+#[test]
+fn test_hash_collections() {
+    let mut forward: HashMap<String, u32> = HashMap::new();
+    let mut backward: HashMap<String, u32> = HashMap::new();
+
+    for (i, key) in ["a", "b", "c"].iter().enumerate() {
+        forward.insert(key.to_string(), i as u32);
+    }
+
+    for (i, key) in ["a", "b", "c"].iter().enumerate().rev() {
+        backward.insert(key.to_string(), i as u32);
+    }
+
+    let ordered: BTreeMap<String, u32> = forward.clone().into_iter().collect();
+
+    assert_eq!(forward.hash(), backward.hash());
+    assert_eq!(forward.hash(), ordered.hash());
+}
+
+#[test]
+fn test_hash_references_and_boxes() {
+    let boxed: Box<str> = "hello".into();
+
+    assert_eq!("hello".hash(), "hello".to_string().hash());
+    assert_eq!(boxed.hash(), "hello".hash());
+    assert_eq!(Some(&3u8).hash(), Some(3u8).hash());
+}
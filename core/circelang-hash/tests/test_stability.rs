@@ -0,0 +1,70 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::collections::BTreeMap;
+
+use circelang_hash::{CirceHash, Digest, DigestError, HASH_VERSION};
+
+// These values are part of the documented algorithm. If one of them changes,
+// HASH_VERSION has to be bumped along with it.
+#[test]
+fn test_hash_stable_values() {
+    let mut map: BTreeMap<String, u32> = BTreeMap::new();
+    map.insert("a".to_string(), 1);
+    map.insert("b".to_string(), 2);
+
+    assert_eq!(HASH_VERSION, 1);
+    assert_eq!(200u8.hash(), 0xc8);
+    assert_eq!((-5i32).hash(), 0x3ffffffa);
+    assert_eq!((-5isize).hash(), 0x3ffffffffffffffa);
+    assert_eq!(1.5f64.hash(), 0x3ff8000000000000);
+    assert_eq!("hello".hash(), 0xf00000000000004a);
+    assert_eq!(vec![1u32, 2, 3].hash(), 0xc000000000000000);
+    assert_eq!((1u8, "a").hash(), 0x8000000000000031);
+    assert_eq!(map.hash(), 0x80000000000000c4);
+}
+
+#[test]
+fn test_digest_bytes() {
+    let digest: Digest = "hello".digest();
+
+    assert_eq!(digest.version(), HASH_VERSION);
+    assert_eq!(digest.to_bytes(), [1, 0xf0, 0, 0, 0, 0, 0, 0, 0x4a]);
+    assert_eq!(Digest::from_bytes(&digest.to_bytes()), Ok(digest));
+}
+
+#[test]
+fn test_digest_hex() {
+    let digest: Digest = "hello".digest();
+
+    assert_eq!(digest.to_string(), "01f00000000000004a");
+    assert_eq!("01f00000000000004a".parse::<Digest>(), Ok(digest));
+    assert_eq!(Digest::from_hex("01F00000000000004A"), Ok(digest));
+}
+
+#[test]
+fn test_digest_errors() {
+    assert_eq!(
+        Digest::from_bytes(&[1, 2, 3]),
+        Err(DigestError::InvalidLength { expected: 9, found: 3 })
+    );
+    assert_eq!(Digest::from_hex("02f00000000000004a"), Err(DigestError::UnsupportedVersion(2)));
+    assert_eq!(Digest::from_hex("01f0000000000000zz"), Err(DigestError::InvalidHex('z')));
+}
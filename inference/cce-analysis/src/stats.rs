@@ -0,0 +1,198 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use cce_ast::ParseNode;
+use cce_infer::{match_signature, KnowledgeBase};
+use cce_infer_ast::visit::{walk_howto, walk_module, walk_whatis};
+use cce_infer_ast::{convert, CommandComponent, CommandNode, HowToNode, Modifier, ModuleNode, ProgramNode, Visit, WhatIsNode};
+
+// How many words `Display` lists under top words
+const TOP_WORDS: usize = 10;
+
+// Counts over a set of definitions, for deciding what a knowledge base is
+// missing or has too much of
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorpusStats {
+  pub howtos: usize,
+  pub whatises: usize,
+  pub modules: usize,
+  // Top-level commands, howto steps and whatis alternatives that are commands
+  pub commands: usize,
+  // Commands that match no known signature
+  pub unresolved: usize,
+  // Components across every howto and whatis signature
+  pub signature_components: usize,
+  // Slots in signatures, and how many of them have each type
+  pub slots: usize,
+  pub slot_types: BTreeMap<String, usize>,
+  // How often each keyword is used, in signatures and commands alike
+  pub vocabulary: BTreeMap<String, usize>,
+}
+
+impl CorpusStats {
+  pub fn definitions(&self) -> usize {
+    self.howtos + self.whatises
+  }
+
+  pub fn average_signature_len(&self) -> f64 {
+    ratio(self.signature_components, self.definitions())
+  }
+
+  pub fn unresolved_ratio(&self) -> f64 {
+    ratio(self.unresolved, self.commands)
+  }
+
+  // The `n` most used keywords, most used first and then alphabetically
+  pub fn top_words(&self, n: usize) -> Vec<(&str, usize)> {
+    let mut words: Vec<(&str, usize)> = self.vocabulary.iter().map(|(word, &count)| (word.as_str(), count)).collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    words.truncate(n);
+    words
+  }
+}
+
+impl fmt::Display for CorpusStats {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{:<18} {} ({} howto, {} whatis, {} modules)", "definitions", self.definitions(), self.howtos, self.whatises, self.modules)?;
+    writeln!(f, "{:<18} {:.2}", "signature length", self.average_signature_len())?;
+
+    let types: Vec<String> = self.slot_types.iter().map(|(ty, count)| format!("{} {}", count, ty)).collect();
+    let types: String = if types.is_empty() { "none typed".to_string() } else { types.join(", ") };
+    writeln!(f, "{:<18} {} ({})", "slots", self.slots, types)?;
+
+    writeln!(f, "{:<18} {} ({} unresolved, {:.1}%)", "commands", self.commands, self.unresolved, self.unresolved_ratio() * 100.0)?;
+
+    let words: Vec<String> = self.top_words(TOP_WORDS).iter().map(|(word, count)| format!("{} ({})", word, count)).collect();
+    writeln!(f, "{:<18} {}", "top words", words.join(", "))
+  }
+}
+
+// Commands are checked against the signatures the nodes define
+pub fn corpus_stats(nodes: &[ParseNode]) -> CorpusStats {
+  analyze(&convert(nodes.to_vec()), None)
+}
+
+// Commands are checked against every definition visible in `knowledge`,
+// such as `std`, instead of only those the nodes define
+pub fn corpus_stats_with(nodes: &[ParseNode], knowledge: &KnowledgeBase) -> CorpusStats {
+  analyze(&convert(nodes.to_vec()), Some(knowledge))
+}
+
+fn analyze(program: &[ProgramNode], knowledge: Option<&KnowledgeBase>) -> CorpusStats {
+  let mut corpus: Corpus = Corpus::default();
+
+  for node in program {
+    corpus.visit_program_node(node);
+  }
+
+  let Corpus { howtos, whatises, modules, mut signatures, commands } = corpus;
+  let mut stats: CorpusStats = CorpusStats { howtos, whatises, modules, ..CorpusStats::default() };
+
+  for signature in &signatures {
+    stats.signature_components += signature.len();
+    count_words(signature, &mut stats.vocabulary);
+
+    for component in signature.iter() {
+      if let CommandComponent::Slot { ty, .. } = component {
+        stats.slots += 1;
+
+        if let Some(ty) = ty {
+          *stats.slot_types.entry(ty.to_string()).or_default() += 1;
+        }
+      }
+    }
+  }
+
+  if let Some(knowledge) = knowledge {
+    signatures = knowledge.visible().map(|entry| entry.definition.signature()).collect();
+  }
+
+  for command in &commands {
+    stats.commands += 1;
+    count_words(&command.command, &mut stats.vocabulary);
+
+    for modifier in &command.modifiers {
+      match modifier {
+        Modifier::Named { args, .. } => count_words(args, &mut stats.vocabulary),
+        Modifier::FreeForm(components) => count_words(components, &mut stats.vocabulary),
+      }
+    }
+
+    if !signatures.iter().any(|signature| match_signature(signature, &command.command).is_some()) {
+      stats.unresolved += 1;
+    }
+  }
+
+  stats
+}
+
+// Definitions, and every command in the program: statements, howto steps
+// at any depth and whatis alternatives
+#[derive(Default)]
+struct Corpus<'a> {
+  howtos: usize,
+  whatises: usize,
+  modules: usize,
+  signatures: Vec<&'a [CommandComponent]>,
+  commands: Vec<&'a CommandNode>,
+}
+
+impl<'a> Visit<'a> for Corpus<'a> {
+  fn visit_command(&mut self, command: &'a CommandNode) {
+    self.commands.push(command);
+  }
+
+  fn visit_howto(&mut self, howto: &'a HowToNode) {
+    self.howtos += 1;
+    self.signatures.push(&howto.signature);
+    walk_howto(self, howto);
+  }
+
+  fn visit_whatis(&mut self, whatis: &'a WhatIsNode) {
+    self.whatises += 1;
+    self.signatures.push(&whatis.signature);
+    walk_whatis(self, whatis);
+  }
+
+  fn visit_module(&mut self, module: &'a ModuleNode) {
+    self.modules += 1;
+    walk_module(self, module);
+  }
+}
+
+fn count_words(components: &[CommandComponent], vocabulary: &mut BTreeMap<String, usize>) {
+  for component in components {
+    if let CommandComponent::Keyword(word) = component {
+      *vocabulary.entry(word.to_string()).or_default() += 1;
+    }
+  }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+  if whole == 0 {
+    0.0
+  } else {
+    part as f64 / whole as f64
+  }
+}
@@ -0,0 +1,61 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_analysis::*;
+use cce_ast::parse_all;
+
+
+#[test]
+fn test_corpus_stats() {
+  let stats: CorpusStats = corpus_stats(&parse_all(
+    "module io:\nwhatis print %what?\n- $$println!(\"{}\", what)$$\nend\n\nhowto greet %name:string?\n- print 'hello'\n- print %name\n\ngreet 'bob'.\ndance wildly.",
+  ).unwrap());
+
+  assert_eq!((stats.howtos, stats.whatises, stats.modules), (1, 1, 1));
+  assert_eq!(stats.definitions(), 2);
+  assert_eq!(stats.average_signature_len(), 2.0);
+  assert_eq!(stats.slots, 2);
+  assert_eq!(stats.slot_types.get("string"), Some(&1));
+  assert_eq!((stats.commands, stats.unresolved), (4, 1));
+  assert_eq!(stats.unresolved_ratio(), 0.25);
+  assert_eq!(stats.top_words(2), vec![("print", 3), ("greet", 2)]);
+}
+
+#[test]
+fn test_corpus_stats_nested_steps() {
+  let stats: CorpusStats = corpus_stats(&parse_all(
+    "module manners:\nhowto greet %who?\n- when %who is shy:\n  - wave\n  - smile:\n    - nod\n- bow\n\nend\n\ngreet 'bob'.",
+  ).unwrap());
+
+  assert_eq!((stats.howtos, stats.whatises, stats.modules), (1, 0, 1));
+  // The condition, the four steps and the statement
+  assert_eq!(stats.commands, 6);
+}
+
+#[test]
+fn test_corpus_stats_empty() {
+  let stats: CorpusStats = corpus_stats(&[]);
+
+  assert_eq!(stats, CorpusStats::default());
+  assert_eq!(stats.average_signature_len(), 0.0);
+  assert_eq!(stats.unresolved_ratio(), 0.0);
+  assert!(stats.to_string().contains("0 (0 unresolved, 0.0%)"));
+}
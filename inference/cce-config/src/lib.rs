@@ -0,0 +1,207 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Project manifests. A `circe.toml` at the root of a project says where its
+// sources are and how to build them, so every tool that opens the project
+// sees the same layout:
+//
+//   [project]
+//   name = "greetings"
+//   sources = ["src"]
+//   targets = ["rust", "sh"]
+//   std = true
+//   dependencies = ["polite"]
+//
+//   [lints]
+//   unused_howto = "allow"
+//
+// Only `name` is required. `sources` are directories searched recursively
+// for .cce files, or single files, and default to the project root.
+// Dependencies are installed packages, loaded by name.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cce_lint::{LintLevel, LintRegistry};
+use serde::Deserialize;
+
+use thiserror::Error;
+
+
+pub const MANIFEST_NAME: &str = "circe.toml";
+
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+  #[error("{0}")]
+  IoError(#[from] io::Error),
+  #[error("{0}")]
+  TomlError(#[from] toml::de::Error),
+  #[error("Unknown lint `{0}`")]
+  UnknownLint(String),
+  #[error("No {} in {} or any directory above it", MANIFEST_NAME, .0.display())]
+  NotFound(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+  // The directory the manifest is in. Paths in the manifest are relative
+  // to it.
+  pub root: PathBuf,
+  pub name: String,
+  pub sources: Vec<PathBuf>,
+  pub targets: Vec<String>,
+  pub std: bool,
+  pub dependencies: Vec<String>,
+  pub lints: BTreeMap<String, LintLevel>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+  project: ProjectTable,
+  #[serde(default)]
+  lints: BTreeMap<String, Level>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectTable {
+  name: String,
+  #[serde(default)]
+  sources: Vec<PathBuf>,
+  #[serde(default)]
+  targets: Vec<String>,
+  #[serde(default)]
+  std: bool,
+  #[serde(default)]
+  dependencies: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+impl From<Level> for LintLevel {
+  fn from(level: Level) -> Self {
+    match level {
+      Level::Allow => LintLevel::Allow,
+      Level::Warn => LintLevel::Warn,
+      Level::Deny => LintLevel::Deny,
+    }
+  }
+}
+
+impl Project {
+  pub fn parse(source: &str, root: impl Into<PathBuf>) -> Result<Project, ConfigError> {
+    let root: PathBuf = root.into();
+    let Manifest { project, lints } = toml::from_str(source)?;
+
+    let names: Vec<&str> = LintRegistry::default().names();
+    if let Some(unknown) = lints.keys().find(|lint| !names.contains(&lint.as_str())) {
+      return Err(ConfigError::UnknownLint(unknown.clone()));
+    }
+
+    let sources: Vec<PathBuf> = if project.sources.is_empty() {
+      vec![root.clone()]
+    } else {
+      project.sources.iter().map(|source| root.join(source)).collect()
+    };
+
+    Ok(Project {
+      root,
+      name: project.name,
+      sources,
+      targets: project.targets,
+      std: project.std,
+      dependencies: project.dependencies,
+      lints: lints.into_iter().map(|(lint, level)| (lint, level.into())).collect(),
+    })
+  }
+
+  pub fn load(path: &Path) -> Result<Project, ConfigError> {
+    let root: &Path = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Project::parse(&fs::read_to_string(path)?, root)
+  }
+
+  // Loads the manifest in `start` or the nearest directory above it
+  pub fn discover(start: &Path) -> Result<Project, ConfigError> {
+    match find_manifest(start) {
+      Some(path) => Project::load(&path),
+      None => Err(ConfigError::NotFound(start.to_path_buf())),
+    }
+  }
+
+  // Every source file of the project, sorted
+  pub fn files(&self) -> Result<Vec<PathBuf>, ConfigError> {
+    Ok(source_files(&self.sources)?)
+  }
+}
+
+pub fn find_manifest(start: &Path) -> Option<PathBuf> {
+  start
+    .ancestors()
+    .map(|dir| dir.join(MANIFEST_NAME))
+    .find(|path| path.is_file())
+}
+
+// The .cce files in `roots`, which may also be files themselves, sorted.
+// Paths that do not exist are skipped, as they may have been removed.
+pub fn source_files(roots: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+  let mut found: Vec<PathBuf> = Vec::new();
+
+  for root in roots {
+    collect(root, &mut found)?;
+  }
+
+  found.sort();
+  found.dedup();
+  Ok(found)
+}
+
+fn collect(path: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+  if path.is_file() {
+    found.push(path.to_path_buf());
+    return Ok(());
+  }
+
+  if !path.is_dir() {
+    return Ok(());
+  }
+
+  for entry in fs::read_dir(path)? {
+    let path: PathBuf = entry?.path();
+
+    if path.is_dir() {
+      collect(&path, found)?;
+    } else if path.extension().is_some_and(|extension| extension == "cce") {
+      found.push(path);
+    }
+  }
+
+  Ok(())
+}
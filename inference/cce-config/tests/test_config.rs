@@ -0,0 +1,85 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cce_config::*;
+use cce_lint::LintLevel;
+
+
+fn directory(name: &str) -> PathBuf {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-config-{}-{}", std::process::id(), name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+
+#[test]
+fn test_config_parse() {
+  let project: Project = Project::parse(
+    "[project]\nname = \"greetings\"\nsources = [\"src\", \"extra.cce\"]\ntargets = [\"rust\"]\nstd = true\ndependencies = [\"polite\"]\n\n[lints]\nunused_howto = \"allow\"\nunbound_slot = \"deny\"\n",
+    "/work",
+  ).unwrap();
+
+  assert_eq!(project.name, "greetings");
+  assert_eq!(project.sources, vec![PathBuf::from("/work/src"), PathBuf::from("/work/extra.cce")]);
+  assert_eq!(project.targets, vec!["rust"]);
+  assert!(project.std);
+  assert_eq!(project.dependencies, vec!["polite"]);
+  assert_eq!(project.lints.get("unused_howto"), Some(&LintLevel::Allow));
+  assert_eq!(project.lints.get("unbound_slot"), Some(&LintLevel::Deny));
+
+  // Everything but the name has a default
+  let project: Project = Project::parse("[project]\nname = \"bare\"\n", "/work").unwrap();
+  assert_eq!(project.sources, vec![PathBuf::from("/work")]);
+  assert!(!project.std && project.targets.is_empty() && project.lints.is_empty());
+}
+
+#[test]
+fn test_config_errors() {
+  let parse = |source: &str| Project::parse(source, "/work").unwrap_err();
+
+  assert!(matches!(parse("[project]\n"), ConfigError::TomlError(_)));
+  assert!(matches!(parse("[project]\nname = \"x\"\nsauces = []\n"), ConfigError::TomlError(_)));
+  assert!(matches!(parse("[project]\nname = \"x\"\n[lints]\nunused_howto = \"loud\"\n"), ConfigError::TomlError(_)));
+  assert!(matches!(parse("[project]\nname = \"x\"\n[lints]\nno_such_lint = \"deny\"\n"), ConfigError::UnknownLint(lint) if lint == "no_such_lint"));
+}
+
+#[test]
+fn test_config_discover() {
+  let dir: PathBuf = directory("discover");
+  fs::create_dir_all(dir.join("src").join("nested")).unwrap();
+  fs::write(dir.join(MANIFEST_NAME), "[project]\nname = \"demo\"\nsources = [\"src\"]\n").unwrap();
+  fs::write(dir.join("src").join("b.cce"), "wave.\n").unwrap();
+  fs::write(dir.join("src").join("nested").join("a.cce"), "nod.\n").unwrap();
+  fs::write(dir.join("src").join("notes.txt"), "not a source\n").unwrap();
+  fs::write(dir.join("outside.cce"), "bow.\n").unwrap();
+
+  let project: Project = Project::discover(&dir.join("src").join("nested")).unwrap();
+  assert_eq!(project.root, dir);
+  assert_eq!(project.files().unwrap(), vec![dir.join("src").join("b.cce"), dir.join("src").join("nested").join("a.cce")]);
+
+  assert!(matches!(Project::discover(Path::new("/")), Err(ConfigError::NotFound(_))));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
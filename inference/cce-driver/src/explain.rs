@@ -0,0 +1,281 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Extended explanations for diagnostic codes, shown by `ccec explain`.
+// Codes are grouped by where they come from: CCE00xx for reading and
+// lexing, CCE01xx for parsing, CCE02xx for resolution and expansion and
+// CCE03xx for lints. A code is never reused once it has been published.
+
+use std::fmt;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+  pub code: &'static str,
+  pub title: &'static str,
+  pub explanation: &'static str,
+  // Circe source that produces the diagnostic
+  pub example: &'static str,
+}
+
+impl fmt::Display for Explanation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{}: {}", self.code, self.title)?;
+    writeln!(f)?;
+    writeln!(f, "{}", self.explanation)?;
+    writeln!(f)?;
+    writeln!(f, "Example:")?;
+    writeln!(f)?;
+
+    for line in self.example.lines() {
+      writeln!(f, "    {}", line)?;
+    }
+
+    Ok(())
+  }
+}
+
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+  EXPLANATIONS.iter().find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+pub fn explanations() -> &'static [Explanation] {
+  EXPLANATIONS
+}
+
+static EXPLANATIONS: &[Explanation] = &[
+  Explanation {
+    code: "CCE0001",
+    title: "the input could not be read",
+    explanation: "The input could not be read as text, for example because it is not valid UTF-8. Files are read as UTF-8 unless they start with a byte order mark or look like UTF-16. `SessionConfig::decode` can name the encoding, or replace invalid bytes with U+FFFD instead of failing.",
+    example: "(a source file saved as Latin-1 with accented characters)",
+  },
+  Explanation {
+    code: "CCE0002",
+    title: "unexpected end of input",
+    explanation: "The input ended in the middle of a token. Literals need a closing quote and final sequences need a closing fence with as many dollars as the opening one.",
+    example: "say 'hello\n",
+  },
+  Explanation {
+    code: "CCE0003",
+    title: "unexpected character",
+    explanation: "The lexer found a character that does not start any token. Identifiers start with a letter or `_`, numbers with a digit, and literals with a quote.",
+    example: "add #5 to %x.\n",
+  },
+  Explanation {
+    code: "CCE0004",
+    title: "too many tokens",
+    explanation: "The input has more tokens than the parser's `max_tokens` limit allows. Split the program into smaller files or raise the limit.",
+    example: "(any input longer than the configured limit)",
+  },
+  Explanation {
+    code: "CCE0005",
+    title: "a source file could not be read",
+    explanation: "A file given to the compiler does not exist or could not be opened. Relative names are also looked up in the session's search paths.",
+    example: "ccec missing.cce",
+  },
+  Explanation {
+    code: "CCE0100",
+    title: "expected a different token",
+    explanation: "The parser needed one specific token here, such as the `?` that ends a howto or whatis signature.",
+    example: "howto greet %who\n- wave\n",
+  },
+  Explanation {
+    code: "CCE0101",
+    title: "expected one of several tokens",
+    explanation: "Several tokens could have come next, but another one was found. After a whatis alternative, for example, only a newline or the `-` of the next alternative may follow.",
+    example: "whatis the world?\n- $$ planet $$\n say\n",
+  },
+  Explanation {
+    code: "CCE0102",
+    title: "final sequence outside a whatis",
+    explanation: "Final sequences (`$$ ... $$`) are only allowed as alternatives of a whatis statement, not inside commands or howto steps.",
+    example: "howto greet?\n- $$echo hi$$\n",
+  },
+  Explanation {
+    code: "CCE0103",
+    title: "a statement cannot start here",
+    explanation: "Statements start with a keyword like `howto` or `whatis`, or with a command. Punctuation such as a stray `-` cannot start one.",
+    example: "- say hello\n",
+  },
+  Explanation {
+    code: "CCE0104",
+    title: "inconsistent step indentation",
+    explanation: "Steps at the same level of a howto body have to start in the same column. Indent a step further to nest it under the one above.",
+    example: "howto greet?\n  - wave\n - smile\n",
+  },
+  Explanation {
+    code: "CCE0105",
+    title: "more than one canonical alternative",
+    explanation: "At most one alternative of a whatis can be marked canonical with a leading `=`.",
+    example: "whatis stdout?\n- = the console\n- = file descriptor '1'\n",
+  },
+  Explanation {
+    code: "CCE0106",
+    title: "unknown statement keyword",
+    explanation: "The statement starts with a keyword the parser has no statement for. This happens when a lexer configuration adds keywords the parser does not know.",
+    example: "(with `define` added to the lexer's keywords)\ndefine greet?\n- wave\n",
+  },
+  Explanation {
+    code: "CCE0107",
+    title: "too many statements and commands",
+    explanation: "A file has more statements and commands than the parser's `max_nodes` limit allows.",
+    example: "(any file with more commands than the configured limit)",
+  },
+  Explanation {
+    code: "CCE0108",
+    title: "steps nested too deeply",
+    explanation: "Howto steps are nested more levels deep than the parser's `max_depth` limit allows. Move deeply nested steps into a howto of their own.",
+    example: "howto greet?\n- wave\n  - slowly\n    - very slowly\n",
+  },
+  Explanation {
+    code: "CCE0109",
+    title: "misspelled statement keyword",
+    explanation: "A command is followed by the `?` that ends a signature, and its first word is close to `howto` or `whatis`. The keyword is most likely misspelled.",
+    example: "hwoto greet?\n- wave\n",
+  },
+  Explanation {
+    code: "CCE0110",
+    title: "statement not allowed in a module",
+    explanation: "Modules only group definitions. Commands and other modules have to go outside of them, after the module's `end`.",
+    example: "module greetings:\ngreet 'Bob'.\nend\n",
+  },
+  Explanation {
+    code: "CCE0111",
+    title: "unclosed module",
+    explanation: "The input ended inside a module. Every `module <name>:` needs an `end` after its last definition.",
+    example: "module greetings:\nhowto greet %who?\n- wave\n",
+  },
+  Explanation {
+    code: "CCE0112",
+    title: "block steps not indented",
+    explanation: "A howto step ending in `:` starts a block, but the `-` on the next line is not indented further than the step, so it cannot belong to the block. Indent the block's steps under it, or remove the `:`.",
+    example: "howto cook?\n- prepare:\n- chop onions\n",
+  },
+  Explanation {
+    code: "CCE0113",
+    title: "invalid edit",
+    explanation: "A text edit applied to a document has a range that starts after it ends, runs past the end of the document, or falls inside a multi-byte character. Edit ranges are byte offsets into the document's current source.",
+    example: "(none)",
+  },
+  Explanation {
+    code: "CCE0199",
+    title: "internal parser error",
+    explanation: "The parser reached a state it should never be in. This is a bug in Circe; please report it with the input that caused it.",
+    example: "(none)",
+  },
+  Explanation {
+    code: "CCE0200",
+    title: "conflicting definitions",
+    explanation: "Two definitions of the same kind have equivalent signatures, so a command matching one always matches the other. Keywords compare without case and slots compare by type, not by name.",
+    example: "howto greet %who?\n- wave\n\nhowto Greet %person?\n- nod\n",
+  },
+  Explanation {
+    code: "CCE0201",
+    title: "howto expansion nested too deeply",
+    explanation: "Expanding a command went through more nested howtos than the expansion `max_depth` limit allows. This usually means a howto calls itself, directly or through others.",
+    example: "howto spin?\n- turn\n- spin\n\nspin.\n",
+  },
+  Explanation {
+    code: "CCE0202",
+    title: "expansion produced too many commands",
+    explanation: "Expanding the program produced more commands than the expansion `max_commands` limit allows.",
+    example: "(a program whose howtos expand into more commands than the configured limit)",
+  },
+  Explanation {
+    code: "CCE0203",
+    title: "ambiguous command",
+    explanation: "More than one howto matches the command with an equally specific signature, and the disambiguation policy did not choose one of them.",
+    example: "(`greet 'Bob'.` with both `howto greet %who?` and `howto greet %who:string?` defined, under a policy that does not choose)",
+  },
+  Explanation {
+    code: "CCE0204",
+    title: "replay diverged from the session log",
+    explanation: "Expansion was replaying a session log, but the program resolved a different command than the log recorded next, or the howto the log chose no longer matches. The program or its definitions changed since the log was recorded; record it again.",
+    example: "(replaying a log recorded for `greet 'Bob'.` against a program that says `greet 'Alice'.`)",
+  },
+  Explanation {
+    code: "CCE0205",
+    title: "unknown modifier",
+    explanation: "Codegen found a modifier it has no handler for on a command it generates. Named modifiers are looked up by name and free-form ones by their leading words, as in `| add a newline`. Embedders can register their own handlers.",
+    example: "(`say hello | sparkle.` when generating code, with no `sparkle` handler registered)",
+  },
+  Explanation {
+    code: "CCE0206",
+    title: "modifier failed",
+    explanation: "A modifier's handler rejected its arguments or panicked while transforming the output of a command.",
+    example: "(`say hello | with indent = 'wide'.` when generating code)",
+  },
+  Explanation {
+    code: "CCE0207",
+    title: "modifier output too large",
+    explanation: "A modifier produced more output than the modifier registry's `max_output` limit allows.",
+    example: "(a handler that repeats its input, applied until the output passes the limit)",
+  },
+  Explanation {
+    code: "CCE0208",
+    title: "invalid final sequence template",
+    explanation: "Codegen could not fill in the final sequence of the whatis a command names. A `$name` placeholder must name a slot of the whatis's signature, and `${name:spec}` must use a known format spec: `upper`, `lower` or `trim`.",
+    example: "(`greet 'Bob'.` when generating code, with `whatis greet %who?` whose final sequence says `$whom`)",
+  },
+  Explanation {
+    code: "CCE0209",
+    title: "ambiguous whatis",
+    explanation: "More than one whatis with a final sequence for the target matches a command with an equally specific signature, and the disambiguation policy did not choose one of them.",
+    example: "(`greet 'Bob'.` when generating code, with both `whatis greet %who?` and `whatis greet %whom?` defined, under a policy that does not choose)",
+  },
+  Explanation {
+    code: "CCE0300",
+    title: "unused howto",
+    explanation: "No command in the program matches this howto's signature, so it is never used.",
+    example: "howto greet %who?\n- wave\n",
+  },
+  Explanation {
+    code: "CCE0301",
+    title: "unbound slot",
+    explanation: "A step of a howto or an alternative of a whatis uses a slot or back-reference that its signature does not bind, so it has no value when the definition is used.",
+    example: "howto greet?\n- say hello to %who\n",
+  },
+  Explanation {
+    code: "CCE0302",
+    title: "duplicate howto signature",
+    explanation: "A howto has exactly the same signature as one defined before it, so commands matching it are ambiguous.",
+    example: "howto greet %who?\n- wave\n\nhowto greet %who?\n- nod\n",
+  },
+  Explanation {
+    code: "CCE0303",
+    title: "command made only of literals",
+    explanation: "A command has no keywords, only literals, so no howto can describe what it does.",
+    example: "howto greet %who?\n- 'hello'\n\ngreet 'Bob'.\n",
+  },
+  Explanation {
+    code: "CCE0304",
+    title: "shadowed whatis",
+    explanation: "A later whatis has the same signature and replaces this one, so its alternatives are never used.",
+    example: "whatis stdout?\n- the console\n\nwhatis stdout?\n- file descriptor '1'\n",
+  },
+  Explanation {
+    code: "CCE0305",
+    title: "misspelled command",
+    explanation: "A command matches no howto or whatis, but would if one of its keywords were spelled like a keyword of a known signature.",
+    example: "howto greet %who?\n- wave to %who\n\ngret 'Bob'.\n",
+  },
+];
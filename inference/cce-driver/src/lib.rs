@@ -0,0 +1,37 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod explain;
+mod metrics;
+mod modifiers;
+mod session;
+pub mod snapshot;
+mod timings;
+mod watch;
+
+pub use explain::{explain, explanations, Explanation};
+pub use metrics::{Counter, Gauge, Metrics, NoMetrics};
+pub use modifiers::{ModifierError, ModifierHandler, ModifierRegistry};
+pub use session::*;
+pub use timings::{StageTiming, Timings};
+#[cfg(feature = "alloc-tracking")]
+pub use timings::TrackingAllocator;
+pub use watch::{ParseCache, Watcher};
@@ -0,0 +1,146 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+// Usage metrics for embedders running Circe as a service. A session
+// reports what each compile did to a `Metrics` implementation, which can
+// feed Prometheus or any other dashboard. Nothing is collected or sent
+// anywhere unless an embedder installs one; the default does nothing.
+
+use std::fmt;
+use std::sync::Arc;
+
+
+// Totals that only go up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Counter {
+  Compiles,
+  FilesParsed,
+  StatementsParsed,
+  // Files the parse cache returned without parsing them again
+  ParseCacheHits,
+  ParseCacheMisses,
+  // Commands looked up while expanding, nested ones included
+  ResolutionsAttempted,
+  ResolutionsUnmatched,
+  // Calls to each backend that settles a resolution: the matcher alone, or
+  // the disambiguation policy when howtos tie
+  MatcherCalls,
+  PolicyCalls,
+  ReplayedDecisions,
+  // Lookups the resolver answered from its cache, and those it matched
+  // against the knowledge base
+  ResolverCacheHits,
+  ResolverCacheMisses,
+  Diagnostics,
+  FailedCompiles,
+}
+
+// Values from the latest compile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Gauge {
+  KnowledgeEntries,
+  ExpandedCommands,
+}
+
+impl Counter {
+  pub const ALL: [Counter; 14] = [
+    Counter::Compiles,
+    Counter::FilesParsed,
+    Counter::StatementsParsed,
+    Counter::ParseCacheHits,
+    Counter::ParseCacheMisses,
+    Counter::ResolutionsAttempted,
+    Counter::ResolutionsUnmatched,
+    Counter::MatcherCalls,
+    Counter::PolicyCalls,
+    Counter::ReplayedDecisions,
+    Counter::ResolverCacheHits,
+    Counter::ResolverCacheMisses,
+    Counter::Diagnostics,
+    Counter::FailedCompiles,
+  ];
+
+  // A stable name for exporting, in snake case
+  pub fn name(&self) -> &'static str {
+    match self {
+      Counter::Compiles => "compiles",
+      Counter::FilesParsed => "files_parsed",
+      Counter::StatementsParsed => "statements_parsed",
+      Counter::ParseCacheHits => "parse_cache_hits",
+      Counter::ParseCacheMisses => "parse_cache_misses",
+      Counter::ResolutionsAttempted => "resolutions_attempted",
+      Counter::ResolutionsUnmatched => "resolutions_unmatched",
+      Counter::MatcherCalls => "matcher_calls",
+      Counter::PolicyCalls => "policy_calls",
+      Counter::ReplayedDecisions => "replayed_decisions",
+      Counter::ResolverCacheHits => "resolver_cache_hits",
+      Counter::ResolverCacheMisses => "resolver_cache_misses",
+      Counter::Diagnostics => "diagnostics",
+      Counter::FailedCompiles => "failed_compiles",
+    }
+  }
+}
+
+impl Gauge {
+  pub const ALL: [Gauge; 2] = [Gauge::KnowledgeEntries, Gauge::ExpandedCommands];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Gauge::KnowledgeEntries => "knowledge_entries",
+      Gauge::ExpandedCommands => "expanded_commands",
+    }
+  }
+}
+
+impl fmt::Display for Counter {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+impl fmt::Display for Gauge {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+// Both methods do nothing unless implemented. They take `&self` so one
+// implementation can be shared by sessions on several threads.
+pub trait Metrics {
+  fn increment(&self, _counter: Counter, _by: u64) {}
+
+  fn set(&self, _gauge: Gauge, _value: u64) {}
+}
+
+// Discards everything. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMetrics;
+
+impl Metrics for NoMetrics {}
+
+impl<M: Metrics + ?Sized> Metrics for Arc<M> {
+  fn increment(&self, counter: Counter, by: u64) {
+    (**self).increment(counter, by);
+  }
+
+  fn set(&self, gauge: Gauge, value: u64) {
+    (**self).set(gauge, value);
+  }
+}
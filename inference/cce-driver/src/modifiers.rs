@@ -0,0 +1,207 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Modifiers as transforms of generated output. Codegen renders a command
+// that names a whatis as that whatis's final sequence, then applies the
+// command's `| ...` modifiers to the text in order. Named modifiers like
+// `with indent = 2` are looked up by name and free-form ones like
+// `add a newline` by their leading words; values after the words are the
+// modifier's arguments.
+//
+// Handlers only see the text and their arguments. One that panics fails
+// its modifier instead of the session, and output over the registry's
+// `max_output` is rejected, so embedders can register handlers from
+// plugins without trusting them.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use cce_infer_ast::{CommandComponent, Modifier};
+
+use thiserror::Error;
+
+
+pub trait ModifierHandler {
+  // Returns the transformed output, or why the arguments do not fit
+  fn apply(&self, args: &[CommandComponent], output: &str) -> Result<String, String>;
+}
+
+impl<F: Fn(&[CommandComponent], &str) -> Result<String, String>> ModifierHandler for F {
+  fn apply(&self, args: &[CommandComponent], output: &str) -> Result<String, String> {
+    self(args, output)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ModifierError {
+  #[error("Unknown modifier `{modifier}`")]
+  Unknown { modifier: String },
+  #[error("Modifier `{modifier}` failed: {message}")]
+  Failed { modifier: String, message: String },
+  #[error("Limit exceeded: Modifier `{modifier}` produced more than {limit} bytes")]
+  OutputLimit { modifier: String, limit: usize },
+}
+
+impl ModifierError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      ModifierError::Unknown { .. } => "CCE0205",
+      ModifierError::Failed { .. } => "CCE0206",
+      ModifierError::OutputLimit { .. } => "CCE0207",
+    }
+  }
+}
+
+pub struct ModifierRegistry {
+  handlers: Vec<(String, Box<dyn ModifierHandler>)>,
+  max_output: Option<usize>,
+}
+
+// The built-in modifiers, with output limited to a megabyte
+impl Default for ModifierRegistry {
+  fn default() -> Self {
+    let mut registry: ModifierRegistry = ModifierRegistry::empty().with_max_output(Some(1 << 20));
+
+    registry.register("add a newline", |args: &[CommandComponent], output: &str| {
+      no_args(args)?;
+      Ok(format!("{}\n", output))
+    });
+    registry.register("uppercase", |args: &[CommandComponent], output: &str| {
+      no_args(args)?;
+      Ok(output.to_uppercase())
+    });
+    registry.register("lowercase", |args: &[CommandComponent], output: &str| {
+      no_args(args)?;
+      Ok(output.to_lowercase())
+    });
+    registry.register("trim", |args: &[CommandComponent], output: &str| {
+      no_args(args)?;
+      Ok(output.trim().to_string())
+    });
+    registry.register("indent", |args: &[CommandComponent], output: &str| {
+      let width: usize = match args {
+        [] => 2,
+        [arg] => arg.as_number().filter(|width| width.fract() == 0.0 && (0.0..=64.0).contains(width)).ok_or("expected a width from 0 to 64")? as usize,
+        _ => return Err("expected a width".to_string()),
+      };
+
+      let indent: String = " ".repeat(width);
+      Ok(output.split_inclusive('\n').map(|line| if line.trim().is_empty() { line.to_string() } else { format!("{}{}", indent, line) }).collect())
+    });
+    registry.register("prefix", |args: &[CommandComponent], output: &str| Ok(format!("{}{}", text(args)?, output)));
+    registry.register("suffix", |args: &[CommandComponent], output: &str| Ok(format!("{}{}", output, text(args)?)));
+
+    registry
+  }
+}
+
+impl ModifierRegistry {
+  // A registry without any handlers or output limit
+  pub fn empty() -> Self {
+    Self { handlers: Vec::new(), max_output: None }
+  }
+
+  pub fn with_max_output(self, limit: Option<usize>) -> Self {
+    Self { max_output: limit, ..self }
+  }
+
+  pub fn max_output(&self) -> Option<usize> {
+    self.max_output
+  }
+
+  // Replaces any handler already registered under `name`. Names compare
+  // without case.
+  pub fn register(&mut self, name: &str, handler: impl ModifierHandler + 'static) {
+    let name: String = name.to_lowercase();
+    self.handlers.retain(|(registered, _)| *registered != name);
+    self.handlers.push((name, Box::new(handler)));
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.handler(&name.to_lowercase()).is_some()
+  }
+
+  // Registered names, in the order they were registered
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.handlers.iter().map(|(name, _)| name.as_str())
+  }
+
+  // Applies `modifiers` to `output`, in order
+  pub fn apply(&self, modifiers: &[Modifier], output: &str) -> Result<String, ModifierError> {
+    let mut output: String = output.to_string();
+
+    for modifier in modifiers {
+      let (name, args) = split(modifier);
+      let handler: &dyn ModifierHandler = self.handler(&name).ok_or_else(|| ModifierError::Unknown { modifier: name.clone() })?;
+
+      output = match panic::catch_unwind(AssertUnwindSafe(|| handler.apply(args, &output))) {
+        Ok(Ok(output)) => output,
+        Ok(Err(message)) => return Err(ModifierError::Failed { modifier: name, message }),
+        Err(_) => return Err(ModifierError::Failed { modifier: name, message: "the handler panicked".to_string() }),
+      };
+
+      if let Some(limit) = self.max_output.filter(|limit| output.len() > *limit) {
+        return Err(ModifierError::OutputLimit { modifier: name, limit });
+      }
+    }
+
+    Ok(output)
+  }
+
+  fn handler(&self, name: &str) -> Option<&dyn ModifierHandler> {
+    self.handlers.iter().find(|(registered, _)| registered == name).map(|(_, handler)| handler.as_ref())
+  }
+}
+
+// The name a modifier is registered under, and its arguments
+fn split(modifier: &Modifier) -> (String, &[CommandComponent]) {
+  match modifier {
+    Modifier::Named { name, args } => (name.to_lowercase(), args),
+    Modifier::FreeForm(components) => {
+      let words: usize = components.iter().take_while(|component| matches!(component, CommandComponent::Keyword(_))).count();
+      let name: Vec<String> = components[..words]
+        .iter()
+        .filter_map(|component| match component {
+          CommandComponent::Keyword(word) => Some(word.to_lowercase()),
+          _ => None,
+        })
+        .collect();
+
+      (name.join(" "), &components[words..])
+    }
+  }
+}
+
+fn no_args(args: &[CommandComponent]) -> Result<(), String> {
+  match args {
+    [] => Ok(()),
+    _ => Err("expected no arguments".to_string()),
+  }
+}
+
+// The text of a single literal, number or word argument
+fn text(args: &[CommandComponent]) -> Result<String, String> {
+  match args {
+    [CommandComponent::Literal(text)] => Ok(text.clone()),
+    [CommandComponent::Keyword(word)] => Ok(word.to_string()),
+    [CommandComponent::Number { value, .. }] => Ok(value.clone()),
+    _ => Err("expected one literal".to_string()),
+  }
+}
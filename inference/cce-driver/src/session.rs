@@ -0,0 +1,704 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// The compilation pipeline. A session takes source files through each stage
+// in turn,
+//
+//   parse -> convert -> resolve -> lint -> lower -> codegen
+//
+// and collects what every stage produced in one `Output`, so the CLI, editor
+// tooling and tests all run the same pipeline. It stops after the first
+// stage that fails, or after `SessionConfig::stop_after`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use cce_ast::{parse_template, parse_workspace_with, DefinitionLocation, ParseOptions, ParserLimits, TemplateError, WorkspaceParseResult};
+use cce_config::{ConfigError, Project};
+use cce_infer::{specificity, Bindings, CacheStats, Conflict, ConflictPolicy, Deducer, Definition, DisambiguationPolicy, Entry, ExpandError, Expander, ExpansionLimits, ExpansionStats, FirstDefined, KnowledgeBase, KnowledgeBaseError, MatchOptions, ResolutionTrace, Resolver, SessionLog};
+use cce_infer_ast::{convert, CommandComponent, CommandNode, FinalSequence, HowToCommand, NormalizeOptions, ProgramNode};
+use cce_lint::{Diagnostic, LintContext, LintLevel, LintRegistry, Severity};
+use cce_stream::DecodeOptions;
+use circelang_db::{PackageError, Store};
+
+use crate::metrics::{Counter, Gauge, Metrics, NoMetrics};
+use crate::modifiers::{ModifierError, ModifierHandler, ModifierRegistry};
+use crate::timings::{Probe, StageTiming, Timings};
+use crate::watch::ParseCache;
+
+use thiserror::Error;
+
+
+// There is no separate lex stage: the parser pulls tokens from the lexer as
+// it needs them, so lexing is timed and counted as part of `Parse`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+  Parse,
+  Convert,
+  Resolve,
+  Lint,
+  Lower,
+  Codegen,
+}
+
+impl Stage {
+  pub const ALL: [Stage; 6] = [Stage::Parse, Stage::Convert, Stage::Resolve, Stage::Lint, Stage::Lower, Stage::Codegen];
+}
+
+impl fmt::Display for Stage {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Stage::Parse => write!(f, "parse"),
+      Stage::Convert => write!(f, "convert"),
+      Stage::Resolve => write!(f, "resolve"),
+      Stage::Lint => write!(f, "lint"),
+      Stage::Lower => write!(f, "lower"),
+      Stage::Codegen => write!(f, "codegen"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionConfig {
+  // Languages to pick final sequences for during codegen
+  pub targets: Vec<String>,
+  // Directories searched, in order, for relative file names that do not
+  // exist as given
+  pub search_paths: Vec<PathBuf>,
+  // Load the installed `std` package before the program's definitions
+  pub std: bool,
+  // Other installed packages to load
+  pub packages: Vec<String>,
+  // Store to load packages from, instead of `Store::default_path`
+  pub store: Option<PathBuf>,
+  pub policy: ConflictPolicy,
+  // How loosely commands match signatures when resolving and expanding
+  pub matching: MatchOptions,
+  // Normalize commands and signatures before matching them. `None` matches
+  // them as written.
+  pub normalization: Option<NormalizeOptions>,
+  // Namespaces, packages' or modules', visible to commands outside them.
+  // `None` makes every namespace visible.
+  pub imports: Option<Vec<String>>,
+  pub lint: bool,
+  // Overrides for the severity of lints, by name
+  pub lint_levels: BTreeMap<String, LintLevel>,
+  pub stop_after: Option<Stage>,
+  // Record how long each stage took and how much it allocated
+  pub timings: bool,
+  // Record why every command expanded resolved the way it did
+  pub trace_resolution: bool,
+  // Log every decision expansion makes, so the session can be replayed
+  pub record_decisions: bool,
+  // Bounds for parsing each file and for expanding the program, for
+  // sessions that accept untrusted input
+  pub parser_limits: ParserLimits,
+  pub expansion_limits: ExpansionLimits,
+  // How source files are decoded. Detects the encoding and rejects
+  // invalid input by default.
+  pub decode: DecodeOptions,
+}
+
+impl Default for SessionConfig {
+  fn default() -> Self {
+    Self {
+      targets: Vec::new(),
+      search_paths: Vec::new(),
+      std: false,
+      packages: Vec::new(),
+      store: None,
+      policy: ConflictPolicy::Warn,
+      matching: MatchOptions::default(),
+      normalization: None,
+      imports: None,
+      lint: true,
+      lint_levels: BTreeMap::new(),
+      stop_after: None,
+      timings: false,
+      trace_resolution: false,
+      record_decisions: false,
+      parser_limits: ParserLimits::default(),
+      expansion_limits: ExpansionLimits::default(),
+      decode: DecodeOptions::default(),
+    }
+  }
+}
+
+impl SessionConfig {
+  // Every package the session loads, in the order they are loaded
+  fn packages(&self) -> Vec<&str> {
+    let mut packages: Vec<&str> = self.packages.iter().map(String::as_str).collect();
+    if self.std {
+      packages.insert(0, "std");
+    }
+
+    packages
+  }
+}
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+  #[error("{0}")]
+  PackageError(#[from] PackageError),
+  #[error("{0}")]
+  ConfigError(#[from] ConfigError),
+  #[error("Session was not opened from a project manifest")]
+  NoProject,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CodegenError {
+  #[error("{0}")]
+  ModifierError(#[from] ModifierError),
+  #[error("{error}, in the final sequence of the whatis at {site}")]
+  Template { error: TemplateError, site: DefinitionLocation },
+  #[error("Ambiguous command: {} whatis definitions match `{command}` equally well, the first at {}", .sites.len(), .sites[0])]
+  Ambiguous { command: String, sites: Vec<DefinitionLocation> },
+}
+
+impl CodegenError {
+  // Stable code for `ccec explain`
+  pub fn code(&self) -> &'static str {
+    match self {
+      CodegenError::ModifierError(err) => err.code(),
+      CodegenError::Template { .. } => "CCE0208",
+      CodegenError::Ambiguous { .. } => "CCE0209",
+    }
+  }
+}
+
+// The final sequences codegen picked for one target language, from the
+// program's own whatis definitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artifact {
+  pub target: String,
+  pub sequences: Vec<(DefinitionLocation, FinalSequence)>,
+  // The expanded commands that name a whatis with a final sequence for the
+  // target, as that sequence filled in from the command, with the command's
+  // modifiers applied
+  pub outputs: Vec<String>,
+}
+
+// Fields belonging to stages that did not run are left empty
+pub struct Output {
+  pub workspace: WorkspaceParseResult,
+  pub files: Vec<Vec<ProgramNode>>,
+  pub knowledge: KnowledgeBase,
+  pub conflicts: Vec<Conflict>,
+  pub diagnostics: Vec<Diagnostic>,
+  pub program: Vec<ProgramNode>,
+  // The program's top-level commands with every howto call expanded
+  pub expanded: Vec<HowToCommand>,
+  pub expansion_error: Option<ExpandError>,
+  // Only recorded with `SessionConfig::trace_resolution`
+  pub traces: Vec<ResolutionTrace>,
+  // Only recorded with `SessionConfig::record_decisions`
+  pub decisions: SessionLog,
+  pub artifacts: Vec<Artifact>,
+  pub codegen_error: Option<CodegenError>,
+  // Stages that ran, in order
+  pub completed: Vec<Stage>,
+  // The stage that stopped the pipeline, if one failed
+  pub failed: Option<Stage>,
+  pub timings: Option<Timings>,
+}
+
+impl Output {
+  pub fn succeeded(&self) -> bool {
+    self.failed.is_none()
+  }
+
+  pub fn artifact(&self, target: &str) -> Option<&Artifact> {
+    self.artifacts.iter().find(|artifact| artifact.target == target)
+  }
+
+  // How many items `stage` left in the output
+  fn produced(&self, stage: Stage) -> usize {
+    match stage {
+      Stage::Parse => self.workspace.files.iter().map(|file| file.nodes.len()).sum(),
+      Stage::Convert => self.files.iter().map(Vec::len).sum(),
+      Stage::Resolve => self.knowledge.entries().len(),
+      Stage::Lint => self.diagnostics.len(),
+      Stage::Lower => self.expanded.len(),
+      Stage::Codegen => self.artifacts.iter().map(|artifact| artifact.sequences.len()).sum(),
+    }
+  }
+}
+
+pub type Hook = Box<dyn FnMut(Stage, &Output)>;
+
+pub struct Session {
+  config: SessionConfig,
+  hooks: Vec<Hook>,
+  // `None` expands the howto defined first
+  disambiguation: Option<Box<dyn DisambiguationPolicy>>,
+  // Followed instead of the disambiguation policy when set
+  replay: Option<SessionLog>,
+  modifiers: ModifierRegistry,
+  metrics: Box<dyn Metrics>,
+  // Shared by expansion and codegen, so codegen finds the commands
+  // expansion already matched
+  resolver: Resolver,
+  // Kept across compiles, for rechecking files as they change
+  cache: Option<ParseCache>,
+  project: Option<Project>,
+}
+
+impl Default for Session {
+  fn default() -> Self {
+    Self::new(SessionConfig::default())
+  }
+}
+
+impl Session {
+  pub fn new(config: SessionConfig) -> Self {
+    Self { config, hooks: Vec::new(), disambiguation: None, replay: None, modifiers: ModifierRegistry::default(), metrics: Box::new(NoMetrics), resolver: Resolver::new(), cache: None, project: None }
+  }
+
+  // A session for the project a `circe.toml` describes
+  pub fn from_manifest(path: &Path) -> Result<Self, SessionError> {
+    Ok(Self::from_project(Project::load(path)?, SessionConfig::default()))
+  }
+
+  // The project's settings replace those in `config`. Relative file names
+  // are also looked for in the project root.
+  pub fn from_project(project: Project, config: SessionConfig) -> Self {
+    let mut search_paths: Vec<PathBuf> = config.search_paths;
+    search_paths.push(project.root.clone());
+
+    let config: SessionConfig = SessionConfig {
+      targets: project.targets.clone(),
+      search_paths,
+      std: project.std,
+      packages: project.dependencies.clone(),
+      lint_levels: project.lints.clone(),
+      ..config
+    };
+
+    Self { project: Some(project), ..Self::new(config) }
+  }
+
+  pub fn project(&self) -> Option<&Project> {
+    self.project.as_ref()
+  }
+
+  // Compiles every source file of the project
+  pub fn compile_project(&mut self) -> Result<Output, SessionError> {
+    let files: Vec<PathBuf> = self.project.as_ref().ok_or(SessionError::NoProject)?.files()?;
+    self.compile(&files)
+  }
+
+  pub fn config(&self) -> &SessionConfig {
+    &self.config
+  }
+
+  // Hooks run after every stage, with the output so far
+  pub fn add_hook(&mut self, hook: impl FnMut(Stage, &Output) + 'static) {
+    self.hooks.push(Box::new(hook));
+  }
+
+  // Asked to choose when howtos match a command equally well
+  pub fn set_disambiguation(&mut self, policy: impl DisambiguationPolicy + 'static) {
+    self.disambiguation = Some(Box::new(policy));
+  }
+
+  // Later compiles expand the program as `log` records, so a program
+  // compiled under a nondeterministic policy expands the same way again
+  pub fn set_replay(&mut self, log: SessionLog) {
+    self.replay = Some(log);
+  }
+
+  // Handlers for the modifiers codegen applies, the built-in ones by default
+  pub fn set_modifiers(&mut self, modifiers: ModifierRegistry) {
+    self.modifiers = modifiers;
+  }
+
+  pub fn modifiers(&self) -> &ModifierRegistry {
+    &self.modifiers
+  }
+
+  pub fn register_modifier(&mut self, name: &str, handler: impl ModifierHandler + 'static) {
+    self.modifiers.register(name, handler);
+  }
+
+  // Receives what every later compile did. Pass an `Arc` to keep reading
+  // the metrics from elsewhere.
+  pub fn set_metrics(&mut self, metrics: impl Metrics + 'static) {
+    self.metrics = Box::new(metrics);
+  }
+
+  // Later compiles only parse files whose contents changed
+  pub fn enable_parse_cache(&mut self) {
+    self.cache.get_or_insert_with(ParseCache::default);
+  }
+
+  pub fn parse_cache(&self) -> Option<&ParseCache> {
+    self.cache.as_ref()
+  }
+
+  // Cached lookups from the last compile's expansion and codegen
+  pub fn resolver(&self) -> &Resolver {
+    &self.resolver
+  }
+
+  pub fn resolve_path(&self, path: &Path) -> PathBuf {
+    if path.is_relative() && !path.exists() {
+      for dir in &self.config.search_paths {
+        let candidate: PathBuf = dir.join(path);
+
+        if candidate.exists() {
+          return candidate;
+        }
+      }
+    }
+
+    path.to_path_buf()
+  }
+
+  fn knowledge_base(&self) -> KnowledgeBase {
+    let knowledge: KnowledgeBase = KnowledgeBase::new(self.config.policy).with_match_options(self.config.matching);
+
+    match &self.config.normalization {
+      Some(options) => knowledge.with_normalization(options.clone()),
+      None => knowledge,
+    }
+  }
+
+  pub fn compile(&mut self, filenames: &[PathBuf]) -> Result<Output, SessionError> {
+    let paths: Vec<PathBuf> = filenames.iter().map(|path| self.resolve_path(path)).collect();
+
+    let mut output: Output = Output {
+      workspace: WorkspaceParseResult { files: Vec::new(), definitions: HashMap::new() },
+      files: Vec::new(),
+      knowledge: self.knowledge_base(),
+      conflicts: Vec::new(),
+      diagnostics: Vec::new(),
+      program: Vec::new(),
+      expanded: Vec::new(),
+      expansion_error: None,
+      traces: Vec::new(),
+      decisions: SessionLog::new(),
+      artifacts: Vec::new(),
+      codegen_error: None,
+      completed: Vec::new(),
+      failed: None,
+      timings: self.config.timings.then(Timings::default),
+    };
+
+    self.metrics.increment(Counter::Compiles, 1);
+    // The resolver is kept across compiles, so only this compile's lookups
+    // are reported
+    let resolved: CacheStats = self.resolver.stats();
+
+    for stage in Stage::ALL {
+      #[cfg(feature = "tracing")]
+      let _span = tracing::info_span!("stage", %stage).entered();
+
+      let probe: Option<Probe> = self.config.timings.then(Probe::start);
+
+      let succeeded: bool = match stage {
+        Stage::Parse => {
+          let options: ParseOptions = ParseOptions { limits: self.config.parser_limits, decode: self.config.decode, ..ParseOptions::default() };
+          output.workspace = match &mut self.cache {
+            Some(cache) => cache.parse_with(&paths, &options),
+            None => parse_workspace_with(&paths, &options),
+          };
+          !output.workspace.has_errors()
+        }
+        Stage::Convert => {
+          output.files = output.workspace.files
+            .iter()
+            .map(|file| convert(file.nodes.clone()))
+            .collect();
+          true
+        }
+        Stage::Resolve => self.resolve(&mut output)?,
+        Stage::Lint if !self.config.lint => continue,
+        Stage::Lint => {
+          let mut registry: LintRegistry = LintRegistry::default();
+          for (lint, level) in &self.config.lint_levels {
+            registry.set_level(lint, *level);
+          }
+
+          output.diagnostics = registry.run(&LintContext::new(&output.files).with_knowledge(&output.knowledge));
+          !output.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+        }
+        Stage::Lower => {
+          let mut deducer: Deducer = Deducer::new();
+          for node in output.files.iter().flatten() {
+            deducer.add_node(node.clone());
+          }
+
+          output.program = deducer.deduce();
+          self.expand(&mut output)
+        }
+        Stage::Codegen => {
+          let packages: Vec<&str> = self.config.packages();
+          let mut first: FirstDefined = FirstDefined;
+          let policy: &mut dyn DisambiguationPolicy = match &mut self.disambiguation {
+            Some(policy) => policy.as_mut(),
+            None => &mut first,
+          };
+
+          let artifacts: Result<Vec<Artifact>, CodegenError> = self.config.targets
+            .iter()
+            .map(|target| codegen(&output.knowledge, &mut self.resolver, &packages, &output.expanded, &self.modifiers, policy, target))
+            .collect();
+
+          match artifacts {
+            Ok(artifacts) => output.artifacts = artifacts,
+            Err(err) => output.codegen_error = Some(err),
+          }
+          output.codegen_error.is_none()
+        }
+      };
+
+      if let Some(probe) = probe {
+        let timing: StageTiming = probe.finish(stage, output.produced(stage));
+        output.timings.get_or_insert_with(Timings::default).stages.push(timing);
+      }
+
+      output.completed.push(stage);
+      self.report(stage, &output);
+      if !succeeded {
+        output.failed = Some(stage);
+
+        #[cfg(feature = "tracing")]
+        tracing::info!("stage failed");
+      }
+
+      for hook in &mut self.hooks {
+        hook(stage, &output);
+      }
+
+      if !succeeded || self.config.stop_after == Some(stage) {
+        break;
+      }
+    }
+
+    let stats: CacheStats = self.resolver.stats();
+    self.metrics.increment(Counter::ResolverCacheHits, stats.hits - resolved.hits);
+    self.metrics.increment(Counter::ResolverCacheMisses, stats.misses - resolved.misses);
+
+    if output.failed.is_some() {
+      self.metrics.increment(Counter::FailedCompiles, 1);
+    }
+
+    Ok(output)
+  }
+
+  fn report(&self, stage: Stage, output: &Output) {
+    match stage {
+      Stage::Parse => {
+        self.metrics.increment(Counter::FilesParsed, output.workspace.files.len() as u64);
+        self.metrics.increment(Counter::StatementsParsed, output.produced(Stage::Parse) as u64);
+
+        if let Some(cache) = &self.cache {
+          self.metrics.increment(Counter::ParseCacheHits, cache.hits() as u64);
+          self.metrics.increment(Counter::ParseCacheMisses, cache.misses() as u64);
+        }
+      }
+      Stage::Resolve => self.metrics.set(Gauge::KnowledgeEntries, output.knowledge.entries().len() as u64),
+      Stage::Lint => self.metrics.increment(Counter::Diagnostics, output.diagnostics.len() as u64),
+      Stage::Lower => self.metrics.set(Gauge::ExpandedCommands, output.expanded.len() as u64),
+      Stage::Convert | Stage::Codegen => {}
+    }
+  }
+
+  fn expand(&mut self, output: &mut Output) -> bool {
+    let mut expander: Expander = Expander::new(&output.knowledge, self.config.expansion_limits).with_resolver(std::mem::take(&mut self.resolver));
+    if let Some(policy) = &mut self.disambiguation {
+      expander = expander.with_disambiguation(|command: &[CommandComponent], candidates: &[&Entry]| policy.choose(command, candidates));
+    }
+    if self.config.trace_resolution {
+      expander = expander.with_trace();
+    }
+    if self.config.record_decisions {
+      expander = expander.with_log();
+    }
+    if let Some(log) = &self.replay {
+      expander = expander.replaying(log.clone());
+    }
+
+    for node in &output.program {
+      let ProgramNode::Command(command) = node else {
+        continue;
+      };
+
+      match expander.expand(command) {
+        Ok(steps) => output.expanded.extend(steps),
+        Err(err) => {
+          output.expansion_error = Some(err);
+          break;
+        }
+      }
+    }
+
+    let stats: ExpansionStats = expander.stats();
+    self.metrics.increment(Counter::ResolutionsAttempted, stats.resolutions);
+    self.metrics.increment(Counter::ResolutionsUnmatched, stats.unresolved);
+    self.metrics.increment(Counter::MatcherCalls, stats.matcher_calls);
+    self.metrics.increment(Counter::PolicyCalls, stats.policy_calls);
+    self.metrics.increment(Counter::ReplayedDecisions, stats.replayed);
+
+    self.resolver = expander.take_resolver();
+    output.traces = expander.take_traces();
+    output.decisions = expander.take_log();
+    output.expansion_error.is_none()
+  }
+
+  // Packages go in first, each under its own namespace, then the program's
+  // definitions, with those in modules under the module's namespace. Fails if a conflict is an error under the session's policy.
+  fn resolve(&self, output: &mut Output) -> Result<bool, SessionError> {
+    let packages: Vec<&str> = self.config.packages();
+
+    if !packages.is_empty() {
+      let store: Store = match &self.config.store {
+        Some(root) => Store::open(root).map_err(PackageError::from)?,
+        None => Store::open_default().map_err(PackageError::from)?,
+      };
+
+      for package in packages {
+        output.conflicts.extend(store.load(package, &mut output.knowledge)?);
+      }
+    }
+
+    let mut succeeded: bool = true;
+
+    for (file, nodes) in output.files.iter().enumerate() {
+      for (node, (namespace, program_node)) in nodes.iter().flat_map(ProgramNode::flatten_scoped).enumerate() {
+        let site: DefinitionLocation = DefinitionLocation { file, node };
+
+        let definition: Definition = match program_node {
+          ProgramNode::HowTo(howto) => howto.clone().into(),
+          ProgramNode::WhatIs(whatis) => whatis.clone().into(),
+          ProgramNode::Command(_) | ProgramNode::Module(_) => continue,
+        };
+
+        // Definitions in a module go under its namespace
+        let inserted = match namespace {
+          Some(namespace) => output.knowledge.insert_in(&namespace, definition, site),
+          None => output.knowledge.insert(definition, site),
+        };
+
+        match inserted {
+          Ok(Some(conflict)) => output.conflicts.push(conflict),
+          Ok(None) => {}
+          Err(KnowledgeBaseError::Conflict(conflict)) => {
+            output.conflicts.push(conflict);
+            succeeded = false;
+          }
+        }
+      }
+    }
+
+    if let Some(imports) = &self.config.imports {
+      output.knowledge.set_imports(imports.clone());
+    }
+
+    Ok(succeeded)
+  }
+}
+
+// Only the program's own definitions are generated, those in its modules
+// included, not those of packages
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(knowledge, resolver, packages, expanded, modifiers, policy)))]
+fn codegen(knowledge: &KnowledgeBase, resolver: &mut Resolver, packages: &[&str], expanded: &[HowToCommand], modifiers: &ModifierRegistry, policy: &mut dyn DisambiguationPolicy, target: &str) -> Result<Artifact, CodegenError> {
+  let sequences: Vec<(DefinitionLocation, FinalSequence)> = knowledge.entries()
+    .iter()
+    .filter(|entry| entry.namespace.as_deref().is_none_or(|namespace| !packages.contains(&namespace)))
+    .filter_map(|entry| match &entry.definition {
+      Definition::WhatIs(whatis) => Some((entry.site, whatis.final_for(target)?.clone())),
+      Definition::HowTo(_) => None,
+    })
+    .collect();
+
+  #[cfg(feature = "tracing")]
+  for (site, sequence) in &sequences {
+    tracing::debug!(file = site.file, node = site.node, language = sequence.language.as_deref(), "picked final sequence");
+  }
+
+  let mut outputs: Vec<String> = Vec::new();
+  render(knowledge, resolver, expanded, modifiers, policy, target, &mut outputs)?;
+
+  Ok(Artifact { target: target.to_string(), sequences, outputs })
+}
+
+// Package definitions count here, so a program can use the whatis of `std`.
+// Like howtos, the most specific visible whatis wins and the disambiguation
+// policy breaks ties.
+fn render(knowledge: &KnowledgeBase, resolver: &mut Resolver, steps: &[HowToCommand], modifiers: &ModifierRegistry, policy: &mut dyn DisambiguationPolicy, target: &str, outputs: &mut Vec<String>) -> Result<(), CodegenError> {
+  for step in steps {
+    let command: &CommandNode = match step {
+      HowToCommand::Command(command) => command,
+      HowToCommand::Block { body, .. } | HowToCommand::When { body, .. } => {
+        render(knowledge, resolver, body, modifiers, policy, target, outputs)?;
+        continue;
+      }
+    };
+
+    let mut whatises: Vec<(&Entry, Bindings, &FinalSequence)> = resolver.resolve(knowledge, &command.command)
+      .into_iter()
+      .filter_map(|(entry, bindings)| match &entry.definition {
+        Definition::WhatIs(whatis) => Some((entry, bindings, whatis.final_for(target)?)),
+        Definition::HowTo(_) => None,
+      })
+      .collect();
+
+    if let Some(best) = whatises.iter().map(|(entry, ..)| specificity(entry.definition.signature())).max() {
+      whatises.retain(|(entry, ..)| specificity(entry.definition.signature()) == best);
+    }
+
+    let chosen: Option<usize> = match whatises.len() {
+      0 => continue,
+      1 => Some(0),
+      count => {
+        let candidates: Vec<&Entry> = whatises.iter().map(|(entry, ..)| *entry).collect();
+        policy.choose(&command.command, &candidates).filter(|index| *index < count)
+      }
+    };
+
+    let Some((entry, bindings, sequence)) = chosen.map(|index| whatises.swap_remove(index)) else {
+      return Err(CodegenError::Ambiguous {
+        command: command.to_string(),
+        sites: whatises.iter().map(|(entry, ..)| entry.site).collect(),
+      });
+    };
+
+    let values: HashMap<String, String> = bindings
+      .iter()
+      .map(|(name, value)| match value {
+        CommandComponent::Literal(literal) => (name.clone(), literal.clone()),
+        value => (name.clone(), value.to_string()),
+      })
+      .collect();
+
+    // A placeholder that names no slot of the signature is left unbound
+    let code: String = parse_template(&sequence.code)
+      .and_then(|parts| cce_ast::render(&parts, &values))
+      .map_err(|error| CodegenError::Template { error, site: entry.site })?;
+
+    outputs.push(modifiers.apply(&command.modifiers, &code)?);
+  }
+
+  Ok(())
+}
@@ -0,0 +1,235 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Golden tests for the whole pipeline. `snapshot` renders everything the
+// pipeline makes of one file as text that only changes when its behavior
+// does: the tokens, the AST, how each command resolved, the expansion and
+// the final sequences picked for every language the file mentions.
+// `check_golden` compares the snapshot of every `.cce` file in a directory
+// with the `.snap` file next to it, so an end-to-end case is added by
+// dropping an input there and blessing its snapshot.
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cce_ast::{parse_source_with, Lexer, ParseOptions, Span, Token};
+use cce_infer_ast::{convert, ProgramNode, WhatIsCommand};
+
+use crate::session::{Output, Session, SessionConfig};
+
+
+// Set to write snapshots instead of checking them
+pub const BLESS_VAR: &str = "CIRCE_BLESS";
+
+pub const SNAPSHOT_EXTENSION: &str = "snap";
+
+
+// A `.cce` file whose snapshot is not the one on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenMismatch {
+  pub input: PathBuf,
+  // `None` if there is no snapshot yet
+  pub expected: Option<String>,
+  pub actual: String,
+}
+
+impl fmt::Display for GoldenMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let Some(expected) = &self.expected else {
+      return writeln!(f, "{}: no snapshot", self.input.display());
+    };
+
+    let line: usize = expected
+      .lines()
+      .zip(self.actual.lines())
+      .take_while(|(expected, actual)| expected == actual)
+      .count();
+
+    writeln!(f, "{}: snapshot differs from line {}", self.input.display(), line + 1)?;
+    writeln!(f, "  expected: {}", expected.lines().nth(line).unwrap_or("<end>"))?;
+    writeln!(f, "  actual:   {}", self.actual.lines().nth(line).unwrap_or("<end>"))
+  }
+}
+
+pub fn snapshot(path: &Path) -> io::Result<String> {
+  let source: String = fs::read_to_string(path)?;
+  let mut snapshot: String = String::new();
+
+  // Writing to a `String` cannot fail
+  let _ = render(&mut snapshot, path, &source);
+  Ok(snapshot)
+}
+
+fn render(out: &mut String, path: &Path, source: &str) -> fmt::Result {
+  writeln!(out, "== tokens")?;
+  let mut lexer: Lexer = Lexer::from(source);
+  loop {
+    match lexer.next() {
+      Ok(Some(token)) => {
+        let span: Span = lexer.span();
+        writeln!(out, "{}:{} {}", span.line, span.column, describe(&token))?;
+      }
+      Ok(None) => break,
+      Err(err) => {
+        writeln!(out, "error[{}]: {}", err.code(), err)?;
+        break;
+      }
+    }
+  }
+
+  // Every language the file has final sequences for is a target
+  let nodes: Vec<ProgramNode> = convert(parse_source_with(path, source, &ParseOptions::default()).nodes);
+  let config: SessionConfig = SessionConfig { targets: languages(&nodes), trace_resolution: true, ..SessionConfig::default() };
+  let output: Output = match Session::new(config).compile(&[path.to_path_buf()]) {
+    Ok(output) => output,
+    Err(err) => return writeln!(out, "\nerror: {}", err),
+  };
+
+  writeln!(out, "\n== ast")?;
+  for file in &output.workspace.files {
+    for node in &file.nodes {
+      writeln!(out, "{:#?}", node)?;
+    }
+
+    for err in &file.errors {
+      match err.span() {
+        Some(span) => writeln!(out, "error[{}]: {}:{}: {}", err.code(), span.line, span.column, err)?,
+        None => writeln!(out, "error[{}]: {}", err.code(), err)?,
+      }
+    }
+  }
+
+  if !output.conflicts.is_empty() || !output.diagnostics.is_empty() {
+    writeln!(out, "\n== diagnostics")?;
+  }
+  for conflict in &output.conflicts {
+    writeln!(out, "conflict: statement {} with statement {}", conflict.new.node + 1, conflict.existing.node + 1)?;
+  }
+  for diagnostic in &output.diagnostics {
+    let code: String = diagnostic.code.map(|code| format!("[{}]", code)).unwrap_or_default();
+    writeln!(out, "{}{}: statement {}: {} [{}]", diagnostic.severity, code, diagnostic.location.node + 1, diagnostic.message, diagnostic.lint)?;
+  }
+
+  writeln!(out, "\n== resolutions")?;
+  for trace in &output.traces {
+    write!(out, "{}", trace)?;
+  }
+
+  writeln!(out, "\n== expansion")?;
+  for step in &output.expanded {
+    write!(out, "{}", step)?;
+  }
+  if let Some(err) = &output.expansion_error {
+    writeln!(out, "error[{}]: {}", err.code(), err)?;
+  }
+
+  for artifact in &output.artifacts {
+    writeln!(out, "\n== codegen {}", artifact.target)?;
+
+    for (site, sequence) in &artifact.sequences {
+      writeln!(out, "statement {}: {}", site.node + 1, sequence.code)?;
+    }
+    for text in &artifact.outputs {
+      writeln!(out, "output: {:?}", text)?;
+    }
+  }
+  if let Some(err) = &output.codegen_error {
+    writeln!(out, "error[{}]: {}", err.code(), err)?;
+  }
+
+  Ok(())
+}
+
+fn describe(token: &Token) -> String {
+  match token {
+    Token::Identifier(text) | Token::Keyword(text) | Token::Literal(text) | Token::FinalSequence(text) => format!("{} {:?}", token.kind(), text),
+    _ => token.kind().to_string(),
+  }
+}
+
+// Every language a final sequence is written for, sorted
+fn languages(nodes: &[ProgramNode]) -> Vec<String> {
+  let mut languages: Vec<String> = nodes
+    .iter()
+    .flat_map(ProgramNode::flatten)
+    .filter_map(|node| match node {
+      ProgramNode::WhatIs(whatis) => Some(whatis),
+      _ => None,
+    })
+    .flat_map(|whatis| &whatis.alternatives)
+    .filter_map(|alternative| match &alternative.command {
+      WhatIsCommand::Final(sequence) => sequence.language.clone(),
+      _ => None,
+    })
+    .collect();
+
+  languages.sort();
+  languages.dedup();
+  languages
+}
+
+// Checks, or with `bless` writes, the snapshot of every `.cce` file directly
+// in `dir`, in order of their names
+pub fn check_golden(dir: &Path, bless: bool) -> io::Result<Vec<GoldenMismatch>> {
+  let mut inputs: Vec<PathBuf> = fs::read_dir(dir)?
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<io::Result<Vec<PathBuf>>>()?;
+  inputs.retain(|path| path.extension().is_some_and(|extension| extension == "cce"));
+  inputs.sort();
+
+  let mut mismatches: Vec<GoldenMismatch> = Vec::new();
+
+  for input in inputs {
+    let actual: String = snapshot(&input)?;
+    let path: PathBuf = input.with_extension(SNAPSHOT_EXTENSION);
+    let expected: Option<String> = fs::read_to_string(&path).ok();
+
+    if expected.as_deref() == Some(actual.as_str()) {
+      continue;
+    }
+
+    if bless {
+      fs::write(&path, &actual)?;
+    } else {
+      mismatches.push(GoldenMismatch { input, expected, actual });
+    }
+  }
+
+  Ok(mismatches)
+}
+
+// For a `#[test]`: panics listing every mismatch. Setting `CIRCE_BLESS`
+// writes the snapshots instead.
+pub fn run_golden(dir: &Path) {
+  let bless: bool = std::env::var_os(BLESS_VAR).is_some();
+
+  let mismatches: Vec<GoldenMismatch> = match check_golden(dir, bless) {
+    Ok(mismatches) => mismatches,
+    Err(err) => panic!("cannot run golden tests in {}: {}", dir.display(), err),
+  };
+
+  if !mismatches.is_empty() {
+    let report: String = mismatches.iter().map(GoldenMismatch::to_string).collect();
+    panic!("{} golden tests failed, run with {}=1 to bless them\n{}", mismatches.len(), BLESS_VAR, report);
+  }
+}
@@ -0,0 +1,168 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Per-stage instrumentation, enabled with `SessionConfig::timings`. Wall
+// time and node counts are always recorded; peak memory is only known when
+// the `alloc-tracking` feature is on and the embedding binary installs
+// `TrackingAllocator` as its global allocator:
+//
+//   #[global_allocator]
+//   static ALLOCATOR: cce_driver::TrackingAllocator = cce_driver::TrackingAllocator;
+//
+// Allocations are counted process-wide, so work on other threads shows up
+// in whichever stage is running.
+
+#[cfg(feature = "alloc-tracking")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::session::Stage;
+
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "alloc-tracking")]
+pub struct TrackingAllocator;
+
+#[cfg(feature = "alloc-tracking")]
+impl TrackingAllocator {
+  fn grow(size: usize) {
+    let now: usize = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+  }
+
+  fn shrink(size: usize) {
+    CURRENT.fetch_sub(size, Ordering::Relaxed);
+  }
+}
+
+#[cfg(feature = "alloc-tracking")]
+unsafe impl GlobalAlloc for TrackingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    INSTALLED.store(true, Ordering::Relaxed);
+
+    let ptr: *mut u8 = System.alloc(layout);
+    if !ptr.is_null() {
+      Self::grow(layout.size());
+    }
+    ptr
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout);
+    Self::shrink(layout.size());
+  }
+
+  unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    let new: *mut u8 = System.realloc(ptr, layout, new_size);
+    if !new.is_null() {
+      Self::shrink(layout.size());
+      Self::grow(new_size);
+    }
+    new
+  }
+}
+
+// Measures one stage from `start` to `finish`
+pub(crate) struct Probe {
+  started: Instant,
+  baseline: usize,
+}
+
+impl Probe {
+  pub(crate) fn start() -> Self {
+    let baseline: usize = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    Probe { started: Instant::now(), baseline }
+  }
+
+  pub(crate) fn finish(self, stage: Stage, nodes: usize) -> StageTiming {
+    let peak_bytes: Option<usize> = INSTALLED
+      .load(Ordering::Relaxed)
+      .then(|| PEAK.load(Ordering::Relaxed).saturating_sub(self.baseline));
+
+    StageTiming { stage, wall: self.started.elapsed(), nodes, peak_bytes }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageTiming {
+  pub stage: Stage,
+  pub wall: Duration,
+  // What the stage produced: parse and convert nodes, knowledge base
+  // entries, diagnostics, lowered nodes or final sequences
+  pub nodes: usize,
+  // Bytes allocated above what was live when the stage started, at the
+  // highest point during it
+  pub peak_bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Timings {
+  pub stages: Vec<StageTiming>,
+}
+
+impl Timings {
+  pub fn total(&self) -> Duration {
+    self.stages.iter().map(|timing| timing.wall).sum()
+  }
+
+  pub fn stage(&self, stage: Stage) -> Option<&StageTiming> {
+    self.stages.iter().find(|timing| timing.stage == stage)
+  }
+
+  pub fn to_json(&self) -> String {
+    let stages: Vec<String> = self.stages
+      .iter()
+      .map(|timing| format!(
+        "{{\"stage\":\"{}\",\"wall_ms\":{:.3},\"nodes\":{},\"peak_bytes\":{}}}",
+        timing.stage,
+        millis(timing.wall),
+        timing.nodes,
+        timing.peak_bytes.map_or("null".to_string(), |bytes| bytes.to_string()),
+      ))
+      .collect();
+
+    format!("{{\"stages\":[{}],\"total_ms\":{:.3}}}", stages.join(","), millis(self.total()))
+  }
+}
+
+impl fmt::Display for Timings {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{:<10} {:>12} {:>8} {:>12}", "stage", "time (ms)", "nodes", "peak (B)")?;
+
+    for timing in &self.stages {
+      let peak: String = timing.peak_bytes.map_or("-".to_string(), |bytes| bytes.to_string());
+      writeln!(f, "{:<10} {:>12.3} {:>8} {:>12}", timing.stage.to_string(), millis(timing.wall), timing.nodes, peak)?;
+    }
+
+    writeln!(f, "{:<10} {:>12.3}", "total", millis(self.total()))
+  }
+}
+
+fn millis(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
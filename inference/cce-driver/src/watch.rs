@@ -0,0 +1,229 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Support for rechecking files as they are edited. `Watcher` wakes up on
+// filesystem notifications and compares modification times to find which
+// source files changed, and
+// `ParseCache` keeps the parse of every file keyed by its contents, so a
+// recheck only parses the files that really changed.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use cce_ast::{parse_source_with, read_source, FileParseResult, ParseNode, ParseOptions, ParserLimits, WorkspaceParseResult};
+use cce_config::source_files;
+use cce_stream::{DecodeOptions, Decoded};
+use circelang_hash::CirceHash;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+
+#[derive(Debug)]
+struct Cached {
+  contents: String,
+  nodes: Vec<ParseNode>,
+  used: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ParseCache {
+  nodes: HashMap<u64, Vec<Cached>>,
+  hits: usize,
+  misses: usize,
+}
+
+impl ParseCache {
+  // Files with errors are not cached, as they are likely being edited
+  pub fn parse(&mut self, paths: &[PathBuf], limits: ParserLimits) -> WorkspaceParseResult {
+    self.parse_with(paths, &ParseOptions { limits, ..ParseOptions::default() })
+  }
+
+  pub fn parse_with_options(&mut self, paths: &[PathBuf], limits: ParserLimits, decode: DecodeOptions) -> WorkspaceParseResult {
+    self.parse_with(paths, &ParseOptions { limits, decode, ..ParseOptions::default() })
+  }
+
+  // Files are cached by their decoded contents, so a file saved again in
+  // another encoding is not parsed again
+  pub fn parse_with(&mut self, paths: &[PathBuf], options: &ParseOptions) -> WorkspaceParseResult {
+    self.hits = 0;
+    self.misses = 0;
+
+    for cached in self.nodes.values_mut().flatten() {
+      cached.used = false;
+    }
+
+    let files: Vec<FileParseResult> = paths
+      .iter()
+      .map(|path| {
+        let Decoded { text: contents, warnings, .. } = match read_source(path, options.decode) {
+          Ok(decoded) => decoded,
+          Err(err) => return FileParseResult { path: path.clone(), nodes: Vec::new(), errors: vec![err], warnings: Vec::new() },
+        };
+
+        // Hashes can collide, so every bucket keeps the contents it was
+        // parsed from
+        let bucket: &mut Vec<Cached> = self.nodes.entry(contents.hash()).or_default();
+
+        if let Some(cached) = bucket.iter_mut().find(|cached| cached.contents == contents) {
+          self.hits += 1;
+          cached.used = true;
+          return FileParseResult { path: path.clone(), nodes: cached.nodes.clone(), errors: Vec::new(), warnings };
+        }
+
+        self.misses += 1;
+        let result: FileParseResult = FileParseResult { warnings, ..parse_source_with(path, &contents, options) };
+
+        if result.errors.is_empty() {
+          bucket.push(Cached { contents, nodes: result.nodes.clone(), used: true });
+        }
+
+        result
+      })
+      .collect();
+
+    // Contents no file has any more are not coming back
+    for bucket in self.nodes.values_mut() {
+      bucket.retain(|cached| cached.used);
+    }
+    self.nodes.retain(|_, bucket| !bucket.is_empty());
+
+    WorkspaceParseResult::from_files(files)
+  }
+
+  // Files the last `parse` took from the cache
+  pub fn hits(&self) -> usize {
+    self.hits
+  }
+
+  // Files the last `parse` had to parse
+  pub fn misses(&self) -> usize {
+    self.misses
+  }
+
+  pub fn len(&self) -> usize {
+    self.nodes.values().map(Vec::len).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+}
+
+// Watches the `.cce` files in some directories, searched recursively, and
+// any files given directly
+#[derive(Debug)]
+pub struct Watcher {
+  roots: Vec<PathBuf>,
+  seen: HashMap<PathBuf, (SystemTime, u64)>,
+  events: Option<(RecommendedWatcher, Receiver<notify::Result<Event>>)>,
+}
+
+impl Watcher {
+  // Without filesystem notifications, for example when the system is out of
+  // watches, `wait` falls back to polling
+  pub fn new(roots: Vec<PathBuf>) -> Self {
+    let events = subscribe(&roots).ok();
+    Self { roots, seen: HashMap::new(), events }
+  }
+
+  // Whether `wait` is woken by filesystem notifications
+  pub fn notifications(&self) -> bool {
+    self.events.is_some()
+  }
+
+  // Every file found by the last poll, sorted
+  pub fn files(&self) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = self.seen.keys().cloned().collect();
+    files.sort();
+    files
+  }
+
+  // Files added, changed or removed since the last poll. The first poll
+  // returns every file.
+  pub fn poll(&mut self) -> io::Result<Vec<PathBuf>> {
+    let mut current: HashMap<PathBuf, (SystemTime, u64)> = HashMap::new();
+    for path in source_files(&self.roots)? {
+      // A file removed since it was listed counts as removed
+      let metadata: fs::Metadata = match fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+        Err(err) => return Err(err),
+      };
+      current.insert(path, (metadata.modified()?, metadata.len()));
+    }
+
+    let mut changed: Vec<PathBuf> = current
+      .iter()
+      .filter(|(path, stamp)| self.seen.get(*path) != Some(stamp))
+      .map(|(path, _)| path.clone())
+      .chain(self.seen.keys().filter(|path| !current.contains_key(*path)).cloned())
+      .collect();
+    changed.sort();
+
+    self.seen = current;
+    Ok(changed)
+  }
+
+  // Blocks until a poll finds a change. Polls happen after every
+  // notification, or every `interval` without them.
+  pub fn wait(&mut self, interval: Duration) -> io::Result<Vec<PathBuf>> {
+    loop {
+      let changed: Vec<PathBuf> = self.poll()?;
+      if !changed.is_empty() {
+        return Ok(changed);
+      }
+
+      match &self.events {
+        Some((_, events)) => {
+          if events.recv().is_err() {
+            thread::sleep(interval);
+          }
+
+          // One poll covers every notification that arrived meanwhile
+          while events.try_recv().is_ok() {}
+        }
+        None => thread::sleep(interval),
+      }
+    }
+  }
+}
+
+// Files given directly are watched through their directory, as editors
+// often save by replacing the file
+fn subscribe(roots: &[PathBuf]) -> notify::Result<(RecommendedWatcher, Receiver<notify::Result<Event>>)> {
+  let (sender, events) = channel();
+  let mut watcher: RecommendedWatcher = notify::recommended_watcher(sender)?;
+
+  for root in roots {
+    if root.is_dir() {
+      watcher.watch(root, RecursiveMode::Recursive)?;
+    } else {
+      let parent: &Path = root.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+      watcher.watch(parent, RecursiveMode::NonRecursive)?;
+    }
+  }
+
+  Ok((watcher, events))
+}
@@ -0,0 +1,92 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use cce_ast::ParserLimits;
+use cce_driver::*;
+use cce_infer::ConflictPolicy;
+
+
+// Every code the pipeline reports for `source`
+fn reported_codes(path: &PathBuf, source: &str) -> HashSet<&'static str> {
+  fs::write(path, source).unwrap();
+
+  let mut codes: HashSet<&'static str> = HashSet::new();
+
+  for policy in [ConflictPolicy::Warn, ConflictPolicy::Error] {
+    let config: SessionConfig = SessionConfig {
+      policy,
+      parser_limits: ParserLimits { max_depth: Some(2), ..ParserLimits::default() },
+      ..SessionConfig::default()
+    };
+    let output: Output = Session::new(config).compile(std::slice::from_ref(path)).unwrap();
+
+    codes.extend(output.workspace.files.iter().flat_map(|file| file.errors.iter().map(|err| err.code())));
+    codes.extend(output.diagnostics.iter().filter_map(|diagnostic| diagnostic.code));
+    codes.extend(output.expansion_error.iter().map(|err| err.code()));
+
+    if policy == ConflictPolicy::Error && output.failed == Some(Stage::Resolve) {
+      codes.insert("CCE0200");
+    }
+  }
+
+  codes
+}
+
+
+#[test]
+fn test_explain_lookup() {
+  let explanation: &Explanation = explain("CCE0100").unwrap();
+
+  assert_eq!(explanation.code, "CCE0100");
+  assert_eq!(explain("cce0100"), Some(explanation));
+  assert_eq!(explain("CCE9999"), None);
+  assert!(explanation.to_string().starts_with("CCE0100: expected a different token\n"));
+}
+
+#[test]
+fn test_explain_codes_unique() {
+  let codes: HashSet<&str> = explanations().iter().map(|explanation| explanation.code).collect();
+
+  assert_eq!(codes.len(), explanations().len());
+}
+
+#[test]
+fn test_explain_examples_produce_their_code() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-explain-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let path: PathBuf = dir.join("example.cce");
+
+  for explanation in explanations() {
+    // Examples that are not Circe source, like limits or IO failures
+    if explanation.example.starts_with('(') || explanation.example.starts_with("ccec") {
+      continue;
+    }
+
+    let codes: HashSet<&str> = reported_codes(&path, explanation.example);
+    assert!(codes.contains(explanation.code), "{} example reported {:?}", explanation.code, codes);
+  }
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,53 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cce_driver::snapshot::*;
+
+
+// Run with CIRCE_BLESS=1 to update the snapshots after a deliberate change
+#[test]
+fn test_golden() {
+  run_golden(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden"));
+}
+
+#[test]
+fn test_golden_mismatch() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-golden-{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("wave.cce"), "wave.\n").unwrap();
+
+  let mismatches: Vec<GoldenMismatch> = check_golden(&dir, false).unwrap();
+  assert_eq!(mismatches.len(), 1);
+  assert_eq!(mismatches[0].expected, None);
+
+  assert!(check_golden(&dir, true).unwrap().is_empty());
+  assert!(check_golden(&dir, false).unwrap().is_empty());
+
+  fs::write(dir.join("wave.cce"), "nod.\n").unwrap();
+  let mismatches: Vec<GoldenMismatch> = check_golden(&dir, false).unwrap();
+  assert!(mismatches[0].to_string().contains("expected: 1:1 identifier \"wave\""));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,155 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use cce_driver::*;
+use cce_infer::Entry;
+use cce_infer_ast::CommandComponent;
+
+
+#[derive(Default)]
+struct Recorded {
+  counters: Mutex<HashMap<Counter, u64>>,
+  gauges: Mutex<HashMap<Gauge, u64>>,
+}
+
+impl Recorded {
+  fn counter(&self, counter: Counter) -> u64 {
+    self.counters.lock().unwrap().get(&counter).copied().unwrap_or(0)
+  }
+
+  fn gauge(&self, gauge: Gauge) -> Option<u64> {
+    self.gauges.lock().unwrap().get(&gauge).copied()
+  }
+}
+
+impl Metrics for Recorded {
+  fn increment(&self, counter: Counter, by: u64) {
+    *self.counters.lock().unwrap().entry(counter).or_default() += by;
+  }
+
+  fn set(&self, gauge: Gauge, value: u64) {
+    self.gauges.lock().unwrap().insert(gauge, value);
+  }
+}
+
+fn source(name: &str, text: &str) -> (PathBuf, PathBuf) {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-metrics-{}-{}", std::process::id(), name));
+  fs::create_dir_all(&dir).unwrap();
+  let path: PathBuf = dir.join("main.cce");
+  fs::write(&path, text).unwrap();
+
+  (dir, path)
+}
+
+
+#[test]
+fn test_metrics_names_unique() {
+  let mut names: Vec<&str> = Counter::ALL.iter().map(Counter::name).chain(Gauge::ALL.iter().map(Gauge::name)).collect();
+  names.sort();
+  names.dedup();
+
+  assert_eq!(names.len(), Counter::ALL.len() + Gauge::ALL.len());
+  assert_eq!(Counter::StatementsParsed.to_string(), "statements_parsed");
+}
+
+#[test]
+fn test_metrics_compile() {
+  let (dir, path) = source("compile", "howto greet %who?\n- wave\n\nhowto greet %who:string?\n- bow\n\ngreet 'Bob'.\nsmile.\n");
+
+  let metrics: Arc<Recorded> = Arc::new(Recorded::default());
+  let mut session: Session = Session::new(SessionConfig::default());
+  session.set_metrics(metrics.clone());
+  session.set_disambiguation(|_: &[CommandComponent], _: &[&Entry]| Some(1));
+
+  let output: Output = session.compile(std::slice::from_ref(&path)).unwrap();
+  assert!(output.succeeded());
+
+  assert_eq!(metrics.counter(Counter::Compiles), 1);
+  assert_eq!(metrics.counter(Counter::FilesParsed), 1);
+  assert_eq!(metrics.counter(Counter::StatementsParsed), 4);
+  // `greet 'Bob'`, then `bow` and `smile`, which match nothing
+  assert_eq!(metrics.counter(Counter::ResolutionsAttempted), 3);
+  assert_eq!(metrics.counter(Counter::ResolutionsUnmatched), 2);
+  assert_eq!(metrics.counter(Counter::MatcherCalls), 2);
+  assert_eq!(metrics.counter(Counter::PolicyCalls), 1);
+  assert_eq!(metrics.counter(Counter::ResolverCacheMisses), 3);
+  assert_eq!(metrics.counter(Counter::FailedCompiles), 0);
+  assert_eq!(metrics.gauge(Gauge::KnowledgeEntries), Some(2));
+  assert_eq!(metrics.gauge(Gauge::ExpandedCommands), Some(2));
+
+  // Counters add up across compiles, and the parse cache reports hits
+  session.enable_parse_cache();
+  session.compile(std::slice::from_ref(&path)).unwrap();
+  session.compile(std::slice::from_ref(&path)).unwrap();
+
+  assert_eq!(metrics.counter(Counter::Compiles), 3);
+  assert_eq!(metrics.counter(Counter::StatementsParsed), 12);
+  assert_eq!(metrics.counter(Counter::ParseCacheMisses), 1);
+  assert_eq!(metrics.counter(Counter::ParseCacheHits), 1);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_metrics_resolver_cache() {
+  let (dir, path) = source("resolver", "smile.\nsmile.\n");
+
+  let metrics: Arc<Recorded> = Arc::new(Recorded::default());
+  let mut session: Session = Session::new(SessionConfig::default());
+  session.set_metrics(metrics.clone());
+
+  session.compile(std::slice::from_ref(&path)).unwrap();
+  assert_eq!(metrics.counter(Counter::ResolverCacheMisses), 1);
+  assert_eq!(metrics.counter(Counter::ResolverCacheHits), 1);
+
+  // Each compile reports its own lookups, though the resolver is kept
+  session.compile(std::slice::from_ref(&path)).unwrap();
+  assert_eq!(metrics.counter(Counter::ResolverCacheMisses), 2);
+  assert_eq!(metrics.counter(Counter::ResolverCacheHits), 2);
+  assert_eq!(session.resolver().stats().lookups(), 4);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_metrics_failed_compile() {
+  let (dir, path) = source("failed", "howto greet %who\n- wave\n");
+
+  let metrics: Arc<Recorded> = Arc::new(Recorded::default());
+  let mut session: Session = Session::new(SessionConfig::default());
+  session.set_metrics(metrics.clone());
+
+  let output: Output = session.compile(std::slice::from_ref(&path)).unwrap();
+  assert_eq!(output.failed, Some(Stage::Parse));
+  assert_eq!(metrics.counter(Counter::FailedCompiles), 1);
+  assert_eq!(metrics.counter(Counter::ResolutionsAttempted), 0);
+  assert_eq!(metrics.gauge(Gauge::KnowledgeEntries), None);
+
+  // Sessions without metrics compile the same
+  assert_eq!(Session::default().compile(std::slice::from_ref(&path)).unwrap().failed, Some(Stage::Parse));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,173 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::PathBuf;
+
+use cce_ast::TemplateError;
+use cce_driver::*;
+use cce_infer::RejectAmbiguous;
+use cce_infer_ast::{CommandComponent, Modifier};
+
+
+fn free_form(words: &str) -> Modifier {
+  Modifier::FreeForm(words.split(' ').map(|word| CommandComponent::Keyword(word.into())).collect())
+}
+
+fn named(name: &str, args: Vec<CommandComponent>) -> Modifier {
+  Modifier::Named { name: name.to_string(), args }
+}
+
+fn number(value: &str) -> CommandComponent {
+  CommandComponent::Number { value: value.to_string(), unit: None }
+}
+
+
+#[test]
+fn test_modifiers_builtin() {
+  let registry: ModifierRegistry = ModifierRegistry::default();
+
+  assert_eq!(registry.apply(&[free_form("add a newline")], "echo hi").unwrap(), "echo hi\n");
+  assert_eq!(registry.apply(&[free_form("Uppercase"), free_form("trim")], " echo hi ").unwrap(), "ECHO HI");
+  assert_eq!(registry.apply(&[named("indent", vec![number("4")])], "a\n\nb\n").unwrap(), "    a\n\n    b\n");
+  assert_eq!(registry.apply(&[free_form("indent")], "a").unwrap(), "  a");
+  assert_eq!(
+    registry.apply(&[named("prefix", vec![CommandComponent::Literal("$ ".to_string())]), named("suffix", vec![CommandComponent::Literal(";".to_string())])], "ls").unwrap(),
+    "$ ls;"
+  );
+  assert_eq!(registry.apply(&[], "ls").unwrap(), "ls");
+  assert!(registry.contains("ADD A NEWLINE"));
+}
+
+#[test]
+fn test_modifiers_free_form_arguments() {
+  let registry: ModifierRegistry = ModifierRegistry::default();
+  let modifier: Modifier = Modifier::FreeForm(vec![CommandComponent::Keyword("indent".into()), number("3")]);
+
+  assert_eq!(registry.apply(&[modifier], "x").unwrap(), "   x");
+}
+
+#[test]
+fn test_modifiers_errors() {
+  let registry: ModifierRegistry = ModifierRegistry::default();
+
+  assert_eq!(
+    registry.apply(&[free_form("sparkle")], "x"),
+    Err(ModifierError::Unknown { modifier: "sparkle".to_string() })
+  );
+
+  let err: ModifierError = registry.apply(&[named("indent", vec![CommandComponent::Literal("wide".to_string())])], "x").unwrap_err();
+  assert_eq!(err.code(), "CCE0206");
+  assert!(matches!(err, ModifierError::Failed { modifier, .. } if modifier == "indent"));
+
+  assert!(matches!(registry.apply(&[named("uppercase", vec![number("1")])], "x"), Err(ModifierError::Failed { .. })));
+}
+
+#[test]
+fn test_modifiers_sandboxed() {
+  let mut registry: ModifierRegistry = ModifierRegistry::empty().with_max_output(Some(8));
+  assert_eq!(registry.names().count(), 0);
+
+  registry.register("double", |_: &[CommandComponent], output: &str| Ok(output.repeat(2)));
+  registry.register("explode", |_: &[CommandComponent], _: &str| -> Result<String, String> { panic!("boom") });
+
+  assert_eq!(registry.apply(&[free_form("double")], "ab").unwrap(), "abab");
+  assert_eq!(
+    registry.apply(&[free_form("double"), free_form("double"), free_form("double")], "ab"),
+    Err(ModifierError::OutputLimit { modifier: "double".to_string(), limit: 8 })
+  );
+  assert_eq!(
+    registry.apply(&[free_form("explode")], "ab"),
+    Err(ModifierError::Failed { modifier: "explode".to_string(), message: "the handler panicked".to_string() })
+  );
+
+  // Registering again replaces the handler
+  registry.register("Double", |_: &[CommandComponent], output: &str| Ok(output.to_string()));
+  assert_eq!(registry.names().collect::<Vec<&str>>(), vec!["explode", "double"]);
+  assert_eq!(registry.apply(&[free_form("double")], "ab").unwrap(), "ab");
+}
+
+#[test]
+fn test_modifiers_in_codegen() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-modifiers-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let path: PathBuf = dir.join("main.cce");
+  fs::write(&path, "whatis hello?\n- $$sh\necho hello$$\n\nhello | uppercase | add a newline.\nhello | shout.\n").unwrap();
+
+  let config: SessionConfig = SessionConfig { targets: vec!["sh".to_string()], ..SessionConfig::default() };
+
+  let output: Output = Session::new(config.clone()).compile(std::slice::from_ref(&path)).unwrap();
+  assert_eq!(output.failed, Some(Stage::Codegen));
+  assert_eq!(output.codegen_error, Some(CodegenError::ModifierError(ModifierError::Unknown { modifier: "shout".to_string() })));
+  assert_eq!(output.codegen_error.unwrap().code(), "CCE0205");
+  assert!(output.artifacts.is_empty());
+
+  let mut session: Session = Session::new(config);
+  session.register_modifier("shout", |_: &[CommandComponent], output: &str| Ok(format!("{}!", output)));
+  let output: Output = session.compile(std::slice::from_ref(&path)).unwrap();
+
+  assert!(output.succeeded());
+  assert_eq!(output.artifact("sh").unwrap().outputs, vec!["ECHO HELLO\n", "echo hello!"]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_modifiers_after_placeholders() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-modifiers-placeholders-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let path: PathBuf = dir.join("main.cce");
+  let config: SessionConfig = SessionConfig { targets: vec!["sh".to_string()], ..SessionConfig::default() };
+
+  // The most specific whatis wins, and its placeholders are filled in before
+  // modifiers see the text
+  fs::write(
+    &path,
+    "whatis greet %who?\n- $$sh\necho hello $who$$\n\nwhatis greet 'Bob'?\n- $$sh\necho hi Bob$$\n\ngreet 'Alice' | uppercase.\ngreet 'Bob'.\n",
+  )
+  .unwrap();
+
+  let output: Output = Session::new(config.clone()).compile(std::slice::from_ref(&path)).unwrap();
+  assert!(output.succeeded(), "{:?}", output.codegen_error);
+  assert_eq!(output.artifact("sh").unwrap().outputs, vec!["ECHO HELLO ALICE", "echo hi Bob"]);
+
+  // A placeholder that names no slot
+  fs::write(&path, "whatis greet %who?\n- $$sh\necho hello $whom$$\n\ngreet 'Alice'.\n").unwrap();
+
+  let output: Output = Session::new(config.clone()).compile(std::slice::from_ref(&path)).unwrap();
+  let err: CodegenError = output.codegen_error.unwrap();
+  assert_eq!(err.code(), "CCE0208");
+  assert!(matches!(err, CodegenError::Template { error: TemplateError::Unbound(name), .. } if name == "whom"));
+
+  // Equally specific whatis definitions go to the disambiguation policy
+  fs::write(&path, "whatis greet %who?\n- $$sh\necho hello $who$$\n\nwhatis greet %whom?\n- $$sh\necho hi $whom$$\n\ngreet 'Alice'.\n").unwrap();
+
+  let output: Output = Session::new(config.clone()).compile(std::slice::from_ref(&path)).unwrap();
+  assert_eq!(output.artifact("sh").unwrap().outputs, vec!["echo hello Alice"]);
+
+  let mut session: Session = Session::new(config);
+  session.set_disambiguation(RejectAmbiguous);
+  let output: Output = session.compile(std::slice::from_ref(&path)).unwrap();
+  assert!(matches!(output.codegen_error, Some(CodegenError::Ambiguous { ref sites, .. }) if sites.len() == 2));
+  assert_eq!(output.codegen_error.unwrap().code(), "CCE0209");
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,265 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use cce_driver::*;
+use cce_ast::{ParserError, ParserLimits, WorkspaceError};
+use cce_infer::{Backend, CacheStats, ConflictPolicy, Entry, ExpandError, RejectAmbiguous, SessionLog};
+use cce_infer_ast::{CommandComponent, HowToCommand, NormalizeOptions};
+use circelang_db::{Manifest, Package, Store};
+
+
+// Writes each source to its own file in a fresh directory
+fn workspace(name: &str, sources: &[&str]) -> (PathBuf, Vec<PathBuf>) {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-session-{}-{}", std::process::id(), name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+
+  let files: Vec<PathBuf> = sources
+    .iter()
+    .enumerate()
+    .map(|(i, source)| {
+      let path: PathBuf = dir.join(format!("file{}.cce", i));
+      fs::write(&path, source).unwrap();
+      path
+    })
+    .collect();
+
+  (dir, files)
+}
+
+
+#[test]
+fn test_session_full_pipeline() {
+  let (dir, files) = workspace("full", &[
+    "howto greet %who?\n- say hello to %who\n\n",
+    "whatis hello?\n- $$rust\nprintln!(\"hello\");$$\n- $$echo hello$$\n\ngreet 'Bob'.",
+  ]);
+
+  let mut session: Session = Session::new(SessionConfig { targets: vec!["rust".to_string(), "sh".to_string()], ..SessionConfig::default() });
+  let output: Output = session.compile(&files).unwrap();
+
+  assert!(output.succeeded());
+  assert_eq!(output.completed, Stage::ALL.to_vec());
+  assert_eq!(output.files.len(), 2);
+  assert_eq!(output.knowledge.entries().len(), 2);
+  assert_eq!(output.program.len(), 3);
+
+  assert_eq!(output.artifact("rust").unwrap().sequences[0].1.code, "println!(\"hello\");");
+  assert_eq!(output.artifact("sh").unwrap().sequences[0].1.code, "echo hello");
+  assert_eq!(output.artifact("go"), None);
+
+  // Codegen, once per target, finds the commands expansion already matched
+  assert_eq!(session.resolver().stats(), CacheStats { hits: 2, misses: 2, evictions: 0 });
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_hooks_and_stop_after() {
+  let (dir, files) = workspace("hooks", &["howto greet?\n- wave\n\n"]);
+
+  let stages: Rc<RefCell<Vec<Stage>>> = Rc::new(RefCell::new(Vec::new()));
+  let seen: Rc<RefCell<Vec<Stage>>> = stages.clone();
+
+  let mut session: Session = Session::new(SessionConfig { lint: false, stop_after: Some(Stage::Lower), ..SessionConfig::default() });
+  session.add_hook(move |stage, _| seen.borrow_mut().push(stage));
+
+  let output: Output = session.compile(&files).unwrap();
+
+  assert!(output.succeeded());
+  assert_eq!(*stages.borrow(), vec![Stage::Parse, Stage::Convert, Stage::Resolve, Stage::Lower]);
+  assert_eq!(output.completed, *stages.borrow());
+  assert!(output.artifacts.is_empty());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_stops_on_failure() {
+  let (dir, files) = workspace("failure", &["howto greet\n- wave\n\n"]);
+
+  let output: Output = Session::default().compile(&files).unwrap();
+  assert_eq!(output.failed, Some(Stage::Parse));
+  assert_eq!(output.completed, vec![Stage::Parse]);
+  assert!(output.files.is_empty());
+
+  let (dir2, files) = workspace("conflict", &["howto greet %who?\n- wave\n\n", "howto Greet %person?\n- nod\n\n"]);
+
+  let output: Output = Session::new(SessionConfig { policy: ConflictPolicy::Error, ..SessionConfig::default() }).compile(&files).unwrap();
+  assert_eq!(output.failed, Some(Stage::Resolve));
+  assert_eq!(output.conflicts.len(), 1);
+
+  fs::remove_dir_all(&dir).unwrap();
+  fs::remove_dir_all(&dir2).unwrap();
+}
+
+#[test]
+fn test_session_search_paths_and_std() {
+  let (dir, _) = workspace("std", &["howto greet %who?\n- say hello to %who\n\n"]);
+  let store_root: PathBuf = dir.join("store");
+
+  let std: Package = Package {
+    manifest: Manifest { name: "std".to_string(), version: "0.1.0".to_string(), files: vec!["io.cce".to_string()] },
+    sources: vec!["whatis stdout?\n- the console output\n\n".to_string()],
+  };
+  Store::open(&store_root).unwrap().install(&std).unwrap();
+
+  let mut session: Session = Session::new(SessionConfig {
+    search_paths: vec![dir.join("missing"), dir.clone()],
+    std: true,
+    store: Some(store_root),
+    ..SessionConfig::default()
+  });
+
+  assert_eq!(session.resolve_path(&PathBuf::from("file0.cce")), dir.join("file0.cce"));
+
+  let output: Output = session.compile(&[PathBuf::from("file0.cce")]).unwrap();
+
+  assert!(output.succeeded());
+  assert_eq!(output.knowledge.entries().len(), 2);
+  assert_eq!(output.knowledge.entries()[0].namespace.as_deref(), Some("std"));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_limits() {
+  let (dir, files) = workspace("limits", &["howto spin?\n- turn\n- spin\n\nspin."]);
+
+  let output: Output = Session::default().compile(&files).unwrap();
+  assert_eq!(output.failed, Some(Stage::Lower));
+  assert!(matches!(output.expansion_error, Some(ExpandError::DepthLimit { limit: 256, .. })));
+
+  let config: SessionConfig = SessionConfig { parser_limits: ParserLimits { max_nodes: Some(2), ..ParserLimits::default() }, ..SessionConfig::default() };
+  let output: Output = Session::new(config).compile(&files).unwrap();
+  assert_eq!(output.failed, Some(Stage::Parse));
+
+  // Step nesting is bounded without asking for it
+  let steps: String = (0..5000).map(|level| format!("{}- a:\n", "  ".repeat(level))).collect();
+  fs::write(&files[0], format!("howto deep?\n{}\n", steps)).unwrap();
+  let output: Output = Session::default().compile(&files).unwrap();
+  assert!(matches!(output.workspace.files[0].errors.as_slice(), [WorkspaceError::ParserError(ParserError::DepthLimit { limit: 256, .. })]));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_disambiguation() {
+  let (dir, files) = workspace("disambiguation", &["howto greet %who?\n- wave\n\nhowto greet %who:string?\n- bow\n\ngreet 'Bob'.\n"]);
+
+  let mut session: Session = Session::new(SessionConfig { lint: false, ..SessionConfig::default() });
+  session.set_disambiguation(RejectAmbiguous);
+
+  let output: Output = session.compile(&files).unwrap();
+
+  assert_eq!(output.failed, Some(Stage::Lower));
+  assert!(matches!(output.expansion_error, Some(ExpandError::Ambiguous { .. })));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_replay() {
+  let (dir, files) = workspace("replay", &["howto greet %who?\n- wave\n\nhowto greet %who:string?\n- bow\n\ngreet 'Bob'.\n"]);
+
+  let mut session: Session = Session::new(SessionConfig { lint: false, record_decisions: true, ..SessionConfig::default() });
+  session.set_disambiguation(|_: &[CommandComponent], _: &[&Entry]| Some(1));
+
+  let recorded: Output = session.compile(&files).unwrap();
+  assert_eq!(recorded.decisions.len(), 2);
+  assert_eq!(recorded.decisions.decisions[0].backend, Backend::Policy);
+
+  // The log stands in for the policy that made the choice
+  let mut session: Session = Session::new(SessionConfig { lint: false, ..SessionConfig::default() });
+  session.set_disambiguation(RejectAmbiguous);
+  session.set_replay(SessionLog::parse(&recorded.decisions.to_string()).unwrap());
+
+  let replayed: Output = session.compile(&files).unwrap();
+  assert!(replayed.succeeded());
+  assert_eq!(replayed.expanded, recorded.expanded);
+  assert!(matches!(&replayed.expanded[0], HowToCommand::Command(node) if node.to_string() == "bow"));
+  assert!(replayed.decisions.is_empty());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_modules_and_imports() {
+  let (dir, files) = workspace("modules", &["module greetings:\nhowto greet?\n- wave\nend.\n\ngreet.\n"]);
+
+  let output: Output = Session::new(SessionConfig { lint: false, ..SessionConfig::default() }).compile(&files).unwrap();
+
+  assert!(output.succeeded());
+  assert_eq!(output.knowledge.entries().len(), 1);
+  assert_eq!(output.knowledge.entries()[0].namespace.as_deref(), Some("greetings"));
+  assert_eq!(output.expanded.len(), 1);
+  assert!(matches!(&output.expanded[0], HowToCommand::Command(node) if node.to_string() == "wave"));
+
+  let config: SessionConfig = SessionConfig { lint: false, imports: Some(Vec::new()), ..SessionConfig::default() };
+  let output: Output = Session::new(config).compile(&files).unwrap();
+  // Hidden howtos are left unexpanded
+  assert!(output.succeeded());
+  assert!(matches!(&output.expanded[0], HowToCommand::Command(node) if node.to_string() == "greet"));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_normalization() {
+  let (dir, files) = workspace("normalization", &["howto Greet the %who?\n- wave\n\ngreet 'Bob'.\n"]);
+
+  let output: Output = Session::new(SessionConfig { lint: false, ..SessionConfig::default() }).compile(&files).unwrap();
+  assert!(matches!(&output.expanded[0], HowToCommand::Command(node) if node.to_string() == "greet 'Bob'"));
+
+  let config: SessionConfig = SessionConfig { lint: false, normalization: Some(NormalizeOptions::default()), ..SessionConfig::default() };
+  let output: Output = Session::new(config).compile(&files).unwrap();
+  assert!(output.succeeded());
+  assert!(matches!(&output.expanded[0], HowToCommand::Command(node) if node.to_string() == "wave"));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_from_manifest() {
+  let (dir, _) = workspace("manifest", &[]);
+  fs::create_dir_all(dir.join("src")).unwrap();
+  fs::write(dir.join("circe.toml"), "[project]\nname = \"demo\"\nsources = [\"src\"]\ntargets = [\"sh\"]\n\n[lints]\nunused_howto = \"deny\"\n").unwrap();
+  fs::write(dir.join("src").join("greet.cce"), "howto greet?\n- wave\n\n").unwrap();
+  fs::write(dir.join("src").join("wave.cce"), "whatis wave?\n- $$echo hi$$\n\n").unwrap();
+
+  let mut session: Session = Session::from_manifest(&dir.join("circe.toml")).unwrap();
+  assert_eq!(session.project().unwrap().name, "demo");
+  assert_eq!(session.config().targets, vec!["sh"]);
+
+  let output: Output = session.compile_project().unwrap();
+  assert_eq!(output.files.len(), 2);
+  assert_eq!(output.failed, Some(Stage::Lint));
+  assert_eq!(output.diagnostics[0].lint, "unused_howto");
+
+  assert!(matches!(Session::default().compile_project(), Err(SessionError::NoProject)));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
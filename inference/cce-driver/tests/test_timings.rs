@@ -0,0 +1,80 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use cce_driver::*;
+
+
+#[cfg(feature = "alloc-tracking")]
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+
+#[test]
+fn test_timings_recorded_per_stage() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-timings-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let file: PathBuf = dir.join("greet.cce");
+  fs::write(&file, "howto greet %who?\n- say hello to %who\n\ngreet 'Bob'.").unwrap();
+
+  let output: Output = Session::new(SessionConfig { timings: true, ..SessionConfig::default() }).compile(&[file]).unwrap();
+  let timings: &Timings = output.timings.as_ref().unwrap();
+
+  let stages: Vec<Stage> = timings.stages.iter().map(|timing| timing.stage).collect();
+  assert_eq!(stages, output.completed);
+
+  assert_eq!(timings.stage(Stage::Parse).unwrap().nodes, 2);
+  assert_eq!(timings.stage(Stage::Resolve).unwrap().nodes, 1);
+  assert_eq!(timings.stage(Stage::Parse).unwrap().peak_bytes.is_some_and(|bytes| bytes > 0), cfg!(feature = "alloc-tracking"));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_timings_disabled_by_default() {
+  let output: Output = Session::default().compile(&[]).unwrap();
+
+  assert_eq!(output.timings, None);
+}
+
+#[test]
+fn test_timings_reports() {
+  let timings: Timings = Timings {
+    stages: vec![
+      StageTiming { stage: Stage::Parse, wall: Duration::from_micros(1500), nodes: 3, peak_bytes: Some(2048) },
+      StageTiming { stage: Stage::Convert, wall: Duration::from_micros(500), nodes: 3, peak_bytes: None },
+    ],
+  };
+
+  assert_eq!(timings.total(), Duration::from_millis(2));
+  assert_eq!(
+    timings.to_json(),
+    "{\"stages\":[{\"stage\":\"parse\",\"wall_ms\":1.500,\"nodes\":3,\"peak_bytes\":2048},\
+{\"stage\":\"convert\",\"wall_ms\":0.500,\"nodes\":3,\"peak_bytes\":null}],\"total_ms\":2.000}"
+  );
+
+  let text: String = timings.to_string();
+  assert!(text.lines().nth(1).unwrap().starts_with("parse"));
+  assert!(text.lines().last().unwrap().contains("2.000"));
+}
@@ -0,0 +1,105 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+#![cfg(feature = "tracing")]
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use cce_driver::*;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+
+// Records span names and event messages, in order
+#[derive(Clone, Default)]
+struct Recorder {
+  seen: Arc<Mutex<Vec<String>>>,
+  next_id: Arc<AtomicU64>,
+}
+
+struct Message(Option<String>);
+
+impl Visit for Message {
+  fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+    if field.name() == "message" {
+      self.0 = Some(format!("{:?}", value));
+    }
+  }
+}
+
+impl Subscriber for Recorder {
+  fn enabled(&self, _: &Metadata<'_>) -> bool {
+    true
+  }
+
+  fn new_span(&self, span: &Attributes<'_>) -> Id {
+    self.seen.lock().unwrap().push(format!("span {}", span.metadata().name()));
+    Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+  }
+
+  fn record(&self, _: &Id, _: &Record<'_>) {}
+
+  fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+  fn event(&self, event: &Event<'_>) {
+    let mut message: Message = Message(None);
+    event.record(&mut message);
+
+    if let Some(message) = message.0 {
+      self.seen.lock().unwrap().push(message);
+    }
+  }
+
+  fn enter(&self, _: &Id) {}
+
+  fn exit(&self, _: &Id) {}
+}
+
+
+#[test]
+fn test_tracing_pipeline() {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-tracing-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let file: PathBuf = dir.join("hello.cce");
+  fs::write(&file, "whatis hello?\n- $$rust\nprintln!(\"hello\");$$\n\n").unwrap();
+
+  let recorder: Recorder = Recorder::default();
+  let mut session: Session = Session::new(SessionConfig { targets: vec!["rust".to_string()], ..SessionConfig::default() });
+
+  let output: Output = tracing::subscriber::with_default(recorder.clone(), || session.compile(&[file]).unwrap());
+  assert!(output.succeeded());
+
+  let seen: Vec<String> = recorder.seen.lock().unwrap().clone();
+
+  assert_eq!(seen.iter().filter(|name| *name == "span stage").count(), Stage::ALL.len());
+  assert!(seen.contains(&"span statement".to_string()));
+  assert!(seen.contains(&"token".to_string()));
+  assert!(seen.contains(&"end of input".to_string()));
+  assert!(seen.contains(&"span codegen".to_string()));
+  assert!(seen.contains(&"picked final sequence".to_string()));
+
+  fs::remove_dir_all(&dir).unwrap();
+}
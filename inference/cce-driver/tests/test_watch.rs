@@ -0,0 +1,169 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cce_ast::{ParserLimits, WorkspaceParseResult};
+use cce_driver::*;
+use circelang_hash::CirceHash;
+
+
+fn directory(name: &str) -> PathBuf {
+  let dir: PathBuf = std::env::temp_dir().join(format!("circe-watch-{}-{}", std::process::id(), name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(dir.join("nested")).unwrap();
+  dir
+}
+
+
+#[test]
+fn test_watch_parse_cache() {
+  let dir: PathBuf = directory("cache");
+  let files: Vec<PathBuf> = vec![dir.join("a.cce"), dir.join("b.cce")];
+  fs::write(&files[0], "howto greet?\n- wave\n\n").unwrap();
+  fs::write(&files[1], "greet.\n").unwrap();
+
+  let mut cache: ParseCache = ParseCache::default();
+  assert_eq!(cache.parse(&files, ParserLimits::default()).files[0].nodes.len(), 1);
+  assert_eq!((cache.hits(), cache.misses()), (0, 2));
+
+  fs::write(&files[1], "greet.\ngreet.\n").unwrap();
+  let workspace: WorkspaceParseResult = cache.parse(&files, ParserLimits::default());
+  assert_eq!(workspace.files[1].nodes.len(), 2);
+  assert_eq!(workspace.definitions.len(), 1);
+  assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+  // The old contents of b.cce are dropped, and files with errors are not kept
+  assert_eq!(cache.len(), 2);
+  fs::write(&files[1], "howto greet\n").unwrap();
+  assert!(cache.parse(&files, ParserLimits::default()).has_errors());
+  assert_eq!(cache.len(), 1);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_parse_cache_collision() {
+  let dir: PathBuf = directory("collision");
+  let files: Vec<PathBuf> = vec![dir.join("a.cce"), dir.join("b.cce")];
+  fs::write(&files[0], "ab.\n").unwrap();
+  fs::write(&files[1], "bd.\n").unwrap();
+  assert_eq!("ab.\n".hash(), "bd.\n".hash());
+
+  let mut cache: ParseCache = ParseCache::default();
+  let workspace: WorkspaceParseResult = cache.parse(&files, ParserLimits::default());
+  assert_eq!((cache.hits(), cache.misses()), (0, 2));
+  assert_eq!(cache.len(), 2);
+  assert_ne!(workspace.files[0].nodes, workspace.files[1].nodes);
+
+  let again: WorkspaceParseResult = cache.parse(&files, ParserLimits::default());
+  assert_eq!((cache.hits(), cache.misses()), (2, 0));
+  assert_eq!(again.files[0].nodes, workspace.files[0].nodes);
+  assert_eq!(again.files[1].nodes, workspace.files[1].nodes);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_session_reuses_parses() {
+  let dir: PathBuf = directory("session");
+  let files: Vec<PathBuf> = vec![dir.join("a.cce"), dir.join("b.cce")];
+  fs::write(&files[0], "howto greet?\n- wave\n\n").unwrap();
+  fs::write(&files[1], "greet.\n").unwrap();
+
+  let mut session: Session = Session::default();
+  assert!(session.parse_cache().is_none());
+  session.enable_parse_cache();
+
+  assert!(session.compile(&files).unwrap().succeeded());
+  fs::write(&files[1], "greet.\ngreet.\n").unwrap();
+
+  let output: Output = session.compile(&files).unwrap();
+  assert!(output.succeeded());
+  assert_eq!(output.expanded.len(), 2);
+  assert_eq!(session.parse_cache().unwrap().hits(), 1);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_changes() {
+  let dir: PathBuf = directory("changes");
+  fs::write(dir.join("a.cce"), "greet.\n").unwrap();
+  fs::write(dir.join("nested").join("b.cce"), "wave.\n").unwrap();
+  fs::write(dir.join("notes.txt"), "not a source\n").unwrap();
+
+  let mut watcher: Watcher = Watcher::new(vec![dir.clone()]);
+  assert_eq!(watcher.poll().unwrap(), vec![dir.join("a.cce"), dir.join("nested").join("b.cce")]);
+  assert!(watcher.poll().unwrap().is_empty());
+
+  fs::write(dir.join("a.cce"), "greet loudly.\n").unwrap();
+  fs::write(dir.join("c.cce"), "nod.\n").unwrap();
+  fs::remove_file(dir.join("nested").join("b.cce")).unwrap();
+
+  assert_eq!(watcher.poll().unwrap(), vec![dir.join("a.cce"), dir.join("c.cce"), dir.join("nested").join("b.cce")]);
+  assert_eq!(watcher.files(), vec![dir.join("a.cce"), dir.join("c.cce")]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+// A link to nothing is listed but has no metadata, like a file removed
+// while polling
+#[cfg(unix)]
+#[test]
+fn test_watch_skips_missing() {
+  let dir: PathBuf = directory("missing");
+  fs::write(dir.join("a.cce"), "greet.\n").unwrap();
+  std::os::unix::fs::symlink(dir.join("gone.cce"), dir.join("b.cce")).unwrap();
+
+  let mut watcher: Watcher = Watcher::new(vec![dir.clone()]);
+  assert_eq!(watcher.poll().unwrap(), vec![dir.join("a.cce")]);
+  assert_eq!(watcher.files(), vec![dir.join("a.cce")]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_wait_notified() {
+  let dir: PathBuf = directory("notified");
+  fs::write(dir.join("a.cce"), "greet.\n").unwrap();
+
+  let mut watcher: Watcher = Watcher::new(vec![dir.clone()]);
+  assert!(watcher.notifications());
+  assert_eq!(watcher.wait(Duration::from_secs(1)).unwrap(), vec![dir.join("a.cce")]);
+
+  let path: PathBuf = dir.join("nested").join("b.cce");
+  let writer = thread::spawn(move || {
+    thread::sleep(Duration::from_millis(50));
+    fs::write(path, "wave.\n").unwrap();
+  });
+
+  // A long interval, so only a notification ends the wait in time
+  let started: Instant = Instant::now();
+  assert_eq!(watcher.wait(Duration::from_secs(60)).unwrap(), vec![dir.join("nested").join("b.cce")]);
+  assert!(started.elapsed() < Duration::from_secs(30));
+  writer.join().unwrap();
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -22,14 +22,19 @@ use crate::nodes::*;
 use cce_ast as ast;
 
 pub fn convert(program: Vec<ast::ParseNode>) -> Vec<ProgramNode> {
-    program
-        .into_iter()
-        .map(|node| match node {
-            ast::ParseNode::Command(command) => ProgramNode::Command(convert_command(command)),
-            ast::ParseNode::HowToStatement(howto) => ProgramNode::HowTo(convert_howto(howto)),
-            ast::ParseNode::WhatIsStatement(whatis) => ProgramNode::WhatIs(convert_whatis(whatis)),
-        })
-        .collect()
+    program.into_iter().map(convert_node).collect()
+}
+
+fn convert_node(node: ast::ParseNode) -> ProgramNode {
+    match node {
+        ast::ParseNode::Command(command) => ProgramNode::Command(convert_command(command)),
+        ast::ParseNode::HowToStatement(howto) => ProgramNode::HowTo(convert_howto(howto)),
+        ast::ParseNode::WhatIsStatement(whatis) => ProgramNode::WhatIs(convert_whatis(whatis)),
+        ast::ParseNode::Module(module) => ProgramNode::Module(ModuleNode {
+            name: module.name,
+            body: convert(module.body),
+        }),
+    }
 }
 
 fn convert_command(command: ast::Command) -> CommandNode {
@@ -42,22 +47,46 @@ fn convert_command(command: ast::Command) -> CommandNode {
         modifiers: command
             .modifiers
             .into_iter()
-            .map(|modifier| {
-                modifier
-                    .into_iter()
-                    .map(convert_command_component)
-                    .collect()
-            })
+            .map(convert_modifier)
             .collect(),
     }
 }
 
+fn convert_modifier(modifier: ast::Modifier) -> Modifier {
+    match modifier {
+        ast::Modifier::Named { name, args } => Modifier::Named {
+            name,
+            args: args.into_iter().map(convert_command_component).collect(),
+        },
+        ast::Modifier::FreeForm(components) => Modifier::FreeForm(
+            components
+                .into_iter()
+                .map(convert_command_component)
+                .collect(),
+        ),
+    }
+}
+
 fn convert_command_component(component: ast::CommandComponent) -> CommandComponent {
     match component {
         ast::CommandComponent::Literal(literal) => CommandComponent::Literal(literal),
         ast::CommandComponent::Keyword(keyword) => CommandComponent::Keyword(keyword),
-        ast::CommandComponent::Slot(slot) => CommandComponent::Slot(slot),
+        ast::CommandComponent::Slot { name, ty } => CommandComponent::Slot {
+            name,
+            ty: ty.map(convert_slot_type),
+        },
         ast::CommandComponent::BackRef(backref) => CommandComponent::BackRef(backref),
+        ast::CommandComponent::Number { value, unit } => CommandComponent::Number { value, unit },
+        ast::CommandComponent::Boolean(value) => CommandComponent::Boolean(value),
+    }
+}
+
+fn convert_slot_type(ty: ast::SlotType) -> SlotType {
+    match ty {
+        ast::SlotType::String => SlotType::String,
+        ast::SlotType::Number => SlotType::Number,
+        ast::SlotType::Boolean => SlotType::Boolean,
+        ast::SlotType::Other(name) => SlotType::Other(name),
     }
 }
 
@@ -68,14 +97,31 @@ fn convert_howto(howto: ast::HowToStatement) -> HowToNode {
             .into_iter()
             .map(convert_command_component)
             .collect(),
-        body: howto.body.into_iter().map(convert_command).collect(),
+        body: howto.body.into_iter().map(convert_howto_command).collect(),
+    }
+}
+
+fn convert_howto_command(command: ast::HowToCommand) -> HowToCommand {
+    match command {
+        ast::HowToCommand::Command(command) => HowToCommand::Command(convert_command(command)),
+        ast::HowToCommand::Block { head, body } => HowToCommand::Block {
+            head: convert_command(head),
+            body: body.into_iter().map(convert_howto_command).collect(),
+        },
+        ast::HowToCommand::When { condition, body } => HowToCommand::When {
+            condition: convert_command(condition),
+            body: body.into_iter().map(convert_howto_command).collect(),
+        },
     }
 }
 
 fn convert_whatis_command(command: ast::WhatIsCommand) -> WhatIsCommand {
     match command {
         ast::WhatIsCommand::Command(command) => WhatIsCommand::Command(convert_command(command)),
-        ast::WhatIsCommand::Final(lowlevel) => WhatIsCommand::Final(lowlevel),
+        ast::WhatIsCommand::Final(sequence) => WhatIsCommand::Final(FinalSequence {
+            language: sequence.language,
+            code: sequence.code,
+        }),
     }
 }
 
@@ -86,10 +132,13 @@ fn convert_whatis(whatis: ast::WhatIsStatement) -> WhatIsNode {
             .into_iter()
             .map(convert_command_component)
             .collect(),
-        body: whatis
-            .body
+        alternatives: whatis
+            .alternatives
             .into_iter()
-            .map(convert_whatis_command)
+            .map(|alternative| WhatIsAlternative {
+                canonical: alternative.canonical,
+                command: convert_whatis_command(alternative.command),
+            })
             .collect(),
     }
 }
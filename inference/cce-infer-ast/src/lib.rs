@@ -20,7 +20,12 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 
 pub mod nodes;
+pub mod visit;
 mod convert;
+mod normalize;
+mod print;
 
 pub use nodes::*;
-pub use convert::convert;
\ No newline at end of file
+pub use visit::{Visit, VisitMut};
+pub use convert::convert;
+pub use normalize::{normalize, NormalizeOptions, NormalizedCommand};
\ No newline at end of file
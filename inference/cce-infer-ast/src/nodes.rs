@@ -18,41 +18,194 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 */
 
-#[derive(Debug, Clone, PartialEq)]
+use cce_ast::Symbol;
+use circelang_hash::CirceHash;
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub enum ProgramNode {
     Command(CommandNode),
     HowTo(HowToNode),
     WhatIs(WhatIsNode),
+    Module(ModuleNode),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl ProgramNode {
+    // The node, then the members of a module after it, in the order
+    // definition locations count them
+    pub fn flatten(&self) -> Vec<&ProgramNode> {
+        self.flatten_scoped()
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect()
+    }
+
+    // Like `flatten`, with the namespace each node is defined in. Nested
+    // modules are joined with dots.
+    pub fn flatten_scoped(&self) -> Vec<(Option<String>, &ProgramNode)> {
+        let mut nodes: Vec<(Option<String>, &ProgramNode)> = Vec::new();
+        flatten_into(self, None, &mut nodes);
+        nodes
+    }
+}
+
+fn flatten_into<'n>(
+    node: &'n ProgramNode,
+    namespace: Option<String>,
+    nodes: &mut Vec<(Option<String>, &'n ProgramNode)>,
+) {
+    nodes.push((namespace.clone(), node));
+
+    if let ProgramNode::Module(module) = node {
+        let inner: String = match namespace {
+            Some(outer) => format!("{}.{}", outer, module.name),
+            None => module.name.clone(),
+        };
+
+        for member in &module.body {
+            flatten_into(member, Some(inner.clone()), nodes);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub struct ModuleNode {
+    pub name: String,
+    pub body: Vec<ProgramNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub struct CommandNode {
     pub command: Vec<CommandComponent>,
-    pub modifiers: Vec<Vec<CommandComponent>>,
+    pub modifiers: Vec<Modifier>,
+}
+
+impl CommandNode {
+    pub fn modifier(&self, name: &str) -> Option<&Modifier> {
+        self.modifiers
+            .iter()
+            .find(|modifier| modifier.name() == Some(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub enum Modifier {
+    Named {
+        name: String,
+        args: Vec<CommandComponent>,
+    },
+    FreeForm(Vec<CommandComponent>),
+}
+
+impl Modifier {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Modifier::Named { name, .. } => Some(name),
+            Modifier::FreeForm(_) => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub struct HowToNode {
     pub signature: Vec<CommandComponent>,
-    pub body: Vec<CommandNode>,
+    pub body: Vec<HowToCommand>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub enum HowToCommand {
+    Command(CommandNode),
+    Block {
+        head: CommandNode,
+        body: Vec<HowToCommand>,
+    },
+    When {
+        condition: CommandNode,
+        body: Vec<HowToCommand>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub struct WhatIsNode {
     pub signature: Vec<CommandComponent>,
-    pub body: Vec<WhatIsCommand>,
+    pub alternatives: Vec<WhatIsAlternative>,
+}
+
+impl WhatIsNode {
+    pub fn canonical(&self) -> Option<&WhatIsAlternative> {
+        self.alternatives
+            .iter()
+            .find(|alternative| alternative.canonical)
+            .or(self.alternatives.first())
+    }
+
+    pub fn final_for(&self, language: &str) -> Option<&FinalSequence> {
+        let finals = || {
+            self.alternatives
+                .iter()
+                .filter_map(|alternative| match &alternative.command {
+                    WhatIsCommand::Final(sequence) => Some(sequence),
+                    WhatIsCommand::Command(_) => None,
+                })
+        };
+
+        finals()
+            .find(|sequence| sequence.is_for(language))
+            .or_else(|| finals().find(|sequence| sequence.language.is_none()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub struct WhatIsAlternative {
+    pub canonical: bool,
+    pub command: WhatIsCommand,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub enum WhatIsCommand {
     Command(CommandNode),
-    Final(String),
+    Final(FinalSequence),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub struct FinalSequence {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+impl FinalSequence {
+    pub fn is_for(&self, language: &str) -> bool {
+        self.language
+            .as_deref()
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(language))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
 pub enum CommandComponent {
     Literal(String),
-    Keyword(String),
-    Slot(String),
-    BackRef(String),
+    Keyword(Symbol),
+    Slot { name: Symbol, ty: Option<SlotType> },
+    BackRef(Symbol),
+    // The digits as written, so printing gives them back unchanged
+    Number { value: String, unit: Option<Symbol> },
+    Boolean(bool),
+}
+
+impl CommandComponent {
+    // The value of a number, or of a literal that holds one, as in `'42'`
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            CommandComponent::Number { value, .. } => value.parse().ok(),
+            CommandComponent::Literal(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub enum SlotType {
+    String,
+    Number,
+    Other(String),
+    Boolean,
 }
@@ -0,0 +1,111 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Canonical forms of commands, so ones written differently but meaning the
+// same hash and look up alike. The original components are kept alongside
+// for messages.
+
+use cce_ast::Symbol;
+use circelang_hash::CirceHash;
+
+use crate::nodes::CommandComponent;
+
+// What normalization does to a command. The default does all of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    // `Print` becomes `print`
+    pub lowercase: bool,
+    // Keywords left out entirely, compared without case
+    pub dropped_words: Vec<String>,
+    // Runs of whitespace in literals become a single space, and leading and
+    // trailing whitespace is removed
+    pub collapse_whitespace: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            lowercase: true,
+            dropped_words: vec!["a".to_string(), "an".to_string(), "the".to_string()],
+            collapse_whitespace: true,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    // Leaves commands as they are
+    pub fn none() -> Self {
+        NormalizeOptions {
+            lowercase: false,
+            dropped_words: Vec::new(),
+            collapse_whitespace: false,
+        }
+    }
+}
+
+// Equality and hashing only look at the normalized components
+#[derive(Debug, Clone, CirceHash)]
+pub struct NormalizedCommand {
+    pub components: Vec<CommandComponent>,
+    #[circe_hash(skip)]
+    pub original: Vec<CommandComponent>,
+}
+
+impl NormalizedCommand {
+    pub fn new(command: &[CommandComponent], options: &NormalizeOptions) -> Self {
+        NormalizedCommand {
+            components: normalize(command, options),
+            original: command.to_vec(),
+        }
+    }
+}
+
+impl PartialEq for NormalizedCommand {
+    fn eq(&self, other: &Self) -> bool {
+        self.components == other.components
+    }
+}
+
+pub fn normalize(
+    command: &[CommandComponent],
+    options: &NormalizeOptions,
+) -> Vec<CommandComponent> {
+    command
+        .iter()
+        .filter(|component| match component {
+            CommandComponent::Keyword(word) => !options
+                .dropped_words
+                .iter()
+                .any(|dropped| word.eq_ignore_ascii_case(dropped)),
+            _ => true,
+        })
+        .map(|component| match component {
+            CommandComponent::Keyword(word) if options.lowercase => {
+                CommandComponent::Keyword(Symbol::intern(&word.to_lowercase()))
+            }
+            CommandComponent::Literal(literal) if options.collapse_whitespace => {
+                CommandComponent::Literal(
+                    literal.split_whitespace().collect::<Vec<&str>>().join(" "),
+                )
+            }
+            component => component.clone(),
+        })
+        .collect()
+}
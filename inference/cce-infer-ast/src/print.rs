@@ -0,0 +1,201 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+// Prints nodes back as Circe source that parses to the same nodes
+
+use std::fmt;
+
+use crate::nodes::*;
+use crate::normalize::NormalizedCommand;
+
+impl fmt::Display for ProgramNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramNode::Command(command) => writeln!(f, "{}.", command),
+            ProgramNode::HowTo(howto) => write!(f, "{}", howto),
+            ProgramNode::WhatIs(whatis) => write!(f, "{}", whatis),
+            ProgramNode::Module(module) => write!(f, "{}", module),
+        }
+    }
+}
+
+impl fmt::Display for ModuleNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "module {}:", self.name)?;
+
+        for node in &self.body {
+            write!(f, "{}", node)?;
+        }
+
+        writeln!(f, "end")?;
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for CommandNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_components(f, &self.command)?;
+
+        for modifier in &self.modifiers {
+            write!(f, " | {}", modifier)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Shows the command as it was written
+impl fmt::Display for NormalizedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_components(f, &self.original)
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Modifier::Named { name, args } => {
+                write!(f, "with {} = ", name)?;
+                write_components(f, args)
+            }
+            Modifier::FreeForm(components) => write_components(f, components),
+        }
+    }
+}
+
+impl fmt::Display for CommandComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandComponent::Literal(literal) => write!(f, "'{}'", literal),
+            CommandComponent::Keyword(keyword) => write!(f, "{}", keyword),
+            CommandComponent::Slot { name, ty: None } => write!(f, "%{}", name),
+            CommandComponent::Slot { name, ty: Some(ty) } => write!(f, "%{}:{}", name, ty),
+            CommandComponent::BackRef(name) => write!(f, "&{}", name),
+            CommandComponent::Number { value, unit: None } => write!(f, "{}", value),
+            CommandComponent::Number {
+                value,
+                unit: Some(unit),
+            } => write!(f, "{} {}", value, unit),
+            CommandComponent::Boolean(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for SlotType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlotType::String => write!(f, "string"),
+            SlotType::Number => write!(f, "number"),
+            SlotType::Boolean => write!(f, "boolean"),
+            SlotType::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// A step of a howto body, with the steps nested in it
+impl fmt::Display for HowToCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_steps(f, std::slice::from_ref(self), 0)
+    }
+}
+
+impl fmt::Display for HowToNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "howto ")?;
+        write_components(f, &self.signature)?;
+        writeln!(f, "?")?;
+
+        write_steps(f, &self.body, 0)?;
+
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for WhatIsNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "whatis ")?;
+        write_components(f, &self.signature)?;
+        writeln!(f, "?")?;
+
+        for alternative in &self.alternatives {
+            write!(f, "- ")?;
+
+            if alternative.canonical {
+                write!(f, "= ")?;
+            }
+
+            match &alternative.command {
+                WhatIsCommand::Command(command) => writeln!(f, "{}", command)?,
+                WhatIsCommand::Final(sequence) => {
+                    // Use more dollars than the longest run inside the sequence
+                    let longest: usize = sequence
+                        .code
+                        .split(|c| c != '$')
+                        .map(str::len)
+                        .max()
+                        .unwrap_or(0);
+                    let fence: String = "$".repeat((longest + 1).max(2));
+
+                    write!(f, "{}", fence)?;
+
+                    if let Some(language) = &sequence.language {
+                        writeln!(f, "{}", language)?;
+                    }
+
+                    writeln!(f, "{}{}", sequence.code, fence)?;
+                }
+            }
+        }
+
+        writeln!(f)
+    }
+}
+
+fn write_components(f: &mut fmt::Formatter, components: &[CommandComponent]) -> fmt::Result {
+    for (i, component) in components.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+
+        write!(f, "{}", component)?;
+    }
+
+    Ok(())
+}
+
+fn write_steps(f: &mut fmt::Formatter, steps: &[HowToCommand], depth: usize) -> fmt::Result {
+    let indent: String = "  ".repeat(depth);
+
+    for step in steps {
+        match step {
+            HowToCommand::Command(command) => writeln!(f, "{}- {}", indent, command)?,
+            HowToCommand::Block { head, body } => {
+                writeln!(f, "{}- {}:", indent, head)?;
+                write_steps(f, body, depth + 1)?;
+            }
+            HowToCommand::When { condition, body } => {
+                writeln!(f, "{}- when {}", indent, condition)?;
+                write_steps(f, body, depth + 1)?;
+            }
+        }
+    }
+
+    Ok(())
+}
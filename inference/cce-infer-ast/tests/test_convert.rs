@@ -34,11 +34,11 @@ fn test_convert_basic() {
 
     let expected: Vec<ProgramNode> = vec![ProgramNode::Command(CommandNode {
         command: vec![
-            CommandComponent::Keyword("print".to_string()),
+            CommandComponent::Keyword("print".into()),
             CommandComponent::Literal("Hello, world!".to_string()),
-            CommandComponent::Keyword("to".to_string()),
-            CommandComponent::Keyword("the".to_string()),
-            CommandComponent::Keyword("console".to_string()),
+            CommandComponent::Keyword("to".into()),
+            CommandComponent::Keyword("the".into()),
+            CommandComponent::Keyword("console".into()),
         ],
         modifiers: vec![],
     })];
@@ -59,17 +59,17 @@ fn test_convert_modifiers() {
 
     let expected: Vec<ProgramNode> = vec![ProgramNode::Command(CommandNode {
         command: vec![
-            CommandComponent::Keyword("print".to_string()),
+            CommandComponent::Keyword("print".into()),
             CommandComponent::Literal("Hello, world!".to_string()),
-            CommandComponent::Keyword("to".to_string()),
-            CommandComponent::Keyword("the".to_string()),
-            CommandComponent::Keyword("console".to_string()),
+            CommandComponent::Keyword("to".into()),
+            CommandComponent::Keyword("the".into()),
+            CommandComponent::Keyword("console".into()),
         ],
-        modifiers: vec![vec![
-            CommandComponent::Keyword("add".to_string()),
-            CommandComponent::Keyword("a".to_string()),
-            CommandComponent::Keyword("newline".to_string()),
-        ]],
+        modifiers: vec![Modifier::FreeForm(vec![
+            CommandComponent::Keyword("add".into()),
+            CommandComponent::Keyword("a".into()),
+            CommandComponent::Keyword("newline".into()),
+        ])],
     })];
 
     assert_eq!(ast_nodes, expected);
@@ -91,21 +91,21 @@ fn test_convert_multiple_commands() {
     let expected: Vec<ProgramNode> = vec![
         ProgramNode::Command(CommandNode {
             command: vec![
-                CommandComponent::Keyword("print".to_string()),
+                CommandComponent::Keyword("print".into()),
                 CommandComponent::Literal("Hello, world!".to_string()),
-                CommandComponent::Keyword("to".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("console".to_string()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("console".into()),
             ],
             modifiers: vec![],
         }),
         ProgramNode::Command(CommandNode {
             command: vec![
-                CommandComponent::Keyword("print".to_string()),
+                CommandComponent::Keyword("print".into()),
                 CommandComponent::Literal("Goodbye, world!".to_string()),
-                CommandComponent::Keyword("to".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("console".to_string()),
+                CommandComponent::Keyword("to".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("console".into()),
             ],
             modifiers: vec![],
         }),
@@ -127,18 +127,18 @@ fn test_convert_howto() {
 
     let expected: Vec<ProgramNode> = vec![ProgramNode::HowTo(HowToNode {
         signature: vec![
-            CommandComponent::Keyword("print".to_string()),
-            CommandComponent::Keyword("a".to_string()),
-            CommandComponent::Keyword("string".to_string()),
+            CommandComponent::Keyword("print".into()),
+            CommandComponent::Keyword("a".into()),
+            CommandComponent::Keyword("string".into()),
         ],
-        body: vec![CommandNode {
+        body: vec![HowToCommand::Command(CommandNode {
             command: vec![
-                CommandComponent::Keyword("write".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("string".to_string()),
+                CommandComponent::Keyword("write".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("string".into()),
             ],
             modifiers: vec![],
-        }],
+        })],
     })];
 
     assert_eq!(ast_nodes, expected);
@@ -158,22 +158,22 @@ fn test_convert_howto_modifiers() {
 
     let expected: Vec<ProgramNode> = vec![ProgramNode::HowTo(HowToNode {
         signature: vec![
-            CommandComponent::Keyword("print".to_string()),
-            CommandComponent::Keyword("a".to_string()),
-            CommandComponent::Keyword("string".to_string()),
+            CommandComponent::Keyword("print".into()),
+            CommandComponent::Keyword("a".into()),
+            CommandComponent::Keyword("string".into()),
         ],
-        body: vec![CommandNode {
+        body: vec![HowToCommand::Command(CommandNode {
             command: vec![
-                CommandComponent::Keyword("write".to_string()),
-                CommandComponent::Keyword("the".to_string()),
-                CommandComponent::Keyword("string".to_string()),
+                CommandComponent::Keyword("write".into()),
+                CommandComponent::Keyword("the".into()),
+                CommandComponent::Keyword("string".into()),
             ],
-            modifiers: vec![vec![
-                CommandComponent::Keyword("add".to_string()),
-                CommandComponent::Keyword("a".to_string()),
-                CommandComponent::Keyword("newline".to_string()),
-            ]],
-        }],
+            modifiers: vec![Modifier::FreeForm(vec![
+                CommandComponent::Keyword("add".into()),
+                CommandComponent::Keyword("a".into()),
+                CommandComponent::Keyword("newline".into()),
+            ])],
+        })],
     })];
 
     assert_eq!(ast_nodes, expected);
@@ -192,19 +192,80 @@ fn test_convert_whatis() {
 
     let expected: Vec<ProgramNode> = vec![ProgramNode::WhatIs(WhatIsNode {
         signature: vec![
-            CommandComponent::Keyword("a".to_string()),
-            CommandComponent::Keyword("string".to_string()),
+            CommandComponent::Keyword("a".into()),
+            CommandComponent::Keyword("string".into()),
         ],
-        body: vec![WhatIsCommand::Command(CommandNode {
-            command: vec![
-                CommandComponent::Keyword("a".to_string()),
-                CommandComponent::Keyword("sequence".to_string()),
-                CommandComponent::Keyword("of".to_string()),
-                CommandComponent::Keyword("characters".to_string()),
-            ],
-            modifiers: vec![],
-        })],
+        alternatives: vec![WhatIsAlternative {
+            canonical: false,
+            command: WhatIsCommand::Command(CommandNode {
+                command: vec![
+                    CommandComponent::Keyword("a".into()),
+                    CommandComponent::Keyword("sequence".into()),
+                    CommandComponent::Keyword("of".into()),
+                    CommandComponent::Keyword("characters".into()),
+                ],
+                modifiers: vec![],
+            }),
+        }],
+    })];
+
+    assert_eq!(ast_nodes, expected);
+}
+
+#[test]
+fn test_convert_howto_nested() {
+    let mut parser: Parser =
+        Parser::from("howto leave?\n- when the door is closed:\n  - open the door");
+
+    let mut parse_nodes: Vec<ParseNode> = Vec::new();
+    while let Some(node) = parser.next().unwrap() {
+        parse_nodes.push(node);
+    }
+
+    let ast_nodes: Vec<ProgramNode> = convert(parse_nodes);
+
+    let expected: Vec<ProgramNode> = vec![ProgramNode::HowTo(HowToNode {
+        signature: vec![CommandComponent::Keyword("leave".into())],
+        body: vec![HowToCommand::When {
+            condition: CommandNode {
+                command: vec![
+                    CommandComponent::Keyword("the".into()),
+                    CommandComponent::Keyword("door".into()),
+                    CommandComponent::Keyword("is".into()),
+                    CommandComponent::Keyword("closed".into()),
+                ],
+                modifiers: vec![],
+            },
+            body: vec![HowToCommand::Command(CommandNode {
+                command: vec![
+                    CommandComponent::Keyword("open".into()),
+                    CommandComponent::Keyword("the".into()),
+                    CommandComponent::Keyword("door".into()),
+                ],
+                modifiers: vec![],
+            })],
+        }],
     })];
 
     assert_eq!(ast_nodes, expected);
 }
+
+#[test]
+fn test_convert_named_modifier() {
+    let mut parser: Parser = Parser::from("print 'hi' | with color = 'red'.");
+
+    let node: ParseNode = parser.next().unwrap().unwrap();
+    let ast_nodes: Vec<ProgramNode> = convert(vec![node]);
+
+    if let ProgramNode::Command(command) = &ast_nodes[0] {
+        assert_eq!(
+            command.modifier("color"),
+            Some(&Modifier::Named {
+                name: "color".to_string(),
+                args: vec![CommandComponent::Literal("red".to_string())],
+            })
+        );
+    } else {
+        panic!("expected a command");
+    }
+}
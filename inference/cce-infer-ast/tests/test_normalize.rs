@@ -0,0 +1,89 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_infer_ast::*;
+use circelang_hash::CirceHash;
+
+fn keyword(word: &str) -> CommandComponent {
+    CommandComponent::Keyword(word.into())
+}
+
+#[test]
+fn test_normalize_command() {
+    let command: Vec<CommandComponent> = vec![
+        keyword("Print"),
+        keyword("THE"),
+        CommandComponent::Literal(" hello \n  world ".to_string()),
+        keyword("to"),
+        keyword("an"),
+        CommandComponent::Slot {
+            name: "Out".into(),
+            ty: None,
+        },
+    ];
+
+    assert_eq!(
+        normalize(&command, &NormalizeOptions::default()),
+        vec![
+            keyword("print"),
+            CommandComponent::Literal("hello world".to_string()),
+            keyword("to"),
+            CommandComponent::Slot {
+                name: "Out".into(),
+                ty: None,
+            },
+        ]
+    );
+    assert_eq!(normalize(&command, &NormalizeOptions::none()), command);
+
+    let options: NormalizeOptions = NormalizeOptions {
+        dropped_words: vec!["to".to_string()],
+        ..NormalizeOptions::none()
+    };
+    assert_eq!(normalize(&command, &options).len(), 5);
+}
+
+#[test]
+fn test_normalized_command() {
+    let options: NormalizeOptions = NormalizeOptions::default();
+    let a: NormalizedCommand = NormalizedCommand::new(
+        &[
+            keyword("Print"),
+            keyword("the"),
+            CommandComponent::Literal("hi  there".to_string()),
+        ],
+        &options,
+    );
+    let b: NormalizedCommand = NormalizedCommand::new(
+        &[
+            keyword("print"),
+            CommandComponent::Literal("hi there".to_string()),
+        ],
+        &options,
+    );
+
+    assert_eq!(a, b);
+    assert_eq!(a.hash(), b.hash());
+    assert_ne!(a.original, b.original);
+
+    // The original is what gets shown
+    assert_eq!(a.to_string(), "Print the 'hi  there'");
+    assert_eq!(b.to_string(), "print 'hi there'");
+}
@@ -0,0 +1,81 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+mod common;
+
+use cce_infer_ast::*;
+use common::parse;
+
+fn assert_round_trip(source: &str) {
+    let nodes: Vec<ProgramNode> = parse(source);
+    let printed: String = nodes.iter().map(ProgramNode::to_string).collect();
+
+    assert_eq!(parse(&printed), nodes, "printed as:\n{}", printed);
+}
+
+#[test]
+fn test_print_command() {
+    let nodes: Vec<ProgramNode> = parse("print 'hi' to %out | with color = 'red' | add a newline.");
+
+    assert_eq!(
+        nodes[0].to_string(),
+        "print 'hi' to %out | with color = 'red' | add a newline.\n"
+    );
+}
+
+#[test]
+fn test_print_howto() {
+    let nodes: Vec<ProgramNode> = parse(
+        "howto greet %who:string?\n- when %who is here\n  - say hello\n- for each friend:\n  - wave\n\n",
+    );
+
+    assert_eq!(
+        nodes[0].to_string(),
+        "howto greet %who:string?\n- when %who is here\n  - say hello\n- for each friend:\n  - wave\n\n"
+    );
+}
+
+#[test]
+fn test_print_module() {
+    let nodes: Vec<ProgramNode> = parse("module greetings:\nhowto greet?\n- wave\nend\n");
+
+    assert_eq!(
+        nodes[0].to_string(),
+        "module greetings:\nhowto greet?\n- wave\n\nend\n\n"
+    );
+    assert_eq!(
+        nodes[0]
+            .flatten_scoped()
+            .into_iter()
+            .map(|(namespace, _)| namespace)
+            .collect::<Vec<_>>(),
+        vec![None, Some("greetings".to_string())]
+    );
+}
+
+#[test]
+fn test_print_round_trip() {
+    assert_round_trip("howto greet %who?\n- say hello to %who\n- for each %who:\n  - wave\n\n");
+    assert_round_trip("whatis stdout?\n- the console output\n- = file descriptor '1'\n\n");
+    assert_round_trip("whatis the world?\n- $$ planet $ earth $$\n\n");
+    assert_round_trip("whatis the world?\n- $$rust\nlet planet = \"earth\";$$\n- $$ planet $$\n\n");
+    assert_round_trip("print 'hi' | with color = 'red'.");
+    assert_round_trip("set volume to 7.50 | with muted = false.");
+}
@@ -0,0 +1,60 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+use cce_ast::{arbitrary, parse_all, parse_cst, CstParseResult, ParseNode};
+use cce_infer_ast::*;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn test_round_trip_node(node in any::<ParseNode>()) {
+        let printed: String = convert(vec![node.clone()])[0].to_string();
+
+        prop_assert_eq!(parse_all(&printed).map_err(|err| err.to_string()), Ok(vec![node]), "printed as:\n{}", printed);
+    }
+
+    #[test]
+    fn test_round_trip_program(program in arbitrary::program()) {
+        let printed: String = convert(program.clone())
+            .iter()
+            .map(ProgramNode::to_string)
+            .collect();
+
+        prop_assert_eq!(parse_all(&printed).map_err(|err| err.to_string()), Ok(program), "printed as:\n{}", printed);
+    }
+
+    #[test]
+    fn test_round_trip_cst(program in arbitrary::program()) {
+        let printed: String = convert(program.clone())
+            .iter()
+            .map(ProgramNode::to_string)
+            .collect();
+        let result: CstParseResult = parse_cst(&printed);
+
+        prop_assert!(result.error.is_none());
+        prop_assert_eq!(result.root.to_string(), printed);
+        prop_assert_eq!(result.root.lower(), program);
+    }
+
+    #[test]
+    fn test_grammar_sentences_parse(source in arbitrary::sentence("program")) {
+        prop_assert_eq!(parse_all(&source).map(|_| ()).map_err(|err| err.to_string()), Ok(()), "source:\n{}", source);
+    }
+}
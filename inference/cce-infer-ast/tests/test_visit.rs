@@ -0,0 +1,81 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+mod common;
+
+use cce_infer_ast::visit::walk_howto;
+use cce_infer_ast::*;
+use common::parse;
+
+#[derive(Default)]
+struct HowToKeywords {
+    in_howto: bool,
+    keywords: Vec<String>,
+}
+
+impl Visit<'_> for HowToKeywords {
+    fn visit_howto(&mut self, howto: &HowToNode) {
+        self.in_howto = true;
+        walk_howto(self, howto);
+        self.in_howto = false;
+    }
+
+    fn visit_command_component(&mut self, component: &CommandComponent) {
+        if let (true, CommandComponent::Keyword(keyword)) = (self.in_howto, component) {
+            self.keywords.push(keyword.to_string());
+        }
+    }
+}
+
+struct Lowercase;
+
+impl VisitMut for Lowercase {
+    fn visit_command_component_mut(&mut self, component: &mut CommandComponent) {
+        if let CommandComponent::Keyword(keyword) = component {
+            *keyword = keyword.to_lowercase().into();
+        }
+    }
+}
+
+#[test]
+fn test_visit() {
+    let nodes = parse("say hi.\nhowto say hi?\n- wave | twice\n\nsay hi.");
+    let mut visitor = HowToKeywords::default();
+
+    for node in &nodes {
+        visitor.visit_program_node(node);
+    }
+
+    assert_eq!(visitor.keywords, vec!["say", "hi", "wave", "twice"]);
+}
+
+#[test]
+fn test_visit_mut() {
+    let mut nodes = parse("Say HI | Loudly.\nwhatis A Wave?\n- A Gesture\n\n");
+
+    for node in &mut nodes {
+        Lowercase.visit_program_node_mut(node);
+    }
+
+    assert_eq!(
+        nodes,
+        parse("say hi | loudly.\nwhatis a wave?\n- a gesture\n\n")
+    );
+}
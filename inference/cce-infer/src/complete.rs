@@ -0,0 +1,103 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Completions for a command that is being typed. Every definition whose
+// signature starts like the command suggests the component that comes
+// next in it, and the whole rest of the signature. The more definitions
+// suggest a completion the higher it is ranked.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use cce_infer_ast::CommandComponent;
+
+use crate::knowledge::KnowledgeBase;
+use crate::matcher::try_match;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+  // The next keyword of a signature
+  Keyword,
+  // The next slot of a signature, to be filled in
+  Slot,
+  // Everything left of a signature
+  Signature,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+  pub kind: CompletionKind,
+  // What comes after the prefix, one component for keywords and slots
+  pub components: Vec<CommandComponent>,
+  // How many definitions suggested it
+  pub count: usize,
+}
+
+impl fmt::Display for Completion {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, component) in self.components.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+      write!(f, "{}", component)?;
+    }
+
+    Ok(())
+  }
+}
+
+// Ties are ranked in the order the definitions were made
+pub fn complete(prefix_components: &[CommandComponent], kb: &KnowledgeBase) -> Vec<Completion> {
+  let mut completions: Vec<Completion> = Vec::new();
+
+  let mut add = |kind: CompletionKind, components: &[CommandComponent]| {
+    match completions.iter_mut().find(|completion| completion.kind == kind && completion.components == components) {
+      Some(completion) => completion.count += 1,
+      None => completions.push(Completion { kind, components: components.to_vec(), count: 1 }),
+    }
+  };
+
+  for entry in kb.visible() {
+    let signature: &[CommandComponent] = entry.definition.signature();
+
+    // The shortest start of the signature the prefix matches, which is not
+    // always as long as the prefix when stopwords are ignored
+    let Some(start) = (0..signature.len()).find(|&len| try_match(&signature[..len], prefix_components, kb.match_options()).is_ok()) else {
+      continue;
+    };
+
+    let rest: &[CommandComponent] = &signature[start..];
+
+    match &rest[0] {
+      CommandComponent::Slot { .. } => add(CompletionKind::Slot, &rest[..1]),
+      CommandComponent::Keyword(_) => add(CompletionKind::Keyword, &rest[..1]),
+      _ => {}
+    }
+
+    if rest.len() > 1 {
+      add(CompletionKind::Signature, rest);
+    }
+  }
+
+  completions.sort_by_key(|completion| Reverse(completion.count));
+  completions
+}
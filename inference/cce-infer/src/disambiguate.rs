@@ -0,0 +1,62 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Choosing between equally good howtos. When more than one howto matches a
+// command with the same specificity, the expander hands them to a
+// disambiguation policy: a command-line tool might ask the user, an editor
+// offer a code action, and tests pick deterministically.
+
+use cce_infer_ast::CommandComponent;
+
+use crate::knowledge::Entry;
+
+
+pub trait DisambiguationPolicy {
+  // `candidates` holds at least two howtos, in the order they were defined.
+  // Returns the index of the one to expand, or `None` to fail expansion.
+  fn choose(&mut self, command: &[CommandComponent], candidates: &[&Entry]) -> Option<usize>;
+}
+
+// Expands the howto defined first. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstDefined;
+
+impl DisambiguationPolicy for FirstDefined {
+  fn choose(&mut self, _: &[CommandComponent], _: &[&Entry]) -> Option<usize> {
+    Some(0)
+  }
+}
+
+// Fails expansion with `ExpandError::Ambiguous`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RejectAmbiguous;
+
+impl DisambiguationPolicy for RejectAmbiguous {
+  fn choose(&mut self, _: &[CommandComponent], _: &[&Entry]) -> Option<usize> {
+    None
+  }
+}
+
+impl<F: FnMut(&[CommandComponent], &[&Entry]) -> Option<usize>> DisambiguationPolicy for F {
+  fn choose(&mut self, command: &[CommandComponent], candidates: &[&Entry]) -> Option<usize> {
+    self(command, candidates)
+  }
+}
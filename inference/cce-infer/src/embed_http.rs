@@ -0,0 +1,100 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Embeddings from a server speaking the Ollama embeddings API,
+//
+//   POST /api/embeddings {"model": "...", "prompt": "..."}
+//     -> {"embedding": [0.1, ...]}
+//
+// Only plain HTTP is spoken, so the server should run on the same machine
+// or on a network the compiler trusts.
+
+use std::time::Duration;
+
+use crate::semantic::{EmbedError, Embedder};
+
+
+pub struct HttpEmbedder {
+  // `host:port`
+  address: String,
+  path: String,
+  model: String,
+  // Keeps connections to the server open between requests
+  agent: ureq::Agent,
+}
+
+impl HttpEmbedder {
+  pub fn new(address: impl Into<String>, model: impl Into<String>) -> Self {
+    Self {
+      address: address.into(),
+      path: "/api/embeddings".to_string(),
+      model: model.into(),
+      agent: agent(Duration::from_secs(10)),
+    }
+  }
+
+  pub fn with_path(self, path: impl Into<String>) -> Self {
+    Self { path: path.into(), ..self }
+  }
+
+  pub fn with_timeout(self, timeout: Duration) -> Self {
+    Self { agent: agent(timeout), ..self }
+  }
+
+  fn post(&self, body: &str) -> Result<String, EmbedError> {
+    let url: String = format!("http://{}{}", self.address, self.path);
+
+    let mut response = self.agent.post(&url).header("Content-Type", "application/json").send(body).map_err(error)?;
+    response.body_mut().read_to_string().map_err(error)
+  }
+}
+
+// `timeout` bounds a whole request, from connecting to reading the body
+fn agent(timeout: Duration) -> ureq::Agent {
+  ureq::Agent::config_builder().timeout_global(Some(timeout)).build().into()
+}
+
+fn error(err: ureq::Error) -> EmbedError {
+  match err {
+    ureq::Error::Io(err) => EmbedError::IoError(err),
+    err => unavailable(&err.to_string()),
+  }
+}
+
+impl Embedder for HttpEmbedder {
+  fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError> {
+    let request: String = serde_json::json!({ "model": self.model, "prompt": text }).to_string();
+    let response: String = self.post(&request)?;
+
+    let value: serde_json::Value = serde_json::from_str(&response).map_err(|err| unavailable(&err.to_string()))?;
+
+    value["embedding"]
+      .as_array()
+      .ok_or_else(|| unavailable("response has no embedding"))?
+      .iter()
+      .map(|x| x.as_f64().map(|x| x as f32).ok_or_else(|| unavailable("embedding is not a list of numbers")))
+      .collect()
+  }
+}
+
+fn unavailable(reason: &str) -> EmbedError {
+  EmbedError::Unavailable(reason.to_string())
+}
@@ -0,0 +1,329 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Howto expansion. A command that matches a howto is replaced by the howto's
+// steps, with the slots filled in from the command, and those steps are
+// expanded in turn; commands that match no howto are kept as they are.
+// The most specific matching howto wins, and a disambiguation policy breaks
+// ties.
+// Limits stop self-referencing howtos and oversized programs from running
+// away with the stack or memory.
+
+use cce_ast::DefinitionLocation;
+use cce_infer_ast::{CommandComponent, CommandNode, HowToCommand, VisitMut};
+
+use crate::disambiguate::{DisambiguationPolicy, FirstDefined};
+use crate::knowledge::{Definition, Entry, KnowledgeBase};
+use crate::matcher::Bindings;
+use crate::replay::{Backend, Decision, SessionLog};
+use crate::resolve::Resolver;
+use crate::trace::ResolutionTrace;
+
+use thiserror::Error;
+
+
+// `None` means unlimited. Depth is bounded by default, since a howto that
+// calls itself would otherwise overflow the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpansionLimits {
+  // Howtos expanded inside one another
+  pub max_depth: Option<usize>,
+  // Commands in the expanded result, across every `expand` call
+  pub max_commands: Option<usize>,
+}
+
+impl Default for ExpansionLimits {
+  fn default() -> Self {
+    Self { max_depth: Some(256), max_commands: None }
+  }
+}
+
+// What an expander did, across every `expand` call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpansionStats {
+  // Commands looked up in the knowledge base, nested ones included
+  pub resolutions: u64,
+  // Commands no howto matched, left as they are
+  pub unresolved: u64,
+  // Commands the matcher settled on its own, with one most specific howto
+  // or none
+  pub matcher_calls: u64,
+  // Times the disambiguation policy was asked to choose
+  pub policy_calls: u64,
+  // Decisions taken from a replayed log
+  pub replayed: u64,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExpandError {
+  #[error("Limit exceeded: Howtos expanded more than {limit} levels deep, at {site}")]
+  DepthLimit { limit: usize, site: DefinitionLocation },
+  #[error("Limit exceeded: Expansion produced more than {limit} commands")]
+  CommandLimit { limit: usize },
+  #[error("Ambiguous command: {} howtos match `{command}` equally well, the first at {}", .sites.len(), .sites[0])]
+  Ambiguous { command: String, sites: Vec<DefinitionLocation> },
+  #[error("Replay diverged: decision {index} of the session log is for `{expected}`, but `{found}` was resolved")]
+  Diverged { index: usize, expected: String, found: String },
+}
+
+impl ExpandError {
+  // Stable code for `ccec explain`
+  pub fn code(&self) -> &'static str {
+    match self {
+      ExpandError::DepthLimit { .. } => "CCE0201",
+      ExpandError::CommandLimit { .. } => "CCE0202",
+      ExpandError::Ambiguous { .. } => "CCE0203",
+      ExpandError::Diverged { .. } => "CCE0204",
+    }
+  }
+}
+
+pub struct Expander<'k> {
+  knowledge: &'k KnowledgeBase,
+  limits: ExpansionLimits,
+  commands: usize,
+  policy: Box<dyn DisambiguationPolicy + 'k>,
+  // `None` unless tracing was asked for
+  traces: Option<Vec<ResolutionTrace>>,
+  // `None` unless recording was asked for
+  log: Option<SessionLog>,
+  // The log being replayed, and how many of its decisions were followed
+  replay: Option<(SessionLog, usize)>,
+  resolver: Resolver,
+  stats: ExpansionStats,
+}
+
+impl<'k> Expander<'k> {
+  pub fn new(knowledge: &'k KnowledgeBase, limits: ExpansionLimits) -> Self {
+    Self { knowledge, limits, commands: 0, policy: Box::new(FirstDefined), traces: None, log: None, replay: None, resolver: Resolver::new(), stats: ExpansionStats::default() }
+  }
+
+  // How to choose between howtos that match a command equally well
+  pub fn with_disambiguation(self, policy: impl DisambiguationPolicy + 'k) -> Self {
+    Self { policy: Box::new(policy), ..self }
+  }
+
+  // Record a trace for every command resolved, nested ones included
+  pub fn with_trace(self) -> Self {
+    Self { traces: Some(Vec::new()), ..self }
+  }
+
+  pub fn traces(&self) -> &[ResolutionTrace] {
+    self.traces.as_deref().unwrap_or_default()
+  }
+
+  pub fn take_traces(&mut self) -> Vec<ResolutionTrace> {
+    self.traces.as_mut().map(std::mem::take).unwrap_or_default()
+  }
+
+  // Record every decision in a session log, nested commands included
+  pub fn with_log(self) -> Self {
+    Self { log: Some(SessionLog::new()), ..self }
+  }
+
+  pub fn log(&self) -> Option<&SessionLog> {
+    self.log.as_ref()
+  }
+
+  pub fn take_log(&mut self) -> SessionLog {
+    self.log.as_mut().map(std::mem::take).unwrap_or_default()
+  }
+
+  // Follow `log` instead of the disambiguation policy. Commands must be
+  // resolved in the order they were logged, and the chosen howtos must
+  // still match them, or expansion fails with `ExpandError::Diverged`.
+  pub fn replaying(self, log: SessionLog) -> Self {
+    Self { replay: Some((log, 0)), ..self }
+  }
+
+  // Look commands up through `resolver`, keeping what it already cached
+  pub fn with_resolver(self, resolver: Resolver) -> Self {
+    Self { resolver, ..self }
+  }
+
+  pub fn resolver(&self) -> &Resolver {
+    &self.resolver
+  }
+
+  pub fn take_resolver(&mut self) -> Resolver {
+    std::mem::take(&mut self.resolver)
+  }
+
+  // Commands produced so far
+  pub fn expanded(&self) -> usize {
+    self.commands
+  }
+
+  pub fn stats(&self) -> ExpansionStats {
+    self.stats
+  }
+
+  pub fn expand(&mut self, command: &CommandNode) -> Result<Vec<HowToCommand>, ExpandError> {
+    self.expand_command(command, 0)
+  }
+
+  fn expand_command(&mut self, command: &CommandNode, depth: usize) -> Result<Vec<HowToCommand>, ExpandError> {
+    let mut howtos: Vec<(&Entry, Bindings)> = self.resolver.best_howtos(self.knowledge, &command.command);
+    self.stats.resolutions += 1;
+
+    let backend: Backend = if howtos.len() > 1 { Backend::Policy } else { Backend::Matcher };
+
+    let chosen: Option<usize> = match (&mut self.replay, howtos.len()) {
+      (Some((log, index)), _) => {
+        let decision: Option<&Decision> = log.decisions.get(*index).filter(|decision| decision.command == command.command);
+
+        let found: Option<Option<usize>> = decision.and_then(|decision| match decision.chosen {
+          None => Some(None),
+          Some(site) => howtos.iter().position(|(entry, _)| entry.site == site).map(Some),
+        });
+
+        let (Some(decision), Some(chosen)) = (decision, found) else {
+          return Err(ExpandError::Diverged {
+            index: *index,
+            expected: log.decisions.get(*index).map(|decision| phrase(&decision.command)).unwrap_or_else(|| "the end of the log".to_string()),
+            found: phrase(&command.command),
+          });
+        };
+
+        if let Some(chosen) = chosen {
+          howtos[chosen].1 = decision.bindings.clone();
+        }
+
+        *index += 1;
+        self.stats.replayed += 1;
+        chosen
+      }
+      (None, 0) => {
+        self.stats.matcher_calls += 1;
+        None
+      }
+      (None, 1) => {
+        self.stats.matcher_calls += 1;
+        Some(0)
+      }
+      (None, count) => {
+        let candidates: Vec<&Entry> = howtos.iter().map(|(entry, _)| *entry).collect();
+        self.stats.policy_calls += 1;
+        self.policy.choose(&command.command, &candidates).filter(|index| *index < count)
+      }
+    };
+
+    if let Some(log) = &mut self.log {
+      log.decisions.push(Decision {
+        command: command.command.clone(),
+        candidates: howtos.iter().map(|(entry, _)| entry.site).collect(),
+        chosen: chosen.map(|index| howtos[index].0.site),
+        bindings: chosen.map(|index| howtos[index].1.clone()).unwrap_or_default(),
+        backend,
+      });
+    }
+
+    if let Some(traces) = &mut self.traces {
+      let site: Option<DefinitionLocation> = chosen.map(|index| howtos[index].0.site);
+      traces.push(self.knowledge.trace_choosing(&command.command, site));
+    }
+
+    if chosen.is_none() && !howtos.is_empty() {
+      return Err(ExpandError::Ambiguous {
+        command: command.to_string(),
+        sites: howtos.iter().map(|(entry, _)| entry.site).collect(),
+      });
+    }
+
+    let Some((entry, bindings)) = chosen.map(|index| howtos.swap_remove(index)) else {
+      self.stats.unresolved += 1;
+      self.count()?;
+      return Ok(vec![HowToCommand::Command(command.clone())]);
+    };
+
+    let Definition::HowTo(howto) = &entry.definition else {
+      unreachable!("best_howtos only returns howtos");
+    };
+
+    if let Some(limit) = self.limits.max_depth.filter(|limit| depth >= *limit) {
+      return Err(ExpandError::DepthLimit { limit, site: entry.site });
+    }
+
+    let mut steps: Vec<HowToCommand> = Vec::new();
+
+    for step in &howto.body {
+      steps.extend(self.expand_step(step, &bindings, depth + 1)?);
+    }
+
+    Ok(steps)
+  }
+
+  fn expand_step(&mut self, step: &HowToCommand, bindings: &Bindings, depth: usize) -> Result<Vec<HowToCommand>, ExpandError> {
+    match step {
+      HowToCommand::Command(command) => self.expand_command(&substitute(command, bindings), depth),
+      HowToCommand::Block { head, body } => {
+        self.count()?;
+        Ok(vec![HowToCommand::Block { head: substitute(head, bindings), body: self.expand_body(body, bindings, depth)? }])
+      }
+      HowToCommand::When { condition, body } => {
+        self.count()?;
+        Ok(vec![HowToCommand::When { condition: substitute(condition, bindings), body: self.expand_body(body, bindings, depth)? }])
+      }
+    }
+  }
+
+  fn expand_body(&mut self, body: &[HowToCommand], bindings: &Bindings, depth: usize) -> Result<Vec<HowToCommand>, ExpandError> {
+    let mut steps: Vec<HowToCommand> = Vec::new();
+
+    for step in body {
+      steps.extend(self.expand_step(step, bindings, depth)?);
+    }
+
+    Ok(steps)
+  }
+
+  fn count(&mut self) -> Result<(), ExpandError> {
+    self.commands += 1;
+
+    match self.limits.max_commands {
+      Some(limit) if self.commands > limit => Err(ExpandError::CommandLimit { limit }),
+      _ => Ok(()),
+    }
+  }
+}
+
+fn phrase(components: &[CommandComponent]) -> String {
+  components.iter().map(ToString::to_string).collect::<Vec<String>>().join(" ")
+}
+
+// The command with every bound slot replaced by what it was bound to
+fn substitute(command: &CommandNode, bindings: &Bindings) -> CommandNode {
+  struct Substitute<'b>(&'b Bindings);
+
+  impl VisitMut for Substitute<'_> {
+    fn visit_command_component_mut(&mut self, component: &mut CommandComponent) {
+      if let CommandComponent::Slot { name, .. } = component {
+        if let Some(bound) = self.0.get(name.as_str()) {
+          *component = bound.clone();
+        }
+      }
+    }
+  }
+
+  let mut command: CommandNode = command.clone();
+  Substitute(bindings).visit_command_mut(&mut command);
+  command
+}
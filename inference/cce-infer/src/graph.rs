@@ -0,0 +1,244 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use cce_infer_ast::{CommandNode, Visit};
+use crate::knowledge::{Definition, Entry, KnowledgeBase};
+use crate::matcher::match_signature_with;
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DefGraphError {
+  #[error("Definitions depend on each other in a cycle: {0:?}")]
+  Cycle(Vec<usize>),
+}
+
+// Which definitions use which others. Definitions are numbered as in the
+// knowledge base the graph was built from, and an edge goes from a
+// definition to every definition one of its commands matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefGraph {
+  entries: Vec<Entry>,
+  dependencies: Vec<Vec<usize>>,
+}
+
+impl DefGraph {
+  pub fn build(knowledge: &KnowledgeBase) -> Self {
+    let entries: Vec<Entry> = knowledge.entries().to_vec();
+
+    let dependencies: Vec<Vec<usize>> = entries
+      .iter()
+      .map(|entry| {
+        let mut commands: Vec<&CommandNode> = Vec::new();
+        collect_commands(&entry.definition, &mut commands);
+
+        let used: BTreeSet<usize> = commands
+          .iter()
+          .flat_map(|command| {
+            entries
+              .iter()
+              .enumerate()
+              .filter(|(_, other)| match_signature_with(other.definition.signature(), &command.command, knowledge.match_options()).is_some())
+              .map(|(i, _)| i)
+          })
+          .collect();
+
+        used.into_iter().collect()
+      })
+      .collect();
+
+    Self { entries, dependencies }
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn entry(&self, index: usize) -> &Entry {
+    &self.entries[index]
+  }
+
+  pub fn dependencies(&self, index: usize) -> &[usize] {
+    &self.dependencies[index]
+  }
+
+  pub fn dependents(&self, index: usize) -> Vec<usize> {
+    (0..self.len())
+      .filter(|&i| self.dependencies[i].contains(&index))
+      .collect()
+  }
+
+  // Every group of definitions that depend on each other, including single
+  // definitions that use themselves. Groups and their members are sorted.
+  pub fn cycles(&self) -> Vec<Vec<usize>> {
+    let mut tarjan = Tarjan {
+      graph: self,
+      index: 0,
+      indices: vec![None; self.len()],
+      lowlinks: vec![0; self.len()],
+      stack: Vec::new(),
+      on_stack: vec![false; self.len()],
+      components: Vec::new(),
+    };
+
+    for node in 0..self.len() {
+      if tarjan.indices[node].is_none() {
+        tarjan.connect(node);
+      }
+    }
+
+    let mut cycles: Vec<Vec<usize>> = tarjan.components
+      .into_iter()
+      .filter(|component| component.len() > 1 || self.dependencies[component[0]].contains(&component[0]))
+      .map(|mut component| {
+        component.sort();
+        component
+      })
+      .collect();
+
+    cycles.sort();
+    cycles
+  }
+
+  // Definitions ordered so that each comes after everything it uses, with
+  // ties broken by index
+  pub fn topological_order(&self) -> Result<Vec<usize>, DefGraphError> {
+    if let Some(cycle) = self.cycles().into_iter().next() {
+      return Err(DefGraphError::Cycle(cycle));
+    }
+
+    let mut remaining: Vec<usize> = self.dependencies.iter().map(Vec::len).collect();
+    let mut ready: BTreeSet<usize> = (0..self.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut order: Vec<usize> = Vec::with_capacity(self.len());
+
+    while let Some(node) = ready.pop_first() {
+      order.push(node);
+
+      for dependent in self.dependents(node) {
+        remaining[dependent] -= 1;
+
+        if remaining[dependent] == 0 {
+          ready.insert(dependent);
+        }
+      }
+    }
+
+    Ok(order)
+  }
+
+  // Graphviz source with one node per definition, labelled by its signature
+  pub fn to_dot(&self) -> String {
+    let mut dot: String = String::from("digraph definitions {\n");
+
+    for (i, entry) in self.entries.iter().enumerate() {
+      let kind: &str = match entry.definition {
+        Definition::HowTo(_) => "howto",
+        Definition::WhatIs(_) => "whatis",
+      };
+
+      let signature: Vec<String> = entry.definition.signature().iter().map(ToString::to_string).collect();
+      let label: String = format!("{} {}", kind, signature.join(" "));
+
+      let _ = writeln!(dot, "  n{} [label=\"{}\"];", i, label.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+
+    for (i, dependencies) in self.dependencies.iter().enumerate() {
+      for dependency in dependencies {
+        let _ = writeln!(dot, "  n{} -> n{};", i, dependency);
+      }
+    }
+
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+fn collect_commands<'a>(definition: &'a Definition, commands: &mut Vec<&'a CommandNode>) {
+  struct Commands<'a, 'c>(&'c mut Vec<&'a CommandNode>);
+
+  impl<'a> Visit<'a> for Commands<'a, '_> {
+    fn visit_command(&mut self, command: &'a CommandNode) {
+      self.0.push(command);
+    }
+  }
+
+  match definition {
+    Definition::HowTo(howto) => Commands(commands).visit_howto(howto),
+    Definition::WhatIs(whatis) => Commands(commands).visit_whatis(whatis),
+  }
+}
+
+// Tarjan's strongly connected components
+struct Tarjan<'a> {
+  graph: &'a DefGraph,
+  index: usize,
+  indices: Vec<Option<usize>>,
+  lowlinks: Vec<usize>,
+  stack: Vec<usize>,
+  on_stack: Vec<bool>,
+  components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+  fn connect(&mut self, node: usize) {
+    self.indices[node] = Some(self.index);
+    self.lowlinks[node] = self.index;
+    self.index += 1;
+    self.stack.push(node);
+    self.on_stack[node] = true;
+
+    for &next in self.graph.dependencies(node) {
+      match self.indices[next] {
+        None => {
+          self.connect(next);
+          self.lowlinks[node] = self.lowlinks[node].min(self.lowlinks[next]);
+        }
+        Some(index) if self.on_stack[next] => {
+          self.lowlinks[node] = self.lowlinks[node].min(index);
+        }
+        Some(_) => {}
+      }
+    }
+
+    if Some(self.lowlinks[node]) == self.indices[node] {
+      let mut component: Vec<usize> = Vec::new();
+
+      while let Some(member) = self.stack.pop() {
+        self.on_stack[member] = false;
+        component.push(member);
+
+        if member == node {
+          break;
+        }
+      }
+
+      self.components.push(component);
+    }
+  }
+}
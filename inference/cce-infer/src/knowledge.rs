@@ -0,0 +1,340 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use cce_ast::DefinitionLocation;
+use cce_infer_ast::{normalize, CommandComponent, HowToNode, NormalizeOptions, WhatIsNode};
+use circelang_hash::CirceHash;
+use crate::matcher::{match_signature_with, specificity, Bindings, MatchOptions};
+
+use thiserror::Error;
+
+
+#[derive(Debug, Clone, PartialEq, CirceHash)]
+pub enum Definition {
+  HowTo(HowToNode),
+  WhatIs(WhatIsNode),
+}
+
+impl Definition {
+  pub fn signature(&self) -> &[CommandComponent] {
+    match self {
+      Definition::HowTo(howto) => &howto.signature,
+      Definition::WhatIs(whatis) => &whatis.signature,
+    }
+  }
+}
+
+impl fmt::Display for Definition {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Definition::HowTo(howto) => write!(f, "{}", howto),
+      Definition::WhatIs(whatis) => write!(f, "{}", whatis),
+    }
+  }
+}
+
+impl From<HowToNode> for Definition {
+  fn from(howto: HowToNode) -> Self {
+    Definition::HowTo(howto)
+  }
+}
+
+impl From<WhatIsNode> for Definition {
+  fn from(whatis: WhatIsNode) -> Self {
+    Definition::WhatIs(whatis)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+  pub definition: Definition,
+  pub site: DefinitionLocation,
+  // The package the definition was installed from or the module it was
+  // defined in, if any
+  pub namespace: Option<String>,
+}
+
+// What to do when a definition is equivalent to one already known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+  // Reject the new definition
+  Error,
+  // Keep both and report the conflict
+  #[default]
+  Warn,
+  // Replace the existing definition and report the conflict
+  Shadow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+  pub existing: DefinitionLocation,
+  pub new: DefinitionLocation,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum KnowledgeBaseError {
+  #[error("Conflicting definition: {} is equivalent to {}", .0.new, .0.existing)]
+  Conflict(Conflict),
+}
+
+impl KnowledgeBaseError {
+  // Stable code for `ccec explain`
+  pub fn code(&self) -> &'static str {
+    match self {
+      KnowledgeBaseError::Conflict(_) => "CCE0200",
+    }
+  }
+}
+
+// Cloning is cheap: clones share their entries until one of them changes,
+// so a clone is a snapshot that later inserts do not affect
+#[derive(Debug, Clone)]
+pub struct KnowledgeBase {
+  policy: ConflictPolicy,
+  options: MatchOptions,
+  normalization: Option<NormalizeOptions>,
+  entries: Arc<Vec<Entry>>,
+  // `None` until something is imported, making every namespace visible
+  imports: Option<Vec<String>>,
+  revision: u64,
+}
+
+impl Default for KnowledgeBase {
+  fn default() -> Self {
+    Self::new(ConflictPolicy::default())
+  }
+}
+
+impl KnowledgeBase {
+  pub fn new(policy: ConflictPolicy) -> Self {
+    Self {
+      policy,
+      options: MatchOptions::default(),
+      normalization: None,
+      entries: Arc::new(Vec::new()),
+      imports: None,
+      revision: next_revision(),
+    }
+  }
+
+  // How commands are matched against the signatures in this knowledge base
+  pub fn with_match_options(self, options: MatchOptions) -> Self {
+    Self { options, revision: next_revision(), ..self }
+  }
+
+  // Normalizes signatures and commands before matching them. Bindings then
+  // hold the normalized components, such as literals with their whitespace
+  // collapsed.
+  pub fn with_normalization(self, options: NormalizeOptions) -> Self {
+    Self { normalization: Some(options), revision: next_revision(), ..self }
+  }
+
+  pub fn normalization(&self) -> Option<&NormalizeOptions> {
+    self.normalization.as_ref()
+  }
+
+  pub fn policy(&self) -> ConflictPolicy {
+    self.policy
+  }
+
+  pub fn match_options(&self) -> MatchOptions {
+    self.options
+  }
+
+  pub fn entries(&self) -> &[Entry] {
+    &self.entries
+  }
+
+  // Changes every time the knowledge base does, so callers can tell
+  // whether results computed earlier are still valid. Revisions are never
+  // reused, not even by other knowledge bases or by clones that changed
+  // differently.
+  pub fn revision(&self) -> u64 {
+    self.revision
+  }
+
+  // Once anything is imported, lookups only see definitions outside any
+  // namespace and those in imported namespaces. Importing a namespace also
+  // imports the modules nested in it.
+  pub fn import(&mut self, namespace: &str) {
+    let imports: &mut Vec<String> = self.imports.get_or_insert_with(Vec::new);
+
+    if !imports.iter().any(|import| import == namespace) {
+      imports.push(namespace.to_string());
+      self.revision = next_revision();
+    }
+  }
+
+  // Restricts lookups to `imports`, even when it is empty
+  pub fn set_imports(&mut self, imports: Vec<String>) {
+    self.imports = Some(imports);
+    self.revision = next_revision();
+  }
+
+  pub fn imports(&self) -> Option<&[String]> {
+    self.imports.as_deref()
+  }
+
+  pub fn is_visible(&self, entry: &Entry) -> bool {
+    match (&entry.namespace, &self.imports) {
+      (None, _) | (_, None) => true,
+      (Some(namespace), Some(imports)) => imports.iter().any(|import| {
+        namespace.strip_prefix(import.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+      }),
+    }
+  }
+
+  // The entries lookups can match, in the order they were defined
+  pub fn visible(&self) -> impl Iterator<Item = &Entry> {
+    self.entries.iter().filter(|entry| self.is_visible(entry))
+  }
+
+  // Returns the conflict, if any, so the caller can report both sites
+  pub fn insert(&mut self, definition: impl Into<Definition>, site: DefinitionLocation) -> Result<Option<Conflict>, KnowledgeBaseError> {
+    self.insert_entry(Entry { definition: definition.into(), site, namespace: None })
+  }
+
+  // Definitions only conflict with others in the same namespace, so
+  // packages can reuse each other's signatures
+  pub fn insert_in(&mut self, namespace: &str, definition: impl Into<Definition>, site: DefinitionLocation) -> Result<Option<Conflict>, KnowledgeBaseError> {
+    self.insert_entry(Entry { definition: definition.into(), site, namespace: Some(namespace.to_string()) })
+  }
+
+  fn insert_entry(&mut self, entry: Entry) -> Result<Option<Conflict>, KnowledgeBaseError> {
+    let existing: Option<usize> = self.entries.iter().position(|known| {
+      known.namespace == entry.namespace
+        && same_kind(&known.definition, &entry.definition)
+        && equivalent(known.definition.signature(), entry.definition.signature())
+    });
+
+    let Some(index) = existing else {
+      Arc::make_mut(&mut self.entries).push(entry);
+      self.revision = next_revision();
+      return Ok(None);
+    };
+
+    let site: DefinitionLocation = entry.site;
+
+    let conflict: Conflict = Conflict {
+      existing: self.entries[index].site,
+      new: site,
+    };
+
+    match self.policy {
+      ConflictPolicy::Error => return Err(KnowledgeBaseError::Conflict(conflict)),
+      ConflictPolicy::Warn => Arc::make_mut(&mut self.entries).push(entry),
+      ConflictPolicy::Shadow => Arc::make_mut(&mut self.entries)[index] = entry,
+    }
+
+    self.revision = next_revision();
+
+    Ok(Some(conflict))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(candidates = self.entries.len())))]
+  pub fn lookup(&self, command: &[CommandComponent]) -> Vec<(&Entry, Bindings)> {
+    let command: Vec<CommandComponent> = self.normalized(command);
+    let found: Vec<(&Entry, Bindings)> = self.visible()
+      .filter_map(|entry| Some((entry, self.match_entry(entry, &command)?)))
+      .collect();
+
+    #[cfg(feature = "tracing")]
+    for (entry, _) in &found {
+      tracing::debug!(
+        file = entry.site.file,
+        node = entry.site.node,
+        namespace = entry.namespace.as_deref(),
+        score = crate::matcher::specificity(entry.definition.signature()),
+        "matched"
+      );
+    }
+
+    found
+  }
+
+  // The matching howtos with the most specific signature, in the order they
+  // were defined. More than one means the command is ambiguous.
+  pub fn best_howtos(&self, command: &[CommandComponent]) -> Vec<(&Entry, Bindings)> {
+    best_howtos(self.lookup(command))
+  }
+
+  // Qualified lookup: only definitions in `namespace`, whether or not it
+  // was imported
+  pub fn lookup_in(&self, namespace: &str, command: &[CommandComponent]) -> Vec<(&Entry, Bindings)> {
+    let command: Vec<CommandComponent> = self.normalized(command);
+
+    self.entries
+      .iter()
+      .filter(|entry| entry.namespace.as_deref() == Some(namespace))
+      .filter_map(|entry| Some((entry, self.match_entry(entry, &command)?)))
+      .collect()
+  }
+
+  pub(crate) fn normalized(&self, command: &[CommandComponent]) -> Vec<CommandComponent> {
+    match &self.normalization {
+      Some(options) => normalize(command, options),
+      None => command.to_vec(),
+    }
+  }
+
+  // `command` is already normalized
+  pub(crate) fn match_entry(&self, entry: &Entry, command: &[CommandComponent]) -> Option<Bindings> {
+    match_signature_with(&self.normalized(entry.definition.signature()), command, self.options)
+  }
+}
+
+// Keeps the most specific howtos among `found`
+pub(crate) fn best_howtos(found: Vec<(&Entry, Bindings)>) -> Vec<(&Entry, Bindings)> {
+  let mut howtos: Vec<(&Entry, Bindings)> = found
+    .into_iter()
+    .filter(|(entry, _)| matches!(entry.definition, Definition::HowTo(_)))
+    .collect();
+
+  if let Some(best) = howtos.iter().map(|(entry, _)| specificity(entry.definition.signature())).max() {
+    howtos.retain(|(entry, _)| specificity(entry.definition.signature()) == best);
+  }
+
+  howtos
+}
+
+fn next_revision() -> u64 {
+  static NEXT: AtomicU64 = AtomicU64::new(1);
+  NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+fn same_kind(a: &Definition, b: &Definition) -> bool {
+  matches!((a, b), (Definition::HowTo(_), Definition::HowTo(_)) | (Definition::WhatIs(_), Definition::WhatIs(_)))
+}
+
+// Equivalent signatures make a program ambiguous: keywords compare
+// without case and slots compare by type, not by name
+pub fn equivalent(a: &[CommandComponent], b: &[CommandComponent]) -> bool {
+  a.len() == b.len() && a.iter().zip(b.iter()).all(|pair| match pair {
+    (CommandComponent::Keyword(a), CommandComponent::Keyword(b)) => a.eq_ignore_ascii_case(b),
+    (CommandComponent::Slot { ty: a, .. }, CommandComponent::Slot { ty: b, .. }) => a == b,
+    (a, b) => a == b,
+  })
+}
@@ -19,8 +19,36 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 */
 
 
+mod complete;
 mod deduce;
+mod disambiguate;
+#[cfg(feature = "embeddings-http")]
+mod embed_http;
+mod expand;
+mod graph;
 mod infer;
-
+mod knowledge;
+mod matcher;
+mod replay;
+mod resolve;
+mod semantic;
+mod shared;
+mod suggest;
+mod trace;
+
+pub use complete::*;
 pub use deduce::*;
-pub use infer::*;
\ No newline at end of file
+pub use disambiguate::*;
+#[cfg(feature = "embeddings-http")]
+pub use embed_http::*;
+pub use expand::*;
+pub use graph::*;
+pub use infer::*;
+pub use knowledge::*;
+pub use matcher::*;
+pub use replay::*;
+pub use resolve::*;
+pub use semantic::*;
+pub use shared::*;
+pub use suggest::*;
+pub use trace::*;
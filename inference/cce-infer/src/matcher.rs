@@ -0,0 +1,202 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::HashMap;
+use std::fmt;
+
+use cce_ast::Symbol;
+use cce_infer_ast::{CommandComponent, SlotType};
+
+pub type Bindings = HashMap<String, CommandComponent>;
+
+// How loosely keywords are compared. The default compares them exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchOptions {
+  // `Print` matches `print`
+  pub case_insensitive: bool,
+  // Keywords are reduced to a basic English stem, so `printing`, `prints`
+  // and `printed` all match `print`
+  pub stemming: bool,
+  // `a`, `an` and `the` are left out of both sides, so `print a string`
+  // matches `print the string`
+  pub ignore_stopwords: bool,
+}
+
+const STOPWORDS: &[&str] = &["a", "an", "the"];
+
+// Matches a command against a howto/whatis signature, binding each slot to
+// exactly one component of the command
+pub fn match_signature(signature: &[CommandComponent], command: &[CommandComponent]) -> Option<Bindings> {
+  match_signature_with(signature, command, MatchOptions::default())
+}
+
+pub fn match_signature_with(signature: &[CommandComponent], command: &[CommandComponent], options: MatchOptions) -> Option<Bindings> {
+  try_match(signature, command, options).ok()
+}
+
+// Why a command does not match a signature
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+  // Counted without stopwords, if those are ignored
+  Length { expected: usize, found: usize },
+  // A keyword or literal of the signature is not the one in the command
+  Component { expected: CommandComponent, found: CommandComponent },
+  SlotType { slot: String, ty: SlotType, found: CommandComponent },
+  // The slot is used twice and the command has different things there
+  Inconsistent { slot: String, first: CommandComponent, second: CommandComponent },
+}
+
+impl fmt::Display for Mismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Mismatch::Length { expected, found } => write!(f, "expected {} components, found {}", expected, found),
+      Mismatch::Component { expected, found } => write!(f, "expected {}, found {}", expected, found),
+      Mismatch::SlotType { slot, ty, found } => write!(f, "%{} takes a {}, found {}", slot, ty, found),
+      Mismatch::Inconsistent { slot, first, second } => write!(f, "%{} is bound to both {} and {}", slot, first, second),
+    }
+  }
+}
+
+// Like `match_signature_with`, but says why the command does not match
+pub fn try_match(signature: &[CommandComponent], command: &[CommandComponent], options: MatchOptions) -> Result<Bindings, Mismatch> {
+  let significant = |component: &&CommandComponent| !(options.ignore_stopwords && is_stopword(component));
+  let signature = signature.iter().filter(significant);
+  let command = command.iter().filter(significant);
+
+  let (expected, found) = (signature.clone().count(), command.clone().count());
+  if expected != found {
+    return Err(Mismatch::Length { expected, found });
+  }
+
+  let mut bindings: Bindings = HashMap::new();
+
+  for (expected, found) in signature.zip(command) {
+    match (expected, found) {
+      (CommandComponent::Slot { name, ty }, _) => {
+        if !accepts(ty.as_ref(), found) {
+          return Err(Mismatch::SlotType { slot: name.to_string(), ty: ty.clone().unwrap(), found: found.clone() });
+        }
+
+        if let Some(bound) = bindings.get(name.as_str()) {
+          if bound != found {
+            return Err(Mismatch::Inconsistent { slot: name.to_string(), first: bound.clone(), second: found.clone() });
+          }
+        } else {
+          bindings.insert(name.to_string(), found.clone());
+        }
+      }
+      (CommandComponent::Keyword(a), CommandComponent::Keyword(b)) => {
+        if !same_keyword(a, b, options) {
+          return Err(Mismatch::Component { expected: expected.clone(), found: found.clone() });
+        }
+      }
+      _ => {
+        if expected != found {
+          return Err(Mismatch::Component { expected: expected.clone(), found: found.clone() });
+        }
+      }
+    }
+  }
+
+  Ok(bindings)
+}
+
+fn is_stopword(component: &CommandComponent) -> bool {
+  match component {
+    CommandComponent::Keyword(keyword) => STOPWORDS.iter().any(|stopword| keyword.eq_ignore_ascii_case(stopword)),
+    _ => false,
+  }
+}
+
+fn same_keyword(a: &Symbol, b: &Symbol, options: MatchOptions) -> bool {
+  if a == b {
+    return true;
+  }
+
+  let (a, b) = if options.stemming { (stem(a), stem(b)) } else { (a.as_str(), b.as_str()) };
+
+  if options.case_insensitive {
+    a.eq_ignore_ascii_case(b)
+  } else {
+    a == b
+  }
+}
+
+// Strips the commonest English suffixes. This is far from a real stemmer,
+// but covers how commands are usually inflected: `printing`, `prints`,
+// `printed`, `matches` and `running` become `print`, `match` and `run`.
+pub fn stem(word: &str) -> &str {
+  for suffix in ["ing", "ed", "es", "s"] {
+    let Some(stem) = strip_suffix(word, suffix) else {
+      continue;
+    };
+
+    // What is left has to look like a word, so `string` does not become `str`
+    let has_vowel: bool = stem.chars().any(|c| "aeiouyAEIOUY".contains(c));
+
+    if stem.len() < 3 || !has_vowel || (suffix == "s" && stem.ends_with(['s', 'S'])) {
+      continue;
+    }
+
+    // `es` only follows a sibilant, otherwise the `e` is part of the stem,
+    // as in `writes`
+    if suffix == "es" && !["s", "x", "z", "ch", "sh"].iter().any(|end| strip_suffix(stem, end).is_some()) {
+      continue;
+    }
+
+    // `running` has its consonant doubled. Compared as characters, since
+    // keywords need not be ASCII.
+    let mut chars = stem.chars().rev().map(|c| c.to_ascii_lowercase());
+    let (Some(last), Some(before)) = (chars.next(), chars.next()) else {
+      return stem;
+    };
+    let doubled: bool = suffix.len() > 1 && last == before && !"aeiouls".contains(last);
+
+    return if doubled { &stem[..stem.len() - last.len_utf8()] } else { stem };
+  }
+
+  word
+}
+
+fn strip_suffix<'a>(word: &'a str, suffix: &str) -> Option<&'a str> {
+  let split: usize = word.len().checked_sub(suffix.len())?;
+
+  if word.is_char_boundary(split) && word[split..].eq_ignore_ascii_case(suffix) {
+    Some(&word[..split])
+  } else {
+    None
+  }
+}
+
+// How specific a signature is: the number of components a command has to
+// match exactly rather than bind to a slot
+pub fn specificity(signature: &[CommandComponent]) -> usize {
+  signature.iter().filter(|component| !matches!(component, CommandComponent::Slot { .. })).count()
+}
+
+pub(crate) fn accepts(ty: Option<&SlotType>, component: &CommandComponent) -> bool {
+  match ty {
+    Some(SlotType::String) => matches!(component, CommandComponent::Literal(_)),
+    Some(SlotType::Number) => component.as_number().is_some(),
+    Some(SlotType::Boolean) => matches!(component, CommandComponent::Boolean(_)),
+    Some(SlotType::Other(_)) | None => true,
+  }
+}
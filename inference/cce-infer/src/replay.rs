@@ -0,0 +1,218 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Replayable sessions. An expander can log every resolution decision it
+// makes, and later expand the same program again following the log instead
+// of asking its disambiguation policy, which may be a person or a model and
+// is not guaranteed to choose the same way twice.
+//
+// Logs are written as text, one decision after another:
+//
+//   circe-session 1
+//   decision policy
+//   command greet 'Bob'
+//   candidates 0:0 0:1
+//   chosen 0:1
+//   bind who 'Bob'
+
+use std::fmt;
+
+use cce_ast::{DefinitionLocation, ParseNode, Parser};
+use cce_infer_ast::{convert, CommandComponent, ProgramNode};
+
+use crate::matcher::Bindings;
+
+use thiserror::Error;
+
+
+const LOG_HEADER: &str = "circe-session 1";
+
+// What settled a decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+  // A single most specific howto matched, or none did
+  Matcher,
+  // Howtos tied and the disambiguation policy was asked
+  Policy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+  pub command: Vec<CommandComponent>,
+  // The most specific howtos that matched, in the order they were defined
+  pub candidates: Vec<DefinitionLocation>,
+  pub chosen: Option<DefinitionLocation>,
+  pub bindings: Bindings,
+  pub backend: Backend,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ReplayError {
+  #[error("Invalid session log at line {line}: {message}")]
+  InvalidLog { line: usize, message: String },
+}
+
+// Decisions in the order they were made, nested commands included
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SessionLog {
+  pub decisions: Vec<Decision>,
+}
+
+impl SessionLog {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.decisions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.decisions.is_empty()
+  }
+
+  pub fn parse(text: &str) -> Result<SessionLog, ReplayError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line)).filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+      Some((_, LOG_HEADER)) => {}
+      Some((line, _)) => return Err(invalid(line, "missing session log header")),
+      None => return Err(invalid(1, "missing session log header")),
+    }
+
+    let mut log: SessionLog = SessionLog::new();
+
+    for (line, text) in lines {
+      let (key, rest) = text.split_once(' ').unwrap_or((text, ""));
+
+      if key == "decision" {
+        let backend: Backend = match rest {
+          "matcher" => Backend::Matcher,
+          "policy" => Backend::Policy,
+          _ => return Err(invalid(line, "unknown backend")),
+        };
+
+        log.decisions.push(Decision { command: Vec::new(), candidates: Vec::new(), chosen: None, bindings: Bindings::new(), backend });
+        continue;
+      }
+
+      let decision: &mut Decision = log.decisions.last_mut().ok_or_else(|| invalid(line, "expected a decision"))?;
+
+      match key {
+        "command" => decision.command = components(rest).ok_or_else(|| invalid(line, "malformed command"))?,
+        "candidates" => {
+          decision.candidates = rest
+            .split_whitespace()
+            .map(site)
+            .collect::<Option<Vec<DefinitionLocation>>>()
+            .ok_or_else(|| invalid(line, "malformed candidates"))?;
+        }
+        "chosen" if rest == "none" => decision.chosen = None,
+        "chosen" => decision.chosen = Some(site(rest).ok_or_else(|| invalid(line, "malformed site"))?),
+        "bind" => {
+          let (slot, value) = rest.split_once(' ').ok_or_else(|| invalid(line, "malformed binding"))?;
+
+          let value: CommandComponent = match components(value).as_deref() {
+            Some([value]) => value.clone(),
+            _ => return Err(invalid(line, "a binding takes a single component")),
+          };
+
+          decision.bindings.insert(slot.to_string(), value);
+        }
+        _ => return Err(invalid(line, "unknown entry")),
+      }
+    }
+
+    Ok(log)
+  }
+}
+
+fn invalid(line: usize, message: &str) -> ReplayError {
+  ReplayError::InvalidLog { line, message: message.to_string() }
+}
+
+fn site(text: &str) -> Option<DefinitionLocation> {
+  let (file, node) = text.split_once(':')?;
+  Some(DefinitionLocation { file: file.parse().ok()?, node: node.parse().ok()? })
+}
+
+// Reads components back the way they were printed, as a command statement.
+// Statements cannot start with a literal, so a keyword goes first.
+fn components(text: &str) -> Option<Vec<CommandComponent>> {
+  let source: String = format!("log {}.", text);
+  let mut parser: Parser = Parser::from(source.as_str());
+
+  let node: ParseNode = parser.next().ok()??;
+  if !matches!(parser.next(), Ok(None)) {
+    return None;
+  }
+
+  match convert(vec![node]).pop()? {
+    ProgramNode::Command(mut command) if command.modifiers.is_empty() => Some(command.command.split_off(1)),
+    _ => None,
+  }
+}
+
+impl fmt::Display for Backend {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Backend::Matcher => write!(f, "matcher"),
+      Backend::Policy => write!(f, "policy"),
+    }
+  }
+}
+
+impl fmt::Display for SessionLog {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{}", LOG_HEADER)?;
+
+    for decision in &self.decisions {
+      writeln!(f, "decision {}", decision.backend)?;
+
+      write!(f, "command")?;
+      for component in &decision.command {
+        write!(f, " {}", component)?;
+      }
+      writeln!(f)?;
+
+      write!(f, "candidates")?;
+      for site in &decision.candidates {
+        write!(f, " {}:{}", site.file, site.node)?;
+      }
+      writeln!(f)?;
+
+      match decision.chosen {
+        Some(site) => writeln!(f, "chosen {}:{}", site.file, site.node)?,
+        None => writeln!(f, "chosen none")?,
+      }
+
+      // Sorted so the log does not depend on the map's order
+      let mut bindings: Vec<(&String, &CommandComponent)> = decision.bindings.iter().collect();
+      bindings.sort_by(|a, b| a.0.cmp(b.0));
+
+      for (slot, bound) in bindings {
+        writeln!(f, "bind {} {}", slot, bound)?;
+      }
+    }
+
+    Ok(())
+  }
+}
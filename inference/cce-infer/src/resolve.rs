@@ -0,0 +1,145 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Memoized lookups. Large programs resolve the same phrases over and over;
+// the resolver keeps the matches for each command, keyed by the command's
+// CirceHash and the knowledge base revision, so a phrase is only matched
+// against every signature once per revision.
+//
+// Revisions are never reused, so a resolver handed another knowledge base
+// starts over instead of returning matches for the old one.
+
+use std::collections::HashMap;
+
+use cce_infer_ast::CommandComponent;
+use circelang_hash::CirceHash;
+
+use crate::knowledge::{best_howtos, Entry, KnowledgeBase};
+use crate::matcher::Bindings;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  // Entries dropped because the knowledge base changed
+  pub evictions: u64,
+}
+
+impl CacheStats {
+  pub fn lookups(&self) -> u64 {
+    self.hits + self.misses
+  }
+
+  pub fn hit_rate(&self) -> f64 {
+    match self.lookups() {
+      0 => 0.0,
+      lookups => self.hits as f64 / lookups as f64,
+    }
+  }
+}
+
+struct Cached {
+  command: Vec<CommandComponent>,
+  matches: Vec<(usize, Bindings)>,
+}
+
+#[derive(Default)]
+pub struct Resolver {
+  cache: HashMap<(u64, u64), Vec<Cached>>,
+  revision: u64,
+  stats: CacheStats,
+}
+
+impl Resolver {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // Same result as `KnowledgeBase::lookup`
+  pub fn resolve<'k>(&mut self, knowledge: &'k KnowledgeBase, command: &[CommandComponent]) -> Vec<(&'k Entry, Bindings)> {
+    if knowledge.revision() != self.revision {
+      self.stats.evictions += self.len() as u64;
+      self.cache.clear();
+      self.revision = knowledge.revision();
+    }
+
+    // Hashes can collide, so every bucket keeps the commands it was
+    // computed for
+    let bucket: &mut Vec<Cached> = self.cache.entry((command.hash(), self.revision)).or_default();
+
+    let cached: &Cached = match bucket.iter().position(|cached| cached.command == command) {
+      Some(index) => {
+        self.stats.hits += 1;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(matches = bucket[index].matches.len(), "cache hit");
+        &bucket[index]
+      }
+      None => {
+        self.stats.misses += 1;
+
+        // Keyed by the command as given, matched as the knowledge base
+        // normalizes it
+        let normalized: Vec<CommandComponent> = knowledge.normalized(command);
+        let matches: Vec<(usize, Bindings)> = knowledge.entries()
+          .iter()
+          .enumerate()
+          .filter(|(_, entry)| knowledge.is_visible(entry))
+          .filter_map(|(index, entry)| Some((index, knowledge.match_entry(entry, &normalized)?)))
+          .collect();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(matches = matches.len(), "cache miss");
+
+        bucket.push(Cached { command: command.to_vec(), matches });
+        bucket.last().unwrap()
+      }
+    };
+
+    cached.matches
+      .iter()
+      .map(|(index, bindings)| (&knowledge.entries()[*index], bindings.clone()))
+      .collect()
+  }
+
+  // Same result as `KnowledgeBase::best_howtos`
+  pub fn best_howtos<'k>(&mut self, knowledge: &'k KnowledgeBase, command: &[CommandComponent]) -> Vec<(&'k Entry, Bindings)> {
+    best_howtos(self.resolve(knowledge, command))
+  }
+
+  pub fn stats(&self) -> CacheStats {
+    self.stats
+  }
+
+  // Number of commands currently cached
+  pub fn len(&self) -> usize {
+    self.cache.values().map(Vec::len).sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cache.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.stats.evictions += self.len() as u64;
+    self.cache.clear();
+  }
+}
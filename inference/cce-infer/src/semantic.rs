@@ -0,0 +1,208 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Matching by meaning instead of by tokens. An `Embedder` turns the keywords
+// of a signature or command into a vector, and candidates are ranked by the
+// cosine similarity of the two, so `display 'hi'` can find `howto print
+// %text?`. Slots are bound to the command's non-keyword components in
+// order, so a candidate needs as many of them as it has slots.
+//
+// Whenever an embedding is unavailable the matcher falls back to the
+// knowledge base's own token matching.
+
+use std::collections::HashMap;
+
+use cce_ast::Symbol;
+use cce_infer_ast::{CommandComponent, SlotType};
+
+use crate::knowledge::{Entry, KnowledgeBase};
+use crate::matcher::{accepts, Bindings};
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug)]
+pub enum EmbedError {
+  #[error("Embedding unavailable: {0}")]
+  Unavailable(String),
+  #[error("Failed to reach the embedder")]
+  IoError(#[from] std::io::Error),
+}
+
+pub trait Embedder {
+  fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError>;
+}
+
+// 0.0 for vectors of different lengths or with no length at all
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+  if a.len() != b.len() {
+    return 0.0;
+  }
+
+  let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+  let norms: f32 = norm(a) * norm(b);
+
+  if norms == 0.0 {
+    0.0
+  } else {
+    dot / norms
+  }
+}
+
+fn norm(v: &[f32]) -> f32 {
+  v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+// The text that gets embedded: only the keywords, as literals and slot
+// names say little about what a command does
+pub fn phrase(components: &[CommandComponent]) -> String {
+  components
+    .iter()
+    .filter_map(|component| match component {
+      CommandComponent::Keyword(keyword) => Some(keyword.as_str()),
+      _ => None,
+    })
+    .collect::<Vec<&str>>()
+    .join(" ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranked<'k> {
+  pub entry: &'k Entry,
+  pub bindings: Bindings,
+  // Cosine similarity, or 1.0 for a token match
+  pub score: f32,
+}
+
+pub struct SemanticMatcher<E> {
+  embedder: E,
+  threshold: f32,
+  // Embeddings of signature phrases, which repeat across lookups
+  cache: HashMap<String, Vec<f32>>,
+}
+
+impl<E: Embedder> SemanticMatcher<E> {
+  pub fn new(embedder: E) -> Self {
+    Self {
+      embedder,
+      threshold: 0.8,
+      cache: HashMap::new(),
+    }
+  }
+
+  // Candidates scoring below the threshold are left out
+  pub fn with_threshold(self, threshold: f32) -> Self {
+    Self { threshold, ..self }
+  }
+
+  pub fn embedder(&self) -> &E {
+    &self.embedder
+  }
+
+  // Candidates for the command, best first. Token matches always rank
+  // first, with a score of 1.0.
+  pub fn rank<'k>(&mut self, knowledge: &'k KnowledgeBase, command: &[CommandComponent]) -> Vec<Ranked<'k>> {
+    let exact: Vec<Ranked<'k>> = knowledge.lookup(command)
+      .into_iter()
+      .map(|(entry, bindings)| Ranked { entry, bindings, score: 1.0 })
+      .collect();
+
+    match self.rank_semantic(knowledge, command) {
+      Ok(mut ranked) => {
+        ranked.retain(|candidate| !exact.iter().any(|exact| std::ptr::eq(exact.entry, candidate.entry)));
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        exact.into_iter().chain(ranked).collect()
+      }
+      Err(_err) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(error = %_err, "falling back to token matching");
+        exact
+      }
+    }
+  }
+
+  fn rank_semantic<'k>(&mut self, knowledge: &'k KnowledgeBase, command: &[CommandComponent]) -> Result<Vec<Ranked<'k>>, EmbedError> {
+    let target: Vec<f32> = self.embedder.embed(&phrase(command))?;
+    let mut ranked: Vec<Ranked<'k>> = Vec::new();
+
+    for entry in knowledge.visible() {
+      let signature: &[CommandComponent] = entry.definition.signature();
+
+      let Some(bindings) = bind_in_order(signature, command) else {
+        continue;
+      };
+
+      let text: String = phrase(signature);
+      let embedding: &Vec<f32> = match self.cache.get(&text) {
+        Some(embedding) => embedding,
+        None => {
+          let embedding: Vec<f32> = self.embedder.embed(&text)?;
+          self.cache.entry(text).or_insert(embedding)
+        }
+      };
+
+      let score: f32 = cosine_similarity(&target, embedding);
+
+      if score >= self.threshold {
+        ranked.push(Ranked { entry, bindings, score });
+      }
+    }
+
+    Ok(ranked)
+  }
+}
+
+fn bind_in_order(signature: &[CommandComponent], command: &[CommandComponent]) -> Option<Bindings> {
+  let slots: Vec<(Symbol, Option<&SlotType>)> = signature
+    .iter()
+    .filter_map(|component| match component {
+      CommandComponent::Slot { name, ty } => Some((name.clone(), ty.as_ref())),
+      _ => None,
+    })
+    .collect();
+  let values: Vec<&CommandComponent> = command
+    .iter()
+    .filter(|component| !matches!(component, CommandComponent::Keyword(_)))
+    .collect();
+
+  if slots.len() != values.len() {
+    return None;
+  }
+
+  let mut bindings: Bindings = Bindings::new();
+
+  for ((name, ty), value) in slots.into_iter().zip(values) {
+    if !accepts(ty, value) {
+      return None;
+    }
+
+    match bindings.get(name.as_str()) {
+      Some(bound) if bound != value => return None,
+      Some(_) => {}
+      None => {
+        bindings.insert(name.to_string(), value.clone());
+      }
+    }
+  }
+
+  Some(bindings)
+}
@@ -0,0 +1,80 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// A knowledge base shared between threads, such as a language server's
+// request handlers and a parallel resolver. Readers take a snapshot, the
+// knowledge base as it was at one revision, and match against it without
+// holding any lock. Writers update a copy and publish it as the next
+// revision; snapshots taken earlier never change under their readers.
+// Since the copy is only published once the update returns, an update that
+// panics leaves the knowledge base as it was.
+
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use crate::knowledge::KnowledgeBase;
+
+
+#[derive(Debug, Clone, Default)]
+pub struct SharedKnowledgeBase {
+  current: Arc<RwLock<Arc<KnowledgeBase>>>,
+  // Held for a whole update, so writers take turns without keeping
+  // readers waiting
+  writer: Arc<Mutex<()>>,
+}
+
+impl SharedKnowledgeBase {
+  pub fn new(knowledge: KnowledgeBase) -> Self {
+    Self { current: Arc::new(RwLock::new(Arc::new(knowledge))), writer: Arc::default() }
+  }
+
+  // The knowledge base at its latest revision
+  pub fn snapshot(&self) -> Arc<KnowledgeBase> {
+    self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+  }
+
+  pub fn revision(&self) -> u64 {
+    self.snapshot().revision()
+  }
+
+  // Runs `update` on the knowledge base and publishes the result.
+  // Concurrent updates run one after the other, each seeing the ones
+  // before it.
+  pub fn update<T>(&self, update: impl FnOnce(&mut KnowledgeBase) -> T) -> T {
+    let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut next: KnowledgeBase = KnowledgeBase::clone(&self.snapshot());
+    let result: T = update(&mut next);
+
+    *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(next);
+    result
+  }
+
+  pub fn replace(&self, knowledge: KnowledgeBase) {
+    let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+    *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(knowledge);
+  }
+}
+
+impl From<KnowledgeBase> for SharedKnowledgeBase {
+  fn from(knowledge: KnowledgeBase) -> Self {
+    Self::new(knowledge)
+  }
+}
@@ -0,0 +1,99 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Suggestions for commands that match no definition. A command is most
+// often unmatched because one keyword is misspelled, so each keyword is
+// swapped in turn for the closest keyword of a known signature, and the
+// swap is only suggested if the command then matches.
+
+use cce_ast::{closest, edit_distance, Suggestion, Symbol};
+use cce_infer_ast::CommandComponent;
+
+use crate::knowledge::KnowledgeBase;
+use crate::matcher::{match_signature_with, MatchOptions};
+
+
+pub fn suggest_command(signatures: &[&[CommandComponent]], command: &[CommandComponent]) -> Option<Suggestion> {
+  suggest_with(signatures, command, MatchOptions::default())
+}
+
+fn suggest_with(signatures: &[&[CommandComponent]], command: &[CommandComponent], options: MatchOptions) -> Option<Suggestion> {
+  let matches = |command: &[CommandComponent]| {
+    signatures.iter().any(|signature| match_signature_with(signature, command, options).is_some())
+  };
+
+  if matches(command) {
+    return None;
+  }
+
+  let mut keywords: Vec<&str> = signatures
+    .iter()
+    .flat_map(|signature| signature.iter())
+    .filter_map(|component| match component {
+      CommandComponent::Keyword(keyword) => Some(keyword.as_str()),
+      _ => None,
+    })
+    .collect();
+  keywords.sort_unstable();
+  keywords.dedup();
+
+  let mut best: Option<(usize, Suggestion)> = None;
+
+  for (i, component) in command.iter().enumerate() {
+    let CommandComponent::Keyword(word) = component else {
+      continue;
+    };
+
+    let Some(replacement) = closest(word, keywords.iter().copied()) else {
+      continue;
+    };
+
+    let mut fixed: Vec<CommandComponent> = command.to_vec();
+    fixed[i] = CommandComponent::Keyword(Symbol::intern(replacement));
+
+    if !matches(&fixed) {
+      continue;
+    }
+
+    let distance: usize = edit_distance(word, replacement);
+
+    if best.as_ref().is_none_or(|(smallest, _)| distance < *smallest) {
+      best = Some((distance, Suggestion {
+        original: word.to_string(),
+        replacement: replacement.to_string(),
+        span: None,
+      }));
+    }
+  }
+
+  best.map(|(_, suggestion)| suggestion)
+}
+
+impl KnowledgeBase {
+  // A fix for a command that matches nothing in the knowledge base
+  pub fn suggest(&self, command: &[CommandComponent]) -> Option<Suggestion> {
+    let signatures: Vec<&[CommandComponent]> = self.visible()
+      .map(|entry| entry.definition.signature())
+      .collect();
+
+    suggest_with(&signatures, command, self.match_options())
+  }
+}
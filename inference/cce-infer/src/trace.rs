@@ -0,0 +1,172 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+// Why a command resolved the way it did. A trace lists every signature in
+// the knowledge base with the outcome of matching the command against it,
+// in the order they were defined.
+
+use std::fmt;
+
+use cce_ast::DefinitionLocation;
+use cce_infer_ast::CommandComponent;
+
+use crate::knowledge::{Definition, KnowledgeBase};
+use crate::matcher::{specificity, try_match, Bindings, Mismatch};
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+  Chosen,
+  // Matched, but another howto was chosen, either because it is more
+  // specific or by the disambiguation policy
+  Shadowed { by: DefinitionLocation },
+  // Tied with other howtos, and the disambiguation policy chose none
+  Undecided,
+  // Matched, but whatis definitions are not expanded
+  WhatIs,
+  Rejected(Mismatch),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+  pub site: DefinitionLocation,
+  pub namespace: Option<String>,
+  pub signature: Vec<CommandComponent>,
+  pub score: usize,
+  // Empty unless the signature matched
+  pub bindings: Bindings,
+  pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolutionTrace {
+  pub command: Vec<CommandComponent>,
+  pub candidates: Vec<Candidate>,
+}
+
+impl ResolutionTrace {
+  pub fn chosen(&self) -> Option<&Candidate> {
+    self.candidates.iter().find(|candidate| candidate.verdict == Verdict::Chosen)
+  }
+
+  pub fn matched(&self) -> impl Iterator<Item = &Candidate> {
+    self.candidates.iter().filter(|candidate| !matches!(candidate.verdict, Verdict::Rejected(_)))
+  }
+}
+
+impl KnowledgeBase {
+  // Ties are broken as `FirstDefined` would break them
+  pub fn trace(&self, command: &[CommandComponent]) -> ResolutionTrace {
+    let chosen: Option<DefinitionLocation> = self.best_howtos(command).first().map(|(entry, _)| entry.site);
+    self.trace_choosing(command, chosen)
+  }
+
+  pub(crate) fn trace_choosing(&self, command: &[CommandComponent], chosen: Option<DefinitionLocation>) -> ResolutionTrace {
+    let candidates: Vec<Candidate> = self.visible()
+      .map(|entry| {
+        let signature: &[CommandComponent] = entry.definition.signature();
+
+        let (bindings, verdict) = match try_match(signature, command, self.match_options()) {
+          Err(mismatch) => (Bindings::new(), Verdict::Rejected(mismatch)),
+          Ok(bindings) => match (&entry.definition, chosen) {
+            (Definition::WhatIs(_), _) => (bindings, Verdict::WhatIs),
+            (Definition::HowTo(_), Some(site)) if site == entry.site => (bindings, Verdict::Chosen),
+            (Definition::HowTo(_), Some(by)) => (bindings, Verdict::Shadowed { by }),
+            (Definition::HowTo(_), None) => (bindings, Verdict::Undecided),
+          },
+        };
+
+        Candidate {
+          site: entry.site,
+          namespace: entry.namespace.clone(),
+          signature: signature.to_vec(),
+          score: specificity(signature),
+          bindings,
+          verdict,
+        }
+      })
+      .collect();
+
+    ResolutionTrace { command: command.to_vec(), candidates }
+  }
+}
+
+impl fmt::Display for Verdict {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Verdict::Chosen => write!(f, "chosen"),
+      Verdict::Shadowed { by } => write!(f, "shadowed by {}", by),
+      Verdict::Undecided => write!(f, "ambiguous"),
+      Verdict::WhatIs => write!(f, "matched a whatis"),
+      Verdict::Rejected(mismatch) => write!(f, "rejected: {}", mismatch),
+    }
+  }
+}
+
+// One line for the command, then one per candidate:
+//
+//   greet 'Bob'
+//     [chosen] greet %who (statement 1 of file 1, score 1) with who = 'Bob'
+//     [rejected: expected 3 components, found 2] wave at %who (statement 2 of file 1, score 2)
+impl fmt::Display for ResolutionTrace {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{}", Phrase(&self.command))?;
+
+    if self.candidates.is_empty() {
+      return writeln!(f, "  no definitions");
+    }
+
+    for candidate in &self.candidates {
+      write!(f, "  [{}] {} (", candidate.verdict, Phrase(&candidate.signature))?;
+      if let Some(namespace) = &candidate.namespace {
+        write!(f, "{}, ", namespace)?;
+      }
+      write!(f, "{}, score {})", candidate.site, candidate.score)?;
+
+      // Sorted so the rendering does not depend on the map's order
+      let mut bindings: Vec<(&String, &CommandComponent)> = candidate.bindings.iter().collect();
+      bindings.sort_by(|a, b| a.0.cmp(b.0));
+
+      for (i, (slot, bound)) in bindings.into_iter().enumerate() {
+        write!(f, "{} {} = {}", if i == 0 { " with" } else { "," }, slot, bound)?;
+      }
+
+      writeln!(f)?;
+    }
+
+    Ok(())
+  }
+}
+
+struct Phrase<'c>(&'c [CommandComponent]);
+
+impl fmt::Display for Phrase<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (i, component) in self.0.iter().enumerate() {
+      if i > 0 {
+        write!(f, " ")?;
+      }
+      write!(f, "{}", component)?;
+    }
+
+    Ok(())
+  }
+}
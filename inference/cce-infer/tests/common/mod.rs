@@ -0,0 +1,48 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+// Helpers shared by the integration tests. Each test binary only uses some
+// of them.
+#![allow(dead_code)]
+
+use cce_ast::{parse_all, DefinitionLocation};
+use cce_infer_ast::{convert, CommandComponent, HowToNode, ProgramNode};
+
+pub fn parse(source: &str) -> Vec<ProgramNode> {
+  convert(parse_all(source).unwrap())
+}
+
+pub fn howto(source: &str) -> HowToNode {
+  match parse(source).remove(0) {
+    ProgramNode::HowTo(howto) => howto,
+    _ => panic!("expected a howto statement")
+  }
+}
+
+pub fn command(source: &str) -> Vec<CommandComponent> {
+  match parse(source).remove(0) {
+    ProgramNode::Command(command) => command.command,
+    _ => panic!("expected a command")
+  }
+}
+
+pub fn site(node: usize) -> DefinitionLocation {
+  DefinitionLocation { file: 0, node }
+}
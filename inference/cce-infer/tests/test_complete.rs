@@ -0,0 +1,91 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_ast::{self as ast, DefinitionLocation};
+use cce_infer::*;
+use cce_infer_ast::{convert, CommandComponent, ProgramNode};
+use common::command;
+
+
+fn knowledge(sources: &[&str]) -> KnowledgeBase {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+
+  for (node, source) in sources.iter().enumerate() {
+    let mut parser: ast::Parser = ast::Parser::from(*source);
+    let site: DefinitionLocation = DefinitionLocation { file: 0, node };
+
+    match convert(vec![parser.next().unwrap().unwrap()]).remove(0) {
+      ProgramNode::HowTo(howto) => knowledge.insert(howto, site).unwrap(),
+      ProgramNode::WhatIs(whatis) => knowledge.insert(whatis, site).unwrap(),
+      _ => panic!("expected a definition"),
+    };
+  }
+
+  knowledge
+}
+
+fn rendered(completions: &[Completion]) -> Vec<(CompletionKind, String, usize)> {
+  completions
+    .iter()
+    .map(|completion| (completion.kind, completion.to_string(), completion.count))
+    .collect()
+}
+
+
+#[test]
+fn test_complete_ranked_by_frequency() {
+  let knowledge: KnowledgeBase = knowledge(&[
+    "howto say hello to %who?\n- wave\n\n",
+    "howto say goodbye to %who?\n- wave\n\n",
+    "whatis say hello?\n- hello\n\n",
+    "howto greet %who?\n- wave\n\n",
+  ]);
+
+  assert_eq!(rendered(&complete(&command("say."), &knowledge)), vec![
+    (CompletionKind::Keyword, "hello".to_string(), 2),
+    (CompletionKind::Signature, "hello to %who".to_string(), 1),
+    (CompletionKind::Keyword, "goodbye".to_string(), 1),
+    (CompletionKind::Signature, "goodbye to %who".to_string(), 1),
+  ]);
+
+  assert_eq!(rendered(&complete(&[], &knowledge))[0], (CompletionKind::Keyword, "say".to_string(), 3));
+}
+
+#[test]
+fn test_complete_slots() {
+  let knowledge: KnowledgeBase = knowledge(&[
+    "howto greet %who:string?\n- wave\n\n",
+    "howto greet %who:string loudly?\n- shout\n\n",
+  ]);
+
+  // Slots in the prefix match whatever fills them
+  let prefix: Vec<CommandComponent> = vec![CommandComponent::Keyword("greet".into()), CommandComponent::Literal("Bob".to_string())];
+  assert_eq!(rendered(&complete(&prefix, &knowledge)), vec![(CompletionKind::Keyword, "loudly".to_string(), 1)]);
+
+  let completions: Vec<Completion> = complete(&command("greet."), &knowledge);
+  assert_eq!(completions[0].kind, CompletionKind::Slot);
+  assert_eq!(completions[0].count, 2);
+  assert_eq!(completions[0].to_string(), "%who:string");
+
+  assert!(complete(&command("wave."), &knowledge).is_empty());
+}
@@ -0,0 +1,152 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::{self as ast, DefinitionLocation};
+use cce_infer::*;
+use cce_infer_ast::{convert, CommandComponent, CommandNode, HowToCommand, ProgramNode};
+
+
+fn knowledge(source: &str) -> (KnowledgeBase, Vec<CommandNode>) {
+  let mut parser: ast::Parser = ast::Parser::from(source);
+  let mut nodes: Vec<ast::ParseNode> = Vec::new();
+  while let Some(node) = parser.next().unwrap() {
+    nodes.push(node);
+  }
+
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  let mut commands: Vec<CommandNode> = Vec::new();
+
+  for (node, program_node) in convert(nodes).into_iter().enumerate() {
+    let site: DefinitionLocation = DefinitionLocation { file: 0, node };
+
+    match program_node {
+      ProgramNode::HowTo(howto) => { knowledge.insert(howto, site).unwrap(); }
+      ProgramNode::WhatIs(whatis) => { knowledge.insert(whatis, site).unwrap(); }
+      ProgramNode::Command(command) => commands.push(command),
+      ProgramNode::Module(_) => {}
+    }
+  }
+
+  (knowledge, commands)
+}
+
+fn printed(steps: &[HowToCommand]) -> Vec<String> {
+  steps
+    .iter()
+    .map(|step| match step {
+      HowToCommand::Command(command) => command.to_string(),
+      _ => panic!("expected a plain command"),
+    })
+    .collect()
+}
+
+
+#[test]
+fn test_expand_nested_howtos() {
+  let (knowledge, commands) = knowledge(
+    "howto greet %who?\n- wave at %who\n- say hello to %who\n\nhowto wave at %who?\n- raise hand\n- look at %who\n\ngreet 'Bob'.",
+  );
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default());
+  let steps: Vec<HowToCommand> = expander.expand(&commands[0]).unwrap();
+
+  assert_eq!(printed(&steps), vec!["raise hand", "look at 'Bob'", "say hello to 'Bob'"]);
+  assert_eq!(expander.expanded(), 3);
+  assert_eq!(expander.stats(), ExpansionStats { resolutions: 5, unresolved: 3, matcher_calls: 5, policy_calls: 0, replayed: 0 });
+}
+
+#[test]
+fn test_expand_resolver_cache() {
+  let (knowledge, commands) = knowledge("howto greet %who?\n- wave\n- wave\n\ngreet 'Bob'.\ngreet 'Bob'.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default());
+  for command in &commands {
+    expander.expand(command).unwrap();
+  }
+
+  // Each phrase is matched against the knowledge base once
+  assert_eq!(expander.resolver().stats(), CacheStats { hits: 4, misses: 2, evictions: 0 });
+
+  // A resolver handed to another expander keeps what it cached
+  let resolver: Resolver = expander.take_resolver();
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default()).with_resolver(resolver);
+  expander.expand(&commands[0]).unwrap();
+  assert_eq!(expander.resolver().stats(), CacheStats { hits: 7, misses: 2, evictions: 0 });
+}
+
+#[test]
+fn test_expand_depth_limit() {
+  let (knowledge, commands) = knowledge("howto spin?\n- turn\n- spin\n\nspin.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits { max_depth: Some(8), max_commands: None });
+
+  assert_eq!(
+    expander.expand(&commands[0]),
+    Err(ExpandError::DepthLimit { limit: 8, site: DefinitionLocation { file: 0, node: 0 } })
+  );
+}
+
+#[test]
+fn test_expand_command_limit() {
+  let (knowledge, commands) = knowledge("howto greet?\n- wave\n- smile\n\ngreet.\ngreet.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits { max_depth: None, max_commands: Some(3) });
+
+  assert!(expander.expand(&commands[0]).is_ok());
+  assert_eq!(expander.expand(&commands[1]), Err(ExpandError::CommandLimit { limit: 3 }));
+}
+
+#[test]
+fn test_expand_prefers_specific_howtos() {
+  let (knowledge, commands) = knowledge("howto greet %who?\n- wave\n\nhowto greet 'Bob'?\n- hug\n\ngreet 'Bob'.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default());
+  assert_eq!(printed(&expander.expand(&commands[0]).unwrap()), vec!["hug"]);
+}
+
+#[test]
+fn test_expand_disambiguation() {
+  let (knowledge, commands) = knowledge("howto greet %who?\n- wave\n\nhowto greet %who:string?\n- bow\n\ngreet 'Bob'.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default());
+  assert_eq!(printed(&expander.expand(&commands[0]).unwrap()), vec!["wave"]);
+
+  let mut offered: usize = 0;
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default())
+    .with_disambiguation(|_: &[CommandComponent], candidates: &[&Entry]| {
+      offered = candidates.len();
+      Some(1)
+    });
+  assert_eq!(printed(&expander.expand(&commands[0]).unwrap()), vec!["bow"]);
+  assert_eq!(expander.stats().policy_calls, 1);
+  drop(expander);
+  assert_eq!(offered, 2);
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default()).with_disambiguation(RejectAmbiguous).with_trace();
+  let err: ExpandError = expander.expand(&commands[0]).unwrap_err();
+
+  assert_eq!(err.code(), "CCE0203");
+  assert_eq!(err, ExpandError::Ambiguous {
+    command: "greet 'Bob'".to_string(),
+    sites: vec![DefinitionLocation { file: 0, node: 0 }, DefinitionLocation { file: 0, node: 1 }],
+  });
+  assert!(expander.traces()[0].candidates.iter().all(|candidate| candidate.verdict == Verdict::Undecided));
+}
@@ -0,0 +1,90 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::{self as ast, DefinitionLocation, ParseNode};
+use cce_infer::*;
+use cce_infer_ast::{convert, ProgramNode};
+
+
+fn knowledge(source: &str) -> KnowledgeBase {
+  let mut parser: ast::Parser = ast::Parser::from(source);
+  let mut nodes: Vec<ParseNode> = Vec::new();
+
+  while let Some(node) = parser.next().unwrap() {
+    nodes.push(node);
+  }
+
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+
+  for (node, program_node) in convert(nodes).into_iter().enumerate() {
+    let site: DefinitionLocation = DefinitionLocation { file: 0, node };
+
+    match program_node {
+      ProgramNode::HowTo(howto) => knowledge.insert(howto, site).unwrap(),
+      ProgramNode::WhatIs(whatis) => knowledge.insert(whatis, site).unwrap(),
+      ProgramNode::Command(_) | ProgramNode::Module(_) => continue
+    };
+  }
+
+  knowledge
+}
+
+
+#[test]
+fn test_graph_dependencies() {
+  let graph: DefGraph = DefGraph::build(&knowledge(
+    "howto wave?\n- move arm\n\n\
+     howto greet %who?\n- wave\n- when %who is shy\n  - nod\n\n\
+     whatis nod?\n- wave\n\n"));
+
+  assert_eq!(graph.len(), 3);
+  assert_eq!(graph.dependencies(0), &[] as &[usize]);
+  assert_eq!(graph.dependencies(1), &[0, 2]);
+  assert_eq!(graph.dependencies(2), &[0]);
+  assert_eq!(graph.dependents(0), vec![1, 2]);
+
+  assert_eq!(graph.cycles(), Vec::<Vec<usize>>::new());
+  assert_eq!(graph.topological_order(), Ok(vec![0, 2, 1]));
+}
+
+#[test]
+fn test_graph_cycles() {
+  let graph: DefGraph = DefGraph::build(&knowledge(
+    "howto ping?\n- pong\n\n\
+     howto pong?\n- ping\n\n\
+     howto loop?\n- loop\n\n\
+     howto rest?\n- sleep\n\n"));
+
+  assert_eq!(graph.cycles(), vec![vec![0, 1], vec![2]]);
+  assert_eq!(graph.topological_order(), Err(DefGraphError::Cycle(vec![0, 1])));
+}
+
+#[test]
+fn test_graph_dot() {
+  let graph: DefGraph = DefGraph::build(&knowledge(
+    "howto say %what:string?\n- print %what\n\n\
+     whatis a '\"quote\"'?\n- say 'hi'\n\n"));
+
+  assert_eq!(graph.to_dot(), "digraph definitions {\n  \
+    n0 [label=\"howto say %what:string\"];\n  \
+    n1 [label=\"whatis a '\\\"quote\\\"'\"];\n  \
+    n1 -> n0;\n}\n");
+}
@@ -0,0 +1,184 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_infer::*;
+use cce_infer_ast::{CommandComponent, NormalizeOptions};
+use common::{howto, site};
+
+
+#[test]
+fn test_knowledge_insert_distinct() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+
+  assert_eq!(knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)), Ok(None));
+  assert_eq!(knowledge.insert(howto("howto greet %who:string loudly?\n- shout\n\n"), site(1)), Ok(None));
+  assert_eq!(knowledge.entries().len(), 2);
+}
+
+#[test]
+fn test_knowledge_conflict_error() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::new(ConflictPolicy::Error);
+
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  let err: KnowledgeBaseError = knowledge.insert(howto("howto Greet %person?\n- nod\n\n"), site(1)).unwrap_err();
+
+  assert_eq!(err, KnowledgeBaseError::Conflict(Conflict { existing: site(0), new: site(1) }));
+  assert_eq!(err.to_string(), "Conflicting definition: statement 2 of file 1 is equivalent to statement 1 of file 1");
+  assert_eq!(knowledge.entries().len(), 1);
+}
+
+#[test]
+fn test_knowledge_conflict_warn() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::new(ConflictPolicy::Warn);
+
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  let conflict: Option<Conflict> = knowledge.insert(howto("howto greet %person?\n- nod\n\n"), site(1)).unwrap();
+
+  assert_eq!(conflict, Some(Conflict { existing: site(0), new: site(1) }));
+  assert_eq!(knowledge.entries().len(), 2);
+}
+
+#[test]
+fn test_knowledge_conflict_shadow() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::new(ConflictPolicy::Shadow);
+
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  knowledge.insert(howto("howto greet %person?\n- nod\n\n"), site(1)).unwrap();
+
+  assert_eq!(knowledge.entries().len(), 1);
+  assert_eq!(knowledge.entries()[0].site, site(1));
+}
+
+#[test]
+fn test_knowledge_lookup() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  knowledge.insert(howto("howto wave?\n- move arm\n\n"), site(1)).unwrap();
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("greet".into()),
+    CommandComponent::Literal("Bob".to_string()),
+  ];
+  let found: Vec<(&Entry, Bindings)> = knowledge.lookup(&command);
+
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].0.site, site(0));
+  assert_eq!(found[0].1.get("who"), Some(&CommandComponent::Literal("Bob".to_string())));
+}
+
+#[test]
+fn test_knowledge_namespaces() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::new(ConflictPolicy::Error);
+
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  assert_eq!(knowledge.insert_in("greetings", howto("howto greet %who?\n- nod\n\n"), site(1)), Ok(None));
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("greet".into()),
+    CommandComponent::Literal("Bob".to_string()),
+  ];
+
+  assert_eq!(knowledge.lookup(&command).len(), 2);
+  assert_eq!(knowledge.lookup_in("greetings", &command)[0].0.site, site(1));
+}
+
+#[test]
+fn test_knowledge_imports() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  knowledge.insert_in("polite", howto("howto greet %who?\n- bow\n\n"), site(1)).unwrap();
+  knowledge.insert_in("polite.formal", howto("howto greet %who?\n- curtsy\n\n"), site(2)).unwrap();
+  knowledge.insert_in("casual", howto("howto greet %who?\n- nod\n\n"), site(3)).unwrap();
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("greet".into()),
+    CommandComponent::Literal("Bob".to_string()),
+  ];
+  let sites = |knowledge: &KnowledgeBase| -> Vec<usize> {
+    knowledge.lookup(&command).into_iter().map(|(entry, _)| entry.site.node).collect()
+  };
+
+  // Everything is visible until something is imported
+  assert_eq!(knowledge.imports(), None);
+  assert_eq!(sites(&knowledge), vec![0, 1, 2, 3]);
+
+  let revision: u64 = knowledge.revision();
+  knowledge.import("polite");
+  assert!(knowledge.revision() > revision);
+  assert_eq!(sites(&knowledge), vec![0, 1, 2]);
+
+  // Qualified lookups see namespaces that were not imported
+  assert_eq!(knowledge.lookup_in("casual", &command)[0].0.site, site(3));
+
+  let mut resolver: Resolver = Resolver::new();
+  assert_eq!(resolver.resolve(&knowledge, &command).len(), 3);
+
+  knowledge.set_imports(Vec::new());
+  assert_eq!(sites(&knowledge), vec![0]);
+}
+
+#[test]
+fn test_knowledge_match_options() {
+  let options: MatchOptions = MatchOptions { case_insensitive: true, stemming: true, ignore_stopwords: true };
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default().with_match_options(options);
+  knowledge.insert(howto("howto print a %text?\n- write %text\n\n"), site(0)).unwrap();
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("Printing".into()),
+    CommandComponent::Keyword("the".into()),
+    CommandComponent::Literal("hi".to_string()),
+  ];
+
+  assert_eq!(knowledge.match_options(), options);
+  assert_eq!(knowledge.lookup(&command).len(), 1);
+
+  let mut resolver: Resolver = Resolver::new();
+  assert_eq!(resolver.resolve(&knowledge, &command).len(), 1);
+
+  // Exact matching stays the default
+  let mut exact: KnowledgeBase = KnowledgeBase::default();
+  exact.insert(howto("howto print a %text?\n- write %text\n\n"), site(0)).unwrap();
+  assert_eq!(exact.lookup(&command).len(), 0);
+}
+
+#[test]
+fn test_knowledge_normalization() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default().with_normalization(NormalizeOptions::default());
+  knowledge.insert(howto("howto Print the %text?\n- write %text\n\n"), site(0)).unwrap();
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("print".into()),
+    CommandComponent::Keyword("a".into()),
+    CommandComponent::Literal("  hello   world ".to_string()),
+  ];
+
+  let found = knowledge.lookup(&command);
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].1.get("text"), Some(&CommandComponent::Literal("hello world".to_string())));
+  assert_eq!(knowledge.lookup_in("std", &command).len(), 0);
+
+  let mut exact: KnowledgeBase = KnowledgeBase::default();
+  exact.insert(howto("howto Print the %text?\n- write %text\n\n"), site(0)).unwrap();
+  assert_eq!(exact.lookup(&command).len(), 0);
+}
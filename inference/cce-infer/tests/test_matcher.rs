@@ -0,0 +1,157 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_infer::{match_signature, match_signature_with, stem, Bindings, MatchOptions};
+use cce_infer_ast::{CommandComponent, ProgramNode};
+use common::parse;
+
+
+fn signature_and_command(source: &str) -> (Vec<CommandComponent>, Vec<CommandComponent>) {
+  let nodes: Vec<ProgramNode> = parse(source);
+
+  match (&nodes[0], &nodes[1]) {
+    (ProgramNode::HowTo(howto), ProgramNode::Command(command)) => {
+      (howto.signature.clone(), command.command.clone())
+    }
+    _ => panic!("expected a howto followed by a command")
+  }
+}
+
+
+#[test]
+fn test_matcher_untyped() {
+  let (signature, command) = signature_and_command("howto greet %who?\n- say hi\n\ngreet 'Bob'.");
+
+  let bindings: Bindings = match_signature(&signature, &command).unwrap();
+
+  assert_eq!(bindings.get("who"), Some(&CommandComponent::Literal("Bob".to_string())));
+}
+
+#[test]
+fn test_matcher_keyword_mismatch() {
+  let (signature, command) = signature_and_command("howto greet %who?\n- say hi\n\nwave 'Bob'.");
+
+  assert_eq!(match_signature(&signature, &command), None);
+}
+
+#[test]
+fn test_matcher_typed_string() {
+  let (signature, command) = signature_and_command("howto greet %who:string?\n- say hi\n\ngreet bob.");
+
+  assert_eq!(match_signature(&signature, &command), None);
+}
+
+#[test]
+fn test_matcher_typed_number() {
+  let (signature, command) = signature_and_command("howto wait %count:number seconds?\n- sleep\n\nwait '5' seconds.");
+
+  let bindings: Bindings = match_signature(&signature, &command).unwrap();
+  assert_eq!(bindings.get("count"), Some(&CommandComponent::Literal("5".to_string())));
+
+  let (signature, command) = signature_and_command("howto wait %count:number seconds?\n- sleep\n\nwait 'long' seconds.");
+
+  assert_eq!(match_signature(&signature, &command), None);
+}
+
+#[test]
+fn test_matcher_unquoted_number() {
+  let (signature, command) = signature_and_command("howto wait %count:number seconds?\n- sleep\n\nwait 2.5 seconds.");
+
+  let bindings: Bindings = match_signature(&signature, &command).unwrap();
+  assert_eq!(bindings.get("count").and_then(CommandComponent::as_number), Some(2.5));
+
+  // Numbers are not strings
+  let (signature, command) = signature_and_command("howto greet %who:string?\n- say hi\n\ngreet 42.");
+  assert_eq!(match_signature(&signature, &command), None);
+}
+
+#[test]
+fn test_matcher_typed_boolean() {
+  let (signature, command) = signature_and_command("howto mute %on:boolean?\n- sleep\n\nmute true.");
+
+  let bindings: Bindings = match_signature(&signature, &command).unwrap();
+  assert_eq!(bindings.get("on"), Some(&CommandComponent::Boolean(true)));
+
+  let (signature, command) = signature_and_command("howto mute %on:boolean?\n- sleep\n\nmute 'yes'.");
+  assert_eq!(match_signature(&signature, &command), None);
+}
+
+#[test]
+fn test_matcher_stem() {
+  for (word, expected) in [
+    ("printing", "print"),
+    ("prints", "print"),
+    ("printed", "print"),
+    ("matches", "match"),
+    ("writes", "write"),
+    ("running", "run"),
+    ("calling", "call"),
+    ("passes", "pass"),
+    ("string", "string"),
+    ("is", "is"),
+    ("Printing", "Print"),
+    // Non-ASCII characters, whose last bytes can be equal
+    ("aaကing", "aaက"),
+    ("aaကကing", "aaက"),
+  ] {
+    assert_eq!(stem(word), expected, "stem of {}", word);
+  }
+}
+
+#[test]
+fn test_matcher_case_insensitive() {
+  let (signature, command) = signature_and_command("howto Print %text?\n- say hi\n\nprint 'hi'.");
+
+  assert!(match_signature(&signature, &command).is_none());
+
+  let options: MatchOptions = MatchOptions { case_insensitive: true, ..MatchOptions::default() };
+  assert!(match_signature_with(&signature, &command, options).is_some());
+}
+
+#[test]
+fn test_matcher_stemmed_without_stopwords() {
+  let (signature, command) = signature_and_command("howto print a string?\n- say hi\n\nprinting the string.");
+
+  assert!(match_signature(&signature, &command).is_none());
+
+  let stemming: MatchOptions = MatchOptions { stemming: true, ..MatchOptions::default() };
+  let stopwords: MatchOptions = MatchOptions { ignore_stopwords: true, ..MatchOptions::default() };
+  let both: MatchOptions = MatchOptions { stemming: true, ignore_stopwords: true, ..MatchOptions::default() };
+
+  assert!(match_signature_with(&signature, &command, stemming).is_none());
+  assert!(match_signature_with(&signature, &command, stopwords).is_none());
+  assert!(match_signature_with(&signature, &command, both).is_some());
+}
+
+#[test]
+fn test_matcher_stopwords_keep_slots_and_literals() {
+  let (signature, command) = signature_and_command("howto greet %who?\n- say hi\n\ngreet the 'Bob'.");
+  let options: MatchOptions = MatchOptions { ignore_stopwords: true, ..MatchOptions::default() };
+
+  let bindings: Bindings = match_signature_with(&signature, &command, options).unwrap();
+  assert_eq!(bindings.get("who"), Some(&CommandComponent::Literal("Bob".to_string())));
+
+  // A literal `'the'` is not a stopword
+  let (signature, command) = signature_and_command("howto greet %who?\n- say hi\n\ngreet 'the' 'Bob'.");
+  assert!(match_signature_with(&signature, &command, options).is_none());
+}
@@ -0,0 +1,144 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::{self as ast, DefinitionLocation};
+use cce_infer::*;
+use cce_infer_ast::{convert, CommandComponent, CommandNode, HowToCommand, ProgramNode};
+
+
+fn knowledge(source: &str) -> (KnowledgeBase, Vec<CommandNode>) {
+  let mut parser: ast::Parser = ast::Parser::from(source);
+  let mut nodes: Vec<ast::ParseNode> = Vec::new();
+  while let Some(node) = parser.next().unwrap() {
+    nodes.push(node);
+  }
+
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  let mut commands: Vec<CommandNode> = Vec::new();
+
+  for (node, program_node) in convert(nodes).into_iter().enumerate() {
+    let site: DefinitionLocation = DefinitionLocation { file: 0, node };
+
+    match program_node {
+      ProgramNode::HowTo(howto) => { knowledge.insert(howto, site).unwrap(); }
+      ProgramNode::Command(command) => commands.push(command),
+      _ => {}
+    }
+  }
+
+  (knowledge, commands)
+}
+
+const SOURCE: &str = "howto greet %who?\n- wave at %who\n\nhowto greet %who:string?\n- bow to %who\n\nhowto wave at %who?\n- raise hand\n\ngreet 'Bob'.\ngreet 'Alice'.";
+
+
+#[test]
+fn test_replay_record() {
+  let (knowledge, commands) = knowledge(SOURCE);
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default())
+    .with_disambiguation(|_: &[CommandComponent], _: &[&Entry]| Some(0))
+    .with_log();
+  expander.expand(&commands[0]).unwrap();
+
+  let log: SessionLog = expander.take_log();
+  assert_eq!(log.len(), 3);
+
+  let greet: &Decision = &log.decisions[0];
+  assert_eq!(greet.backend, Backend::Policy);
+  assert_eq!(greet.candidates, vec![DefinitionLocation { file: 0, node: 0 }, DefinitionLocation { file: 0, node: 1 }]);
+  assert_eq!(greet.chosen, Some(DefinitionLocation { file: 0, node: 0 }));
+  assert_eq!(greet.bindings.get("who"), Some(&CommandComponent::Literal("Bob".to_string())));
+
+  // The nested command had a single match
+  assert_eq!(log.decisions[1].backend, Backend::Matcher);
+  assert_eq!(log.decisions[1].chosen, Some(DefinitionLocation { file: 0, node: 2 }));
+  // Commands no howto matches are logged too
+  assert_eq!(log.decisions[2].chosen, None);
+
+  assert_eq!(
+    log.to_string(),
+    "circe-session 1\ndecision policy\ncommand greet 'Bob'\ncandidates 0:0 0:1\nchosen 0:0\nbind who 'Bob'\ndecision matcher\ncommand wave at 'Bob'\ncandidates 0:2\nchosen 0:2\nbind who 'Bob'\ndecision matcher\ncommand raise hand\ncandidates\nchosen none\n"
+  );
+  assert_eq!(SessionLog::parse(&log.to_string()).unwrap(), log);
+}
+
+#[test]
+fn test_replay_follows_log() {
+  let (knowledge, commands) = knowledge(SOURCE);
+
+  // A policy that changes its mind, standing in for a model
+  let mut calls: usize = 0;
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default())
+    .with_disambiguation(move |_: &[CommandComponent], _: &[&Entry]| { calls += 1; Some(calls % 2) })
+    .with_log();
+
+  let mut steps: Vec<HowToCommand> = Vec::new();
+  for command in &commands {
+    steps.extend(expander.expand(command).unwrap());
+  }
+  let log: SessionLog = expander.take_log();
+
+  let mut replay: Expander = Expander::new(&knowledge, ExpansionLimits::default())
+    .with_disambiguation(RejectAmbiguous)
+    .replaying(log);
+
+  let mut replayed: Vec<HowToCommand> = Vec::new();
+  for command in &commands {
+    replayed.extend(replay.expand(command).unwrap());
+  }
+
+  assert_eq!(replayed, steps);
+}
+
+#[test]
+fn test_replay_diverged() {
+  let (knowledge, commands) = knowledge(SOURCE);
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default()).with_log();
+  expander.expand(&commands[0]).unwrap();
+  let log: SessionLog = expander.take_log();
+
+  let mut replay: Expander = Expander::new(&knowledge, ExpansionLimits::default()).replaying(log.clone());
+  let err: ExpandError = replay.expand(&commands[1]).unwrap_err();
+  assert_eq!(err, ExpandError::Diverged { index: 0, expected: "greet 'Bob'".to_string(), found: "greet 'Alice'".to_string() });
+  assert_eq!(err.code(), "CCE0204");
+
+  // Running past the end of the log
+  let mut replay: Expander = Expander::new(&knowledge, ExpansionLimits::default()).replaying(log);
+  replay.expand(&commands[0]).unwrap();
+  assert!(matches!(replay.expand(&commands[0]), Err(ExpandError::Diverged { index: 3, .. })));
+}
+
+#[test]
+fn test_replay_parse_errors() {
+  assert!(SessionLog::parse("").is_err());
+  assert_eq!(SessionLog::parse("circe-session 1\n").unwrap(), SessionLog::new());
+  assert_eq!(
+    SessionLog::parse("circe-session 1\ncommand greet 'Bob'\n"),
+    Err(ReplayError::InvalidLog { line: 2, message: "expected a decision".to_string() })
+  );
+  assert_eq!(
+    SessionLog::parse("circe-session 1\ndecision oracle\n"),
+    Err(ReplayError::InvalidLog { line: 2, message: "unknown backend".to_string() })
+  );
+  assert!(SessionLog::parse("circe-session 1\ndecision matcher\nbind who 'a' 'b'\n").is_err());
+}
@@ -0,0 +1,99 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_infer::*;
+use cce_infer_ast::{CommandComponent, NormalizeOptions};
+use common::{howto, site};
+
+
+fn greet(who: &str) -> Vec<CommandComponent> {
+  vec![
+    CommandComponent::Keyword("greet".into()),
+    CommandComponent::Literal(who.to_string()),
+  ]
+}
+
+
+#[test]
+fn test_resolve_matches_lookup() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+  knowledge.insert(howto("howto greet 'Bob'?\n- hug\n\n"), site(1)).unwrap();
+
+  let mut resolver: Resolver = Resolver::new();
+
+  for who in ["Bob", "Alice"] {
+    assert_eq!(resolver.resolve(&knowledge, &greet(who)), knowledge.lookup(&greet(who)));
+  }
+}
+
+#[test]
+fn test_resolve_cache_stats() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+
+  let mut resolver: Resolver = Resolver::new();
+
+  resolver.resolve(&knowledge, &greet("Bob"));
+  resolver.resolve(&knowledge, &greet("Bob"));
+  resolver.resolve(&knowledge, &greet("Alice"));
+
+  assert_eq!(resolver.stats(), CacheStats { hits: 1, misses: 2, evictions: 0 });
+  assert_eq!(resolver.len(), 2);
+  assert_eq!(resolver.stats().lookups(), 3);
+}
+
+#[test]
+fn test_resolve_invalidated_by_revision() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(0)).unwrap();
+
+  let mut resolver: Resolver = Resolver::new();
+  assert_eq!(resolver.resolve(&knowledge, &greet("Bob")).len(), 1);
+
+  let revision: u64 = knowledge.revision();
+  knowledge.insert(howto("howto greet 'Bob'?\n- hug\n\n"), site(1)).unwrap();
+  assert!(knowledge.revision() > revision);
+
+  assert_eq!(resolver.resolve(&knowledge, &greet("Bob")).len(), 2);
+  assert_eq!(resolver.stats(), CacheStats { hits: 0, misses: 2, evictions: 1 });
+}
+
+#[test]
+fn test_resolve_normalized() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default().with_normalization(NormalizeOptions::default());
+  knowledge.insert(howto("howto Greet the %who?\n- wave\n\n"), site(0)).unwrap();
+
+  let command: Vec<CommandComponent> = vec![
+    CommandComponent::Keyword("greet".into()),
+    CommandComponent::Keyword("a".into()),
+    CommandComponent::Literal("  Bob ".to_string()),
+  ];
+
+  let mut resolver: Resolver = Resolver::new();
+  let found: Vec<(&Entry, Bindings)> = resolver.resolve(&knowledge, &command);
+
+  assert_eq!(found, knowledge.lookup(&command));
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].1.get("who"), Some(&CommandComponent::Literal("Bob".to_string())));
+}
@@ -0,0 +1,192 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use std::cell::Cell;
+
+use cce_infer::*;
+use cce_infer_ast::CommandComponent;
+use common::{command, howto, site};
+
+
+// Counts words per topic, with synonyms sharing a topic
+struct Topics {
+  calls: Cell<usize>,
+}
+
+impl Embedder for Topics {
+  fn embed(&self, text: &str) -> Result<Vec<f32>, EmbedError> {
+    self.calls.set(self.calls.get() + 1);
+
+    let mut vector: Vec<f32> = vec![0.0; 3];
+
+    for word in text.split(' ') {
+      match word {
+        "print" | "display" | "show" => vector[0] += 1.0,
+        "greet" | "welcome" => vector[1] += 1.0,
+        _ => vector[2] += 1.0,
+      }
+    }
+
+    Ok(vector)
+  }
+}
+
+fn topics() -> Topics {
+  Topics { calls: Cell::new(0) }
+}
+
+struct Offline;
+
+impl Embedder for Offline {
+  fn embed(&self, _text: &str) -> Result<Vec<f32>, EmbedError> {
+    Err(EmbedError::Unavailable("offline".to_string()))
+  }
+}
+
+fn knowledge() -> KnowledgeBase {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto print %text?\n- write %text\n\n"), site(0)).unwrap();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), site(1)).unwrap();
+  knowledge
+}
+
+
+#[test]
+fn test_semantic_cosine_similarity() {
+  assert_eq!(cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+  assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+  assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+  assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+}
+
+#[test]
+fn test_semantic_synonym() {
+  let knowledge: KnowledgeBase = knowledge();
+  let mut matcher: SemanticMatcher<Topics> = SemanticMatcher::new(topics());
+
+  let ranked: Vec<Ranked> = matcher.rank(&knowledge, &command("display 'hi'."));
+
+  assert_eq!(ranked.len(), 1);
+  assert_eq!(ranked[0].entry.site, site(0));
+  assert_eq!(ranked[0].bindings.get("text"), Some(&CommandComponent::Literal("hi".to_string())));
+  assert!(ranked[0].score > 0.99);
+}
+
+#[test]
+fn test_semantic_token_match_first() {
+  let knowledge: KnowledgeBase = knowledge();
+  let mut matcher: SemanticMatcher<Topics> = SemanticMatcher::new(topics()).with_threshold(0.0);
+
+  let ranked: Vec<Ranked> = matcher.rank(&knowledge, &command("greet 'Bob'."));
+
+  assert_eq!(ranked.iter().map(|ranked| ranked.entry.site).collect::<Vec<_>>(), vec![site(1), site(0)]);
+  assert_eq!(ranked[0].score, 1.0);
+  assert_eq!(ranked[1].score, 0.0);
+}
+
+#[test]
+fn test_semantic_slot_count() {
+  let knowledge: KnowledgeBase = knowledge();
+  let mut matcher: SemanticMatcher<Topics> = SemanticMatcher::new(topics());
+
+  assert_eq!(matcher.rank(&knowledge, &command("display 'hi' 'there'.")), vec![]);
+}
+
+#[test]
+fn test_semantic_slot_type() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto print %count:number?\n- write %count\n\n"), site(0)).unwrap();
+  let mut matcher: SemanticMatcher<Topics> = SemanticMatcher::new(topics());
+
+  // A literal cannot fill a number slot, however close the words are
+  assert_eq!(matcher.rank(&knowledge, &command("display 'hi'.")), vec![]);
+  assert_eq!(matcher.rank(&knowledge, &command("display 3."))[0].entry.site, site(0));
+}
+
+#[test]
+fn test_semantic_caches_signatures() {
+  let knowledge: KnowledgeBase = knowledge();
+  let mut matcher: SemanticMatcher<Topics> = SemanticMatcher::new(topics());
+
+  matcher.rank(&knowledge, &command("display 'hi'."));
+  matcher.rank(&knowledge, &command("show 'hi'."));
+
+  // Two commands and two signatures
+  assert_eq!(matcher.embedder().calls.get(), 4);
+}
+
+#[test]
+fn test_semantic_fallback() {
+  let knowledge: KnowledgeBase = knowledge();
+  let mut matcher: SemanticMatcher<Offline> = SemanticMatcher::new(Offline);
+
+  assert_eq!(matcher.rank(&knowledge, &command("display 'hi'.")), vec![]);
+
+  let ranked: Vec<Ranked> = matcher.rank(&knowledge, &command("print 'hi'."));
+  assert_eq!(ranked.len(), 1);
+  assert_eq!(ranked[0].score, 1.0);
+}
+
+#[cfg(feature = "embeddings-http")]
+#[test]
+fn test_semantic_http_embedder() {
+  use std::io::{Read, Write};
+  use std::net::TcpListener;
+
+  let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let address: String = listener.local_addr().unwrap().to_string();
+
+  let server = std::thread::spawn(move || {
+    let mut requests: Vec<String> = Vec::new();
+
+    for body in ["{\"embedding\": [0.5, 1.5]}", ""] {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut request: Vec<u8> = Vec::new();
+      let mut chunk: Vec<u8> = vec![0; 4096];
+
+      // The body is the last thing sent, and is a JSON object
+      while !request.ends_with(b"}") {
+        let read: usize = stream.read(&mut chunk).unwrap();
+        request.extend_from_slice(&chunk[..read]);
+      }
+      requests.push(String::from_utf8(request).unwrap());
+
+      let response: String = match body {
+        "" => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+        body => format!("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n", body.len(), body),
+      };
+      stream.write_all(response.as_bytes()).unwrap();
+    }
+
+    requests
+  });
+
+  let embedder: HttpEmbedder = HttpEmbedder::new(address, "nomic-embed-text");
+
+  assert_eq!(embedder.embed("print").unwrap(), vec![0.5, 1.5]);
+  assert!(matches!(embedder.embed("print"), Err(EmbedError::Unavailable(_))));
+
+  let requests: Vec<String> = server.join().unwrap();
+  assert!(requests[0].starts_with("POST /api/embeddings HTTP/1.1\r\n"));
+  assert!(requests[0].ends_with("{\"model\":\"nomic-embed-text\",\"prompt\":\"print\"}"));
+}
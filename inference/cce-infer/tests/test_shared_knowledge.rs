@@ -0,0 +1,152 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use std::panic;
+use std::sync::Arc;
+use std::thread;
+
+use cce_infer::*;
+use common::{command, howto, site};
+
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+
+#[test]
+fn test_shared_knowledge_is_send_and_sync() {
+  assert_send_sync::<KnowledgeBase>();
+  assert_send_sync::<SharedKnowledgeBase>();
+  assert_send_sync::<Arc<KnowledgeBase>>();
+}
+
+#[test]
+fn test_shared_knowledge_snapshots() {
+  let shared: SharedKnowledgeBase = SharedKnowledgeBase::default();
+  shared.update(|knowledge| knowledge.insert(howto("howto wave?\n- lift hand\n\n"), site(0))).unwrap();
+
+  let before: Arc<KnowledgeBase> = shared.snapshot();
+  shared.update(|knowledge| knowledge.insert(howto("howto nod?\n- lower head\n\n"), site(1))).unwrap();
+
+  // A snapshot is not affected by later updates
+  assert_eq!(before.entries().len(), 1);
+  assert!(before.lookup(&command("nod.")).is_empty());
+  assert_eq!(shared.snapshot().entries().len(), 2);
+  assert_eq!(shared.snapshot().lookup(&command("nod."))[0].0.site, site(1));
+  assert!(shared.revision() > before.revision());
+
+  shared.replace(KnowledgeBase::default());
+  assert!(shared.snapshot().entries().is_empty());
+}
+
+#[test]
+fn test_shared_knowledge_update_panics() {
+  let shared: SharedKnowledgeBase = SharedKnowledgeBase::default();
+  shared.update(|knowledge| knowledge.insert(howto("howto wave?\n- lift hand\n\n"), site(0))).unwrap();
+  let revision: u64 = shared.revision();
+
+  let result = panic::catch_unwind(|| {
+    shared.update(|knowledge| {
+      knowledge.insert(howto("howto nod?\n- lower head\n\n"), site(1)).unwrap();
+      panic!("update failed halfway");
+    })
+  });
+
+  // Nothing the update did before panicking is published
+  assert!(result.is_err());
+  assert_eq!(shared.revision(), revision);
+  assert!(shared.snapshot().lookup(&command("nod.")).is_empty());
+
+  shared.update(|knowledge| knowledge.insert(howto("howto bow?\n- lower head\n\n"), site(2))).unwrap();
+  assert_eq!(shared.snapshot().entries().len(), 2);
+}
+
+#[test]
+fn test_shared_knowledge_clones_diverge() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto wave?\n- lift hand\n\n"), site(0)).unwrap();
+
+  let mut other: KnowledgeBase = knowledge.clone();
+  assert_eq!(other.revision(), knowledge.revision());
+
+  knowledge.insert(howto("howto nod?\n- lower head\n\n"), site(1)).unwrap();
+  other.insert(howto("howto bow?\n- lower head\n\n"), site(1)).unwrap();
+
+  // Both changed from the same revision, but do not share the next one
+  assert_ne!(other.revision(), knowledge.revision());
+  assert_eq!(knowledge.entries().len(), 2);
+  assert_eq!(other.entries().len(), 2);
+
+  // So a resolver moved between them does not return stale matches
+  let mut resolver: Resolver = Resolver::new();
+  assert_eq!(resolver.resolve(&knowledge, &command("nod.")).len(), 1);
+  assert!(resolver.resolve(&other, &command("nod.")).is_empty());
+  assert_eq!(resolver.resolve(&other, &command("bow.")).len(), 1);
+}
+
+#[test]
+fn test_shared_knowledge_concurrent_reads_and_updates() {
+  let shared: SharedKnowledgeBase = SharedKnowledgeBase::default();
+
+  thread::scope(|scope| {
+    for _ in 0..4 {
+      let shared: SharedKnowledgeBase = shared.clone();
+
+      scope.spawn(move || {
+        let mut seen: usize = 0;
+
+        while seen < 50 {
+          let snapshot: Arc<KnowledgeBase> = shared.snapshot();
+          let count: usize = snapshot.entries().len();
+
+          // Entries only grow, and every one of them can be looked up
+          assert!(count >= seen);
+          for entry in snapshot.entries() {
+            assert_eq!(snapshot.lookup(entry.definition.signature())[0].0.site, entry.site);
+          }
+
+          seen = count;
+        }
+      });
+    }
+
+    for writer in 0..2 {
+      let shared: SharedKnowledgeBase = shared.clone();
+
+      scope.spawn(move || {
+        for i in 0..25 {
+          let node: usize = writer * 25 + i;
+          let source: String = format!("howto step {}?\n- wait\n\n", node);
+
+          shared.update(|knowledge| knowledge.insert(howto(&source), site(node))).unwrap();
+        }
+      });
+    }
+  });
+
+  let knowledge: Arc<KnowledgeBase> = shared.snapshot();
+  assert_eq!(knowledge.entries().len(), 50);
+
+  let mut nodes: Vec<usize> = knowledge.entries().iter().map(|entry| entry.site.node).collect();
+  nodes.sort();
+  assert_eq!(nodes, (0..50).collect::<Vec<usize>>());
+}
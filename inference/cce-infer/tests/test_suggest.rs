@@ -0,0 +1,74 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_ast::{DefinitionLocation, Suggestion};
+use cce_infer::*;
+use cce_infer_ast::CommandComponent;
+use common::{command, howto};
+
+
+fn knowledge() -> KnowledgeBase {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  knowledge.insert(howto("howto greet %who?\n- wave\n\n"), DefinitionLocation { file: 0, node: 0 }).unwrap();
+  knowledge.insert(howto("howto say goodbye to %who?\n- wave\n\n"), DefinitionLocation { file: 0, node: 1 }).unwrap();
+  knowledge
+}
+
+fn replacement(suggestion: Option<Suggestion>) -> Option<String> {
+  suggestion.map(|suggestion| suggestion.replacement)
+}
+
+
+#[test]
+fn test_suggest_misspelled_keyword() {
+  let knowledge: KnowledgeBase = knowledge();
+
+  let suggestion: Suggestion = knowledge.suggest(&command("grete 'Bob'.")).unwrap();
+  assert_eq!(suggestion.original, "grete");
+  assert_eq!(suggestion.replacement, "greet");
+  assert_eq!(suggestion.span, None);
+
+  assert_eq!(replacement(knowledge.suggest(&command("say godbye to 'Bob'."))), Some("goodbye".to_string()));
+}
+
+#[test]
+fn test_suggest_only_when_it_fixes_the_command() {
+  let knowledge: KnowledgeBase = knowledge();
+
+  // Already matches
+  assert_eq!(knowledge.suggest(&command("greet 'Bob'.")), None);
+  // `greet` is close, but `greet 'Bob' 'Ann'` still matches nothing
+  assert_eq!(knowledge.suggest(&command("gret 'Bob' 'Ann'.")), None);
+  // Nothing close
+  assert_eq!(knowledge.suggest(&command("dance 'Bob'.")), None);
+  assert_eq!(KnowledgeBase::default().suggest(&command("gret 'Bob'.")), None);
+}
+
+#[test]
+fn test_suggest_command_signatures() {
+  let signatures: Vec<Vec<CommandComponent>> = vec![command("turn left."), command("turn right.")];
+  let signatures: Vec<&[CommandComponent]> = signatures.iter().map(Vec::as_slice).collect();
+
+  assert_eq!(replacement(suggest_command(&signatures, &command("trun left."))), Some("turn".to_string()));
+  assert_eq!(replacement(suggest_command(&signatures, &command("turn rihgt."))), Some("right".to_string()));
+}
@@ -0,0 +1,97 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::{self as ast, DefinitionLocation};
+use cce_infer::*;
+use cce_infer_ast::{convert, CommandNode, ProgramNode};
+
+
+fn knowledge(source: &str) -> (KnowledgeBase, Vec<CommandNode>) {
+  let mut parser: ast::Parser = ast::Parser::from(source);
+  let mut nodes: Vec<ast::ParseNode> = Vec::new();
+  while let Some(node) = parser.next().unwrap() {
+    nodes.push(node);
+  }
+
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  let mut commands: Vec<CommandNode> = Vec::new();
+
+  for (node, program_node) in convert(nodes).into_iter().enumerate() {
+    let site: DefinitionLocation = DefinitionLocation { file: 0, node };
+
+    match program_node {
+      ProgramNode::HowTo(howto) => { knowledge.insert(howto, site).unwrap(); }
+      ProgramNode::WhatIs(whatis) => { knowledge.insert(whatis, site).unwrap(); }
+      ProgramNode::Command(command) => commands.push(command),
+      ProgramNode::Module(_) => {}
+    }
+  }
+
+  (knowledge, commands)
+}
+
+
+#[test]
+fn test_trace_verdicts() {
+  let (knowledge, commands) = knowledge(
+    "howto greet %who?\n- wave\n\nhowto greet %who:number?\n- nod\n\nhowto wave at %who?\n- wave\n\nhowto greet %someone?\n- bow\n\ngreet 'Bob'.",
+  );
+
+  let trace: ResolutionTrace = knowledge.trace(&commands[0].command);
+  let verdicts: Vec<&Verdict> = trace.candidates.iter().map(|candidate| &candidate.verdict).collect();
+
+  assert!(matches!(verdicts[0], Verdict::Chosen));
+  assert!(matches!(verdicts[1], Verdict::Rejected(Mismatch::SlotType { .. })));
+  assert_eq!(verdicts[2], &Verdict::Rejected(Mismatch::Length { expected: 3, found: 2 }));
+  assert_eq!(verdicts[3], &Verdict::Shadowed { by: DefinitionLocation { file: 0, node: 0 } });
+
+  let chosen: &Candidate = trace.chosen().unwrap();
+  assert_eq!(chosen.score, 1);
+  assert_eq!(chosen.bindings["who"].to_string(), "'Bob'");
+  assert_eq!(trace.matched().count(), 2);
+}
+
+#[test]
+fn test_trace_rendering() {
+  let (knowledge, commands) = knowledge("howto greet %who?\n- wave\n\nhowto wave at %who?\n- wave\n\ngreet 'Bob'.");
+
+  assert_eq!(
+    knowledge.trace(&commands[0].command).to_string(),
+    "greet 'Bob'\n  [chosen] greet %who (statement 1 of file 1, score 1) with who = 'Bob'\n  [rejected: expected 3 components, found 2] wave at %who (statement 2 of file 1, score 2)\n",
+  );
+}
+
+#[test]
+fn test_expander_records_nested_traces() {
+  let (knowledge, commands) = knowledge("howto greet %who?\n- wave at %who\n\ngreet 'Bob'.");
+
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default()).with_trace();
+  expander.expand(&commands[0]).unwrap();
+
+  let traces: Vec<String> = expander.traces().iter().map(|trace| trace.command.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")).collect();
+  assert_eq!(traces, vec!["greet 'Bob'", "wave at 'Bob'"]);
+  assert!(expander.traces()[1].chosen().is_none());
+
+  // Off unless asked for
+  let mut expander: Expander = Expander::new(&knowledge, ExpansionLimits::default());
+  expander.expand(&commands[0]).unwrap();
+  assert!(expander.traces().is_empty());
+}
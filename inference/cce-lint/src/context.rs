@@ -0,0 +1,117 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_infer::KnowledgeBase;
+use cce_infer_ast::{CommandComponent, CommandNode, HowToCommand, ProgramNode, Visit, WhatIsCommand};
+use crate::diagnostic::Location;
+
+pub struct LintContext<'a> {
+  files: &'a [Vec<ProgramNode>],
+  knowledge: Option<&'a KnowledgeBase>,
+}
+
+impl<'a> LintContext<'a> {
+  pub fn new(files: &'a [Vec<ProgramNode>]) -> Self {
+    Self { files, knowledge: None }
+  }
+
+  // Lets lints see definitions from outside the program, such as `std`
+  pub fn with_knowledge(self, knowledge: &'a KnowledgeBase) -> Self {
+    Self { knowledge: Some(knowledge), ..self }
+  }
+
+  pub fn knowledge(&self) -> Option<&'a KnowledgeBase> {
+    self.knowledge
+  }
+
+  // Signatures commands can match: the knowledge base's if there is one,
+  // otherwise those the program defines
+  pub fn signatures(&self) -> Vec<&'a [CommandComponent]> {
+    if let Some(knowledge) = self.knowledge {
+      return knowledge.visible().map(|entry| entry.definition.signature()).collect();
+    }
+
+    self.nodes()
+      .filter_map(|(_, node)| match node {
+        ProgramNode::HowTo(howto) => Some(howto.signature.as_slice()),
+        ProgramNode::WhatIs(whatis) => Some(whatis.signature.as_slice()),
+        ProgramNode::Command(_) | ProgramNode::Module(_) => None,
+      })
+      .collect()
+  }
+
+  // Modules come before their members, which have locations of their own
+  pub fn nodes(&self) -> impl Iterator<Item = (Location, &'a ProgramNode)> {
+    self.files.iter().enumerate().flat_map(|(file, nodes)| {
+      nodes.iter().flat_map(ProgramNode::flatten).enumerate().map(move |(node, program_node)| (Location { file, node }, program_node))
+    })
+  }
+
+  // Every command in the program, including howto steps and whatis alternatives
+  pub fn commands(&self) -> Vec<(Location, &'a CommandNode)> {
+    let mut commands: Vec<(Location, &'a CommandNode)> = Vec::new();
+
+    for (location, node) in self.nodes() {
+      match node {
+        ProgramNode::Command(command) => commands.push((location, command)),
+        ProgramNode::HowTo(howto) => collect_howto_commands(location, &howto.body, &mut commands),
+        ProgramNode::WhatIs(whatis) => {
+          for alternative in &whatis.alternatives {
+            if let WhatIsCommand::Command(command) = &alternative.command {
+              commands.push((location, command));
+            }
+          }
+        }
+        ProgramNode::Module(_) => {}
+      }
+    }
+
+    commands
+  }
+}
+
+pub(crate) fn collect_howto_commands<'a>(location: Location, body: &'a [HowToCommand], commands: &mut Vec<(Location, &'a CommandNode)>) {
+  struct Steps<'a, 'c> {
+    location: Location,
+    commands: &'c mut Vec<(Location, &'a CommandNode)>,
+  }
+
+  impl<'a> Visit<'a> for Steps<'a, '_> {
+    fn visit_command(&mut self, command: &'a CommandNode) {
+      self.commands.push((self.location, command));
+    }
+  }
+
+  let mut steps = Steps { location, commands };
+
+  for step in body {
+    steps.visit_howto_command(step);
+  }
+}
+
+// Renders a signature or command back into Circe source for messages
+pub fn render(components: &[CommandComponent]) -> String {
+  components
+    .iter()
+    .map(CommandComponent::to_string)
+    .collect::<Vec<String>>()
+    .join(" ")
+}
@@ -0,0 +1,59 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::fmt;
+
+use cce_ast::Suggestion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Severity::Warning => write!(f, "Warning"),
+      Severity::Error => write!(f, "Error"),
+    }
+  }
+}
+
+// A node in a linted program, by file and statement index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+  pub file: usize,
+  pub node: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub lint: &'static str,
+  // Stable code for `ccec explain`, if the lint has one
+  pub code: Option<&'static str>,
+  pub severity: Severity,
+  pub message: String,
+  pub location: Location,
+  pub related: Vec<Location>,
+  // Fixes tooling can apply for the user
+  pub suggestions: Vec<Suggestion>,
+}
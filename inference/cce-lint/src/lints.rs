@@ -0,0 +1,313 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use cce_ast::{closest, Suggestion, Symbol};
+use cce_infer::{match_signature, suggest_command};
+use cce_infer_ast::{CommandComponent, CommandNode, Modifier, ProgramNode, WhatIsCommand};
+use crate::context::{collect_howto_commands, render, LintContext};
+use crate::diagnostic::{Diagnostic, Location, Severity};
+use crate::registry::Lint;
+
+// A howto that no command in the program matches
+pub struct UnusedHowTo;
+
+impl Lint for UnusedHowTo {
+  fn name(&self) -> &'static str {
+    "unused_howto"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0300")
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    let commands: Vec<(Location, &CommandNode)> = cx.commands();
+
+    for (location, node) in cx.nodes() {
+      if let ProgramNode::HowTo(howto) = node {
+        let used: bool = commands
+          .iter()
+          .any(|(_, command)| match_signature(&howto.signature, &command.command).is_some());
+
+        if !used {
+          diagnostics.push(Diagnostic {
+            lint: self.name(),
+            code: self.code(),
+            severity: self.severity(),
+            message: format!("howto '{}' is never used", render(&howto.signature)),
+            location,
+            related: Vec::new(),
+            suggestions: Vec::new(),
+          });
+        }
+      }
+    }
+  }
+}
+
+// A slot or back-reference used in a howto or whatis body that its signature
+// never binds. Caught here, it would otherwise only show up as a command
+// failing to resolve once the definition is used.
+pub struct UnboundSlot;
+
+impl Lint for UnboundSlot {
+  fn name(&self) -> &'static str {
+    "unbound_slot"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0301")
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    for (location, node) in cx.nodes() {
+      let (kind, signature, steps) = match node {
+        ProgramNode::HowTo(howto) => {
+          let mut steps: Vec<(Location, &CommandNode)> = Vec::new();
+          collect_howto_commands(location, &howto.body, &mut steps);
+          ("howto", &howto.signature, steps)
+        }
+        ProgramNode::WhatIs(whatis) => {
+          let steps: Vec<(Location, &CommandNode)> = whatis
+            .alternatives
+            .iter()
+            .filter_map(|alternative| match &alternative.command {
+              WhatIsCommand::Command(command) => Some((location, command)),
+              WhatIsCommand::Final(_) => None,
+            })
+            .collect();
+          ("whatis", &whatis.signature, steps)
+        }
+        ProgramNode::Command(_) | ProgramNode::Module(_) => continue,
+      };
+
+      let bound: Vec<&Symbol> = slot_names(signature);
+      let mut reported: Vec<(&str, &Symbol)> = Vec::new();
+
+      for (_, step) in steps {
+        let mut used: Vec<&CommandComponent> = references(&step.command);
+
+        for modifier in &step.modifiers {
+          match modifier {
+            Modifier::Named { args, .. } => used.extend(references(args)),
+            Modifier::FreeForm(components) => used.extend(references(components)),
+          }
+        }
+
+        for reference in used {
+          let (sigil, name, what) = match reference {
+            CommandComponent::Slot { name, .. } => ('%', name, "slot"),
+            CommandComponent::BackRef(name) => ('&', name, "back-reference"),
+            _ => continue,
+          };
+
+          if bound.contains(&name) || reported.contains(&(what, name)) {
+            continue;
+          }
+
+          reported.push((what, name));
+
+          let original: String = format!("{}{}", sigil, name);
+          let suggestion: Option<Suggestion> = closest(name, bound.iter().map(|name| name.as_str())).map(|replacement| Suggestion {
+            replacement: format!("{}{}", sigil, replacement),
+            original: original.clone(),
+            span: None,
+          });
+
+          let mut message: String = format!("{} '{}' in '{}' is not bound by {} '{}'", what, original, render(&step.command), kind, render(signature));
+
+          if let Some(suggestion) = &suggestion {
+            message = format!("{}, {}", message, suggestion);
+          }
+
+          diagnostics.push(Diagnostic {
+            lint: self.name(),
+            code: self.code(),
+            severity: self.severity(),
+            message,
+            location,
+            related: Vec::new(),
+            suggestions: suggestion.into_iter().collect(),
+          });
+        }
+      }
+    }
+  }
+}
+
+fn slot_names(components: &[CommandComponent]) -> Vec<&Symbol> {
+  components
+    .iter()
+    .filter_map(|component| match component {
+      CommandComponent::Slot { name, .. } => Some(name),
+      _ => None,
+    })
+    .collect()
+}
+
+// The slots and back-references among `components`
+fn references(components: &[CommandComponent]) -> Vec<&CommandComponent> {
+  components
+    .iter()
+    .filter(|component| matches!(component, CommandComponent::Slot { .. } | CommandComponent::BackRef(_)))
+    .collect()
+}
+
+// Two howto statements defined with the same signature
+pub struct DuplicateSignature;
+
+impl Lint for DuplicateSignature {
+  fn name(&self) -> &'static str {
+    "duplicate_signature"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0302")
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen: Vec<(&[CommandComponent], Location)> = Vec::new();
+
+    for (location, node) in cx.nodes() {
+      if let ProgramNode::HowTo(howto) = node {
+        match seen.iter().find(|(signature, _)| *signature == howto.signature.as_slice()) {
+          Some((_, first)) => diagnostics.push(Diagnostic {
+            lint: self.name(),
+            code: self.code(),
+            severity: self.severity(),
+            message: format!("howto '{}' is defined more than once", render(&howto.signature)),
+            location,
+            related: vec![*first],
+            suggestions: Vec::new(),
+          }),
+          None => seen.push((&howto.signature, location)),
+        }
+      }
+    }
+  }
+}
+
+// A command made only of literals, which no howto can describe
+pub struct LiteralOnlyCommand;
+
+impl Lint for LiteralOnlyCommand {
+  fn name(&self) -> &'static str {
+    "literal_only_command"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0303")
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    for (location, command) in cx.commands() {
+      let literal_only: bool = !command.command.is_empty() && command
+        .command
+        .iter()
+        .all(|component| matches!(component, CommandComponent::Literal(_)));
+
+      if literal_only {
+        diagnostics.push(Diagnostic {
+          lint: self.name(),
+          code: self.code(),
+          severity: self.severity(),
+          message: format!("command {} has no keywords", render(&command.command)),
+          location,
+          related: Vec::new(),
+          suggestions: Vec::new(),
+        });
+      }
+    }
+  }
+}
+
+// A whatis replaced by a later whatis with the same signature
+pub struct ShadowedWhatIs;
+
+impl Lint for ShadowedWhatIs {
+  fn name(&self) -> &'static str {
+    "shadowed_whatis"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0304")
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    let mut last: Vec<(&[CommandComponent], Location)> = Vec::new();
+
+    for (location, node) in cx.nodes() {
+      if let ProgramNode::WhatIs(whatis) = node {
+        match last.iter_mut().find(|(signature, _)| *signature == whatis.signature.as_slice()) {
+          Some((_, shadowed)) => {
+            diagnostics.push(Diagnostic {
+              lint: self.name(),
+              code: self.code(),
+              severity: self.severity(),
+              message: format!("whatis '{}' is shadowed by a later definition", render(&whatis.signature)),
+              location: *shadowed,
+              related: vec![location],
+              suggestions: Vec::new(),
+            });
+
+            *shadowed = location;
+          }
+          None => last.push((&whatis.signature, location)),
+        }
+      }
+    }
+  }
+}
+
+// A command that matches no definition but would with one keyword fixed
+pub struct MisspelledCommand;
+
+impl Lint for MisspelledCommand {
+  fn name(&self) -> &'static str {
+    "misspelled_command"
+  }
+
+  fn code(&self) -> Option<&'static str> {
+    Some("CCE0305")
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+    let signatures: Vec<&[CommandComponent]> = cx.signatures();
+
+    for (location, command) in cx.commands() {
+      if let Some(suggestion) = suggest_command(&signatures, &command.command) {
+        diagnostics.push(Diagnostic {
+          lint: self.name(),
+          code: self.code(),
+          severity: self.severity(),
+          message: format!("command '{}' matches no definition, {}", render(&command.command), suggestion),
+          location,
+          related: Vec::new(),
+          suggestions: vec![suggestion],
+        });
+      }
+    }
+  }
+}
@@ -0,0 +1,121 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+use std::collections::HashMap;
+
+use crate::context::LintContext;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::lints::{DuplicateSignature, LiteralOnlyCommand, MisspelledCommand, ShadowedWhatIs, UnboundSlot, UnusedHowTo};
+
+pub trait Lint {
+  fn name(&self) -> &'static str;
+
+  fn code(&self) -> Option<&'static str> {
+    None
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Warning
+  }
+
+  fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>);
+}
+
+// How a project treats one lint, overriding the lint's own severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+  Allow,
+  Warn,
+  Deny,
+}
+
+pub struct LintRegistry {
+  lints: Vec<Box<dyn Lint>>,
+  levels: HashMap<String, LintLevel>,
+}
+
+impl Default for LintRegistry {
+  fn default() -> Self {
+    let mut registry: LintRegistry = Self::new();
+
+    registry.register(Box::new(UnusedHowTo));
+    registry.register(Box::new(UnboundSlot));
+    registry.register(Box::new(DuplicateSignature));
+    registry.register(Box::new(LiteralOnlyCommand));
+    registry.register(Box::new(ShadowedWhatIs));
+    registry.register(Box::new(MisspelledCommand));
+
+    registry
+  }
+}
+
+impl LintRegistry {
+  pub fn new() -> Self {
+    Self {
+      lints: Vec::new(),
+      levels: HashMap::new()
+    }
+  }
+
+  pub fn register(&mut self, lint: Box<dyn Lint>) {
+    self.lints.push(lint);
+  }
+
+  pub fn names(&self) -> Vec<&'static str> {
+    self.lints.iter().map(|lint| lint.name()).collect()
+  }
+
+  // Returns false, and changes nothing, if no lint has the name
+  pub fn set_level(&mut self, name: &str, level: LintLevel) -> bool {
+    if !self.names().contains(&name) {
+      return false;
+    }
+
+    self.levels.insert(name.to_string(), level);
+    true
+  }
+
+  pub fn run(&self, cx: &LintContext) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for lint in &self.lints {
+      let first: usize = diagnostics.len();
+      lint.check(cx, &mut diagnostics);
+
+      let severity: Severity = match self.levels.get(lint.name()) {
+        Some(LintLevel::Allow) => {
+          diagnostics.truncate(first);
+          continue;
+        }
+        Some(LintLevel::Warn) => Severity::Warning,
+        Some(LintLevel::Deny) => Severity::Error,
+        None => continue,
+      };
+
+      for diagnostic in &mut diagnostics[first..] {
+        diagnostic.severity = severity;
+      }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location);
+    diagnostics
+  }
+}
@@ -0,0 +1,213 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+
+mod common;
+
+use cce_ast as ast;
+use cce_infer::KnowledgeBase;
+use cce_infer_ast::ProgramNode;
+use cce_lint::*;
+use common::parse;
+
+
+fn lint(sources: &[&str]) -> Vec<Diagnostic> {
+  let files: Vec<Vec<ProgramNode>> = sources.iter().map(|source| parse(source)).collect();
+
+  LintRegistry::default().run(&LintContext::new(&files))
+}
+
+fn lints(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+  diagnostics.iter().map(|diagnostic| diagnostic.lint).collect()
+}
+
+
+#[test]
+fn test_lint_clean() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- say hello to %who\n\ngreet 'Bob'."]);
+
+  assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn test_lint_unused_howto() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- say hello to %who\n\nwave."]);
+
+  assert_eq!(lints(&diagnostics), vec!["unused_howto"]);
+  assert_eq!(diagnostics[0].location, Location { file: 0, node: 0 });
+  assert_eq!(diagnostics[0].message, "howto 'greet %who' is never used");
+}
+
+#[test]
+fn test_lint_unused_howto_across_files() {
+  let diagnostics: Vec<Diagnostic> = lint(&[
+    "howto greet %who?\n- say hello to %who\n\n",
+    "greet 'Bob'.",
+  ]);
+
+  assert_eq!(diagnostics, vec![]);
+}
+
+#[test]
+fn test_lint_unbound_slot() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- say hello to %them\n\ngreet 'Bob'."]);
+
+  assert_eq!(lints(&diagnostics), vec!["unbound_slot"]);
+  assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn test_lint_unbound_slot_suggestion() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %person?\n- say hello to %persn\n- wave at &persn\n\ngreet 'Bob'."]);
+
+  assert_eq!(lints(&diagnostics), vec!["unbound_slot", "unbound_slot"]);
+  assert_eq!(diagnostics[0].message, "slot '%persn' in 'say hello to %persn' is not bound by howto 'greet %person', did you mean '%person'?");
+  assert_eq!(diagnostics[1].suggestions[0].original, "&persn");
+  assert_eq!(diagnostics[1].suggestions[0].replacement, "&person");
+}
+
+#[test]
+fn test_lint_unbound_slot_nested() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- when %who is here:\n  - say hello to %them\n  - wave:\n    - smile at %they\n\ngreet 'Bob'."]);
+
+  assert_eq!(lints(&diagnostics), vec!["unbound_slot", "unbound_slot"]);
+  assert!(diagnostics[0].message.starts_with("slot '%them'"));
+  assert!(diagnostics[1].message.starts_with("slot '%they'"));
+}
+
+#[test]
+fn test_lint_unbound_slot_whatis() {
+  let diagnostics: Vec<Diagnostic> = lint(&[
+    "whatis greet %who?\n- say hello to %them\n- $$echo $who$$\n\n",
+    "whatis say hello to %x?\n- $$echo hi$$\n\ngreet 'Bob'.",
+  ]);
+
+  assert_eq!(lints(&diagnostics), vec!["unbound_slot"]);
+  assert!(diagnostics[0].suggestions.is_empty());
+}
+
+#[test]
+fn test_lint_duplicate_signature() {
+  let diagnostics: Vec<Diagnostic> = lint(&[
+    "howto greet %who?\n- say hello to %who\n\ngreet 'Bob'.",
+    "howto greet %who?\n- wave at %who\n\n",
+  ]);
+
+  assert_eq!(lints(&diagnostics), vec!["duplicate_signature"]);
+  assert_eq!(diagnostics[0].location, Location { file: 1, node: 0 });
+  assert_eq!(diagnostics[0].related, vec![Location { file: 0, node: 0 }]);
+}
+
+#[test]
+fn test_lint_literal_only_command() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- 'hello'\n\ngreet 'Bob'."]);
+
+  assert_eq!(lints(&diagnostics), vec!["literal_only_command"]);
+}
+
+#[test]
+fn test_lint_shadowed_whatis() {
+  let diagnostics: Vec<Diagnostic> = lint(&["whatis stdout?\n- the console output\n\nwhatis stdout?\n- file descriptor '1'\n\n"]);
+
+  assert_eq!(lints(&diagnostics), vec!["shadowed_whatis"]);
+  assert_eq!(diagnostics[0].location, Location { file: 0, node: 0 });
+  assert_eq!(diagnostics[0].related, vec![Location { file: 0, node: 1 }]);
+}
+
+#[test]
+fn test_lint_misspelled_command() {
+  let diagnostics: Vec<Diagnostic> = lint(&["howto greet %who?\n- say hello to %who\n\ngreet 'Bob'.\ngret 'Ann'.\n"]);
+
+  assert_eq!(lints(&diagnostics), vec!["misspelled_command"]);
+  assert_eq!(diagnostics[0].message, "command 'gret 'Ann'' matches no definition, did you mean 'greet'?");
+  assert_eq!(diagnostics[0].suggestions, vec![ast::Suggestion {
+    original: "gret".to_string(),
+    replacement: "greet".to_string(),
+    span: None,
+  }]);
+}
+
+#[test]
+fn test_lint_misspelled_command_knowledge() {
+  let mut knowledge: KnowledgeBase = KnowledgeBase::default();
+  let std: Vec<ProgramNode> = parse("howto print %text?\n- write %text\n");
+
+  if let ProgramNode::HowTo(howto) = &std[0] {
+    knowledge.insert(howto.clone(), ast::DefinitionLocation { file: 1, node: 0 }).unwrap();
+  }
+
+  let files: Vec<Vec<ProgramNode>> = vec![parse("pritn 'hi'.\nprint 'hi'.\n")];
+  let diagnostics: Vec<Diagnostic> = LintRegistry::default().run(&LintContext::new(&files).with_knowledge(&knowledge));
+
+  assert_eq!(lints(&diagnostics), vec!["misspelled_command"]);
+  assert_eq!(diagnostics[0].suggestions[0].replacement, "print");
+
+  // Without the knowledge base nothing defines `print`
+  assert_eq!(lint(&["pritn 'hi'.\n"]), vec![]);
+}
+
+#[test]
+fn test_lint_registry_custom() {
+  struct NoCommands;
+
+  impl Lint for NoCommands {
+    fn name(&self) -> &'static str {
+      "no_commands"
+    }
+
+    fn check(&self, cx: &LintContext, diagnostics: &mut Vec<Diagnostic>) {
+      for (location, _) in cx.commands() {
+        diagnostics.push(Diagnostic {
+          lint: self.name(),
+          code: self.code(),
+          severity: self.severity(),
+          message: "commands are not allowed".to_string(),
+          location,
+          related: Vec::new(),
+          suggestions: Vec::new(),
+        });
+      }
+    }
+  }
+
+  let mut registry: LintRegistry = LintRegistry::new();
+  registry.register(Box::new(NoCommands));
+
+  let files: Vec<Vec<ProgramNode>> = vec![parse("wave.")];
+  let diagnostics: Vec<Diagnostic> = registry.run(&LintContext::new(&files));
+
+  assert_eq!(registry.names(), vec!["no_commands"]);
+  assert_eq!(lints(&diagnostics), vec!["no_commands"]);
+}
+
+#[test]
+fn test_lint_registry_levels() {
+  let files: Vec<Vec<ProgramNode>> = vec![parse("howto greet %who?\n- 'hello'\n\n")];
+  let mut registry: LintRegistry = LintRegistry::default();
+
+  assert!(!registry.set_level("no_such_lint", LintLevel::Deny));
+  assert!(registry.set_level("unused_howto", LintLevel::Deny));
+  assert!(registry.set_level("literal_only_command", LintLevel::Allow));
+
+  let diagnostics: Vec<Diagnostic> = registry.run(&LintContext::new(&files));
+
+  assert_eq!(lints(&diagnostics), vec!["unused_howto"]);
+  assert_eq!(diagnostics[0].severity, Severity::Error);
+}
@@ -20,7 +20,13 @@ Circe. If not, see <https://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
 
+mod package;
+mod store;
+pub use package::{InstalledPackage, Manifest, Package, PackageError, BUNDLE_EXTENSION, MANIFEST_NAME};
+pub use store::{Store, StoreError};
 
+
+#[allow(dead_code)]
 pub struct Database {
     entries: Vec<(String, String)>,
     lookup: HashMap<String, Vec<u64>>
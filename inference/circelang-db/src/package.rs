@@ -0,0 +1,392 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use cce_ast::{DefinitionLocation, Parser, ParserError};
+use cce_infer::{Conflict, Definition, KnowledgeBase, KnowledgeBaseError};
+use cce_infer_ast::{convert, ProgramNode};
+use circelang_hash::Digest;
+use crate::store::{Store, StoreError};
+use serde::{Deserialize, Serialize};
+
+use thiserror::Error;
+
+
+pub const MANIFEST_NAME: &str = "circe.package";
+pub const BUNDLE_EXTENSION: &str = "cpkg";
+
+const BUNDLE_HEADER: &str = "circe-bundle 1";
+
+
+#[derive(Error, Debug)]
+pub enum PackageError {
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    ParserError(#[from] ParserError),
+    #[error("{0}")]
+    StoreError(#[from] StoreError),
+    #[error("{0}")]
+    KnowledgeBaseError(#[from] KnowledgeBaseError),
+    #[error("{0}")]
+    TomlError(#[from] toml::de::Error),
+    #[error("Invalid package name: {0}")]
+    InvalidName(String),
+    #[error("Invalid package path: {0}")]
+    InvalidPath(String),
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+    #[error("Package {0} is not installed")]
+    NotInstalled(String),
+}
+
+// `circe.package` is TOML:
+//
+//   name = "greetings"
+//   version = "0.1.0"
+//   files = ["greet.cce", "io/stdout.cce"]
+//
+// `files` defaults to every .cce file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+impl Manifest {
+    pub fn parse(source: &str) -> Result<Manifest, PackageError> {
+        let manifest: Manifest = toml::from_str(source)?;
+
+        validate_name(&manifest.name)?;
+
+        for file in &manifest.files {
+            validate_path(file)?;
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&toml::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+// The name doubles as the namespace of the package's definitions
+fn validate_name(name: &str) -> Result<(), PackageError> {
+    let valid: bool = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(PackageError::InvalidName(name.to_string()))
+    }
+}
+
+// Package files must stay inside the package when unpacked
+fn validate_path(path: &str) -> Result<(), PackageError> {
+    let valid: bool = !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(PackageError::InvalidPath(path.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub manifest: Manifest,
+    // Contents of each file in `manifest.files`, in order
+    pub sources: Vec<String>,
+}
+
+impl Package {
+    pub fn read_dir(dir: &Path) -> Result<Package, PackageError> {
+        let mut manifest: Manifest = Manifest::parse(&fs::read_to_string(dir.join(MANIFEST_NAME))?)?;
+
+        if manifest.files.is_empty() {
+            for entry in fs::read_dir(dir)? {
+                let path: PathBuf = entry?.path();
+
+                if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("cce") {
+                    if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                        manifest.files.push(name.to_string());
+                    }
+                }
+            }
+
+            manifest.files.sort();
+        }
+
+        let sources: Vec<String> = manifest
+            .files
+            .iter()
+            .map(|file| fs::read_to_string(dir.join(file)))
+            .collect::<Result<Vec<String>, std::io::Error>>()?;
+
+        Ok(Package { manifest, sources })
+    }
+
+    pub fn write_dir(&self, dir: &Path) -> Result<(), PackageError> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(MANIFEST_NAME), self.manifest.to_string())?;
+
+        for (file, source) in self.manifest.files.iter().zip(&self.sources) {
+            let path: PathBuf = dir.join(file);
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(path, source)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn bundle_name(&self) -> String {
+        format!("{}-{}.{}", self.manifest.name, self.manifest.version, BUNDLE_EXTENSION)
+    }
+
+    // A bundle is a header line followed by the manifest and every file, each
+    // written as `<path> <byte length>` on its own line and then the contents
+    pub fn pack(&self) -> String {
+        let mut bundle: String = format!("{}\n", BUNDLE_HEADER);
+        let manifest: String = self.manifest.to_string();
+
+        let entries = std::iter::once((MANIFEST_NAME, &manifest))
+            .chain(self.manifest.files.iter().map(String::as_str).zip(&self.sources));
+
+        for (path, contents) in entries {
+            bundle.push_str(&format!("{} {}\n", path, contents.len()));
+            bundle.push_str(contents);
+            bundle.push('\n');
+        }
+
+        bundle
+    }
+
+    pub fn unpack(bundle: &str) -> Result<Package, PackageError> {
+        let invalid = |message: &str| PackageError::InvalidBundle(message.to_string());
+
+        let mut rest: &str = bundle
+            .strip_prefix(BUNDLE_HEADER)
+            .and_then(|rest| rest.strip_prefix('\n'))
+            .ok_or_else(|| invalid("missing bundle header"))?;
+
+        let mut entries: Vec<(String, String)> = Vec::new();
+
+        while !rest.is_empty() {
+            let (header, after) = rest.split_once('\n').ok_or_else(|| invalid("truncated entry header"))?;
+            let (path, len) = header.rsplit_once(' ').ok_or_else(|| invalid("malformed entry header"))?;
+            let len: usize = len.parse().map_err(|_| invalid("malformed entry length"))?;
+
+            let contents: &str = after.get(..len).ok_or_else(|| invalid("truncated entry"))?;
+            rest = after[len..].strip_prefix('\n').ok_or_else(|| invalid("malformed entry terminator"))?;
+
+            entries.push((path.to_string(), contents.to_string()));
+        }
+
+        let mut entries = entries.into_iter();
+
+        let manifest: Manifest = match entries.next() {
+            Some((path, contents)) if path == MANIFEST_NAME => Manifest::parse(&contents)?,
+            _ => return Err(invalid("the manifest must come first")),
+        };
+
+        if entries.len() != manifest.files.len() {
+            return Err(invalid("files do not match the manifest"));
+        }
+
+        let sources: Vec<String> = entries
+            .zip(&manifest.files)
+            .map(|((path, contents), file)| {
+                if path == *file {
+                    Ok(contents)
+                } else {
+                    Err(invalid(&format!("expected {}, found {}", file, path)))
+                }
+            })
+            .collect::<Result<Vec<String>, PackageError>>()?;
+
+        Ok(Package { manifest, sources })
+    }
+
+    // Every howto and whatis in the package, located by file and node index.
+    // Definitions inside modules are included; they are all installed under
+    // the package's namespace.
+    pub fn definitions(&self) -> Result<Vec<(DefinitionLocation, Definition)>, PackageError> {
+        let mut definitions: Vec<(DefinitionLocation, Definition)> = Vec::new();
+
+        for (file, source) in self.sources.iter().enumerate() {
+            let mut parser = Parser::from(source.as_str());
+            let mut nodes = Vec::new();
+
+            while let Some(node) = parser.next()? {
+                nodes.push(node);
+            }
+
+            let program: Vec<ProgramNode> = convert(nodes);
+
+            for (node, program_node) in program.iter().flat_map(ProgramNode::flatten).enumerate() {
+                let definition: Definition = match program_node {
+                    ProgramNode::HowTo(howto) => Definition::HowTo(howto.clone()),
+                    ProgramNode::WhatIs(whatis) => Definition::WhatIs(whatis.clone()),
+                    ProgramNode::Command(_) | ProgramNode::Module(_) => continue,
+                };
+
+                definitions.push((DefinitionLocation { file, node }, definition));
+            }
+        }
+
+        Ok(definitions)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    pub definitions: Vec<(DefinitionLocation, Digest)>,
+}
+
+impl Store {
+    fn package_index(&self, name: &str) -> PathBuf {
+        self.root().join("packages").join(format!("{}.index", name))
+    }
+
+    // Saves the package's definitions in the store and records which ones
+    // belong to it; installing a package again replaces its index
+    pub fn install(&self, package: &Package) -> Result<InstalledPackage, PackageError> {
+        let mut installed: InstalledPackage = InstalledPackage {
+            name: package.manifest.name.clone(),
+            version: package.manifest.version.clone(),
+            definitions: Vec::new(),
+        };
+
+        for (site, definition) in package.definitions()? {
+            installed.definitions.push((site, self.put(definition)?));
+        }
+
+        let mut index: String = format!("version = {}\n", installed.version);
+
+        for (site, digest) in &installed.definitions {
+            index.push_str(&format!("{} {} {}\n", site.file, site.node, digest));
+        }
+
+        let path: PathBuf = self.package_index(&installed.name);
+        fs::create_dir_all(path.parent().unwrap_or(self.root()))?;
+        fs::write(path, index)?;
+
+        Ok(installed)
+    }
+
+    pub fn installed(&self, name: &str) -> Result<InstalledPackage, PackageError> {
+        validate_name(name)?;
+
+        let index: String = match fs::read_to_string(self.package_index(name)) {
+            Ok(index) => index,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(PackageError::NotInstalled(name.to_string()))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let corrupted = || PackageError::InvalidBundle(format!("corrupted index for package {}", name));
+
+        let mut lines = index.lines();
+        let version: String = lines
+            .next()
+            .and_then(|line| line.strip_prefix("version = "))
+            .ok_or_else(corrupted)?
+            .to_string();
+
+        let mut definitions: Vec<(DefinitionLocation, Digest)> = Vec::new();
+
+        for line in lines {
+            let fields: Vec<&str> = line.split(' ').collect();
+
+            let [file, node, digest] = fields.as_slice() else {
+                return Err(corrupted());
+            };
+
+            let site: DefinitionLocation = DefinitionLocation {
+                file: file.parse().map_err(|_| corrupted())?,
+                node: node.parse().map_err(|_| corrupted())?,
+            };
+
+            definitions.push((site, digest.parse().map_err(|_| corrupted())?));
+        }
+
+        Ok(InstalledPackage { name: name.to_string(), version, definitions })
+    }
+
+    pub fn packages(&self) -> Result<Vec<String>, PackageError> {
+        let dir: PathBuf = self.root().join("packages");
+
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names: Vec<String> = Vec::new();
+
+        for entry in fs::read_dir(dir)? {
+            let path: PathBuf = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) == Some("index") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
+    // Adds an installed package's definitions under its own namespace
+    pub fn load(&self, name: &str, knowledge: &mut KnowledgeBase) -> Result<Vec<Conflict>, PackageError> {
+        let mut conflicts: Vec<Conflict> = Vec::new();
+
+        for (site, digest) in self.installed(name)?.definitions {
+            let definition: Definition = self.get(digest)?.ok_or(StoreError::Corrupted(digest))?;
+
+            if let Some(conflict) = knowledge.insert_in(name, definition, site)? {
+                conflicts.push(conflict);
+            }
+        }
+
+        Ok(conflicts)
+    }
+}
@@ -0,0 +1,183 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cce_ast::{Parser, ParserError};
+use cce_infer::{equivalent, Definition};
+use cce_infer_ast::{convert, CommandComponent, ProgramNode};
+use circelang_hash::{CirceHash, Digest};
+
+use thiserror::Error;
+
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    ParserError(#[from] ParserError),
+    #[error("Could not find a home directory for the definition store")]
+    NoHomeDirectory,
+    #[error("Definition {0} cannot be written as Circe source without changing it")]
+    Unrepresentable(Digest),
+    #[error("Stored definition {0} does not match its hash")]
+    Corrupted(Digest),
+    #[error("A different definition is already stored as {0}")]
+    Collision(Digest),
+}
+
+// Definitions saved on disk as `<digest>.cce`, keyed by their CirceHash
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    pub fn open(root: impl Into<PathBuf>) -> Result<Store, StoreError> {
+        let root: PathBuf = root.into();
+        fs::create_dir_all(&root)?;
+
+        Ok(Store { root })
+    }
+
+    // `$CIRCE_STORE`, or `~/.circe/store`
+    pub fn default_path() -> Result<PathBuf, StoreError> {
+        if let Some(path) = env::var_os("CIRCE_STORE") {
+            return Ok(PathBuf::from(path));
+        }
+
+        env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".circe").join("store"))
+            .ok_or(StoreError::NoHomeDirectory)
+    }
+
+    pub fn open_default() -> Result<Store, StoreError> {
+        Store::open(Store::default_path()?)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, digest: Digest) -> PathBuf {
+        self.root.join(format!("{}.cce", digest))
+    }
+
+    pub fn put(&self, definition: impl Into<Definition>) -> Result<Digest, StoreError> {
+        let definition: Definition = definition.into();
+        let digest: Digest = definition.digest();
+        let source: String = definition.to_string();
+
+        if !matches!(parse_definition(&source), Ok(Some(parsed)) if parsed == definition) {
+            return Err(StoreError::Unrepresentable(digest));
+        }
+
+        let path: PathBuf = self.path(digest);
+
+        match fs::read_to_string(&path) {
+            // CirceHash is not collision resistant, so an existing file is only
+            // reused when it really holds the same definition
+            Ok(existing) if existing == source => {}
+            Ok(existing) => {
+                if !matches!(parse_definition(&existing), Ok(Some(stored)) if stored == definition) {
+                    return Err(StoreError::Collision(digest));
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                // Write to a temporary name first so readers never see half a file
+                let partial: PathBuf = path.with_extension("cce.partial");
+                fs::write(&partial, source)?;
+                fs::rename(&partial, &path)?;
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(digest)
+    }
+
+    pub fn contains(&self, digest: Digest) -> bool {
+        self.path(digest).exists()
+    }
+
+    pub fn get(&self, digest: Digest) -> Result<Option<Definition>, StoreError> {
+        let source: String = match fs::read_to_string(self.path(digest)) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        match parse_definition(&source)? {
+            Some(definition) if definition.digest() == digest => Ok(Some(definition)),
+            _ => Err(StoreError::Corrupted(digest)),
+        }
+    }
+
+    pub fn digests(&self) -> Result<Vec<Digest>, StoreError> {
+        let mut digests: Vec<Digest> = Vec::new();
+
+        for entry in fs::read_dir(&self.root)? {
+            let path: PathBuf = entry?.path();
+
+            if path.extension().and_then(|ext| ext.to_str()) != Some("cce") {
+                continue;
+            }
+
+            if let Some(Ok(digest)) = path.file_stem().and_then(|stem| stem.to_str()).map(str::parse) {
+                digests.push(digest);
+            }
+        }
+
+        digests.sort();
+        Ok(digests)
+    }
+
+    // Every stored definition whose signature is equivalent to `signature`
+    pub fn search(&self, signature: &[CommandComponent]) -> Result<Vec<(Digest, Definition)>, StoreError> {
+        let mut found: Vec<(Digest, Definition)> = Vec::new();
+
+        for digest in self.digests()? {
+            if let Some(definition) = self.get(digest)? {
+                if equivalent(definition.signature(), signature) {
+                    found.push((digest, definition));
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+fn parse_definition(source: &str) -> Result<Option<Definition>, StoreError> {
+    let mut parser = Parser::from(source);
+    let mut nodes = Vec::new();
+
+    while let Some(node) = parser.next()? {
+        nodes.push(node);
+    }
+
+    match convert(nodes).as_slice() {
+        [ProgramNode::HowTo(howto)] => Ok(Some(Definition::HowTo(howto.clone()))),
+        [ProgramNode::WhatIs(whatis)] => Ok(Some(Definition::WhatIs(whatis.clone()))),
+        _ => Ok(None),
+    }
+}
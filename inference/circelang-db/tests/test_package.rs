@@ -0,0 +1,159 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::fs;
+use std::path::PathBuf;
+
+use cce_infer::{ConflictPolicy, KnowledgeBase};
+use circelang_db::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir: PathBuf = std::env::temp_dir().join(format!("circe-package-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+
+    dir
+}
+
+fn package(name: &str) -> Package {
+    Package {
+        manifest: Manifest {
+            name: name.to_string(),
+            version: "0.1.0".to_string(),
+            files: vec!["greet.cce".to_string(), "io/stdout.cce".to_string()],
+        },
+        sources: vec![
+            "howto greet %who?\n- say hello to %who\n\n".to_string(),
+            "whatis stdout?\n- the console output\n\n".to_string(),
+        ],
+    }
+}
+
+#[test]
+fn test_manifest_parse() {
+    let manifest: Manifest = Manifest::parse("# greetings\nname = \"greetings\"\nversion = \"0.1.0\"\nfiles = [\"a.cce\", \"b.cce\"]\n").unwrap();
+
+    assert_eq!(manifest.name, "greetings");
+    assert_eq!(manifest.version, "0.1.0");
+    assert_eq!(manifest.files, vec!["a.cce", "b.cce"]);
+    assert_eq!(Manifest::parse(&manifest.to_string()).unwrap(), manifest);
+}
+
+#[test]
+fn test_manifest_errors() {
+    assert!(matches!(Manifest::parse("version = \"1\"\n"), Err(PackageError::TomlError(_))));
+    assert!(matches!(
+        Manifest::parse("name = \"a\"\nversion = \"1\"\nauthor = \"me\"\n"),
+        Err(PackageError::TomlError(_))
+    ));
+    assert!(matches!(Manifest::parse("name = \"a\"\nversion = 1\n"), Err(PackageError::TomlError(_))));
+    assert!(matches!(
+        Manifest::parse("name = \"a b\"\nversion = \"1\"\n"),
+        Err(PackageError::InvalidName(_))
+    ));
+    assert!(matches!(
+        Manifest::parse("name = \"a\"\nversion = \"1\"\nfiles = [\"../evil.cce\"]\n"),
+        Err(PackageError::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn test_package_pack_unpack() {
+    let package: Package = package("greetings");
+
+    assert_eq!(Package::unpack(&package.pack()).unwrap(), package);
+    assert_eq!(package.bundle_name(), "greetings-0.1.0.cpkg");
+}
+
+#[test]
+fn test_package_unpack_invalid() {
+    let bundle: String = package("greetings").pack();
+
+    assert!(matches!(Package::unpack("not a bundle"), Err(PackageError::InvalidBundle(_))));
+    assert!(matches!(Package::unpack(&bundle[..bundle.len() - 10]), Err(PackageError::InvalidBundle(_))));
+}
+
+#[test]
+fn test_package_dir_round_trip() {
+    let dir: PathBuf = temp_dir("dir");
+    let package: Package = package("greetings");
+
+    package.write_dir(&dir).unwrap();
+    assert_eq!(Package::read_dir(&dir).unwrap(), package);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_package_read_dir_default_files() {
+    let dir: PathBuf = temp_dir("default_files");
+    fs::create_dir_all(&dir).unwrap();
+
+    fs::write(dir.join(MANIFEST_NAME), "name = \"greetings\"\nversion = \"0.1.0\"\n").unwrap();
+    fs::write(dir.join("b.cce"), "howto wave?\n- move arm\n\n").unwrap();
+    fs::write(dir.join("a.cce"), "howto nod?\n- move head\n\n").unwrap();
+
+    let package: Package = Package::read_dir(&dir).unwrap();
+    assert_eq!(package.manifest.files, vec!["a.cce", "b.cce"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_package_install_and_load() {
+    let root: PathBuf = temp_dir("install");
+    let store: Store = Store::open(&root).unwrap();
+
+    let installed: InstalledPackage = store.install(&package("greetings")).unwrap();
+    store.install(&package("salutations")).unwrap();
+
+    assert_eq!(installed.definitions.len(), 2);
+    assert_eq!(store.installed("greetings").unwrap(), installed);
+    assert_eq!(store.packages().unwrap(), vec!["greetings", "salutations"]);
+    assert!(matches!(store.installed("missing"), Err(PackageError::NotInstalled(_))));
+
+    // Both packages define the same signatures, but in separate namespaces
+    let mut knowledge: KnowledgeBase = KnowledgeBase::new(ConflictPolicy::Error);
+    assert_eq!(store.load("greetings", &mut knowledge).unwrap(), vec![]);
+    assert_eq!(store.load("salutations", &mut knowledge).unwrap(), vec![]);
+    assert_eq!(knowledge.entries().len(), 4);
+
+    assert!(matches!(store.load("greetings", &mut knowledge), Err(PackageError::KnowledgeBaseError(_))));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_package_install_collision() {
+    let root: PathBuf = temp_dir("collision");
+    let store: Store = Store::open(&root).unwrap();
+
+    // `howto ab?` and `howto rD?` share a CirceHash
+    let mut first: Package = package("first");
+    first.sources[0] = "howto ab?\n- wave\n\n".to_string();
+    let mut second: Package = package("second");
+    second.sources[0] = "howto rD?\n- wave\n\n".to_string();
+
+    store.install(&first).unwrap();
+
+    assert!(matches!(store.install(&second), Err(PackageError::StoreError(StoreError::Collision(_)))));
+    assert!(matches!(store.installed("second"), Err(PackageError::NotInstalled(_))));
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,137 @@
+/*
+
+Copyright (C) 2023 Carlos Kieliszewski
+
+This file is part of the Circe Project.
+
+Circe is free software: you can redistribute it and/or modify it under
+the terms of the GNU General Public License as published by the Free
+Software Foundation, either version 3 of the License, or (at your option)
+any later version.
+
+Circe is distributed in the hope that it will be useful, but WITHOUT ANY
+WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS
+FOR A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with
+Circe. If not, see <https://www.gnu.org/licenses/>. 
+
+*/
+
+use std::fs;
+use std::path::PathBuf;
+
+use cce_ast::Parser;
+use cce_infer::Definition;
+use cce_infer_ast::{convert, CommandComponent, ProgramNode};
+use circelang_db::{Store, StoreError};
+use circelang_hash::{CirceHash, Digest};
+
+fn store(name: &str) -> Store {
+    let root: PathBuf = std::env::temp_dir().join(format!("circe-store-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&root);
+
+    Store::open(root).unwrap()
+}
+
+fn definition(source: &str) -> Definition {
+    let mut parser = Parser::from(source);
+
+    match convert(vec![parser.next().unwrap().unwrap()]).remove(0) {
+        ProgramNode::HowTo(howto) => Definition::HowTo(howto),
+        ProgramNode::WhatIs(whatis) => Definition::WhatIs(whatis),
+        ProgramNode::Command(_) | ProgramNode::Module(_) => panic!("expected a definition")
+    }
+}
+
+#[test]
+fn test_store_put_get() {
+    let store: Store = store("put_get");
+    let greet: Definition = definition("howto greet %who?\n- say hello to %who\n\n");
+
+    let digest: Digest = store.put(greet.clone()).unwrap();
+
+    assert_eq!(digest, greet.digest());
+    assert!(store.contains(digest));
+    assert_eq!(store.get(digest).unwrap(), Some(greet.clone()));
+    assert_eq!(store.put(greet).unwrap(), digest);
+    assert_eq!(store.digests().unwrap(), vec![digest]);
+
+    fs::remove_dir_all(store.root()).unwrap();
+}
+
+#[test]
+fn test_store_put_collision() {
+    let store: Store = store("collision");
+
+    // Two different definitions with the same CirceHash
+    let ab: Definition = definition("howto ab?\n- wave\n\n");
+    let rd: Definition = definition("howto rD?\n- wave\n\n");
+    assert_eq!(ab.digest(), rd.digest());
+    assert_ne!(ab, rd);
+
+    let digest: Digest = store.put(ab.clone()).unwrap();
+
+    assert!(matches!(store.put(rd), Err(StoreError::Collision(found)) if found == digest));
+    assert_eq!(store.get(digest).unwrap(), Some(ab));
+
+    fs::remove_dir_all(store.root()).unwrap();
+}
+
+#[test]
+fn test_store_get_missing() {
+    let store: Store = store("missing");
+
+    assert_eq!(store.get("greet".digest()).unwrap(), None);
+
+    fs::remove_dir_all(store.root()).unwrap();
+}
+
+#[test]
+fn test_store_search() {
+    let store: Store = store("search");
+
+    let greet: Definition = definition("howto greet %who?\n- say hello to %who\n\n");
+    let wave: Definition = definition("howto wave?\n- move arm\n\n");
+    let stdout: Definition = definition("whatis stdout?\n- file descriptor '1'\n\n");
+
+    store.put(greet.clone()).unwrap();
+    store.put(wave).unwrap();
+    store.put(stdout).unwrap();
+
+    let signature: Vec<CommandComponent> = vec![
+        CommandComponent::Keyword("Greet".into()),
+        CommandComponent::Slot { name: "person".into(), ty: None },
+    ];
+    let found: Vec<(Digest, Definition)> = store.search(&signature).unwrap();
+
+    assert_eq!(found, vec![(greet.digest(), greet)]);
+
+    fs::remove_dir_all(store.root()).unwrap();
+}
+
+#[test]
+fn test_store_corrupted() {
+    let store: Store = store("corrupted");
+    let digest: Digest = store.put(definition("howto wave?\n- move arm\n\n")).unwrap();
+
+    fs::write(store.root().join(format!("{}.cce", digest)), "howto wave?\n- move leg\n\n").unwrap();
+
+    assert!(matches!(store.get(digest), Err(StoreError::Corrupted(d)) if d == digest));
+
+    fs::remove_dir_all(store.root()).unwrap();
+}
+
+#[test]
+fn test_store_unrepresentable() {
+    let store: Store = store("unrepresentable");
+    let mut say: Definition = definition("howto say?\n- print 'hi'\n\n");
+
+    if let Definition::HowTo(howto) = &mut say {
+        howto.signature.push(CommandComponent::Literal("it's".to_string()));
+    }
+
+    assert!(matches!(store.put(say), Err(StoreError::Unrepresentable(_))));
+
+    fs::remove_dir_all(store.root()).unwrap();
+}